@@ -0,0 +1,468 @@
+//! Tokio client for the gateway daemon (the `async` feature).
+//!
+//! One connection is shared by every clone of an [`AsyncGatewayClient`]. A
+//! reader task routes responses to their callers by request id and
+//! notifications to their [`AsyncSubscription`] by subscription id, so
+//! concurrent calls and streams do not wait on each other.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::UnixStream;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+use carapace_protocol::HELLO_METHOD;
+
+use crate::{
+    connect_error, default_socket_path, hello_outcome, hello_params, hello_timeout, is_idempotent, request_line,
+    response_result, ClientError, Notification, Replay, RECONNECT_BACKOFF, WATCH_END_METHOD,
+};
+
+/// What the reader task delivers to a subscription.
+enum Event {
+    Notification(Notification),
+    /// The daemon ended the stream.
+    End,
+    /// The connection dropped; the stream may be resumed on a new one.
+    Disconnected,
+}
+
+/// Where the reader task sends what it reads.
+#[derive(Default)]
+struct Routes {
+    calls: HashMap<u64, oneshot::Sender<Value>>,
+    /// Subscribe requests awaiting their ack, by request id.
+    pending_subscriptions: HashMap<u64, mpsc::UnboundedSender<Event>>,
+    subscriptions: HashMap<String, mpsc::UnboundedSender<Event>>,
+    closed: bool,
+}
+
+type SharedRoutes = Arc<std::sync::Mutex<Routes>>;
+
+/// One socket connection and its reader task.
+struct Connection {
+    writer: Mutex<OwnedWriteHalf>,
+    routes: SharedRoutes,
+    reader: JoinHandle<()>,
+}
+
+impl Connection {
+    async fn open(socket_path: &Path) -> Result<Self, ClientError> {
+        let stream = UnixStream::connect(socket_path)
+            .await
+            .map_err(|e| connect_error(socket_path, e))?;
+        let (read, write) = stream.into_split();
+        let routes = SharedRoutes::default();
+        let reader = tokio::spawn(read_loop(read, routes.clone()));
+        Ok(Self {
+            writer: Mutex::new(write),
+            routes,
+            reader,
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.routes.lock().unwrap().closed
+    }
+
+    fn unroute(&self, id: u64) {
+        let mut routes = self.routes.lock().unwrap();
+        routes.calls.remove(&id);
+        routes.pending_subscriptions.remove(&id);
+    }
+
+    /// Send one request on this connection and wait for its response.
+    async fn request(
+        &self,
+        id: u64,
+        method: &str,
+        params: Value,
+        timeout: Option<Duration>,
+        events: Option<mpsc::UnboundedSender<Event>>,
+    ) -> Result<Value, ClientError> {
+        self.send(id, method, params, events).await?.wait(timeout).await
+    }
+
+    /// Write a request and route its response. A failed send wrote nothing.
+    async fn send(
+        &self,
+        id: u64,
+        method: &str,
+        params: Value,
+        events: Option<mpsc::UnboundedSender<Event>>,
+    ) -> Result<PendingCall<'_>, ClientError> {
+        let (reply, response) = oneshot::channel();
+        {
+            let mut routes = self.routes.lock().unwrap();
+            if routes.closed {
+                return Err(ClientError::Connection("Daemon closed the connection unexpectedly".into()));
+            }
+            routes.calls.insert(id, reply);
+            if let Some(events) = events {
+                routes.pending_subscriptions.insert(id, events);
+            }
+        }
+        let guard = RouteGuard { connection: self, id };
+
+        let line = request_line(id, method, params)?;
+        let written = {
+            let mut writer = self.writer.lock().await;
+            match writer.write_all(line.as_bytes()).await {
+                Ok(()) => writer.flush().await,
+                Err(e) => Err(e),
+            }
+        };
+        if let Err(e) = written {
+            // Don't hand this connection out again before the reader notices.
+            self.routes.lock().unwrap().closed = true;
+            return Err(e.into());
+        }
+        Ok(PendingCall { response, guard })
+    }
+}
+
+/// A request written to the daemon, awaiting its response.
+struct PendingCall<'a> {
+    response: oneshot::Receiver<Value>,
+    guard: RouteGuard<'a>,
+}
+
+impl PendingCall<'_> {
+    async fn wait(self, timeout: Option<Duration>) -> Result<Value, ClientError> {
+        let id = self.guard.id;
+        let value = match timeout {
+            Some(limit) => tokio::time::timeout(limit, self.response)
+                .await
+                .map_err(|_| ClientError::Timeout(limit))?,
+            None => self.response.await,
+        }
+        .map_err(|_| ClientError::Connection("Daemon closed the connection unexpectedly".into()))?;
+
+        response_result(value, id)
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Route every line from the daemon until the connection closes, then fail
+/// outstanding calls and tell subscriptions they were disconnected.
+async fn read_loop(read: OwnedReadHalf, routes: SharedRoutes) {
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(line) else {
+            break;
+        };
+        let mut routes = routes.lock().unwrap();
+        if let Some(id) = value.get("id").and_then(Value::as_u64) {
+            // Register the stream before handing over the ack so that no
+            // notification read after it can miss its route.
+            if let Some(events) = routes.pending_subscriptions.remove(&id) {
+                let subscription = value
+                    .pointer("/result/subscription")
+                    .and_then(Value::as_str);
+                if let Some(subscription) = subscription {
+                    routes.subscriptions.insert(subscription.to_string(), events);
+                }
+            }
+            if let Some(reply) = routes.calls.remove(&id) {
+                reply.send(value).ok();
+            }
+        } else if let Some(notif) = Notification::from_value(value) {
+            let Some(subscription) = notif.subscription.clone() else {
+                continue;
+            };
+            let event = if notif.method == WATCH_END_METHOD {
+                routes.subscriptions.remove(&subscription).map(|events| (events, Event::End))
+            } else {
+                routes
+                    .subscriptions
+                    .get(&subscription)
+                    .cloned()
+                    .map(|events| (events, Event::Notification(notif)))
+            };
+            if let Some((events, event)) = event {
+                if events.send(event).is_err() {
+                    routes.subscriptions.remove(&subscription);
+                }
+            }
+        }
+    }
+
+    let mut routes = routes.lock().unwrap();
+    routes.closed = true;
+    routes.calls.clear();
+    routes.pending_subscriptions.clear();
+    for (_, events) in routes.subscriptions.drain() {
+        events.send(Event::Disconnected).ok();
+    }
+}
+
+/// Removes a call's route if the call is abandoned (timed out or dropped).
+struct RouteGuard<'a> {
+    connection: &'a Connection,
+    id: u64,
+}
+
+impl Drop for RouteGuard<'_> {
+    fn drop(&mut self) {
+        self.connection.unroute(self.id);
+    }
+}
+
+struct Inner {
+    socket_path: PathBuf,
+    next_id: AtomicU64,
+    connection: Mutex<Option<Arc<Connection>>>,
+}
+
+/// An async client for the Carapace gateway daemon.
+///
+/// Cheap to clone; clones share one connection and may call concurrently.
+/// The connection is opened on first use and reopened after the daemon
+/// goes away, with the same retry rules as
+/// [`GatewayClient`](crate::GatewayClient).
+#[derive(Clone)]
+pub struct AsyncGatewayClient {
+    inner: Arc<Inner>,
+    timeout: Option<Duration>,
+}
+
+impl AsyncGatewayClient {
+    /// Connect to the daemon at the default socket path (see
+    /// [`GatewayClient::connect_default`](crate::GatewayClient::connect_default)).
+    pub async fn connect_default() -> Result<Self, ClientError> {
+        Self::connect(&default_socket_path()).await
+    }
+
+    /// Connect to the daemon at a specific socket path.
+    pub async fn connect(socket_path: &Path) -> Result<Self, ClientError> {
+        let client = Self::new(socket_path, None);
+        client.connection().await?;
+        Ok(client)
+    }
+
+    /// A client that connects on its first call. `timeout` is the default
+    /// time each call waits for a response; `None` waits forever.
+    pub fn new(socket_path: &Path, timeout: Option<Duration>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                socket_path: socket_path.to_path_buf(),
+                next_id: AtomicU64::new(1),
+                connection: Mutex::new(None),
+            }),
+            timeout,
+        }
+    }
+
+    /// A client sharing this one's connection with a different default
+    /// timeout.
+    pub fn with_timeout(&self, timeout: Option<Duration>) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            timeout,
+        }
+    }
+
+    /// The open connection, reconnecting (and repeating the `hello`
+    /// handshake) if the last one closed.
+    async fn connection(&self) -> Result<Arc<Connection>, ClientError> {
+        let mut slot = self.inner.connection.lock().await;
+        if let Some(connection) = slot.as_ref().filter(|c| !c.is_closed()) {
+            return Ok(connection.clone());
+        }
+        let connection = Arc::new(Connection::open(&self.inner.socket_path).await?);
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        let hello = connection.request(id, HELLO_METHOD, hello_params(), hello_timeout(self.timeout), None).await;
+        hello_outcome(hello)?;
+        *slot = Some(connection.clone());
+        Ok(connection)
+    }
+
+    /// Send a JSON-RPC request and wait for the response.
+    ///
+    /// Waits at most the client's default timeout. A request that could not
+    /// be written is retried with backoff on a new connection; idempotent
+    /// methods (see [`is_idempotent`](crate::is_idempotent)) are also
+    /// retried if the connection drops before the response arrives.
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, ClientError> {
+        self.call_with_timeout(method, params, self.timeout).await
+    }
+
+    /// [`call`](AsyncGatewayClient::call) with a timeout for this call only.
+    pub async fn call_with_timeout(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Option<Duration>,
+    ) -> Result<Value, ClientError> {
+        let retries = if is_idempotent(method) { RECONNECT_BACKOFF.len() } else { 0 };
+        let mut attempt = 0;
+        loop {
+            let (result, delivered) = match self.connection().await {
+                Ok(connection) => {
+                    let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+                    match connection.send(id, method, params.clone(), None).await {
+                        Ok(pending) => (pending.wait(timeout).await, true),
+                        Err(e) => (Err(e), false),
+                    }
+                }
+                Err(e) => (Err(e), false),
+            };
+            // A request that was never written cannot have taken effect, so
+            // any method may be sent again.
+            let limit = if delivered { retries } else { RECONNECT_BACKOFF.len() };
+            match result {
+                Err(e) if e.is_disconnect() && attempt < limit => {
+                    tokio::time::sleep(RECONNECT_BACKOFF[attempt]).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    async fn call_once(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Option<Duration>,
+        events: Option<mpsc::UnboundedSender<Event>>,
+    ) -> Result<Value, ClientError> {
+        let connection = self.connection().await?;
+        let id = self.inner.next_id.fetch_add(1, Ordering::Relaxed);
+        connection.request(id, method, params, timeout, events).await
+    }
+
+    /// Send a subscription request and stream its notifications.
+    ///
+    /// Returns the acknowledgment and an [`AsyncSubscription`]. The client
+    /// stays usable for other calls meanwhile.
+    pub async fn subscribe(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<(Value, AsyncSubscription), ClientError> {
+        let (ack, events) = self.start_subscription(method, &params).await?;
+        let stream = AsyncSubscription {
+            client: self.clone(),
+            method: method.to_string(),
+            params,
+            subscription: subscription_id(&ack),
+            events,
+            replay: Replay::default(),
+        };
+        Ok((ack, stream))
+    }
+
+    async fn start_subscription(
+        &self,
+        method: &str,
+        params: &Value,
+    ) -> Result<(Value, mpsc::UnboundedReceiver<Event>), ClientError> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let ack = self
+            .call_once(method, params.clone(), self.timeout, Some(tx))
+            .await?;
+        Ok((ack, rx))
+    }
+}
+
+fn subscription_id(ack: &Value) -> Option<String> {
+    ack.get("subscription").and_then(Value::as_str).map(String::from)
+}
+
+/// A stream of notifications from [`AsyncGatewayClient::subscribe`].
+///
+/// Resubscribes with the same params if the daemon restarts, like
+/// [`Subscription`](crate::Subscription). Dropping it cancels the
+/// subscription on the daemon.
+pub struct AsyncSubscription {
+    client: AsyncGatewayClient,
+    method: String,
+    params: Value,
+    subscription: Option<String>,
+    events: mpsc::UnboundedReceiver<Event>,
+    replay: Replay,
+}
+
+impl AsyncSubscription {
+    /// The daemon's id for the current subscription. Changes after a
+    /// resubscribe.
+    pub fn id(&self) -> Option<&str> {
+        self.subscription.as_deref()
+    }
+
+    /// The next notification's params, or `None` once the stream ends.
+    pub async fn next(&mut self) -> Option<Result<Value, ClientError>> {
+        loop {
+            match self.events.recv().await {
+                Some(Event::Notification(notif)) => {
+                    if self.replay.is_duplicate(&notif.params) {
+                        continue;
+                    }
+                    return Some(Ok(notif.params));
+                }
+                Some(Event::End) => {
+                    self.subscription = None;
+                    return None;
+                }
+                Some(Event::Disconnected) | None => {
+                    if let Err(e) = self.resubscribe().await {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Subscribe again, backing off while the daemon is down.
+    async fn resubscribe(&mut self) -> Result<(), ClientError> {
+        let mut last_error = None;
+        for delay in RECONNECT_BACKOFF {
+            tokio::time::sleep(delay).await;
+            match self.client.start_subscription(&self.method, &self.params).await {
+                Ok((ack, events)) => {
+                    self.subscription = subscription_id(&ack);
+                    self.events = events;
+                    return Ok(());
+                }
+                Err(e) if e.is_disconnect() => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| ClientError::Connection("resubscribe failed".into())))
+    }
+}
+
+impl Drop for AsyncSubscription {
+    fn drop(&mut self) {
+        let Some(subscription) = self.subscription.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let client = self.client.clone();
+        runtime.spawn(async move {
+            client
+                .call("channel.unwatch", serde_json::json!({"subscription": subscription}))
+                .await
+                .ok();
+        });
+    }
+}
@@ -0,0 +1,217 @@
+//! Typed `channel.*` calls.
+//!
+//! [`GatewayClient::imsg`], [`GatewayClient::gmail`] and
+//! [`GatewayClient::gdocs`] return a handle that builds each method's params
+//! from the structs in [`carapace_protocol`] and parses its result. Results
+//! the daemon passes through from a proxy unchanged are returned as
+//! `serde_json::Value`.
+
+use carapace_protocol::channel::{AckParams, AckResult, Capabilities, ListParams};
+use carapace_protocol::{gdocs, gmail, imsg};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{ClientError, GatewayClient};
+
+impl GatewayClient {
+    /// The iMessage channel.
+    pub fn imsg(&mut self) -> Imsg<'_> {
+        Imsg { client: self }
+    }
+
+    /// A Gmail account, or the gateway's default account for `None`.
+    pub fn gmail<'a>(&'a mut self, account: impl Into<Option<&'a str>>) -> Gmail<'a> {
+        Gmail { client: self, account: account.into() }
+    }
+
+    /// The Google Docs channel, on the default account unless
+    /// [`GDocs::account`] picks another.
+    pub fn gdocs(&mut self) -> GDocs<'_> {
+        GDocs { client: self, account: None }
+    }
+
+    /// The channels and accounts this gateway serves.
+    pub fn capabilities(&mut self) -> Result<Capabilities, ClientError> {
+        let result = self.call("channel.capabilities", json!({}))?;
+        parse_result("channel.capabilities", result)
+    }
+
+    /// Call a `channel.*` method with typed params and result.
+    fn channel_call<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        channel: &str,
+        account: Option<&str>,
+        params: impl Serialize,
+    ) -> Result<T, ClientError> {
+        let mut params = serde_json::to_value(params)
+            .map_err(|e| ClientError::Parse(format!("Failed to serialize {method} params: {e}")))?;
+        let Some(fields) = params.as_object_mut() else {
+            return Err(ClientError::Parse(format!("{method} params must be an object")));
+        };
+        fields.insert("channel".into(), json!(channel));
+        if let Some(account) = account {
+            fields.insert("account".into(), json!(account));
+        }
+        let result = self.call(method, params)?;
+        parse_result(method, result)
+    }
+}
+
+fn parse_result<T: DeserializeOwned>(method: &str, result: Value) -> Result<T, ClientError> {
+    serde_json::from_value(result).map_err(|e| ClientError::Parse(format!("unexpected {method} result: {e}")))
+}
+
+/// Typed calls on the `imsg` channel. Created by [`GatewayClient::imsg`].
+pub struct Imsg<'a> {
+    client: &'a mut GatewayClient,
+}
+
+impl Imsg<'_> {
+    fn call<T: DeserializeOwned>(&mut self, method: &str, params: impl Serialize) -> Result<T, ClientError> {
+        self.client.channel_call(method, "imsg", None, params)
+    }
+
+    /// Send a message. Fails with [`ClientError::NotInAllowlist`] or
+    /// [`ClientError::ContentBlocked`] when policy rejects it.
+    pub fn send(&mut self, params: &imsg::SendParams) -> Result<imsg::SendResult, ClientError> {
+        self.call("channel.send", params)
+    }
+
+    /// Recent chats, as returned by `imsg chats --json`.
+    pub fn list_chats(&mut self, limit: Option<u32>) -> Result<Value, ClientError> {
+        self.call("channel.list_chats", ListParams { limit })
+    }
+
+    /// Messages in one chat, as returned by `imsg history --json`.
+    pub fn history(&mut self, params: &imsg::HistoryParams) -> Result<Value, ClientError> {
+        self.call("channel.get_history", params)
+    }
+
+    pub fn status(&mut self) -> Result<imsg::Status, ClientError> {
+        self.call("channel.status", Map::new())
+    }
+
+    /// Acknowledge events up to `position` on a named watch cursor.
+    pub fn ack(&mut self, cursor: &str, position: &str) -> Result<AckResult, ClientError> {
+        self.call("channel.ack", ack_params(cursor, position))
+    }
+}
+
+/// Typed calls on one Gmail account. Created by [`GatewayClient::gmail`].
+pub struct Gmail<'a> {
+    client: &'a mut GatewayClient,
+    account: Option<&'a str>,
+}
+
+impl Gmail<'_> {
+    fn call<T: DeserializeOwned>(&mut self, method: &str, params: impl Serialize) -> Result<T, ClientError> {
+        self.client.channel_call(method, "gmail", self.account, params)
+    }
+
+    /// Search messages with Gmail query syntax.
+    pub fn search(&mut self, params: &gmail::SearchParams) -> Result<Value, ClientError> {
+        self.call("channel.search", params)
+    }
+
+    /// Recent inbox threads.
+    pub fn inbox(&mut self, max: Option<u32>) -> Result<Value, ClientError> {
+        self.call("channel.list_chats", ListParams { limit: max })
+    }
+
+    /// Every message in a thread.
+    pub fn thread(&mut self, thread_id: &str) -> Result<Value, ClientError> {
+        self.call("channel.get_history", gmail::ThreadParams { thread_id: thread_id.into() })
+    }
+
+    /// Create a draft. The gateway never sends mail directly.
+    pub fn create_draft(&mut self, params: &gmail::DraftParams) -> Result<gmail::DraftResult, ClientError> {
+        self.call("channel.create_draft", params)
+    }
+
+    /// List a message's attachments.
+    pub fn attachments(&mut self, message_id: &str) -> Result<Value, ClientError> {
+        let params = gmail::AttachmentParams { message_id: message_id.into(), attachment_id: None };
+        self.call("channel.read_attachment", params)
+    }
+
+    /// Read one attachment's content.
+    pub fn read_attachment(&mut self, message_id: &str, attachment_id: &str) -> Result<Value, ClientError> {
+        let params = gmail::AttachmentParams {
+            message_id: message_id.into(),
+            attachment_id: Some(attachment_id.into()),
+        };
+        self.call("channel.read_attachment", params)
+    }
+
+    pub fn status(&mut self) -> Result<gmail::Status, ClientError> {
+        self.call("channel.status", Map::new())
+    }
+
+    /// Acknowledge events up to `position` on a named watch cursor.
+    pub fn ack(&mut self, cursor: &str, position: &str) -> Result<AckResult, ClientError> {
+        self.call("channel.ack", ack_params(cursor, position))
+    }
+}
+
+/// Typed calls on the `gdocs` channel. Created by [`GatewayClient::gdocs`].
+pub struct GDocs<'a> {
+    client: &'a mut GatewayClient,
+    account: Option<&'a str>,
+}
+
+impl<'a> GDocs<'a> {
+    /// Use the named account instead of the default.
+    pub fn account(mut self, account: &'a str) -> Self {
+        self.account = Some(account);
+        self
+    }
+
+    fn call<T: DeserializeOwned>(&mut self, method: &str, params: impl Serialize) -> Result<T, ClientError> {
+        self.client.channel_call(method, "gdocs", self.account, params)
+    }
+
+    /// Search Drive files.
+    pub fn search(&mut self, params: &gdocs::SearchParams) -> Result<Value, ClientError> {
+        self.call("channel.search", params)
+    }
+
+    /// Recently modified files.
+    pub fn recent(&mut self, max: Option<u32>) -> Result<Value, ClientError> {
+        self.call("channel.list_chats", ListParams { limit: max })
+    }
+
+    /// Read a document, a sheet range or form responses.
+    pub fn read(&mut self, params: &gdocs::ReadParams) -> Result<Value, ClientError> {
+        self.call("channel.get_history", params)
+    }
+
+    /// A file's metadata.
+    pub fn file_info(&mut self, file_id: &str) -> Result<Value, ClientError> {
+        self.call("channel.status", gdocs::FileInfoParams { file_id: file_id.into() })
+    }
+
+    /// Create a document.
+    pub fn create_document(&mut self, params: &gdocs::CreateDocumentParams) -> Result<Value, ClientError> {
+        self.call("channel.create_draft", params)
+    }
+
+    /// Perform a write: copy, append, edit, sheet and form changes.
+    pub fn send(&mut self, action: &gdocs::Action) -> Result<Value, ClientError> {
+        self.call("channel.send", action)
+    }
+
+    pub fn status(&mut self) -> Result<gdocs::Status, ClientError> {
+        self.call("channel.status", Map::new())
+    }
+
+    /// Acknowledge events up to `position` on a named watch cursor.
+    pub fn ack(&mut self, cursor: &str, position: &str) -> Result<AckResult, ClientError> {
+        self.call("channel.ack", ack_params(cursor, position))
+    }
+}
+
+fn ack_params(cursor: &str, position: &str) -> AckParams {
+    AckParams { cursor: cursor.into(), position: position.into() }
+}
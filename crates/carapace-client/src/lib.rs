@@ -3,8 +3,10 @@
 //! Provides [`GatewayClient`] – a synchronous client for connecting to the
 //! Carapace daemon over its Unix domain socket and making JSON-RPC calls.
 //!
-//! This crate is intentionally synchronous so that shims can be small,
-//! fast-starting binaries without pulling in an async runtime.
+//! The default build is synchronous so that shims can be small,
+//! fast-starting binaries without pulling in an async runtime. The `async`
+//! feature adds `AsyncGatewayClient`, a tokio client for callers that
+//! already run one.
 //!
 //! # Example
 //!
@@ -16,12 +18,94 @@
 //! let result = client.call("ping", json!({})).unwrap();
 //! println!("Got: {}", result);
 //! ```
-
+//!
+//! Each channel also has typed methods that take and return the structs in
+//! [`protocol`]; policy rejections come back as their own [`ClientError`]
+//! variants.
+//!
+//! ```no_run
+//! use carapace_client::{protocol::imsg::SendParams, ClientError, GatewayClient};
+//!
+//! let mut client = GatewayClient::connect_default().unwrap();
+//! let send = SendParams {
+//!     recipient: "+15551234567".into(),
+//!     message: "Running late".into(),
+//!     attachments: vec![],
+//! };
+//! match client.imsg().send(&send) {
+//!     Ok(result) => println!("sent: {}", result.success),
+//!     Err(ClientError::NotInAllowlist { message, .. }) => eprintln!("not allowed: {message}"),
+//!     Err(e) => eprintln!("{e}"),
+//! }
+//! let unread = client
+//!     .gmail("work")
+//!     .search(&carapace_client::protocol::gmail::SearchParams {
+//!         query: "is:unread".into(),
+//!         max: Some(10),
+//!         page_token: None,
+//!     })
+//!     .unwrap();
+//! println!("{unread}");
+//! ```
+//!
+//! A single connection can carry several watch subscriptions alongside normal
+//! calls; notifications that arrive while waiting for a response are buffered
+//! and returned by [`GatewayClient::next_notification`].
+//!
+//! ```no_run
+//! use carapace_client::GatewayClient;
+//! use serde_json::json;
+//!
+//! let mut client = GatewayClient::connect_default().unwrap();
+//! let imsg = client.watch(json!({"channel": "imsg"})).unwrap();
+//! let _gmail = client.watch(json!({"channel": "gmail", "account": "primary"})).unwrap();
+//! client.call("channel.status", json!({"channel": "imsg"})).unwrap();
+//! while let Some(n) = client.next_notification().unwrap() {
+//!     println!("{:?}: {}", n.subscription, n.params);
+//! }
+//! client.unwatch(&imsg).unwrap();
+//! ```
+//!
+//! # Daemon restarts
+//!
+//! Both clients survive a daemon restart. A call checks that the daemon has
+//! not closed the connection before writing, and reconnects if it has. A
+//! request that cannot be written reached nothing, so every method is
+//! retried with backoff. Read-only and otherwise idempotent methods (see
+//! [`is_idempotent`]) are also retried if the daemon drops them mid-call.
+//! Others then fail once, since they may already have taken effect.
+//! Streaming subscriptions ([`Subscription`]) resubscribe with the same
+//! params. Watches started with [`GatewayClient::watch`] are tied to their
+//! connection and end with it.
+//!
+//! # Protocol version
+//!
+//! Every new connection starts with a `hello` handshake carrying
+//! [`protocol::PROTOCOL_VERSION`]. A daemon speaking an incompatible version
+//! fails the connect (or the reconnecting call) with
+//! [`ClientError::IncompatibleVersion`]. Daemons that predate the handshake
+//! are accepted.
+
+#[cfg(feature = "async")]
+mod async_client;
+mod channels;
+
+#[cfg(feature = "async")]
+pub use async_client::{AsyncGatewayClient, AsyncSubscription};
+pub use carapace_protocol as protocol;
+pub use channels::{GDocs, Gmail, Imsg};
+
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader, Write};
+use std::net::Shutdown;
 use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use serde::{Deserialize, Serialize};
+use carapace_protocol::rpc::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use carapace_protocol::{ErrorCode, HelloParams, HELLO_METHOD, PROTOCOL_VERSION};
 
 /// Default socket path matching the daemon's default.
 const DEFAULT_SOCKET_PATH: &str = "/var/run/carapace/gateway.sock";
@@ -29,6 +113,61 @@ const DEFAULT_SOCKET_PATH: &str = "/var/run/carapace/gateway.sock";
 /// Environment variable to override the socket path.
 const ENV_SOCKET_PATH: &str = "CARAPACE_SOCKET_PATH";
 
+/// Waits between reconnect attempts after the daemon goes away.
+const RECONNECT_BACKOFF: [Duration; 5] = [
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+];
+
+/// Longest wait for the `hello` answer on a new connection when the client
+/// has no shorter default timeout, so a stuck daemon fails `connect`
+/// instead of hanging it.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The timeout for `hello`: the client's default, capped at
+/// [`HELLO_TIMEOUT`].
+fn hello_timeout(timeout: Option<Duration>) -> Option<Duration> {
+    Some(timeout.map_or(HELLO_TIMEOUT, |t| t.min(HELLO_TIMEOUT)))
+}
+
+/// Methods that are safe to send again if the daemon drops them mid-call:
+/// reads, and acks (cursors never move backwards).
+const IDEMPOTENT_METHODS: &[&str] = &[
+    "hello",
+    "ping",
+    "echo",
+    "whoami",
+    "channel.status",
+    "channel.capabilities",
+    "channel.list_chats",
+    "channel.get_history",
+    "channel.search",
+    "channel.read_attachment",
+    "channel.ack",
+];
+
+/// Whether a failed `method` call may be retried transparently.
+pub fn is_idempotent(method: &str) -> bool {
+    IDEMPOTENT_METHODS.contains(&method)
+}
+
+/// The socket path from `CARAPACE_SOCKET_PATH`, or the daemon's default.
+fn default_socket_path() -> PathBuf {
+    std::env::var(ENV_SOCKET_PATH)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_SOCKET_PATH))
+}
+
+fn connect_error(socket_path: &Path, e: std::io::Error) -> ClientError {
+    ClientError::Connection(format!(
+        "Cannot connect to daemon at {}: {e}. Is the daemon running?",
+        socket_path.display()
+    ))
+}
+
 // ── Error types ────────────────────────────────────────────────────────────
 
 /// Errors returned by the gateway client.
@@ -46,51 +185,276 @@ pub enum ClientError {
     #[error("invalid JSON from daemon: {0}")]
     Parse(String),
 
-    /// The daemon returned a JSON-RPC error.
+    /// The recipient is not on the channel's outbound allowlist (-32001).
+    #[error("not in allowlist: {message}")]
+    NotInAllowlist { message: String, data: Option<serde_json::Value> },
+
+    /// The request rate limit was exceeded (-32002).
+    #[error("rate limited: {message}")]
+    RateLimited { message: String, data: Option<serde_json::Value> },
+
+    /// The content filter blocked the message (-32003).
+    #[error("content blocked: {message}")]
+    ContentBlocked { message: String, data: Option<serde_json::Value> },
+
+    /// The channel or account is not configured on the gateway (-32004).
+    #[error("channel unavailable: {message}")]
+    ChannelUnavailable { message: String, data: Option<serde_json::Value> },
+
+    /// The channel failed to deliver the message (-32005).
+    #[error("send failed: {message}")]
+    SendFailed { message: String, data: Option<serde_json::Value> },
+
+    /// The daemon speaks an incompatible protocol version (-32006).
+    #[error("incompatible protocol version: {message}")]
+    IncompatibleVersion { message: String, data: Option<serde_json::Value> },
+
+    /// A write policy refused the request (-32007).
+    #[error("write denied: {message}")]
+    WriteDenied { message: String, data: Option<serde_json::Value> },
+
+    /// The target changed since it was read (-32008).
+    #[error("conflict: {message}")]
+    Conflict { message: String, data: Option<serde_json::Value> },
+
+    /// The daemon returned any other JSON-RPC error.
     #[error("gateway error {code}: {message}")]
-    Gateway { code: i32, message: String },
+    Gateway { code: i32, message: String, data: Option<serde_json::Value> },
 
     /// The response didn't match the expected request ID.
     #[error("response ID mismatch: expected {expected}, got {got}")]
     IdMismatch { expected: u64, got: String },
+
+    /// No response arrived within the call's timeout.
+    #[error("no response from daemon within {0:?}")]
+    Timeout(Duration),
 }
 
-// ── Internal JSON-RPC types (kept private) ─────────────────────────────────
+impl ClientError {
+    /// The typed error for a JSON-RPC error from the daemon.
+    fn from_rpc(err: JsonRpcError) -> Self {
+        let JsonRpcError { code, message, data } = err;
+        match ErrorCode::from_code(code) {
+            Some(ErrorCode::NotInAllowlist) => ClientError::NotInAllowlist { message, data },
+            Some(ErrorCode::RateLimited) => ClientError::RateLimited { message, data },
+            Some(ErrorCode::ContentBlocked) => ClientError::ContentBlocked { message, data },
+            Some(ErrorCode::ChannelUnavailable) => ClientError::ChannelUnavailable { message, data },
+            Some(ErrorCode::SendFailed) => ClientError::SendFailed { message, data },
+            Some(ErrorCode::IncompatibleVersion) => ClientError::IncompatibleVersion { message, data },
+            Some(ErrorCode::WriteDenied) => ClientError::WriteDenied { message, data },
+            Some(ErrorCode::Conflict) => ClientError::Conflict { message, data },
+            _ => ClientError::Gateway { code, message, data },
+        }
+    }
 
-#[derive(Serialize)]
-struct RpcRequest {
-    jsonrpc: &'static str,
-    id: u64,
-    method: String,
-    params: serde_json::Value,
+    /// The JSON-RPC error code, if the daemon answered with an error.
+    pub fn code(&self) -> Option<i32> {
+        match self {
+            ClientError::NotInAllowlist { .. } => Some(ErrorCode::NotInAllowlist.code()),
+            ClientError::RateLimited { .. } => Some(ErrorCode::RateLimited.code()),
+            ClientError::ContentBlocked { .. } => Some(ErrorCode::ContentBlocked.code()),
+            ClientError::ChannelUnavailable { .. } => Some(ErrorCode::ChannelUnavailable.code()),
+            ClientError::SendFailed { .. } => Some(ErrorCode::SendFailed.code()),
+            ClientError::IncompatibleVersion { .. } => Some(ErrorCode::IncompatibleVersion.code()),
+            ClientError::WriteDenied { .. } => Some(ErrorCode::WriteDenied.code()),
+            ClientError::Conflict { .. } => Some(ErrorCode::Conflict.code()),
+            ClientError::Gateway { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
+
+    /// The daemon's error message, if it answered with an error.
+    pub fn gateway_message(&self) -> Option<&str> {
+        match self {
+            ClientError::NotInAllowlist { message, .. }
+            | ClientError::RateLimited { message, .. }
+            | ClientError::ContentBlocked { message, .. }
+            | ClientError::ChannelUnavailable { message, .. }
+            | ClientError::SendFailed { message, .. }
+            | ClientError::IncompatibleVersion { message, .. }
+            | ClientError::WriteDenied { message, .. }
+            | ClientError::Conflict { message, .. }
+            | ClientError::Gateway { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+
+    /// Whether the connection itself failed (as opposed to the request),
+    /// so reconnecting may help.
+    pub fn is_disconnect(&self) -> bool {
+        matches!(self, ClientError::Connection(_) | ClientError::Io(_))
+    }
+}
+
+// ── Wire helpers ───────────────────────────────────────────────────────────
+
+/// A request as one newline-terminated line.
+fn request_line(id: u64, method: &str, params: serde_json::Value) -> Result<String, ClientError> {
+    let request = JsonRpcRequest::new(id, method, params);
+    let mut line = serde_json::to_string(&request)
+        .map_err(|e| ClientError::Parse(format!("Failed to serialize request: {e}")))?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Parse a response line and turn it into the call's outcome.
+fn response_result(value: serde_json::Value, expected_id: u64) -> Result<serde_json::Value, ClientError> {
+    let response: JsonRpcResponse = serde_json::from_value(value.clone())
+        .map_err(|e| ClientError::Parse(format!("{e}: {value}")))?;
+
+    // Verify the response ID matches.
+    let resp_id = match &response.id {
+        serde_json::Value::Number(n) => n.as_u64().unwrap_or(0),
+        _ => 0,
+    };
+    if resp_id != expected_id {
+        return Err(ClientError::IdMismatch {
+            expected: expected_id,
+            got: response.id.to_string(),
+        });
+    }
+
+    // Check for errors.
+    if let Some(err) = response.error {
+        return Err(ClientError::from_rpc(err));
+    }
+
+    Ok(response.result.unwrap_or(serde_json::Value::Null))
+}
+
+/// Params of the `hello` sent on each new connection.
+fn hello_params() -> serde_json::Value {
+    let params = HelloParams {
+        protocol_version: PROTOCOL_VERSION,
+        client: Some(concat!("carapace-client/", env!("CARGO_PKG_VERSION")).into()),
+    };
+    serde_json::to_value(params).expect("hello params serialize")
+}
+
+/// The outcome of `hello`. Daemons that predate it answer "method not found"
+/// and are served as protocol 1.0.
+fn hello_outcome(result: Result<serde_json::Value, ClientError>) -> Result<(), ClientError> {
+    match result {
+        Err(ClientError::Gateway { code, .. }) if code == ErrorCode::MethodNotFound.code() => Ok(()),
+        other => other.map(drop),
+    }
+}
+
+/// Notification method sent when a subscription's source ends on its own.
+const WATCH_END_METHOD: &str = "channel.watch_end";
+
+/// A server → client notification.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    /// `channel.watch` for events, `channel.watch_end` when a stream ends.
+    pub method: String,
+    /// The subscription this notification belongs to, if tagged.
+    pub subscription: Option<String>,
+    pub params: serde_json::Value,
+}
+
+impl Notification {
+    fn from_value(value: serde_json::Value) -> Option<Self> {
+        let method = value.get("method")?.as_str()?.to_string();
+        let params = value.get("params").cloned().unwrap_or(serde_json::Value::Null);
+        let subscription = params
+            .get("subscription")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        Some(Self { method, subscription, params })
+    }
 }
 
-#[derive(Deserialize)]
-struct RpcResponse {
-    #[allow(dead_code)]
-    jsonrpc: String,
-    id: serde_json::Value,
-    result: Option<serde_json::Value>,
-    error: Option<RpcError>,
+/// Tracks the last event `position` a resubscribing stream delivered, so
+/// events replayed from a named cursor after a restart are not repeated.
+///
+/// A position is the point that is safe to acknowledge, not an event id:
+/// Gmail and Drive events of one batch share the batch's starting position
+/// until the last. So only events strictly below the last position are
+/// dropped, and an event at that position may be delivered again after a
+/// reconnect.
+#[derive(Default)]
+struct Replay {
+    last: Option<u64>,
 }
 
-#[derive(Deserialize)]
-struct RpcError {
-    code: i32,
-    message: String,
+impl Replay {
+    /// Whether `event` was already delivered. Events without a numeric
+    /// position are always new.
+    fn is_duplicate(&mut self, event: &serde_json::Value) -> bool {
+        let Some(position) = event
+            .get("position")
+            .and_then(|p| p.as_str())
+            .and_then(|p| p.parse::<u64>().ok())
+        else {
+            return false;
+        };
+        if self.last.is_some_and(|last| position < last) {
+            return true;
+        }
+        self.last = Some(position);
+        false
+    }
+}
+
+/// Read one non-blank line and parse it as JSON. `Ok(None)` means EOF.
+fn read_json_line(
+    reader: &mut BufReader<UnixStream>,
+) -> Result<Option<serde_json::Value>, ClientError> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        return serde_json::from_str(trimmed)
+            .map(Some)
+            .map_err(|e| ClientError::Parse(format!("{e}: {line}")));
+    }
 }
 
 // ── GatewayClient ──────────────────────────────────────────────────────────
 
+/// Aborts a [`GatewayClient`] connection from another thread.
+///
+/// Created by [`GatewayClient::interrupt_handle`].
+#[derive(Debug)]
+pub struct InterruptHandle {
+    stream: UnixStream,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl InterruptHandle {
+    /// Shut the connection down. A blocked call fails with an error instead
+    /// of being retried; the next call reconnects.
+    pub fn interrupt(&self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+}
+
 /// A synchronous client for the Carapace gateway daemon.
 ///
 /// Maintains a persistent connection to the Unix domain socket.
 /// Each [`call`](GatewayClient::call) sends a JSON-RPC request and waits
 /// for the response.
 pub struct GatewayClient {
+    socket_path: PathBuf,
     reader: BufReader<UnixStream>,
     writer: UnixStream,
     next_id: u64,
+    /// Notifications read while waiting for a response.
+    pending: VecDeque<Notification>,
+    /// Default time to wait for each response; `None` waits forever.
+    timeout: Option<Duration>,
+    /// The connection failed or lost its place in the response stream; the
+    /// next call reconnects first.
+    broken: bool,
+    /// Set by an [`InterruptHandle`]; stops the interrupted call retrying.
+    interrupted: Arc<AtomicBool>,
 }
 
 impl GatewayClient {
@@ -100,93 +464,257 @@ impl GatewayClient {
     /// 1. `CARAPACE_SOCKET_PATH` environment variable
     /// 2. `/var/run/carapace/gateway.sock`
     pub fn connect_default() -> Result<Self, ClientError> {
-        let path = std::env::var(ENV_SOCKET_PATH)
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from(DEFAULT_SOCKET_PATH));
-        Self::connect(&path)
+        Self::connect(&default_socket_path())
     }
 
     /// Connect to the daemon at a specific socket path.
     pub fn connect(socket_path: &Path) -> Result<Self, ClientError> {
-        let stream = UnixStream::connect(socket_path).map_err(|e| {
-            ClientError::Connection(format!(
-                "Cannot connect to daemon at {}: {e}. Is the daemon running?",
-                socket_path.display()
-            ))
-        })?;
+        let (reader, writer) = Self::open(socket_path)?;
+        let mut client = Self {
+            socket_path: socket_path.to_path_buf(),
+            reader,
+            writer,
+            next_id: 1,
+            pending: VecDeque::new(),
+            timeout: None,
+            broken: false,
+            interrupted: Arc::new(AtomicBool::new(false)),
+        };
+        client.hello()?;
+        Ok(client)
+    }
 
+    fn open(socket_path: &Path) -> Result<(BufReader<UnixStream>, UnixStream), ClientError> {
+        let stream = UnixStream::connect(socket_path).map_err(|e| connect_error(socket_path, e))?;
         let reader = BufReader::new(stream.try_clone().map_err(|e| {
             ClientError::Connection(format!("Failed to clone stream: {e}"))
         })?);
+        Ok((reader, stream))
+    }
 
-        Ok(Self {
-            reader,
-            writer: stream,
-            next_id: 1,
+    /// Replace a failed connection. Watches on the old one are gone.
+    fn reconnect(&mut self) -> Result<(), ClientError> {
+        let (reader, writer) = Self::open(&self.socket_path)?;
+        self.reader = reader;
+        self.writer = writer;
+        self.pending.clear();
+        self.hello()?;
+        self.broken = false;
+        Ok(())
+    }
+
+    /// Check that the daemon speaks a compatible protocol version.
+    fn hello(&mut self) -> Result<(), ClientError> {
+        let result = self.call_once(HELLO_METHOD, hello_params(), hello_timeout(self.timeout));
+        hello_outcome(result)
+    }
+
+    /// Set the default time [`call`](GatewayClient::call) waits for a
+    /// response. `None` (the default) waits forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// A handle that aborts this connection from another thread.
+    ///
+    /// [`InterruptHandle::interrupt`] shuts the socket down, so a
+    /// [`call`](GatewayClient::call) blocked on the daemon fails right away.
+    /// The client reconnects on its next call.
+    pub fn interrupt_handle(&self) -> Result<InterruptHandle, ClientError> {
+        Ok(InterruptHandle {
+            stream: self.writer.try_clone()?,
+            interrupted: self.interrupted.clone(),
         })
     }
 
     /// Send a JSON-RPC request and wait for the response.
     ///
-    /// Returns the `result` field on success, or a [`ClientError::Gateway`]
-    /// if the daemon returned an error.
+    /// Returns the `result` field on success, or the typed [`ClientError`]
+    /// for the daemon's error code. Notifications for active
+    /// subscriptions that arrive first are queued for
+    /// [`next_notification`](GatewayClient::next_notification).
+    ///
+    /// Waits at most the default timeout (see
+    /// [`set_timeout`](GatewayClient::set_timeout)). A lost connection is
+    /// re-established first, and a request that could not be written is
+    /// sent again; idempotent methods are also retried if the response is
+    /// lost.
     pub fn call(
         &mut self,
         method: &str,
         params: serde_json::Value,
     ) -> Result<serde_json::Value, ClientError> {
-        let id = self.next_id;
-        self.next_id += 1;
+        self.call_with_timeout(method, params, self.timeout)
+    }
 
-        // Build and send the request.
-        let request = RpcRequest {
-            jsonrpc: "2.0",
-            id,
-            method: method.to_string(),
-            params,
+    /// [`call`](GatewayClient::call) with a timeout for this call only.
+    pub fn call_with_timeout(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<serde_json::Value, ClientError> {
+        let retries = if is_idempotent(method) { RECONNECT_BACKOFF.len() } else { 0 };
+        if self.interrupted.swap(false, Ordering::SeqCst) {
+            // Interrupted between calls; the socket is shut down.
+            self.broken = true;
+        }
+        let mut attempt = 0;
+        loop {
+            let sent = self
+                .ensure_connected()
+                .and_then(|()| self.send_request(method, params.clone()));
+            let (result, delivered) = match sent {
+                Ok(id) => (self.read_response(id, timeout), true),
+                Err(e) => (Err(e), false),
+            };
+            match result {
+                Err(e) if e.is_disconnect() => {
+                    self.broken = true;
+                    // A request that was never written cannot have taken
+                    // effect, so any method may be sent again.
+                    let limit = if delivered { retries } else { RECONNECT_BACKOFF.len() };
+                    if self.interrupted.swap(false, Ordering::SeqCst) || attempt >= limit {
+                        return Err(e);
+                    }
+                    std::thread::sleep(RECONNECT_BACKOFF[attempt]);
+                    attempt += 1;
+                }
+                Err(e @ ClientError::Timeout(_)) => {
+                    // The late response would be read as the next call's.
+                    self.broken = true;
+                    return Err(e);
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// Reconnect if the connection failed or the daemon has closed it since
+    /// the last call.
+    fn ensure_connected(&mut self) -> Result<(), ClientError> {
+        if !self.broken && self.peer_closed() {
+            self.broken = true;
+        }
+        if self.broken {
+            self.reconnect()?;
+        }
+        Ok(())
+    }
+
+    /// Whether the daemon has closed the connection, checked without
+    /// blocking. Notifications already sent stay buffered for the reader.
+    fn peer_closed(&mut self) -> bool {
+        if !self.reader.buffer().is_empty() {
+            return false;
+        }
+        if self.reader.get_ref().set_nonblocking(true).is_err() {
+            return true;
+        }
+        let closed = match self.reader.fill_buf() {
+            Ok(buf) => buf.is_empty(),
+            Err(e) => e.kind() != std::io::ErrorKind::WouldBlock,
         };
+        self.reader.get_ref().set_nonblocking(false).is_err() || closed
+    }
 
-        let mut request_json = serde_json::to_string(&request)
-            .map_err(|e| ClientError::Parse(format!("Failed to serialize request: {e}")))?;
-        request_json.push('\n');
+    fn call_once(
+        &mut self,
+        method: &str,
+        params: serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<serde_json::Value, ClientError> {
+        let id = self.send_request(method, params)?;
+        self.read_response(id, timeout)
+    }
 
-        self.writer.write_all(request_json.as_bytes())?;
+    /// Write a request, returning its id. A failed write sent nothing.
+    fn send_request(&mut self, method: &str, params: serde_json::Value) -> Result<u64, ClientError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.writer.write_all(request_line(id, method, params)?.as_bytes())?;
         self.writer.flush()?;
+        Ok(id)
+    }
 
-        // Read the response (one newline-delimited JSON line).
-        let mut line = String::new();
-        self.reader.read_line(&mut line)?;
+    /// Wait for the response to request `id`.
+    fn read_response(&mut self, id: u64, timeout: Option<Duration>) -> Result<serde_json::Value, ClientError> {
+        // Read lines until the response arrives, queueing notifications.
+        let deadline = timeout.map(|t| (Instant::now() + t, t));
+        let value = loop {
+            if let Some((deadline, timeout)) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(ClientError::Timeout(timeout));
+                }
+                self.reader.get_ref().set_read_timeout(Some(remaining))?;
+            }
+            let read = read_json_line(&mut self.reader);
+            if deadline.is_some() {
+                self.reader.get_ref().set_read_timeout(None)?;
+            }
+            let value = match read {
+                Ok(Some(value)) => value,
+                Ok(None) => {
+                    return Err(ClientError::Connection("Daemon closed the connection unexpectedly".into()))
+                }
+                Err(ClientError::Io(e))
+                    if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+                {
+                    return Err(ClientError::Timeout(deadline.map_or(Duration::ZERO, |(_, t)| t)));
+                }
+                Err(e) => return Err(e),
+            };
+            if value.get("id").is_none() {
+                if let Some(notif) = Notification::from_value(value) {
+                    self.pending.push_back(notif);
+                }
+                continue;
+            }
+            break value;
+        };
 
-        if line.is_empty() {
-            return Err(ClientError::Connection(
-                "Daemon closed the connection unexpectedly".into(),
-            ));
-        }
+        response_result(value, id)
+    }
 
-        let response: RpcResponse = serde_json::from_str(line.trim())
-            .map_err(|e| ClientError::Parse(format!("{e}: {line}")))?;
+    /// Start a `channel.watch` subscription and keep the client usable.
+    ///
+    /// Returns the subscription id. Events arrive via
+    /// [`next_notification`](GatewayClient::next_notification), tagged with
+    /// that id, interleaved with responses to further calls.
+    pub fn watch(&mut self, params: serde_json::Value) -> Result<String, ClientError> {
+        let ack = self.call("channel.watch", params)?;
+        ack.get("subscription")
+            .and_then(|v| v.as_str())
+            .map(String::from)
+            .ok_or_else(|| ClientError::Parse(format!("watch ack has no subscription id: {ack}")))
+    }
 
-        // Verify the response ID matches.
-        let resp_id = match &response.id {
-            serde_json::Value::Number(n) => n.as_u64().unwrap_or(0),
-            _ => 0,
-        };
-        if resp_id != id {
-            return Err(ClientError::IdMismatch {
-                expected: id,
-                got: response.id.to_string(),
-            });
-        }
+    /// Cancel a subscription started with [`watch`](GatewayClient::watch).
+    pub fn unwatch(&mut self, subscription: &str) -> Result<(), ClientError> {
+        self.call("channel.unwatch", serde_json::json!({"subscription": subscription}))?;
+        // Drop anything already queued for the cancelled subscription.
+        self.pending
+            .retain(|n| n.subscription.as_deref() != Some(subscription));
+        Ok(())
+    }
 
-        // Check for errors.
-        if let Some(err) = response.error {
-            return Err(ClientError::Gateway {
-                code: err.code,
-                message: err.message,
-            });
+    /// Return the next notification, blocking until one arrives.
+    ///
+    /// Returns `Ok(None)` when the daemon closes the connection.
+    pub fn next_notification(&mut self) -> Result<Option<Notification>, ClientError> {
+        if let Some(notif) = self.pending.pop_front() {
+            return Ok(Some(notif));
+        }
+        loop {
+            let Some(value) = read_json_line(&mut self.reader)? else {
+                return Ok(None);
+            };
+            // A response here belongs to no outstanding call; skip it.
+            if let Some(notif) = Notification::from_value(value) {
+                return Ok(Some(notif));
+            }
         }
-
-        Ok(response.result.unwrap_or(serde_json::Value::Null))
     }
 
     /// Send a subscription request and enter streaming mode.
@@ -194,54 +722,144 @@ impl GatewayClient {
     /// Sends the JSON-RPC request, reads the acknowledgment, then returns
     /// a `Subscription` that yields notification events until the stream ends.
     ///
-    /// Consumes `self` because the connection enters streaming mode and cannot
-    /// be used for normal request-response calls afterward.
+    /// Consumes `self` and yields only this subscription's events; the
+    /// iterator ends when the stream does. Use [`watch`](GatewayClient::watch)
+    /// to keep making calls on the same connection.
     pub fn subscribe(
         mut self,
         method: &str,
         params: serde_json::Value,
     ) -> Result<(serde_json::Value, Subscription), ClientError> {
-        let ack = self.call(method, params)?;
-        Ok((ack, Subscription { reader: self.reader }))
+        let ack = self.call(method, params.clone())?;
+        let subscription = ack
+            .get("subscription")
+            .and_then(|v| v.as_str())
+            .map(String::from);
+        let stream = Subscription {
+            client: self,
+            method: method.to_string(),
+            params,
+            subscription,
+            replay: Replay::default(),
+        };
+        Ok((ack, stream))
     }
 }
 
 /// An iterator over streaming JSON-RPC notifications from the daemon.
 ///
-/// Created by [`GatewayClient::subscribe`]. Reads newline-delimited JSON
-/// notifications until the server closes the connection (EOF).
+/// Created by [`GatewayClient::subscribe`]. Yields notification params until
+/// the daemon reports the end of the stream.
+///
+/// If the connection drops (the daemon restarted), the subscription is made
+/// again with the same params, retrying with backoff. Events that arrive
+/// meanwhile are only recovered with a named `cursor`; events replayed from
+/// the cursor below the last position yielded are skipped; one at that
+/// position may be yielded again.
 pub struct Subscription {
-    reader: BufReader<UnixStream>,
-}
-
-/// A parsed JSON-RPC notification from the daemon.
-#[derive(Deserialize)]
-struct RpcNotification {
-    #[allow(dead_code)]
-    jsonrpc: String,
-    #[allow(dead_code)]
+    client: GatewayClient,
     method: String,
     params: serde_json::Value,
+    subscription: Option<String>,
+    replay: Replay,
+}
+
+impl Subscription {
+    /// Reconnect and subscribe again, backing off while the daemon is down.
+    fn resubscribe(&mut self) -> Result<(), ClientError> {
+        let mut last_error = None;
+        for delay in RECONNECT_BACKOFF {
+            std::thread::sleep(delay);
+            let ack = self
+                .client
+                .reconnect()
+                .and_then(|()| self.client.call(&self.method, self.params.clone()));
+            match ack {
+                Ok(ack) => {
+                    self.subscription = ack.get("subscription").and_then(|v| v.as_str()).map(String::from);
+                    return Ok(());
+                }
+                Err(e) if e.is_disconnect() => last_error = Some(e),
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| ClientError::Connection("resubscribe failed".into())))
+    }
 }
 
 impl Iterator for Subscription {
     type Item = Result<serde_json::Value, ClientError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut line = String::new();
-        match self.reader.read_line(&mut line) {
-            Ok(0) => None, // EOF
-            Ok(_) => {
-                let trimmed = line.trim();
-                if trimmed.is_empty() {
-                    return self.next(); // skip blank lines
-                }
-                match serde_json::from_str::<RpcNotification>(trimmed) {
-                    Ok(notif) => Some(Ok(notif.params)),
-                    Err(e) => Some(Err(ClientError::Parse(format!("{e}: {line}")))),
-                }
+        loop {
+            let notif = match self.client.next_notification() {
+                Ok(Some(n)) => n,
+                // Connection lost without an end-of-stream notification.
+                Ok(None) | Err(ClientError::Io(_)) => match self.resubscribe() {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                },
+                Err(e) => return Some(Err(e)),
+            };
+            if self.subscription.is_some() && notif.subscription != self.subscription {
+                continue;
+            }
+            if notif.method == WATCH_END_METHOD {
+                return None;
             }
-            Err(e) => Some(Err(ClientError::Io(e))),
+            if self.replay.is_duplicate(&notif.params) {
+                continue;
+            }
+            return Some(Ok(notif.params));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn replay_skips_positions_already_delivered() {
+        let mut replay = Replay::default();
+        assert!(!replay.is_duplicate(&json!({"position": "7"})));
+        assert!(!replay.is_duplicate(&json!({"position": "8"})));
+        // Replayed from the cursor after a resubscribe. The last position
+        // may be delivered again: it cannot be told apart from a new event.
+        assert!(replay.is_duplicate(&json!({"position": "7"})));
+        assert!(!replay.is_duplicate(&json!({"position": "8"})));
+        assert!(!replay.is_duplicate(&json!({"position": "9"})));
+        // Without a named cursor there are no positions to compare.
+        assert!(!replay.is_duplicate(&json!({"text": "hi"})));
+    }
+
+    #[test]
+    fn replay_keeps_every_event_of_a_batch_sharing_a_position() {
+        // A Gmail or Drive batch: every event but the last carries the
+        // position the batch started from.
+        let mut replay = Replay::default();
+        for (id, position) in [("a", "40"), ("b", "40"), ("c", "40"), ("d", "52"), ("e", "52"), ("f", "60")] {
+            assert!(!replay.is_duplicate(&json!({"id": id, "position": position})), "{id} dropped");
+        }
+        // A replay after reconnecting drops what lies below the last position.
+        assert!(replay.is_duplicate(&json!({"id": "a", "position": "40"})));
+        assert!(replay.is_duplicate(&json!({"id": "d", "position": "52"})));
+        assert!(!replay.is_duplicate(&json!({"id": "g", "position": "60"})));
+    }
+
+    #[test]
+    fn hello_never_waits_forever() {
+        assert_eq!(hello_timeout(None), Some(HELLO_TIMEOUT));
+        assert_eq!(hello_timeout(Some(Duration::from_secs(2))), Some(Duration::from_secs(2)));
+        assert_eq!(hello_timeout(Some(Duration::from_secs(60))), Some(HELLO_TIMEOUT));
+    }
+
+    #[test]
+    fn idempotent_methods_exclude_writes() {
+        assert!(is_idempotent("channel.get_history"));
+        assert!(!is_idempotent("channel.send"));
+        assert!(!is_idempotent("channel.create_draft"));
+        assert!(!is_idempotent("channel.watch"));
+    }
+}
@@ -4,117 +4,26 @@
 //! This adapter is a thin HTTP client that calls the proxy's endpoints.
 
 use std::path::PathBuf;
+use std::time::Duration;
 
-use serde::Serialize;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::UnixStream;
+use carapace_uds_http::{encode, UdsClient};
+use tokio::sync::mpsc;
 use tracing::{debug, warn};
 
-/// Errors from the Google Docs adapter.
-#[derive(Debug, thiserror::Error)]
-pub enum AdapterError {
-    #[error("gdocs-proxy socket not found at {0}")]
-    SocketNotFound(PathBuf),
+/// Errors from the gdocs-proxy HTTP API.
+pub use carapace_uds_http::Error as AdapterError;
 
-    #[error("failed to connect to gdocs-proxy: {0}")]
-    Connect(std::io::Error),
-
-    #[error("HTTP error from gdocs-proxy: status {status}")]
-    HttpError { status: u16 },
-
-    #[error("failed to parse gdocs-proxy response: {0}")]
-    ParseError(String),
-
-    #[error("I/O error communicating with gdocs-proxy: {0}")]
-    Io(#[from] std::io::Error),
-}
-
-/// Health status of the Google Docs adapter.
-#[derive(Debug, Serialize)]
-pub struct HealthStatus {
-    pub proxy_reachable: bool,
-    pub token_valid: Option<bool>,
-    pub token_expires_in_secs: Option<u64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
-}
+pub use carapace_protocol::channel::ProxyHealth as HealthStatus;
+use carapace_protocol::gdocs::RangeValues;
 
 /// Google Docs adapter — proxies requests to the gdocs-proxy Unix socket.
 pub struct GDocsAdapter {
-    socket_path: PathBuf,
+    http: UdsClient,
 }
 
 impl GDocsAdapter {
     pub fn new(socket_path: PathBuf) -> Self {
-        Self { socket_path }
-    }
-
-    // ── HTTP primitives over Unix socket ────────────────────────────────────
-
-    async fn get(&self, path: &str) -> Result<serde_json::Value, AdapterError> {
-        if !self.socket_path.exists() {
-            return Err(AdapterError::SocketNotFound(self.socket_path.clone()));
-        }
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(AdapterError::Connect)?;
-
-        let request = format!(
-            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"
-        );
-        let (read_half, mut write_half) = tokio::io::split(stream);
-        write_half.write_all(request.as_bytes()).await?;
-        drop(write_half);
-
-        parse_response(read_half).await
-    }
-
-    async fn post(&self, path: &str, body: serde_json::Value) -> Result<serde_json::Value, AdapterError> {
-        if !self.socket_path.exists() {
-            return Err(AdapterError::SocketNotFound(self.socket_path.clone()));
-        }
-        let body_str = serde_json::to_string(&body)
-            .map_err(|e| AdapterError::ParseError(e.to_string()))?;
-
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(AdapterError::Connect)?;
-
-        let request = format!(
-            "POST {path} HTTP/1.1\r\nHost: localhost\r\n\
-             Content-Type: application/json\r\nContent-Length: {}\r\n\
-             Connection: close\r\n\r\n{body_str}",
-            body_str.len()
-        );
-        let (read_half, mut write_half) = tokio::io::split(stream);
-        write_half.write_all(request.as_bytes()).await?;
-        drop(write_half);
-
-        parse_response(read_half).await
-    }
-
-    async fn put(&self, path: &str, body: serde_json::Value) -> Result<serde_json::Value, AdapterError> {
-        if !self.socket_path.exists() {
-            return Err(AdapterError::SocketNotFound(self.socket_path.clone()));
-        }
-        let body_str = serde_json::to_string(&body)
-            .map_err(|e| AdapterError::ParseError(e.to_string()))?;
-
-        let stream = UnixStream::connect(&self.socket_path)
-            .await
-            .map_err(AdapterError::Connect)?;
-
-        let request = format!(
-            "PUT {path} HTTP/1.1\r\nHost: localhost\r\n\
-             Content-Type: application/json\r\nContent-Length: {}\r\n\
-             Connection: close\r\n\r\n{body_str}",
-            body_str.len()
-        );
-        let (read_half, mut write_half) = tokio::io::split(stream);
-        write_half.write_all(request.as_bytes()).await?;
-        drop(write_half);
-
-        parse_response(read_half).await
+        Self { http: UdsClient::new("gdocs-proxy", socket_path) }
     }
 
     // ── Public API methods ───────────────────────────────────────────────────
@@ -130,25 +39,47 @@ impl GDocsAdapter {
         let max = max.unwrap_or(20);
         let mut path = format!(
             "/search?q={}&max={max}&docs_only={docs_only}",
-            url_encode(query)
+            encode(query)
         );
         if let Some(pt) = page_token {
-            path.push_str(&format!("&page_token={}", url_encode(pt)));
+            path.push_str(&format!("&page_token={}", encode(pt)));
         }
         debug!(query, max, docs_only, "gdocs search");
-        self.get(&path).await
+        self.http.get(&path).await
     }
 
-    /// Read a Google Doc (structured content).
-    pub async fn read_document(&self, doc_id: &str) -> Result<serde_json::Value, AdapterError> {
-        debug!(doc_id, "gdocs read_document");
-        self.get(&format!("/doc/{}", url_encode(doc_id))).await
+    /// Read a Google Doc (structured content, or Markdown with `format`).
+    /// `render` selects formatted, formula or raw cell values for Sheets.
+    pub async fn read_document(
+        &self,
+        doc_id: &str,
+        format: Option<&str>,
+        comments: bool,
+        render: Option<&str>,
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(doc_id, ?format, comments, ?render, "gdocs read_document");
+        let mut query = Vec::new();
+        if let Some(f) = format {
+            query.push(format!("format={}", encode(f)));
+        }
+        if let Some(r) = render {
+            query.push(format!("render={}", encode(r)));
+        }
+        if comments {
+            query.push("comments=true".to_string());
+        }
+        let mut path = format!("/doc/{}", encode(doc_id));
+        if !query.is_empty() {
+            path.push('?');
+            path.push_str(&query.join("&"));
+        }
+        self.http.get(&path).await
     }
 
     /// Get file metadata.
     pub async fn get_file_info(&self, file_id: &str) -> Result<serde_json::Value, AdapterError> {
         debug!(file_id, "gdocs get_file_info");
-        self.get(&format!("/file/{}", url_encode(file_id))).await
+        self.http.get(&format!("/file/{}", encode(file_id))).await
     }
 
     /// Create a folder in Google Drive.
@@ -162,7 +93,7 @@ impl GDocsAdapter {
         if let Some(pid) = parent_id {
             payload["parent_id"] = serde_json::json!(pid);
         }
-        self.post("/folders", payload).await
+        self.http.post("/folders", &payload).await
     }
 
     /// Create a new Google Doc, optionally in a specific folder.
@@ -180,7 +111,7 @@ impl GDocsAdapter {
         if let Some(fid) = folder_id {
             payload["folder_id"] = serde_json::json!(fid);
         }
-        self.post("/docs", payload).await
+        self.http.post("/docs", &payload).await
     }
 
     /// Create a new Google Sheet, optionally in a folder, with optional initial data.
@@ -198,7 +129,7 @@ impl GDocsAdapter {
         if let Some(d) = data {
             payload["data"] = serde_json::json!(d);
         }
-        self.post("/sheets", payload).await
+        self.http.post("/sheets", &payload).await
     }
 
     /// Write values to a sheet range.
@@ -210,30 +141,115 @@ impl GDocsAdapter {
     ) -> Result<serde_json::Value, AdapterError> {
         debug!(spreadsheet_id, range, "gdocs update_sheet_values");
         let payload = serde_json::json!({"range": range, "values": values});
-        self.put(&format!("/sheets/{}/values", url_encode(spreadsheet_id)), payload).await
+        self.http.put(&format!("/sheets/{}/values", encode(spreadsheet_id)), &payload).await
+    }
+
+    /// Read one A1 range of a sheet.
+    pub async fn read_sheet_range(
+        &self,
+        spreadsheet_id: &str,
+        range: &str,
+        render: Option<&str>,
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(spreadsheet_id, range, ?render, "gdocs read_sheet_range");
+        let mut path = format!(
+            "/sheets/{}/values?range={}",
+            encode(spreadsheet_id),
+            encode(range)
+        );
+        if let Some(r) = render {
+            path.push_str(&format!("&render={}", encode(r)));
+        }
+        self.http.get(&path).await
     }
 
-    /// Create a Google Form.
+    /// Write several ranges in one request.
+    pub async fn batch_update_sheet_values(
+        &self,
+        spreadsheet_id: &str,
+        data: &[RangeValues],
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(spreadsheet_id, "gdocs batch_update_sheet_values");
+        let payload = serde_json::json!({"data": data});
+        self.http.post(&format!("/sheets/{}/values/batch", encode(spreadsheet_id)), &payload).await
+    }
+
+    /// Append rows after the last filled row of a sheet (the first sheet by default).
+    pub async fn append_rows(
+        &self,
+        spreadsheet_id: &str,
+        sheet: Option<&str>,
+        values: &Vec<Vec<String>>,
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(spreadsheet_id, ?sheet, rows = values.len(), "gdocs append_rows");
+        let mut payload = serde_json::json!({"values": values});
+        if let Some(s) = sheet {
+            payload["sheet"] = serde_json::json!(s);
+        }
+        self.http.post(&format!("/sheets/{}/append", encode(spreadsheet_id)), &payload).await
+    }
+
+    /// Create a Google Form, optionally inside `folder_id`.
     pub async fn create_form(
         &self,
         title: &str,
+        folder_id: Option<&str>,
     ) -> Result<serde_json::Value, AdapterError> {
         debug!(title, "gdocs create_form");
-        self.post("/forms", serde_json::json!({"title": title})).await
+        let mut payload = serde_json::json!({"title": title});
+        if let Some(fid) = folder_id {
+            payload["folder_id"] = serde_json::json!(fid);
+        }
+        self.http.post("/forms", &payload).await
+    }
+
+    /// Add, move or delete form questions. `operations` is passed through to
+    /// the proxy, which validates it.
+    pub async fn edit_form(
+        &self,
+        form_id: &str,
+        operations: &[serde_json::Value],
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(form_id, "gdocs edit_form");
+        let payload = serde_json::json!({"operations": operations});
+        self.http.post(&format!("/forms/{}/questions", encode(form_id)), &payload).await
+    }
+
+    /// Read form responses submitted after `since` (RFC 3339), or all of them.
+    pub async fn read_form_responses(
+        &self,
+        form_id: &str,
+        since: Option<&str>,
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(form_id, ?since, "gdocs read_form_responses");
+        let mut path = format!("/forms/{}/responses", encode(form_id));
+        if let Some(s) = since {
+            path.push_str(&format!("?since={}", encode(s)));
+        }
+        self.http.get(&path).await
     }
 
-    /// Copy a file.
+    /// Copy a file, optionally into a destination folder.
     pub async fn copy_file(
         &self,
         file_id: &str,
         title: Option<&str>,
+        folder_id: Option<&str>,
     ) -> Result<serde_json::Value, AdapterError> {
         debug!(file_id, "gdocs copy_file");
-        let mut path = format!("/docs/copy/{}", url_encode(file_id));
+        let mut query = Vec::new();
         if let Some(t) = title {
-            path.push_str(&format!("?title={}", url_encode(t)));
+            query.push(format!("title={}", encode(t)));
+        }
+        if let Some(f) = folder_id {
+            query.push(format!("folder_id={}", encode(f)));
         }
-        self.post(&path, serde_json::json!({})).await
+        let mut path = format!("/docs/copy/{}", encode(file_id));
+        if !query.is_empty() {
+            path.push('?');
+            path.push_str(&query.join("&"));
+        }
+        self.http.post(&path, &serde_json::json!({})).await
     }
 
     /// Append text to a document.
@@ -244,20 +260,132 @@ impl GDocsAdapter {
     ) -> Result<serde_json::Value, AdapterError> {
         debug!(doc_id, "gdocs append_text");
         let payload = serde_json::json!({"append_text": text});
-        self.put(&format!("/doc/{}", url_encode(doc_id)), payload).await
+        self.http.put(&format!("/doc/{}", encode(doc_id)), &payload).await
+    }
+
+    /// Apply structured edit operations to a document at a known revision.
+    pub async fn edit_document(
+        &self,
+        doc_id: &str,
+        revision_id: &str,
+        operations: &[serde_json::Value],
+    ) -> Result<serde_json::Value, AdapterError> {
+        debug!(doc_id, revision_id, "gdocs edit_document");
+        let payload = serde_json::json!({"revision_id": revision_id, "operations": operations});
+        self.http.post(&format!("/doc/{}/edit", encode(doc_id)), &payload).await
+    }
+
+    /// Files created, modified, shared or commented on since the Drive page
+    /// token `since` (or the proxy's persisted cursor when `None`). `folders`
+    /// restricts the result to files inside those folders.
+    pub async fn changes(
+        &self,
+        since: Option<&str>,
+        max: u32,
+        folders: &[String],
+    ) -> Result<serde_json::Value, AdapterError> {
+        let mut path = format!("/changes?max={max}");
+        if let Some(since) = since {
+            path.push_str(&format!("&since={}", encode(since)));
+        }
+        if !folders.is_empty() {
+            path.push_str(&format!("&folders={}", encode(&folders.join(","))));
+        }
+        debug!(since, max, folders = folders.len(), "gdocs changes");
+        self.http.get(&path).await
+    }
+
+    /// Watch Drive for changes.
+    ///
+    /// Spawns a background task that polls `/changes` every `poll_interval`,
+    /// following the Drive page token from `since` (or the proxy's own cursor
+    /// when `None`), and sends each change to the returned channel. Drop the
+    /// `WatchHandle` to stop polling.
+    ///
+    /// Each change carries a `position`: the page token that is safe to
+    /// acknowledge once that change has been handled. Only the last change of
+    /// a batch advances it, so acknowledging mid-batch never skips changes.
+    pub fn watch(
+        &self,
+        buffer_size: usize,
+        poll_interval: Duration,
+        since: Option<String>,
+        folders: Vec<String>,
+    ) -> (WatchHandle, mpsc::Receiver<serde_json::Value>) {
+        const PAGE: u32 = 50;
+
+        let (tx, rx) = mpsc::channel(buffer_size);
+        let (stop_tx, mut stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let http = self.http.clone();
+
+        let task = tokio::spawn(async move {
+            let adapter = GDocsAdapter { http };
+            // None means the proxy starts from its persisted cursor.
+            let mut cursor = since;
+            let mut interval = tokio::time::interval(poll_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => break,
+                    _ = interval.tick() => {}
+                }
+
+                // Drain the backlog a page at a time before waiting again.
+                loop {
+                    let result = match adapter.changes(cursor.as_deref(), PAGE, &folders).await {
+                        Ok(v) => v,
+                        Err(e) => {
+                            warn!(error = %e, "gdocs watch poll failed");
+                            break;
+                        }
+                    };
+
+                    if result.get("reset").and_then(|v| v.as_bool()) == Some(true) {
+                        warn!("drive page token rejected; changes since the last poll may be missed");
+                    }
+                    let previous = cursor.clone();
+                    if let Some(t) = result.get("page_token").and_then(|v| v.as_str()) {
+                        cursor = Some(t.to_string());
+                    }
+
+                    let changes = result
+                        .get("changes")
+                        .and_then(|c| c.as_array())
+                        .cloned()
+                        .unwrap_or_default();
+                    let more = result.get("more").and_then(|v| v.as_bool()) == Some(true);
+                    let count = changes.len();
+                    for (i, mut change) in changes.into_iter().enumerate() {
+                        let position = if i + 1 == count { &cursor } else { &previous };
+                        if let (Some(p), Some(obj)) = (position, change.as_object_mut()) {
+                            obj.insert("position".into(), serde_json::Value::String(p.clone()));
+                        }
+                        if tx.send(change).await.is_err() {
+                            return; // receiver dropped
+                        }
+                    }
+                    if !more {
+                        break;
+                    }
+                }
+            }
+        });
+
+        (WatchHandle { _task: task, _stop: stop_tx }, rx)
     }
 
     /// Health check.
     pub async fn health_check(&self) -> HealthStatus {
-        if !self.socket_path.exists() {
+        if !self.http.socket_path().exists() {
             return HealthStatus {
                 proxy_reachable: false,
                 token_valid: None,
                 token_expires_in_secs: None,
-                error: Some(format!("socket not found at {}", self.socket_path.display())),
+                error: Some(format!("socket not found at {}", self.http.socket_path().display())),
             };
         }
-        match self.get("/health").await {
+        match self.http.get("/health").await {
             Ok(resp) => {
                 let token_valid = resp
                     .pointer("/token/valid")
@@ -282,92 +410,10 @@ impl GDocsAdapter {
     }
 }
 
-// ---------------------------------------------------------------------------
-// HTTP/1.1 over Unix socket helpers (same pattern as gmail adapter)
-// ---------------------------------------------------------------------------
-
-async fn parse_response(
-    read_half: tokio::io::ReadHalf<UnixStream>,
-) -> Result<serde_json::Value, AdapterError> {
-    let mut buf = Vec::new();
-    let mut reader = tokio::io::BufReader::new(read_half);
-    reader.read_to_end(&mut buf).await?;
-
-    let raw = String::from_utf8_lossy(&buf);
-
-    let sep = raw.find("\r\n\r\n").ok_or_else(|| {
-        AdapterError::ParseError(format!(
-            "no HTTP header separator in response (got {} bytes: {:?})",
-            raw.len(),
-            &raw[..raw.len().min(200)]
-        ))
-    })?;
-    let headers = &raw[..sep];
-    let raw_body = &raw[sep + 4..];
-
-    let status: u16 = headers
-        .lines()
-        .next()
-        .and_then(|line| line.split_whitespace().nth(1))
-        .and_then(|code| code.parse().ok())
-        .unwrap_or(0);
-
-    let is_chunked = headers
-        .lines()
-        .any(|l| l.to_lowercase().contains("transfer-encoding: chunked"));
-
-    let body = if is_chunked {
-        decode_chunked(raw_body).map_err(AdapterError::ParseError)?
-    } else {
-        raw_body.to_string()
-    };
-
-    if !(200..300).contains(&status) {
-        warn!(status, body = %body, "gdocs-proxy returned error");
-        return Err(AdapterError::HttpError { status });
-    }
-
-    serde_json::from_str(&body)
-        .map_err(|e| AdapterError::ParseError(format!("{e}: {body}")))
-}
-
-fn decode_chunked(input: &str) -> Result<String, String> {
-    let mut result = String::new();
-    let mut remaining = input;
-
-    loop {
-        let size_end = remaining.find("\r\n")
-            .ok_or_else(|| format!("chunked: missing size line in: {:?}", &remaining[..remaining.len().min(50)]))?;
-        let size_str = remaining[..size_end].trim();
-        let size_str = size_str.split(';').next().unwrap_or(size_str).trim();
-        let chunk_size = usize::from_str_radix(size_str, 16)
-            .map_err(|e| format!("chunked: invalid chunk size {:?}: {e}", size_str))?;
-
-        remaining = &remaining[size_end + 2..];
-
-        if chunk_size == 0 {
-            break;
-        }
-
-        if remaining.len() < chunk_size {
-            return Err(format!("chunked: body too short for chunk size {chunk_size}"));
-        }
-        result.push_str(&remaining[..chunk_size]);
-        remaining = &remaining[chunk_size..];
-
-        if remaining.starts_with("\r\n") {
-            remaining = &remaining[2..];
-        }
-    }
-
-    Ok(result)
-}
-
-fn url_encode(s: &str) -> String {
-    s.chars()
-        .flat_map(|c| match c {
-            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => vec![c],
-            c => format!("%{:02X}", c as u32).chars().collect(),
-        })
-        .collect()
+/// Handle for a running Google Docs watch poll task.
+///
+/// Dropping the handle stops the polling task.
+pub struct WatchHandle {
+    _task: tokio::task::JoinHandle<()>,
+    _stop: tokio::sync::oneshot::Sender<()>,
 }
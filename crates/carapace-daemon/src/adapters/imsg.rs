@@ -7,7 +7,6 @@
 
 use std::path::PathBuf;
 
-use serde::Serialize;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
@@ -30,24 +29,12 @@ pub enum AdapterError {
 
     #[error("I/O error running imsg: {0}")]
     Io(#[from] std::io::Error),
-}
 
-/// Result of a successful send.
-#[derive(Debug, Serialize)]
-pub struct SendResult {
-    pub success: bool,
-    pub stdout: String,
+    #[error("iMessage attachments are not supported; send the text only")]
+    AttachmentsUnsupported,
 }
 
-/// Health status of the iMessage adapter.
-#[derive(Debug, Serialize)]
-pub struct HealthStatus {
-    pub binary_exists: bool,
-    pub db_exists: bool,
-    pub smoke_test_ok: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
-}
+pub use carapace_protocol::imsg::{HealthStatus, SendResult};
 
 /// iMessage adapter — wraps the `imsg` CLI binary.
 pub struct ImsgAdapter {
@@ -64,12 +51,18 @@ impl ImsgAdapter {
     }
 
     /// Send a message via `imsg send`.
+    ///
+    /// The send helper only takes a recipient and text, so a non-empty
+    /// `attachments` list is refused rather than silently dropped.
     pub async fn send(
         &self,
         recipient: &str,
         message: &str,
         attachments: &[String],
     ) -> Result<SendResult, AdapterError> {
+        if !attachments.is_empty() {
+            return Err(AdapterError::AttachmentsUnsupported);
+        }
         self.ensure_binary()?;
 
         // Run osascript as root via a narrow sudoers rule so it bypasses TCC.
@@ -220,18 +213,32 @@ impl ImsgAdapter {
 
     /// Query the current maximum message rowid from the Messages database.
     /// Used to pass `--since-rowid` to `imsg watch` so replayed subscriptions
-    /// don't re-deliver already-seen messages.
-    pub async fn max_message_rowid(&self) -> Option<u64> {
-        let output = tokio::process::Command::new("sqlite3")
+    /// don't re-deliver already-seen messages. An empty database gives 0.
+    pub async fn max_message_rowid(&self) -> Result<u64, AdapterError> {
+        if !self.db_path.exists() {
+            return Err(AdapterError::DatabaseNotFound(self.db_path.clone()));
+        }
+        let output = Command::new("sqlite3")
             .arg(&self.db_path)
             .arg("SELECT MAX(ROWID) FROM message")
             .output()
-            .await
-            .ok()?;
-        String::from_utf8_lossy(&output.stdout)
-            .trim()
-            .parse::<u64>()
-            .ok()
+            .await?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            return Err(AdapterError::CommandFailed {
+                stderr,
+                exit_code: output.status.code().unwrap_or(-1),
+            });
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        match stdout.trim() {
+            "" => Ok(0),
+            rowid => rowid
+                .parse()
+                .map_err(|e| AdapterError::OutputParse(format!("{e}: {rowid}"))),
+        }
     }
 
     /// Start watching for incoming messages via `imsg watch --json`.
@@ -322,7 +329,7 @@ fn parse_json_lines(output: &str) -> Result<serde_json::Value, AdapterError> {
         .lines()
         .map(|l| l.trim())
         .filter(|l| !l.is_empty())
-        .map(|l| serde_json::from_str(l))
+        .map(serde_json::from_str)
         .collect();
 
     match items {
@@ -331,18 +338,6 @@ fn parse_json_lines(output: &str) -> Result<serde_json::Value, AdapterError> {
     }
 }
 
-/// Encode a Rust string as an AppleScript string literal, safely handling
-/// embedded double-quotes by concatenating with AppleScript's `quote` constant.
-fn applescript_string(s: &str) -> String {
-    let parts: Vec<&str> = s.split('"').collect();
-    if parts.len() == 1 {
-        format!("\"{}\"", s)
-    } else {
-        let inner = parts.join("\" & quote & \"");
-        format!("\"{}\"", inner)
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,6 +355,20 @@ mod tests {
         assert!(err.to_string().contains("not found"));
     }
 
+    #[tokio::test]
+    async fn send_refuses_attachments() {
+        let adapter = ImsgAdapter::new(PathBuf::from("/nonexistent/imsg"), PathBuf::from("/nonexistent/chat.db"));
+        let err = adapter.send("+1111111111", "hi", &["/tmp/photo.jpg".into()]).await.unwrap_err();
+        assert!(matches!(err, AdapterError::AttachmentsUnsupported), "{err}");
+    }
+
+    #[tokio::test]
+    async fn max_rowid_reports_missing_database() {
+        let adapter = ImsgAdapter::new(PathBuf::from("/nonexistent/imsg"), PathBuf::from("/nonexistent/chat.db"));
+        let err = adapter.max_message_rowid().await.unwrap_err();
+        assert!(matches!(err, AdapterError::DatabaseNotFound(_)), "{err}");
+    }
+
     #[test]
     fn send_result_serializes() {
         let result = SendResult {
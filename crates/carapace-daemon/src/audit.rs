@@ -23,6 +23,7 @@ pub struct AuditLogger {
 pub enum AuditStatus {
     Allowed,
     Blocked,
+    #[allow(dead_code)]
     Error,
 }
 
@@ -69,7 +70,7 @@ impl AuditLogger {
             .await?;
 
         let mut line = serde_json::to_string(entry)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(std::io::Error::other)?;
         line.push('\n');
         file.write_all(line.as_bytes()).await?;
         Ok(())
@@ -5,20 +5,26 @@
 
 use std::time::Duration;
 
+use serde::de::DeserializeOwned;
 use serde_json::json;
 use tokio::sync::mpsc;
 use tracing::{info, warn};
 
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::collections::HashMap;
 
-use crate::adapters::gdocs::GDocsAdapter;
+use crate::adapters::gdocs::{AdapterError as GDocsError, GDocsAdapter};
 use crate::adapters::gmail::GmailAdapter;
-use crate::adapters::imsg::ImsgAdapter;
+use crate::adapters::imsg::{AdapterError, ImsgAdapter};
 use crate::allowlist::{Allowlist, AllowlistResult};
 use crate::audit::{self, AuditLogger};
+use crate::cursor_store::CursorStore;
 use crate::dead_letter::{DeadLetter, DeadLetterQueue};
-use crate::protocol::{self, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ProcessResult};
+use crate::protocol::{ErrorCode, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ProcessResult};
+use carapace_protocol::channel::{
+    AckParams, AckResult, AllowlistInfo, Capabilities, ChannelCapabilities, ListParams, Target,
+    UnwatchParams, WatchAck, WatchParams,
+};
+use carapace_protocol::{gdocs, gmail, imsg};
 
 /// A resolved channel adapter (one of the supported channels).
 enum Channel<'a> {
@@ -26,8 +32,12 @@ enum Channel<'a> {
     Gmail {
         adapter: &'a GmailAdapter,
         inbound: Option<&'a Allowlist>,
+        account: &'a str,
+    },
+    GDocs {
+        adapter: &'a GDocsAdapter,
+        account: &'a str,
     },
-    GDocs(&'a GDocsAdapter),
 }
 
 /// Shared channel state, borrowed from AppState.
@@ -37,8 +47,8 @@ pub struct ChannelContext<'a> {
     pub imsg_inbound: Option<&'a Allowlist>,
     pub audit_logger: &'a AuditLogger,
     pub dead_letter_queue: &'a DeadLetterQueue,
-    /// Shared across all iMessage watch subscriptions.
-    pub seen_message_ids: Arc<tokio::sync::Mutex<HashSet<u64>>>,
+    /// Named watch cursors, persisted across restarts.
+    pub cursor_store: &'a CursorStore,
     // Gmail — keyed by account name
     pub gmail_adapters: &'a HashMap<String, GmailAdapter>,
     pub gmail_inbound_allowlists: &'a HashMap<String, Allowlist>,
@@ -60,96 +70,80 @@ pub async fn handle_channel_request(
         "channel.list_chats" => ProcessResult::Response(handle_list_chats(req, ctx).await),
         "channel.get_history" => ProcessResult::Response(handle_get_history(req, ctx).await),
         "channel.status" => ProcessResult::Response(handle_status(req, ctx).await),
+        "channel.capabilities" => ProcessResult::Response(handle_capabilities(req, ctx)),
         "channel.watch" => handle_watch(req, ctx).await,
+        "channel.ack" => ProcessResult::Response(handle_ack(req, ctx).await),
+        "channel.unwatch" => handle_unwatch(req),
         // Gmail-specific methods
         "channel.search" => ProcessResult::Response(handle_search(req, ctx).await),
         "channel.create_draft" => ProcessResult::Response(handle_create_draft(req, ctx).await),
+        "channel.read_attachment" => ProcessResult::Response(handle_read_attachment(req, ctx).await),
         _ => {
             warn!(method = %req.method, "unknown channel method");
             ProcessResult::Response(JsonRpcResponse::error(
                 req.id.clone(),
-                protocol::METHOD_NOT_FOUND,
+                ErrorCode::MethodNotFound,
                 format!("Unknown method: {}", req.method),
             ))
         }
     }
 }
 
+/// Deserialize a request's params into their protocol type. Params that are
+/// not an object are read as `{}`, so they fall back to the defaults (imsg).
+fn parse_params<T: DeserializeOwned>(req: &JsonRpcRequest) -> Result<T, Box<JsonRpcResponse>> {
+    let empty = serde_json::Value::Object(serde_json::Map::new());
+    let params = if req.params.is_object() { &req.params } else { &empty };
+    T::deserialize(params).map_err(|e| {
+        Box::new(JsonRpcResponse::error(req.id.clone(), ErrorCode::InvalidParams, format!("Invalid params: {e}")))
+    })
+}
+
 /// Resolve which channel is being targeted and verify it's available.
 ///
-/// For Gmail, also resolves the account name from the `account` parameter,
-/// falling back to the configured default account.
+/// For Gmail and Google Docs, also resolves the account name from the
+/// `account` parameter, falling back to the configured default account.
 fn resolve_channel<'a>(
-    params: &serde_json::Value,
+    req: &JsonRpcRequest,
     ctx: &'a ChannelContext<'_>,
-) -> Result<Channel<'a>, JsonRpcResponse> {
-    let channel = params
-        .get("channel")
-        .and_then(|v| v.as_str())
-        .unwrap_or("imsg");
+) -> Result<Channel<'a>, Box<JsonRpcResponse>> {
+    let target: Target = parse_params(req)?;
+    let unavailable = |message: String| {
+        Box::new(JsonRpcResponse::error(req.id.clone(), ErrorCode::ChannelUnavailable, message))
+    };
 
-    match channel {
+    match target.channel.as_str() {
         "imsg" => ctx.imsg_adapter.map(Channel::Imsg).ok_or_else(|| {
-            JsonRpcResponse::error(
-                serde_json::Value::Null,
-                protocol::CHANNEL_UNAVAILABLE,
-                "iMessage channel is not configured or unavailable",
-            )
+            unavailable("iMessage channel is not configured or unavailable".into())
         }),
         "gmail" => {
             if ctx.gmail_adapters.is_empty() {
-                return Err(JsonRpcResponse::error(
-                    serde_json::Value::Null,
-                    protocol::CHANNEL_UNAVAILABLE,
-                    "Gmail channel is not configured or unavailable",
-                ));
+                return Err(unavailable("Gmail channel is not configured or unavailable".into()));
             }
-            let account = params
-                .get("account")
-                .and_then(|v| v.as_str())
-                .unwrap_or(ctx.gmail_default_account);
-
-            let adapter = ctx.gmail_adapters.get(account).ok_or_else(|| {
-                JsonRpcResponse::error(
-                    serde_json::Value::Null,
-                    protocol::CHANNEL_UNAVAILABLE,
-                    format!("Gmail account '{}' is not configured. Available: {:?}",
-                        account, ctx.gmail_adapters.keys().collect::<Vec<_>>()),
-                )
+            let account = target.account.as_deref().unwrap_or(ctx.gmail_default_account);
+
+            let (account, adapter) = ctx.gmail_adapters.get_key_value(account).ok_or_else(|| {
+                unavailable(format!("Gmail account '{}' is not configured. Available: {:?}",
+                    account, ctx.gmail_adapters.keys().collect::<Vec<_>>()))
             })?;
             let inbound = ctx.gmail_inbound_allowlists.get(account);
 
-            Ok(Channel::Gmail { adapter, inbound })
+            Ok(Channel::Gmail { adapter, inbound, account })
         }
         "gdocs" => {
             if ctx.gdocs_adapters.is_empty() {
-                return Err(JsonRpcResponse::error(
-                    serde_json::Value::Null,
-                    protocol::CHANNEL_UNAVAILABLE,
-                    "Google Docs channel is not configured or unavailable",
-                ));
+                return Err(unavailable("Google Docs channel is not configured or unavailable".into()));
             }
-            let account = params
-                .get("account")
-                .and_then(|v| v.as_str())
-                .unwrap_or(ctx.gdocs_default_account);
-
-            let adapter = ctx.gdocs_adapters.get(account).ok_or_else(|| {
-                JsonRpcResponse::error(
-                    serde_json::Value::Null,
-                    protocol::CHANNEL_UNAVAILABLE,
-                    format!("Google Docs account '{}' is not configured. Available: {:?}",
-                        account, ctx.gdocs_adapters.keys().collect::<Vec<_>>()),
-                )
+            let account = target.account.as_deref().unwrap_or(ctx.gdocs_default_account);
+
+            let (account, adapter) = ctx.gdocs_adapters.get_key_value(account).ok_or_else(|| {
+                unavailable(format!("Google Docs account '{}' is not configured. Available: {:?}",
+                    account, ctx.gdocs_adapters.keys().collect::<Vec<_>>()))
             })?;
 
-            Ok(Channel::GDocs(adapter))
+            Ok(Channel::GDocs { adapter, account })
         }
-        other => Err(JsonRpcResponse::error(
-            serde_json::Value::Null,
-            protocol::CHANNEL_UNAVAILABLE,
-            format!("Unknown channel: {other}"),
-        )),
+        other => Err(unavailable(format!("Unknown channel: {other}"))),
     }
 }
 
@@ -157,175 +151,42 @@ fn resolve_channel<'a>(
 
 async fn handle_send(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
     // Resolve channel first so channel-level rejections take priority over param validation.
-    let channel = match resolve_channel(&req.params, ctx) {
+    let channel = match resolve_channel(req, ctx) {
         Ok(c) => c,
-        Err(mut e) => { e.id = req.id.clone(); return e; }
+        Err(e) => return *e,
     };
 
     if let Channel::Gmail { .. } = channel {
         return JsonRpcResponse::error(
             req.id.clone(),
-            protocol::METHOD_NOT_FOUND,
+            ErrorCode::MethodNotFound,
             "Gmail channel does not support direct send. Use channel.create_draft instead.",
         );
     }
 
-    if let Channel::GDocs(adapter) = channel {
-        // GDocs uses channel.send for copy and append operations.
+    if let Channel::GDocs { adapter, .. } = channel {
+        // GDocs uses channel.send for its writes, selected by `action`.
         let action = req.params.get("action").and_then(|v| v.as_str()).unwrap_or("");
-        match action {
-            "copy" => {
-                let file_id = match req.params.get("file_id").and_then(|v| v.as_str()) {
-                    Some(f) => f,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"file_id\""),
-                };
-                let title = req.params.get("title").and_then(|v| v.as_str());
-                match adapter.copy_file(file_id, title).await {
-                    Ok(result) => {
-                        info!(file_id, "gdocs file copied");
-                        return JsonRpcResponse::success(req.id.clone(), result);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "gdocs copy failed");
-                        return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("copy failed: {e}"));
-                    }
-                }
-            }
-            "append" => {
-                let doc_id = match req.params.get("document_id").and_then(|v| v.as_str()) {
-                    Some(d) => d,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"document_id\""),
-                };
-                let text = match req.params.get("text").and_then(|v| v.as_str()) {
-                    Some(t) => t,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"text\""),
-                };
-                match adapter.append_text(doc_id, text).await {
-                    Ok(result) => {
-                        info!(doc_id, "gdocs text appended");
-                        return JsonRpcResponse::success(req.id.clone(), result);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "gdocs append failed");
-                        return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("append failed: {e}"));
-                    }
-                }
-            }
-            "create_folder" => {
-                let name = match req.params.get("name").and_then(|v| v.as_str()) {
-                    Some(n) => n,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"name\""),
-                };
-                let parent_id = req.params.get("parent_id").and_then(|v| v.as_str());
-                match adapter.create_folder(name, parent_id).await {
-                    Ok(result) => {
-                        info!(name, "gdocs folder created");
-                        return JsonRpcResponse::success(req.id.clone(), result);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "gdocs create_folder failed");
-                        return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("create_folder failed: {e}"));
-                    }
-                }
-            }
-            "create_sheet" => {
-                let name = match req.params.get("name").and_then(|v| v.as_str()) {
-                    Some(n) => n,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"name\""),
-                };
-                let folder_id = req.params.get("folder_id").and_then(|v| v.as_str());
-                let data: Option<Vec<Vec<String>>> = req.params.get("data")
-                    .and_then(|v| serde_json::from_value(v.clone()).ok());
-                match adapter.create_spreadsheet(name, folder_id, data.as_ref()).await {
-                    Ok(result) => {
-                        info!(name, "gdocs spreadsheet created");
-                        return JsonRpcResponse::success(req.id.clone(), result);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "gdocs create_spreadsheet failed");
-                        return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("create_sheet failed: {e}"));
-                    }
-                }
-            }
-            "update_sheet" => {
-                let spreadsheet_id = match req.params.get("spreadsheet_id").and_then(|v| v.as_str()) {
-                    Some(s) => s,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"spreadsheet_id\""),
-                };
-                let range = match req.params.get("range").and_then(|v| v.as_str()) {
-                    Some(r) => r,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"range\""),
-                };
-                let values: Vec<Vec<String>> = match req.params.get("values")
-                    .and_then(|v| serde_json::from_value(v.clone()).ok()) {
-                    Some(v) => v,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing or invalid param: \"values\" (expected array of arrays)"),
-                };
-                match adapter.update_sheet_values(spreadsheet_id, range, &values).await {
-                    Ok(result) => {
-                        info!(spreadsheet_id, range, "gdocs sheet values updated");
-                        return JsonRpcResponse::success(req.id.clone(), result);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "gdocs update_sheet failed");
-                        return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("update_sheet failed: {e}"));
-                    }
-                }
-            }
-            "create_form" => {
-                let title = match req.params.get("title").and_then(|v| v.as_str()) {
-                    Some(t) => t,
-                    None => return JsonRpcResponse::error(req.id.clone(), protocol::INVALID_PARAMS, "Missing required param: \"title\""),
-                };
-                match adapter.create_form(title).await {
-                    Ok(result) => {
-                        info!(title, "gdocs form created");
-                        return JsonRpcResponse::success(req.id.clone(), result);
-                    }
-                    Err(e) => {
-                        warn!(error = %e, "gdocs create_form failed");
-                        return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("create_form failed: {e}"));
-                    }
-                }
-            }
-            _ => {
-                return JsonRpcResponse::error(
-                    req.id.clone(),
-                    protocol::METHOD_NOT_FOUND,
-                    "Google Docs channel.send requires an 'action' param: 'copy', 'append', 'create_folder', 'create_sheet', 'update_sheet', or 'create_form'",
-                );
-            }
-        }
-    }
-
-    let recipient = match req.params.get("recipient").and_then(|v| v.as_str()) {
-        Some(r) => r,
-        None => {
+        if !GDOCS_ACTIONS.contains(&action) {
             return JsonRpcResponse::error(
                 req.id.clone(),
-                protocol::INVALID_PARAMS,
-                "Missing required param: \"recipient\"",
+                ErrorCode::MethodNotFound,
+                "Google Docs channel.send requires an 'action' param: 'copy', 'append', 'edit', 'create_folder', 'create_sheet', 'update_sheet', 'append_rows', 'create_form', or 'edit_form'",
             );
         }
-    };
+        let action: gdocs::Action = match parse_params(req) {
+            Ok(a) => a,
+            Err(e) => return *e,
+        };
+        return handle_gdocs_action(req, adapter, action).await;
+    }
 
-    let message = match req.params.get("message").and_then(|v| v.as_str()) {
-        Some(m) => m,
-        None => {
-            return JsonRpcResponse::error(
-                req.id.clone(),
-                protocol::INVALID_PARAMS,
-                "Missing required param: \"message\"",
-            );
-        }
+    let params: imsg::SendParams = match parse_params(req) {
+        Ok(p) => p,
+        Err(e) => return *e,
     };
-
-    let attachments: Vec<String> = req
-        .params
-        .get("attachments")
-        .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-        .unwrap_or_default();
+    let recipient = params.recipient.as_str();
+    let message = params.message.as_str();
 
     match channel {
         Channel::Imsg(adapter) => {
@@ -340,34 +201,165 @@ async fn handle_send(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcR
                             req.params.clone(), reason.clone(),
                         ))
                         .await;
-                    return JsonRpcResponse::error(req.id.clone(), protocol::NOT_IN_ALLOWLIST, reason);
+                    return JsonRpcResponse::error(req.id.clone(), ErrorCode::NotInAllowlist, reason);
                 }
             }
-            match adapter.send(recipient, message, &attachments).await {
+            match adapter.send(recipient, message, &params.attachments).await {
                 Ok(result) => {
                     info!(recipient, "message sent via imsg");
                     JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&result).unwrap())
                 }
+                Err(e @ AdapterError::AttachmentsUnsupported) => {
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InvalidParams, e.to_string())
+                }
                 Err(e) => {
                     warn!(error = %e, "imsg send failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::SEND_FAILED, format!("Send failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::SendFailed, format!("Send failed: {e}"))
                 }
             }
         }
         Channel::Gmail { .. } => unreachable!("Gmail send blocked above"),
-        Channel::GDocs(_) => unreachable!("GDocs send handled above"),
+        Channel::GDocs { .. } => unreachable!("GDocs send handled above"),
+    }
+}
+
+/// The `action` values of a Google Docs `channel.send`.
+const GDOCS_ACTIONS: &[&str] = &[
+    "copy", "append", "edit", "create_folder", "create_sheet", "update_sheet", "append_rows", "create_form", "edit_form",
+];
+
+/// Perform one Google Docs write.
+async fn handle_gdocs_action(req: &JsonRpcRequest, adapter: &GDocsAdapter, action: gdocs::Action) -> JsonRpcResponse {
+    let invalid = |message: &str| JsonRpcResponse::error(req.id.clone(), ErrorCode::InvalidParams, message);
+    let failed = |action: &str, e: GDocsError| {
+        warn!(error = %e, action, "gdocs send failed");
+        // The proxy's write policy answers 403 and a stale revision 409.
+        let code = match e {
+            GDocsError::Status { status: 403, .. } => ErrorCode::WriteDenied,
+            GDocsError::Status { status: 409, .. } => ErrorCode::Conflict,
+            _ => ErrorCode::InternalError,
+        };
+        JsonRpcResponse::error(req.id.clone(), code, format!("{action} failed: {e}"))
+    };
+
+    match action {
+        gdocs::Action::Copy { file_id, title, folder_id } => {
+            match adapter.copy_file(&file_id, title.as_deref(), folder_id.as_deref()).await {
+                Ok(result) => {
+                    info!(file_id, "gdocs file copied");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("copy", e),
+            }
+        }
+        gdocs::Action::Append { document_id, text } => {
+            match adapter.append_text(&document_id, &text).await {
+                Ok(result) => {
+                    info!(document_id, "gdocs text appended");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("append", e),
+            }
+        }
+        gdocs::Action::Edit { document_id, revision_id, operations } => {
+            if operations.is_empty() {
+                return invalid("Invalid param: \"operations\" (expected non-empty array)");
+            }
+            match adapter.edit_document(&document_id, &revision_id, &operations).await {
+                Ok(result) => {
+                    info!(document_id, "gdocs document edited");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("edit", e),
+            }
+        }
+        gdocs::Action::CreateFolder { name, parent_id } => {
+            match adapter.create_folder(&name, parent_id.as_deref()).await {
+                Ok(result) => {
+                    info!(name, "gdocs folder created");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("create_folder", e),
+            }
+        }
+        gdocs::Action::CreateSheet { name, folder_id, data } => {
+            match adapter.create_spreadsheet(&name, folder_id.as_deref(), data.as_ref()).await {
+                Ok(result) => {
+                    info!(name, "gdocs spreadsheet created");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("create_sheet", e),
+            }
+        }
+        gdocs::Action::UpdateSheet { spreadsheet_id, range, values, data } => {
+            // Several ranges at once: `data: [{"range", "values"}, ...]`.
+            let result = match (data, range, values) {
+                (Some(data), _, _) => {
+                    if data.is_empty() {
+                        return invalid("Invalid param: \"data\" (expected non-empty array of {range, values})");
+                    }
+                    adapter.batch_update_sheet_values(&spreadsheet_id, &data).await
+                }
+                (None, Some(range), Some(values)) => {
+                    adapter.update_sheet_values(&spreadsheet_id, &range, &values).await
+                }
+                (None, _, _) => {
+                    return invalid("Missing required params: \"range\" and \"values\" (or \"data\" for several ranges)");
+                }
+            };
+            match result {
+                Ok(result) => {
+                    info!(spreadsheet_id, "gdocs sheet values updated");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("update_sheet", e),
+            }
+        }
+        gdocs::Action::AppendRows { spreadsheet_id, sheet, values } => {
+            match adapter.append_rows(&spreadsheet_id, sheet.as_deref(), &values).await {
+                Ok(result) => {
+                    info!(spreadsheet_id, rows = values.len(), "gdocs sheet rows appended");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("append_rows", e),
+            }
+        }
+        gdocs::Action::EditForm { form_id, operations } => {
+            if operations.is_empty() {
+                return invalid("Invalid param: \"operations\" (expected non-empty array)");
+            }
+            match adapter.edit_form(&form_id, &operations).await {
+                Ok(result) => {
+                    info!(form_id, "gdocs form questions edited");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("edit_form", e),
+            }
+        }
+        gdocs::Action::CreateForm { title, folder_id } => {
+            match adapter.create_form(&title, folder_id.as_deref()).await {
+                Ok(result) => {
+                    info!(title, "gdocs form created");
+                    JsonRpcResponse::success(req.id.clone(), result)
+                }
+                Err(e) => failed("create_form", e),
+            }
+        }
     }
 }
 
 // ── channel.list_chats ──────────────────────────────────────────────────────
 
 async fn handle_list_chats(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
-    let channel = match resolve_channel(&req.params, ctx) {
+    let channel = match resolve_channel(req, ctx) {
         Ok(c) => c,
-        Err(mut e) => { e.id = req.id.clone(); return e; }
+        Err(e) => return *e,
     };
 
-    let limit = req.params.get("limit").and_then(|v| v.as_u64()).map(|n| n as u32);
+    let ListParams { limit } = match parse_params(req) {
+        Ok(p) => p,
+        Err(e) => return *e,
+    };
 
     match channel {
         Channel::Imsg(adapter) => {
@@ -375,7 +367,7 @@ async fn handle_list_chats(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> Js
                 Ok(chats) => JsonRpcResponse::success(req.id.clone(), chats),
                 Err(e) => {
                     warn!(error = %e, "imsg list_chats failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("list_chats failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("list_chats failed: {e}"))
                 }
             }
         }
@@ -386,18 +378,18 @@ async fn handle_list_chats(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> Js
                 Ok(result) => JsonRpcResponse::success(req.id.clone(), result),
                 Err(e) => {
                     warn!(error = %e, "gmail list_chats (inbox search) failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("list_chats failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("list_chats failed: {e}"))
                 }
             }
         }
-        Channel::GDocs(adapter) => {
+        Channel::GDocs { adapter, .. } => {
             // For GDocs, list_chats returns recent files.
             let max = limit.unwrap_or(20);
             match adapter.search("", Some(max), false, None).await {
                 Ok(result) => JsonRpcResponse::success(req.id.clone(), result),
                 Err(e) => {
                     warn!(error = %e, "gdocs list_chats (file listing) failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("list_chats failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("list_chats failed: {e}"))
                 }
             }
         }
@@ -407,51 +399,75 @@ async fn handle_list_chats(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> Js
 // ── channel.get_history ─────────────────────────────────────────────────────
 
 async fn handle_get_history(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
-    let chat_id = match req.params.get("chat_id").and_then(|v| v.as_str()) {
-        Some(id) => id,
-        None => {
-            return JsonRpcResponse::error(
-                req.id.clone(), protocol::INVALID_PARAMS,
-                "Missing required param: \"chat_id\"",
-            );
-        }
+    // Every channel needs `chat_id`; reject a request without it before the
+    // channel is resolved.
+    let history: imsg::HistoryParams = match parse_params(req) {
+        Ok(p) => p,
+        Err(e) => return *e,
     };
 
-    let channel = match resolve_channel(&req.params, ctx) {
+    let channel = match resolve_channel(req, ctx) {
         Ok(c) => c,
-        Err(mut e) => { e.id = req.id.clone(); return e; }
+        Err(e) => return *e,
     };
 
-    let limit = req.params.get("limit").and_then(|v| v.as_u64()).map(|n| n as u32);
-    let before = req.params.get("before").and_then(|v| v.as_str());
-
     match channel {
         Channel::Imsg(adapter) => {
-            match adapter.get_history(chat_id, limit, before).await {
+            match adapter.get_history(&history.chat_id, history.limit, history.before.as_deref()).await {
                 Ok(history) => JsonRpcResponse::success(req.id.clone(), history),
                 Err(e) => {
                     warn!(error = %e, "imsg get_history failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("get_history failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("get_history failed: {e}"))
                 }
             }
         }
         Channel::Gmail { adapter, .. } => {
             // For Gmail, chat_id is the thread ID.
-            match adapter.get_thread(chat_id).await {
+            let params: gmail::ThreadParams = match parse_params(req) {
+                Ok(p) => p,
+                Err(e) => return *e,
+            };
+            match adapter.get_thread(&params.thread_id).await {
                 Ok(thread) => JsonRpcResponse::success(req.id.clone(), thread),
                 Err(e) => {
                     warn!(error = %e, "gmail get_history (get_thread) failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("get_history failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("get_history failed: {e}"))
                 }
             }
         }
-        Channel::GDocs(adapter) => {
-            // For GDocs, chat_id is the document ID — read structured content.
-            match adapter.read_document(chat_id).await {
+        Channel::GDocs { adapter, .. } => {
+            // For GDocs, chat_id is the document ID — read structured content,
+            // a single sheet range when `range` is given, or form responses
+            // submitted after `since`.
+            let params: gdocs::ReadParams = match parse_params(req) {
+                Ok(p) => p,
+                Err(e) => return *e,
+            };
+            let file_id = params.file_id.as_str();
+            let render = params.render.as_deref();
+            if let Some(since) = params.since.as_deref() {
+                return match adapter.read_form_responses(file_id, Some(since)).await {
+                    Ok(form) => JsonRpcResponse::success(req.id.clone(), form),
+                    Err(e) => {
+                        warn!(error = %e, "gdocs read_form_responses failed");
+                        JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("get_history failed: {e}"))
+                    }
+                };
+            }
+            if let Some(range) = params.range.as_deref() {
+                return match adapter.read_sheet_range(file_id, range, render).await {
+                    Ok(values) => JsonRpcResponse::success(req.id.clone(), values),
+                    Err(e) => {
+                        warn!(error = %e, "gdocs read_sheet_range failed");
+                        JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("get_history failed: {e}"))
+                    }
+                };
+            }
+            match adapter.read_document(file_id, params.format.as_deref(), params.comments, render).await {
                 Ok(doc) => JsonRpcResponse::success(req.id.clone(), doc),
                 Err(e) => {
                     warn!(error = %e, "gdocs read_document failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("get_history failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("get_history failed: {e}"))
                 }
             }
         }
@@ -461,9 +477,12 @@ async fn handle_get_history(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> J
 // ── channel.status ──────────────────────────────────────────────────────────
 
 async fn handle_status(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
-    let channel = req.params.get("channel").and_then(|v| v.as_str()).unwrap_or("imsg");
+    let target: Target = match parse_params(req) {
+        Ok(t) => t,
+        Err(e) => return *e,
+    };
 
-    match channel {
+    match target.channel.as_str() {
         "imsg" => {
             let (configured, health) = if let Some(adapter) = ctx.imsg_adapter {
                 (true, Some(adapter.health_check().await))
@@ -471,25 +490,17 @@ async fn handle_status(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRp
                 (false, None)
             };
 
-            let outbound_info = ctx.imsg_outbound.map(|al| {
-                json!({ "mode": al.mode_str(), "entries": al.entry_count() })
-            });
-            let inbound_info = ctx.imsg_inbound.map(|al| {
-                json!({ "mode": al.mode_str(), "entries": al.entry_count() })
-            });
-
-            JsonRpcResponse::success(req.id.clone(), json!({
-                "channel": "imsg",
-                "configured": configured,
-                "health": health.map(|h| serde_json::to_value(&h).unwrap()),
-                "outbound": outbound_info,
-                "inbound": inbound_info,
-            }))
+            let status = imsg::Status {
+                channel: "imsg".into(),
+                configured,
+                health,
+                outbound: ctx.imsg_outbound.map(allowlist_info),
+                inbound: ctx.imsg_inbound.map(allowlist_info),
+            };
+            JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&status).unwrap())
         }
         "gmail" => {
-            let account = req.params.get("account")
-                .and_then(|v| v.as_str())
-                .unwrap_or(ctx.gmail_default_account);
+            let account = target.account.as_deref().unwrap_or(ctx.gmail_default_account);
 
             let (configured, health) = if let Some(adapter) = ctx.gmail_adapters.get(account) {
                 (true, Some(adapter.health_check().await))
@@ -498,38 +509,33 @@ async fn handle_status(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRp
             } else {
                 return JsonRpcResponse::error(
                     req.id.clone(),
-                    protocol::CHANNEL_UNAVAILABLE,
+                    ErrorCode::ChannelUnavailable,
                     format!("Gmail account '{}' is not configured. Available: {:?}",
                         account, ctx.gmail_adapters.keys().collect::<Vec<_>>()),
                 );
             };
 
-            let inbound_info = ctx.gmail_inbound_allowlists.get(account).map(|al| {
-                json!({ "mode": al.mode_str(), "entries": al.entry_count() })
-            });
-
-            JsonRpcResponse::success(req.id.clone(), json!({
-                "channel": "gmail",
-                "account": account,
-                "configured": configured,
-                "health": health.map(|h| serde_json::to_value(&h).unwrap()),
-                "inbound": inbound_info,
-                "accounts": ctx.gmail_adapters.keys().collect::<Vec<_>>(),
-            }))
+            let status = gmail::Status {
+                channel: "gmail".into(),
+                account: account.to_string(),
+                configured,
+                health,
+                inbound: ctx.gmail_inbound_allowlists.get(account).map(allowlist_info),
+                accounts: ctx.gmail_adapters.keys().cloned().collect(),
+            };
+            JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&status).unwrap())
         }
         "gdocs" => {
-            let account = req.params.get("account")
-                .and_then(|v| v.as_str())
-                .unwrap_or(ctx.gdocs_default_account);
+            let account = target.account.as_deref().unwrap_or(ctx.gdocs_default_account);
 
             // If a file_id was passed, return file info instead of health.
-            if let Some(file_id) = req.params.get("file_id").and_then(|v| v.as_str()) {
+            if let Ok(gdocs::FileInfoParams { file_id }) = parse_params(req) {
                 if let Some(adapter) = ctx.gdocs_adapters.get(account) {
-                    match adapter.get_file_info(file_id).await {
+                    match adapter.get_file_info(&file_id).await {
                         Ok(info) => return JsonRpcResponse::success(req.id.clone(), info),
                         Err(e) => {
                             warn!(error = %e, "gdocs get_file_info failed");
-                            return JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("get_file_info failed: {e}"));
+                            return JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("get_file_info failed: {e}"));
                         }
                     }
                 }
@@ -542,62 +548,144 @@ async fn handle_status(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRp
             } else {
                 return JsonRpcResponse::error(
                     req.id.clone(),
-                    protocol::CHANNEL_UNAVAILABLE,
+                    ErrorCode::ChannelUnavailable,
                     format!("Google Docs account '{}' is not configured. Available: {:?}",
                         account, ctx.gdocs_adapters.keys().collect::<Vec<_>>()),
                 );
             };
 
-            JsonRpcResponse::success(req.id.clone(), json!({
-                "channel": "gdocs",
-                "account": account,
-                "configured": configured,
-                "health": health.map(|h| serde_json::to_value(&h).unwrap()),
-                "accounts": ctx.gdocs_adapters.keys().collect::<Vec<_>>(),
-            }))
+            let status = gdocs::Status {
+                channel: "gdocs".into(),
+                account: account.to_string(),
+                configured,
+                health,
+                accounts: ctx.gdocs_adapters.keys().cloned().collect(),
+            };
+            JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&status).unwrap())
         }
         other => JsonRpcResponse::error(
             req.id.clone(),
-            protocol::CHANNEL_UNAVAILABLE,
+            ErrorCode::ChannelUnavailable,
             format!("Unknown channel: {other}"),
         ),
     }
 }
 
+fn allowlist_info(allowlist: &Allowlist) -> AllowlistInfo {
+    AllowlistInfo {
+        mode: allowlist.mode_str().to_string(),
+        entries: allowlist.entry_count(),
+    }
+}
+
+// ── channel.capabilities ───────────────────────────────────────────────────
+
+/// List the configured channels and their accounts, so clients (such as the
+/// MCP server) only offer what this gateway can serve.
+fn handle_capabilities(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
+    fn with_accounts<T>(channel: &str, map: &HashMap<String, T>, default: &str) -> ChannelCapabilities {
+        let mut accounts: Vec<String> = map.keys().cloned().collect();
+        accounts.sort_unstable();
+        ChannelCapabilities {
+            channel: channel.into(),
+            accounts,
+            default_account: Some(default.into()),
+        }
+    }
+
+    let mut channels = Vec::new();
+    if ctx.imsg_adapter.is_some() {
+        channels.push(ChannelCapabilities {
+            channel: "imsg".into(),
+            accounts: Vec::new(),
+            default_account: None,
+        });
+    }
+    if !ctx.gmail_adapters.is_empty() {
+        channels.push(with_accounts("gmail", ctx.gmail_adapters, ctx.gmail_default_account));
+    }
+    if !ctx.gdocs_adapters.is_empty() {
+        channels.push(with_accounts("gdocs", ctx.gdocs_adapters, ctx.gdocs_default_account));
+    }
+    let capabilities = Capabilities { channels };
+    JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&capabilities).unwrap())
+}
+
 // ── channel.watch ──────────────────────────────────────────────────────────
 
 async fn handle_watch(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> ProcessResult {
-    let channel = match resolve_channel(&req.params, ctx) {
+    let channel = match resolve_channel(req, ctx) {
         Ok(c) => c,
-        Err(mut e) => { e.id = req.id.clone(); return ProcessResult::Response(e); }
+        Err(e) => return ProcessResult::Response(*e),
     };
+    let WatchParams { cursor, folders } = match parse_params(req) {
+        Ok(p) => p,
+        Err(e) => return ProcessResult::Response(*e),
+    };
+    let cursor_name = cursor.as_deref();
 
     match channel {
         Channel::Imsg(adapter) => {
-            let since_rowid = adapter.max_message_rowid().await;
+            // A named cursor resumes after its last acknowledged ROWID; a new
+            // cursor (or an anonymous watch) starts from the current newest message.
+            let cursor_key = cursor_name.map(|name| CursorStore::key("imsg", None, name));
+            let stored = match cursor_key {
+                Some(ref key) => ctx.cursor_store.get(key).await.and_then(|p| p.parse::<u64>().ok()),
+                None => None,
+            };
+            let since_rowid = match (&cursor_key, stored) {
+                (_, Some(rowid)) => Some(rowid),
+                // A new cursor is saved before the watch starts, so messages
+                // that arrive before the first ack are replayed after a reconnect.
+                (Some(key), None) => {
+                    let rowid = match adapter.max_message_rowid().await {
+                        Ok(rowid) => rowid,
+                        Err(e) => {
+                            warn!(error = %e, "imsg watch failed to read the newest message");
+                            return ProcessResult::Response(JsonRpcResponse::error(
+                                req.id.clone(), ErrorCode::InternalError,
+                                format!("watch failed: {e}"),
+                            ));
+                        }
+                    };
+                    if let Err(e) = ctx.cursor_store.advance(key, &rowid.to_string()).await {
+                        return ProcessResult::Response(cursor_write_failed(&req.id, &e));
+                    }
+                    Some(rowid)
+                }
+                (None, None) => adapter.max_message_rowid().await.ok(),
+            };
+
             let (watch_handle, mut adapter_rx) = match adapter.watch(128, since_rowid) {
                 Ok(pair) => pair,
                 Err(e) => {
                     warn!(error = %e, "imsg watch failed to start");
                     return ProcessResult::Response(JsonRpcResponse::error(
-                        req.id.clone(), protocol::INTERNAL_ERROR,
+                        req.id.clone(), ErrorCode::InternalError,
                         format!("watch failed: {e}"),
                     ));
                 }
             };
 
             let inbound = ctx.imsg_inbound.cloned();
-            let seen = Arc::clone(&ctx.seen_message_ids);
+            let with_position = cursor_key.is_some();
             let (tx, rx) = mpsc::channel::<JsonRpcNotification>(128);
 
             tokio::spawn(async move {
                 let _handle = watch_handle;
-                while let Some(event) = adapter_rx.recv().await {
-                    // Deduplicate by numeric message ID.
-                    if let Some(id) = event.get("id").and_then(|v| v.as_u64()) {
-                        let mut seen_guard = seen.lock().await;
-                        if seen_guard.contains(&id) { continue; }
-                        seen_guard.insert(id);
+                let mut last_rowid = since_rowid.unwrap_or(0);
+                loop {
+                    // Stop as soon as the subscription is cancelled, not at the next event.
+                    let mut event = tokio::select! {
+                        event = adapter_rx.recv() => match event { Some(e) => e, None => break },
+                        _ = tx.closed() => break,
+                    };
+                    // ROWIDs only increase; anything at or below the last one
+                    // delivered on this subscription is a duplicate.
+                    let rowid = event.get("id").and_then(|v| v.as_u64());
+                    if let Some(id) = rowid {
+                        if id <= last_rowid { continue; }
+                        last_rowid = id;
                     }
                     // Inbound allowlist.
                     if let Some(ref al) = inbound {
@@ -605,93 +693,270 @@ async fn handle_watch(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> Process
                             .and_then(|v| v.as_str()).unwrap_or("");
                         if let AllowlistResult::Blocked { .. } = al.check(sender) { continue; }
                     }
+                    if let (true, Some(id), Some(obj)) = (with_position, rowid, event.as_object_mut()) {
+                        obj.insert("position".into(), json!(id.to_string()));
+                    }
                     let notif = JsonRpcNotification::new("channel.watch", event);
                     if tx.send(notif).await.is_err() { break; }
                 }
             });
 
-            let ack = JsonRpcResponse::success(req.id.clone(), json!({"subscribed": true}));
+            let ack = watch_ack(&req.id, cursor_name, since_rowid.map(|r| r.to_string()));
             ProcessResult::Subscription { ack, notifications: rx }
         }
 
-        Channel::Gmail { adapter, inbound: inbound_al } => {
+        Channel::Gmail { adapter, inbound: inbound_al, account } => {
+            // Resolve the starting history ID. A watch without a stored cursor
+            // starts at the proxy's current position, so it neither replays
+            // nor moves the proxy's own cursor. A new named cursor is persisted
+            // immediately so that mail arriving before the first ack is not lost.
+            let cursor_key = cursor_name.map(|name| CursorStore::key("gmail", Some(account), name));
+            let mut since = match cursor_key {
+                Some(ref key) => ctx.cursor_store.get(key).await,
+                None => None,
+            };
+            if since.is_none() {
+                let current = match adapter.changes(None, 0).await {
+                    Ok(v) => v.get("history_id").and_then(|h| h.as_str()).map(String::from),
+                    Err(e) => {
+                        warn!(error = %e, "gmail watch failed to read history cursor");
+                        return ProcessResult::Response(JsonRpcResponse::error(
+                            req.id.clone(), ErrorCode::InternalError,
+                            format!("watch failed: {e}"),
+                        ));
+                    }
+                };
+                if let (Some(key), Some(h)) = (&cursor_key, &current) {
+                    if let Err(e) = ctx.cursor_store.advance(key, h).await {
+                        return ProcessResult::Response(cursor_write_failed(&req.id, &e));
+                    }
+                }
+                since = current;
+            }
+
             let poll_interval = Duration::from_secs(30);
-            let (watch_handle, mut adapter_rx) = adapter.watch(128, poll_interval);
+            let (watch_handle, mut adapter_rx) =
+                adapter.watch(128, poll_interval, since.clone());
             let inbound = inbound_al.cloned();
+            let with_position = cursor_key.is_some();
             let (tx, rx) = mpsc::channel::<JsonRpcNotification>(128);
 
             tokio::spawn(async move {
                 let _handle = watch_handle;
-                while let Some(event) = adapter_rx.recv().await {
-                    // Inbound allowlist (filter by From address).
-                    if let Some(ref al) = inbound {
+                loop {
+                    // Stop as soon as the subscription is cancelled, not at the next event.
+                    let mut event = tokio::select! {
+                        event = adapter_rx.recv() => match event { Some(e) => e, None => break },
+                        _ = tx.closed() => break,
+                    };
+                    // Inbound allowlist (filter by From address). A reset
+                    // notice carries no sender and always goes through.
+                    let reset = event.get("reset").and_then(|v| v.as_bool()) == Some(true);
+                    if let (false, Some(al)) = (reset, &inbound) {
                         let sender = event.get("from").and_then(|v| v.as_str()).unwrap_or("");
                         if let AllowlistResult::Blocked { .. } = al.check(sender) { continue; }
                     }
+                    if !with_position {
+                        if let Some(obj) = event.as_object_mut() {
+                            obj.remove("position");
+                        }
+                    }
                     let notif = JsonRpcNotification::new("channel.watch", event);
                     if tx.send(notif).await.is_err() { break; }
                 }
             });
 
-            let ack = JsonRpcResponse::success(req.id.clone(), json!({"subscribed": true}));
+            let ack = watch_ack(&req.id, cursor_name, since);
             ProcessResult::Subscription { ack, notifications: rx }
         }
 
-        Channel::GDocs(_) => {
-            ProcessResult::Response(JsonRpcResponse::error(
-                req.id.clone(),
-                protocol::METHOD_NOT_FOUND,
-                "Google Docs channel does not support watch subscriptions",
-            ))
+        Channel::GDocs { adapter, account } => {
+            // Same cursor handling as Gmail, with Drive page tokens as positions.
+            let cursor_key = cursor_name.map(|name| CursorStore::key("gdocs", Some(account), name));
+            let mut since: Option<String> = None;
+            if let Some(ref key) = cursor_key {
+                since = ctx.cursor_store.get(key).await;
+                if since.is_none() {
+                    let current = match adapter.changes(None, 0, &[]).await {
+                        Ok(v) => v.get("page_token").and_then(|t| t.as_str()).map(String::from),
+                        Err(e) => {
+                            warn!(error = %e, "gdocs watch failed to read changes cursor");
+                            return ProcessResult::Response(JsonRpcResponse::error(
+                                req.id.clone(), ErrorCode::InternalError,
+                                format!("watch failed: {e}"),
+                            ));
+                        }
+                    };
+                    if let Some(ref t) = current {
+                        if let Err(e) = ctx.cursor_store.advance(key, t).await {
+                            return ProcessResult::Response(cursor_write_failed(&req.id, &e));
+                        }
+                    }
+                    since = current;
+                }
+            }
+
+            let poll_interval = Duration::from_secs(30);
+            // Optional folder restriction, in addition to the proxy's blocked folders.
+            let (watch_handle, mut adapter_rx) =
+                adapter.watch(128, poll_interval, since.clone(), folders);
+            let with_position = cursor_key.is_some();
+            let (tx, rx) = mpsc::channel::<JsonRpcNotification>(128);
+
+            tokio::spawn(async move {
+                let _handle = watch_handle;
+                loop {
+                    // Stop as soon as the subscription is cancelled, not at the next event.
+                    let mut event = tokio::select! {
+                        event = adapter_rx.recv() => match event { Some(e) => e, None => break },
+                        _ = tx.closed() => break,
+                    };
+                    if !with_position {
+                        if let Some(obj) = event.as_object_mut() {
+                            obj.remove("position");
+                        }
+                    }
+                    let notif = JsonRpcNotification::new("channel.watch", event);
+                    if tx.send(notif).await.is_err() { break; }
+                }
+            });
+
+            let ack = watch_ack(&req.id, cursor_name, since);
+            ProcessResult::Subscription { ack, notifications: rx }
+        }
+    }
+}
+
+/// The `channel.watch` acknowledgment; a named cursor reports where it resumes.
+fn watch_ack(id: &serde_json::Value, cursor_name: Option<&str>, position: Option<String>) -> JsonRpcResponse {
+    let ack = match (cursor_name, position) {
+        (Some(name), Some(position)) => WatchAck {
+            subscribed: true,
+            subscription: None,
+            cursor: Some(name.to_string()),
+            position: Some(position),
+        },
+        _ => WatchAck { subscribed: true, subscription: None, cursor: None, position: None },
+    };
+    JsonRpcResponse::success(id.clone(), serde_json::to_value(&ack).unwrap())
+}
+
+// ── channel.unwatch ────────────────────────────────────────────────────────
+
+/// Validate a `channel.unwatch` request; the connection loop cancels it.
+fn handle_unwatch(req: &JsonRpcRequest) -> ProcessResult {
+    let subscription = match parse_params(req) {
+        Ok(UnwatchParams { subscription }) if !subscription.is_empty() => subscription,
+        Ok(_) => {
+            return ProcessResult::Response(JsonRpcResponse::error(
+                req.id.clone(), ErrorCode::InvalidParams,
+                "Missing required param: \"subscription\"",
+            ));
         }
+        Err(e) => return ProcessResult::Response(*e),
+    };
+    ProcessResult::Unwatch { id: req.id.clone(), subscription }
+}
+
+// ── channel.ack ────────────────────────────────────────────────────────────
+
+/// Acknowledge delivery up to `position` on a named watch cursor.
+///
+/// The next `channel.watch` with the same cursor replays everything after
+/// the acknowledged position. Cursors only move forward.
+async fn handle_ack(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
+    let AckParams { cursor: name, position } = match parse_params(req) {
+        Ok(p) => p,
+        Err(e) => return *e,
+    };
+    if name.trim().is_empty() {
+        return JsonRpcResponse::error(
+            req.id.clone(), ErrorCode::InvalidParams,
+            "Missing required param: \"cursor\"",
+        );
     }
+
+    let channel = match resolve_channel(req, ctx) {
+        Ok(c) => c,
+        Err(e) => return *e,
+    };
+    // iMessage ROWIDs and Gmail history IDs are numbers; Drive page tokens
+    // are opaque strings.
+    let (key, numeric) = match channel {
+        Channel::Imsg(_) => (CursorStore::key("imsg", None, &name), true),
+        Channel::Gmail { account, .. } => (CursorStore::key("gmail", Some(account), &name), true),
+        Channel::GDocs { account, .. } => (CursorStore::key("gdocs", Some(account), &name), false),
+    };
+    if position.trim().is_empty() || (numeric && position.parse::<u64>().is_err()) {
+        return JsonRpcResponse::error(
+            req.id.clone(), ErrorCode::InvalidParams,
+            "Invalid param: \"position\" (expected the position from a watch notification)",
+        );
+    }
+
+    let current = match ctx.cursor_store.advance(&key, &position).await {
+        Ok(current) => current,
+        Err(e) => return cursor_write_failed(&req.id, &e),
+    };
+    info!(cursor = %key, position = %current, "watch cursor acknowledged");
+    let result = AckResult {
+        cursor: name,
+        position: current,
+    };
+    JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&result).unwrap())
+}
+
+/// The error for a cursor that could not be saved. The cursor has not moved,
+/// so the client should keep its position and retry.
+fn cursor_write_failed(id: &serde_json::Value, e: &std::io::Error) -> JsonRpcResponse {
+    warn!(error = %e, "watch cursor not saved");
+    JsonRpcResponse::error(id.clone(), ErrorCode::InternalError, format!("Failed to save watch cursor: {e}"))
 }
 
 // ── channel.search ─────────────────────────────────────────
 
 async fn handle_search(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
-    let channel = match resolve_channel(&req.params, ctx) {
+    let channel = match resolve_channel(req, ctx) {
         Ok(c) => c,
-        Err(mut e) => { e.id = req.id.clone(); return e; }
+        Err(e) => return *e,
     };
 
     match channel {
         Channel::Imsg(_) => JsonRpcResponse::error(
-            req.id.clone(), protocol::METHOD_NOT_FOUND,
+            req.id.clone(), ErrorCode::MethodNotFound,
             "channel.search is not supported on the imsg channel",
         ),
         Channel::Gmail { adapter, .. } => {
-            let query = match req.params.get("query").and_then(|v| v.as_str()) {
-                Some(q) if !q.trim().is_empty() => q,
-                _ => {
-                    return JsonRpcResponse::error(
-                        req.id.clone(), protocol::INVALID_PARAMS,
-                        "Missing required param: \"query\"",
-                    );
-                }
+            let params: gmail::SearchParams = match parse_params(req) {
+                Ok(p) => p,
+                Err(e) => return *e,
             };
-            let max = req.params.get("max").and_then(|v| v.as_u64()).map(|n| n as u32);
-            let page_token = req.params.get("page_token").and_then(|v| v.as_str());
+            if params.query.trim().is_empty() {
+                return JsonRpcResponse::error(
+                    req.id.clone(), ErrorCode::InvalidParams,
+                    "Missing required param: \"query\"",
+                );
+            }
 
-            match adapter.search(query, max, page_token).await {
+            match adapter.search(&params.query, params.max, params.page_token.as_deref()).await {
                 Ok(result) => JsonRpcResponse::success(req.id.clone(), result),
                 Err(e) => {
                     warn!(error = %e, "gmail search failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("search failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("search failed: {e}"))
                 }
             }
         }
-        Channel::GDocs(adapter) => {
-            let query = req.params.get("query").and_then(|v| v.as_str()).unwrap_or("");
-            let max = req.params.get("max").and_then(|v| v.as_u64()).map(|n| n as u32);
-            let docs_only = req.params.get("docs_only").and_then(|v| v.as_bool()).unwrap_or(false);
-            let page_token = req.params.get("page_token").and_then(|v| v.as_str());
+        Channel::GDocs { adapter, .. } => {
+            let params: gdocs::SearchParams = match parse_params(req) {
+                Ok(p) => p,
+                Err(e) => return *e,
+            };
 
-            match adapter.search(query, max, docs_only, page_token).await {
+            match adapter.search(&params.query, params.max, params.docs_only, params.page_token.as_deref()).await {
                 Ok(result) => JsonRpcResponse::success(req.id.clone(), result),
                 Err(e) => {
                     warn!(error = %e, "gdocs search failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("search failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("search failed: {e}"))
                 }
             }
         }
@@ -702,76 +967,113 @@ async fn handle_search(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRp
 
 async fn handle_create_draft(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
     // Resolve channel first — Gmail and GDocs have different required params.
-    let channel = match resolve_channel(&req.params, ctx) {
+    let channel = match resolve_channel(req, ctx) {
         Ok(c) => c,
-        Err(mut e) => { e.id = req.id.clone(); return e; }
+        Err(e) => return *e,
     };
 
     match channel {
         Channel::Imsg(_) => JsonRpcResponse::error(
-            req.id.clone(), protocol::METHOD_NOT_FOUND,
+            req.id.clone(), ErrorCode::MethodNotFound,
             "channel.create_draft is only supported on the gmail and gdocs channels",
         ),
         Channel::Gmail { adapter, .. } => {
-            let to = match req.params.get("to").and_then(|v| v.as_str()) {
-                Some(t) if !t.trim().is_empty() => t,
-                _ => {
-                    return JsonRpcResponse::error(
-                        req.id.clone(), protocol::INVALID_PARAMS,
-                        "Missing required param: \"to\"",
-                    );
-                }
+            let params: gmail::DraftParams = match parse_params(req) {
+                Ok(p) => p,
+                Err(e) => return *e,
             };
-            let subject = match req.params.get("subject").and_then(|v| v.as_str()) {
-                Some(s) if !s.trim().is_empty() => s,
-                _ => {
+            for (name, value) in [("to", &params.to), ("subject", &params.subject)] {
+                if value.trim().is_empty() {
                     return JsonRpcResponse::error(
-                        req.id.clone(), protocol::INVALID_PARAMS,
-                        "Missing required param: \"subject\"",
+                        req.id.clone(), ErrorCode::InvalidParams,
+                        format!("Missing required param: \"{name}\""),
                     );
                 }
-            };
-            let body = req.params.get("body").and_then(|v| v.as_str()).unwrap_or("");
-            let cc = req.params.get("cc").and_then(|v| v.as_str());
+            }
+            let (to, subject) = (params.to.as_str(), params.subject.as_str());
 
-            match adapter.create_draft(to, subject, body, cc).await {
+            match adapter.create_draft(to, subject, &params.body, params.cc.as_deref(), params.html_body.as_deref()).await {
                 Ok(result) => {
                     info!(to, subject, draft_id = %result.draft_id, "gmail draft created");
                     JsonRpcResponse::success(req.id.clone(), serde_json::to_value(&result).unwrap())
                 }
                 Err(e) => {
                     warn!(error = %e, "gmail create_draft failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("create_draft failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("create_draft failed: {e}"))
                 }
             }
         }
-        Channel::GDocs(adapter) => {
-            let title = match req.params.get("title").and_then(|v| v.as_str()) {
-                Some(t) if !t.trim().is_empty() => t,
-                _ => {
-                    return JsonRpcResponse::error(
-                        req.id.clone(), protocol::INVALID_PARAMS,
-                        "Missing required param: \"title\"",
-                    );
-                }
+        Channel::GDocs { adapter, .. } => {
+            let params: gdocs::CreateDocumentParams = match parse_params(req) {
+                Ok(p) => p,
+                Err(e) => return *e,
             };
-            let content = req.params.get("content").and_then(|v| v.as_str());
-            let folder_id = req.params.get("folder_id").and_then(|v| v.as_str());
+            if params.title.trim().is_empty() {
+                return JsonRpcResponse::error(
+                    req.id.clone(), ErrorCode::InvalidParams,
+                    "Missing required param: \"title\"",
+                );
+            }
+            let title = params.title.as_str();
 
-            match adapter.create_document(title, content, folder_id).await {
+            match adapter.create_document(title, params.content.as_deref(), params.folder_id.as_deref()).await {
                 Ok(result) => {
                     info!(title, "gdocs document created");
                     JsonRpcResponse::success(req.id.clone(), result)
                 }
                 Err(e) => {
                     warn!(error = %e, "gdocs create_document failed");
-                    JsonRpcResponse::error(req.id.clone(), protocol::INTERNAL_ERROR, format!("create_document failed: {e}"))
+                    JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("create_document failed: {e}"))
                 }
             }
         }
     }
 }
 
+// ── channel.read_attachment (Gmail-specific) ────────────────────────────────
+
+/// List a message's attachments, or read one when `attachment_id` is given.
+async fn handle_read_attachment(req: &JsonRpcRequest, ctx: &ChannelContext<'_>) -> JsonRpcResponse {
+    let channel = match resolve_channel(req, ctx) {
+        Ok(c) => c,
+        Err(e) => return *e,
+    };
+
+    let adapter = match channel {
+        Channel::Gmail { adapter, .. } => adapter,
+        _ => {
+            return JsonRpcResponse::error(
+                req.id.clone(), ErrorCode::MethodNotFound,
+                "channel.read_attachment is only supported on the gmail channel",
+            );
+        }
+    };
+
+    let params: gmail::AttachmentParams = match parse_params(req) {
+        Ok(p) => p,
+        Err(e) => return *e,
+    };
+    if params.message_id.trim().is_empty() {
+        return JsonRpcResponse::error(
+            req.id.clone(), ErrorCode::InvalidParams,
+            "Missing required param: \"message_id\"",
+        );
+    }
+
+    let result = match params.attachment_id.as_deref() {
+        Some(attachment_id) => adapter.read_attachment(&params.message_id, attachment_id).await,
+        None => adapter.list_attachments(&params.message_id).await,
+    };
+
+    match result {
+        Ok(result) => JsonRpcResponse::success(req.id.clone(), result),
+        Err(e) => {
+            warn!(error = %e, "gmail read_attachment failed");
+            JsonRpcResponse::error(req.id.clone(), ErrorCode::InternalError, format!("read_attachment failed: {e}"))
+        }
+    }
+}
+
 // ── Tests ───────────────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -803,11 +1105,20 @@ mod tests {
         match pr {
             ProcessResult::Response(r) => r,
             ProcessResult::Subscription { .. } => panic!("expected Response, got Subscription"),
+            ProcessResult::Unwatch { .. } => panic!("expected Response, got Unwatch"),
+            ProcessResult::Hello { .. } => panic!("expected Response, got Hello"),
         }
     }
 
-    fn noop_seen() -> Arc<tokio::sync::Mutex<HashSet<u64>>> {
-        Arc::new(tokio::sync::Mutex::new(HashSet::new()))
+    fn noop_cursor_store() -> &'static CursorStore {
+        // Shared by every test in this module; the directory is private to this run.
+        static STORE: std::sync::OnceLock<(tempfile::TempDir, CursorStore)> = std::sync::OnceLock::new();
+        let (_, store) = STORE.get_or_init(|| {
+            let dir = tempfile::tempdir().unwrap();
+            let store = CursorStore::load(dir.path().join("cursors.json"));
+            (dir, store)
+        });
+        store
     }
 
     fn empty_gmail_adapters() -> HashMap<String, GmailAdapter> {
@@ -835,7 +1146,7 @@ mod tests {
             imsg_inbound: None,
             audit_logger: audit,
             dead_letter_queue: dlq,
-            seen_message_ids: noop_seen(),
+            cursor_store: noop_cursor_store(),
             gmail_adapters,
             gmail_inbound_allowlists: gmail_allowlists,
             gmail_default_account: "default",
@@ -856,7 +1167,7 @@ mod tests {
         let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
         let req = make_req("channel.send", json!({"message": "hello"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::CHANNEL_UNAVAILABLE);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::ChannelUnavailable.code());
     }
 
     #[tokio::test]
@@ -869,7 +1180,7 @@ mod tests {
         let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
         let req = make_req("channel.send", json!({"recipient": "+1234567890"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::CHANNEL_UNAVAILABLE);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::ChannelUnavailable.code());
     }
 
     #[tokio::test]
@@ -882,7 +1193,7 @@ mod tests {
         let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
         let req = make_req("channel.send", json!({"recipient": "+1234567890", "message": "hello"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::CHANNEL_UNAVAILABLE);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::ChannelUnavailable.code());
     }
 
     #[tokio::test]
@@ -906,7 +1217,7 @@ mod tests {
             imsg_inbound: None,
             audit_logger: &audit,
             dead_letter_queue: &dlq,
-            seen_message_ids: noop_seen(),
+            cursor_store: noop_cursor_store(),
             gmail_adapters: &ga,
             gmail_inbound_allowlists: &gal,
             gmail_default_account: "default",
@@ -915,7 +1226,37 @@ mod tests {
         };
         let req = make_req("channel.send", json!({"recipient": "+9999999999", "message": "hello"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::NOT_IN_ALLOWLIST);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::NotInAllowlist.code());
+    }
+
+    #[tokio::test]
+    async fn send_with_attachments_rejected() {
+        let adapter = ImsgAdapter::new(
+            PathBuf::from("/nonexistent/imsg"),
+            PathBuf::from("/nonexistent/chat.db"),
+        );
+        let outbound = Allowlist::new(&DirectionConfig {
+            mode: AllowlistMode::Allowlist,
+            allowlist: vec!["+1111111111".into()],
+        });
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ga = empty_gmail_adapters();
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let ctx = ChannelContext {
+            imsg_adapter: Some(&adapter),
+            imsg_outbound: Some(&outbound),
+            ..empty_ctx(&audit, &dlq, &ga, &gal, &gda)
+        };
+        let req = make_req(
+            "channel.send",
+            json!({"recipient": "+1111111111", "message": "hello", "attachments": ["/tmp/photo.jpg"]}),
+        );
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        let error = resp.error.unwrap();
+        assert_eq!(error.code, ErrorCode::InvalidParams.code());
+        assert!(error.message.contains("attachments"), "{}", error.message);
     }
 
     #[tokio::test]
@@ -928,7 +1269,7 @@ mod tests {
         let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
         let req = make_req("channel.send", json!({"channel": "signal", "recipient": "+1234567890", "message": "hi"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::CHANNEL_UNAVAILABLE);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::ChannelUnavailable.code());
     }
 
     #[tokio::test]
@@ -941,7 +1282,7 @@ mod tests {
         let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
         let req = make_req("channel.unknown", json!({}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::METHOD_NOT_FOUND);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound.code());
     }
 
     #[tokio::test]
@@ -954,7 +1295,7 @@ mod tests {
         let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
         let req = make_req("channel.get_history", json!({}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::INVALID_PARAMS);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
     }
 
     #[tokio::test]
@@ -972,6 +1313,25 @@ mod tests {
         assert_eq!(result["configured"], false);
     }
 
+    #[tokio::test]
+    async fn non_object_params_default_to_imsg() {
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ga = empty_gmail_adapters();
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+        for params in [json!(null), json!([]), json!("gmail")] {
+            let req = make_req("channel.status", params.clone());
+            let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+            assert_eq!(resp.result.unwrap()["channel"], "imsg", "params: {params}");
+
+            let req = make_req("channel.list_chats", params);
+            let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+            assert_eq!(resp.error.unwrap().code, ErrorCode::ChannelUnavailable.code());
+        }
+    }
+
     #[tokio::test]
     async fn status_unconfigured_gmail() {
         let audit = noop_audit();
@@ -1001,7 +1361,7 @@ mod tests {
             imsg_inbound: None,
             audit_logger: &audit,
             dead_letter_queue: &dlq,
-            seen_message_ids: noop_seen(),
+            cursor_store: noop_cursor_store(),
             gmail_adapters: &ga,
             gmail_inbound_allowlists: &gal,
             gmail_default_account: "default",
@@ -1010,7 +1370,7 @@ mod tests {
         };
         let req = make_req("channel.send", json!({"channel": "gmail", "recipient": "a@b.com", "message": "hi"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::METHOD_NOT_FOUND);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound.code());
     }
 
     #[tokio::test]
@@ -1027,7 +1387,7 @@ mod tests {
             imsg_inbound: None,
             audit_logger: &audit,
             dead_letter_queue: &dlq,
-            seen_message_ids: noop_seen(),
+            cursor_store: noop_cursor_store(),
             gmail_adapters: &ga,
             gmail_inbound_allowlists: &gal,
             gmail_default_account: "default",
@@ -1036,6 +1396,247 @@ mod tests {
         };
         let req = make_req("channel.create_draft", json!({"channel": "gmail", "subject": "Hello"}));
         let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
-        assert_eq!(resp.error.unwrap().code, protocol::INVALID_PARAMS);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+    }
+
+    #[tokio::test]
+    async fn read_attachment_missing_message_id() {
+        let mut ga = HashMap::new();
+        ga.insert("default".to_string(), GmailAdapter::new(PathBuf::from("/nonexistent/gmail-proxy.sock")));
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+        let req = make_req("channel.read_attachment", json!({"channel": "gmail", "attachment_id": "a1"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+    }
+
+    #[tokio::test]
+    async fn ack_requires_cursor_and_position() {
+        let mut ga = HashMap::new();
+        ga.insert("default".to_string(), GmailAdapter::new(PathBuf::from("/nonexistent/gmail-proxy.sock")));
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+
+        let req = make_req("channel.ack", json!({"channel": "gmail", "position": "5"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+
+        let req = make_req("channel.ack", json!({"channel": "gmail", "cursor": "main", "position": "abc"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+    }
+
+    #[tokio::test]
+    async fn ack_advances_named_gmail_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CursorStore::load(dir.path().join("cursors.json"));
+        let mut ga = HashMap::new();
+        ga.insert("primary".to_string(), GmailAdapter::new(PathBuf::from("/nonexistent/gmail-proxy.sock")));
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let mut ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+        ctx.gmail_default_account = "primary";
+        ctx.cursor_store = &store;
+
+        let req = make_req("channel.ack", json!({"channel": "gmail", "cursor": "main", "position": "900"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.result.unwrap()["position"], "900");
+
+        // Stale acks never move the cursor backwards.
+        let req = make_req("channel.ack", json!({"channel": "gmail", "cursor": "main", "position": 100}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.result.unwrap()["position"], "900");
+        assert_eq!(store.get("gmail:primary/main").await.as_deref(), Some("900"));
+    }
+
+    #[tokio::test]
+    async fn gmail_watch_without_a_cursor_still_anchors_at_the_proxy() {
+        let mut ga = HashMap::new();
+        ga.insert("default".to_string(), GmailAdapter::new(PathBuf::from("/nonexistent/gmail-proxy.sock")));
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+
+        // The starting position cannot be read, so the watch fails up front
+        // rather than polling from the proxy's shared cursor.
+        let req = make_req("channel.watch", json!({"channel": "gmail"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InternalError.code());
+    }
+
+    #[tokio::test]
+    async fn ack_fails_when_cursor_cannot_be_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let blocker = dir.path().join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let store = CursorStore::load(blocker.join("cursors.json"));
+        let mut ga = HashMap::new();
+        ga.insert("default".to_string(), GmailAdapter::new(PathBuf::from("/nonexistent/gmail-proxy.sock")));
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let mut ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+        ctx.cursor_store = &store;
+
+        let req = make_req("channel.ack", json!({"channel": "gmail", "cursor": "main", "position": "900"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InternalError.code());
+        assert_eq!(store.get("gmail:default/main").await, None);
+    }
+
+    #[tokio::test]
+    async fn ack_advances_named_gdocs_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = CursorStore::load(dir.path().join("cursors.json"));
+        let ga = empty_gmail_adapters();
+        let gal = empty_gmail_allowlists();
+        let mut gda = HashMap::new();
+        gda.insert("work".to_string(), GDocsAdapter::new(PathBuf::from("/nonexistent/gdocs-proxy.sock")));
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let mut ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+        ctx.gdocs_default_account = "work";
+        ctx.cursor_store = &store;
+
+        let req = make_req("channel.ack", json!({"channel": "gdocs", "cursor": "drive", "position": "4521"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.result.unwrap()["position"], "4521");
+        assert_eq!(store.get("gdocs:work/drive").await.as_deref(), Some("4521"));
+
+        // Page tokens are opaque; a non-numeric one is stored as given.
+        let req = make_req("channel.ack", json!({"channel": "gdocs", "cursor": "drive", "position": "~!!~AI9FV7Q"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.result.unwrap()["position"], "~!!~AI9FV7Q");
+        assert_eq!(store.get("gdocs:work/drive").await.as_deref(), Some("~!!~AI9FV7Q"));
+    }
+
+    #[tokio::test]
+    async fn capabilities_lists_configured_channels_and_accounts() {
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ga = empty_gmail_adapters();
+        let gal = empty_gmail_allowlists();
+        let mut gda = HashMap::new();
+        for account in ["work", "home"] {
+            gda.insert(account.to_string(), GDocsAdapter::new(PathBuf::from("/nonexistent/gdocs-proxy.sock")));
+        }
+        let mut ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+        ctx.gdocs_default_account = "work";
+
+        let req = make_req("channel.capabilities", json!({}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(
+            resp.result.unwrap(),
+            json!({"channels": [{"channel": "gdocs", "accounts": ["home", "work"], "default_account": "work"}]})
+        );
+    }
+
+    #[tokio::test]
+    async fn unwatch_requires_subscription() {
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ga = empty_gmail_adapters();
+        let gal = empty_gmail_allowlists();
+        let gda = empty_gdocs_adapters();
+        let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+
+        let req = make_req("channel.unwatch", json!({}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+
+        let req = make_req("channel.unwatch", json!({"subscription": 3}));
+        match handle_channel_request(&req, &ctx).await {
+            ProcessResult::Unwatch { subscription, .. } => assert_eq!(subscription, "3"),
+            _ => panic!("expected Unwatch"),
+        }
+    }
+    #[tokio::test]
+    async fn gdocs_send_rejects_unknown_actions_and_missing_fields() {
+        let ga = empty_gmail_adapters();
+        let gal = empty_gmail_allowlists();
+        let mut gda = HashMap::new();
+        gda.insert("default".to_string(), GDocsAdapter::new(PathBuf::from("/nonexistent/gdocs-proxy.sock")));
+        let audit = noop_audit();
+        let dlq = noop_dead_letter();
+        let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+
+        let req = make_req("channel.send", json!({"channel": "gdocs", "action": "delete", "file_id": "f1"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound.code());
+
+        let req = make_req("channel.send", json!({"channel": "gdocs", "action": "append", "document_id": "d1"}));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+
+        let req = make_req("channel.send", json!({
+            "channel": "gdocs", "action": "edit", "document_id": "d1", "revision_id": "r1", "operations": []
+        }));
+        let resp = unwrap_response(handle_channel_request(&req, &ctx).await);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
+    }
+
+    /// A gdocs proxy that answers every request with `status` and `error`.
+    fn fake_gdocs_proxy(dir: &std::path::Path, status: &str, error: &str) -> PathBuf {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let path = dir.join("gdocs-proxy.sock");
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let body = json!({"error": error}).to_string();
+        let response = format!(
+            "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let response = response.clone();
+                tokio::spawn(async move {
+                    // Requests are small; one read holds the whole head.
+                    let mut buf = [0u8; 4096];
+                    let _ = stream.read(&mut buf).await;
+                    let _ = stream.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        path
+    }
+
+    #[tokio::test]
+    async fn gdocs_policy_denials_and_conflicts_have_their_own_codes() {
+        let cases = [
+            ("403 Forbidden", json!({"action": "append", "document_id": "d1", "text": "hi"}), ErrorCode::WriteDenied),
+            (
+                "409 Conflict",
+                json!({"action": "edit", "document_id": "d1", "revision_id": "r1",
+                       "operations": [{"type": "replace_all", "find": "a", "replace": "b"}]}),
+                ErrorCode::Conflict,
+            ),
+        ];
+        for (status, params, code) in cases {
+            let dir = tempfile::tempdir().unwrap();
+            let ga = empty_gmail_adapters();
+            let gal = empty_gmail_allowlists();
+            let mut gda = HashMap::new();
+            gda.insert("default".to_string(), GDocsAdapter::new(fake_gdocs_proxy(dir.path(), status, "outside the write zone")));
+            let audit = noop_audit();
+            let dlq = noop_dead_letter();
+            let ctx = empty_ctx(&audit, &dlq, &ga, &gal, &gda);
+
+            let mut params = params;
+            params["channel"] = json!("gdocs");
+            let req = make_req("channel.send", params);
+            let error = unwrap_response(handle_channel_request(&req, &ctx).await).error.unwrap();
+            assert_eq!(error.code, code.code(), "{status}: {}", error.message);
+            assert!(error.message.contains("outside the write zone"), "{}", error.message);
+        }
     }
 }
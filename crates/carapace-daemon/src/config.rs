@@ -25,15 +25,17 @@ pub struct GatewayConfig {
     #[serde(default = "default_socket_path")]
     pub socket_path: PathBuf,
     #[serde(default = "default_log_level")]
+    #[allow(dead_code)]
     pub log_level: String,
     #[serde(default = "default_request_timeout")]
+    #[allow(dead_code)]
     pub request_timeout: u64,
 }
 
 impl Default for GatewayConfig {
     fn default() -> Self {
         Self {
-            socket_path: PathBuf::from(default_socket_path()),
+            socket_path: default_socket_path(),
             log_level: default_log_level(),
             request_timeout: default_request_timeout(),
         }
@@ -205,7 +207,7 @@ pub struct ImsgChannelConfig {
 }
 
 /// Per-direction allowlist/denylist configuration.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct DirectionConfig {
     #[serde(default)]
     pub mode: AllowlistMode,
@@ -213,15 +215,6 @@ pub struct DirectionConfig {
     pub allowlist: Vec<String>,
 }
 
-impl Default for DirectionConfig {
-    fn default() -> Self {
-        Self {
-            mode: AllowlistMode::default(),
-            allowlist: Vec::new(),
-        }
-    }
-}
-
 /// How the allowlist is interpreted.
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -260,6 +253,9 @@ pub struct SecurityConfig {
     pub audit_log_path: PathBuf,
     #[serde(default = "default_dead_letter_path")]
     pub dead_letter_path: PathBuf,
+    /// JSON file holding named `channel.watch` cursors.
+    #[serde(default = "default_cursor_store_path")]
+    pub cursor_store_path: PathBuf,
     #[serde(default = "default_true")]
     pub audit_enabled: bool,
     #[serde(default)]
@@ -273,6 +269,7 @@ impl Default for SecurityConfig {
         Self {
             audit_log_path: default_audit_log_path(),
             dead_letter_path: default_dead_letter_path(),
+            cursor_store_path: default_cursor_store_path(),
             audit_enabled: true,
             rate_limit: RateLimitConfig::default(),
             content_filter: ContentFilterConfig::default(),
@@ -286,6 +283,9 @@ fn default_audit_log_path() -> PathBuf {
 fn default_dead_letter_path() -> PathBuf {
     PathBuf::from("/Users/carapace/.local/share/carapace/dead_letters")
 }
+fn default_cursor_store_path() -> PathBuf {
+    PathBuf::from("/Users/carapace/.local/share/carapace/watch_cursors.json")
+}
 fn default_true() -> bool {
     true
 }
@@ -0,0 +1,205 @@
+//! Named watch cursors — durable per-subscriber positions for `channel.watch`.
+//!
+//! A cursor records the last event a subscriber has acknowledged (an iMessage
+//! ROWID, a Gmail history ID or a Drive page token). Positions are kept as
+//! strings because page tokens are opaque. `channel.watch` with `cursor`
+//! resumes from that position, and `channel.ack` moves it forward. All cursors live in a
+//! single JSON file that is rewritten atomically on every change. A cursor
+//! that cannot be saved is not moved, so an ack is only reported once it is
+//! on disk.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tracing::{error, warn};
+
+/// Persisted map of cursor key → position.
+pub struct CursorStore {
+    path: PathBuf,
+    cursors: Mutex<BTreeMap<String, String>>,
+}
+
+/// A position as stored on disk. Older files hold plain numbers.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredPosition {
+    Text(String),
+    Number(u64),
+}
+
+impl From<StoredPosition> for String {
+    fn from(position: StoredPosition) -> Self {
+        match position {
+            StoredPosition::Text(s) => s,
+            StoredPosition::Number(n) => n.to_string(),
+        }
+    }
+}
+
+/// Whether `new` would move a cursor at `current` backwards or nowhere.
+/// Numeric positions compare as numbers; opaque ones only match themselves.
+/// This is the order the gdocs-proxy uses for its own changes cursor.
+fn not_after(new: &str, current: &str) -> bool {
+    match (current.parse::<u64>(), new.parse::<u64>()) {
+        (Ok(current), Ok(new)) => new <= current,
+        _ => new == current,
+    }
+}
+
+impl CursorStore {
+    /// Load cursors from `path`. A missing file starts empty. A corrupt file
+    /// is renamed to `<path>.corrupt` so it is not overwritten by the next ack
+    /// and can be inspected; every cursor then starts again from scratch.
+    pub fn load(path: PathBuf) -> Self {
+        let cursors = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str::<BTreeMap<String, StoredPosition>>(&text)
+                .map(|stored| stored.into_iter().map(|(key, position)| (key, position.into())).collect())
+                .unwrap_or_else(|e| {
+                    let mut aside = path.clone().into_os_string();
+                    aside.push(".corrupt");
+                    let aside = PathBuf::from(aside);
+                    match std::fs::rename(&path, &aside) {
+                        Ok(()) => error!(
+                            path = %path.display(), moved_to = %aside.display(), error = %e,
+                            "invalid cursor file moved aside; named cursors start from scratch",
+                        ),
+                        Err(rename_err) => error!(
+                            path = %path.display(), error = %e, rename_error = %rename_err,
+                            "invalid cursor file could not be moved aside; named cursors start from scratch",
+                        ),
+                    }
+                    BTreeMap::new()
+                }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                error!(path = %path.display(), error = %e, "cursor file unreadable, starting empty");
+                BTreeMap::new()
+            }
+        };
+        Self {
+            path,
+            cursors: Mutex::new(cursors),
+        }
+    }
+
+    /// Build the storage key for a named cursor on a channel/account.
+    pub fn key(channel: &str, account: Option<&str>, name: &str) -> String {
+        match account {
+            Some(account) => format!("{channel}:{account}/{name}"),
+            None => format!("{channel}/{name}"),
+        }
+    }
+
+    pub async fn get(&self, key: &str) -> Option<String> {
+        self.cursors.lock().await.get(key).cloned()
+    }
+
+    /// Move a cursor forward to `position` and persist it.
+    ///
+    /// Cursors never move backwards; the resulting position is returned. If
+    /// the file cannot be written the cursor keeps its previous position.
+    pub async fn advance(&self, key: &str, position: &str) -> std::io::Result<String> {
+        let mut cursors = self.cursors.lock().await;
+        let current = cursors.get(key).cloned();
+        if let Some(current) = current.clone().filter(|c| not_after(position, c)) {
+            return Ok(current);
+        }
+        cursors.insert(key.to_string(), position.to_string());
+        if let Err(e) = self.persist(&cursors).await {
+            warn!(path = %self.path.display(), error = %e, "cursor write failed");
+            match current {
+                Some(previous) => cursors.insert(key.to_string(), previous),
+                None => cursors.remove(key),
+            };
+            return Err(e);
+        }
+        Ok(position.to_string())
+    }
+
+    async fn persist(&self, cursors: &BTreeMap<String, String>) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let json = serde_json::to_string_pretty(cursors).map_err(std::io::Error::other)?;
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, json).await?;
+        tokio::fs::rename(&tmp, &self.path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_includes_account_when_present() {
+        assert_eq!(CursorStore::key("imsg", None, "main"), "imsg/main");
+        assert_eq!(CursorStore::key("gmail", Some("primary"), "main"), "gmail:primary/main");
+    }
+
+    #[tokio::test]
+    async fn advance_is_monotonic_and_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursors.json");
+
+        let store = CursorStore::load(path.clone());
+        assert_eq!(store.get("imsg/main").await, None);
+        assert_eq!(store.advance("imsg/main", "42").await.unwrap(), "42");
+        assert_eq!(store.advance("imsg/main", "10").await.unwrap(), "42");
+        assert_eq!(store.advance("imsg/main", "100").await.unwrap(), "100");
+
+        let reloaded = CursorStore::load(path);
+        assert_eq!(reloaded.get("imsg/main").await.as_deref(), Some("100"));
+    }
+
+    #[tokio::test]
+    async fn opaque_positions_are_kept_as_strings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursors.json");
+
+        let store = CursorStore::load(path.clone());
+        assert_eq!(store.advance("gdocs:work/drive", "~!!~AI9FV7Q").await.unwrap(), "~!!~AI9FV7Q");
+        assert_eq!(store.advance("gdocs:work/drive", "4521").await.unwrap(), "4521");
+
+        let reloaded = CursorStore::load(path);
+        assert_eq!(reloaded.get("gdocs:work/drive").await.as_deref(), Some("4521"));
+    }
+
+    #[tokio::test]
+    async fn numeric_positions_from_older_files_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursors.json");
+        std::fs::write(&path, r#"{"imsg/main": 42, "gdocs:work/drive": "abc"}"#).unwrap();
+
+        let store = CursorStore::load(path);
+        assert_eq!(store.get("imsg/main").await.as_deref(), Some("42"));
+        assert_eq!(store.get("gdocs:work/drive").await.as_deref(), Some("abc"));
+    }
+
+    #[tokio::test]
+    async fn failed_write_leaves_cursor_unmoved() {
+        let dir = tempfile::tempdir().unwrap();
+        // The parent "directory" is a file, so every write fails.
+        let blocker = dir.path().join("blocker");
+        std::fs::write(&blocker, "").unwrap();
+        let store = CursorStore::load(blocker.join("cursors.json"));
+
+        assert!(store.advance("imsg/main", "42").await.is_err());
+        assert_eq!(store.get("imsg/main").await, None);
+    }
+
+    #[tokio::test]
+    async fn corrupt_file_is_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursors.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let store = CursorStore::load(path.clone());
+        assert_eq!(store.get("imsg/main").await, None);
+        assert!(!path.exists());
+        let aside = dir.path().join("cursors.json.corrupt");
+        assert_eq!(std::fs::read_to_string(aside).unwrap(), "{not json");
+    }
+}
@@ -56,7 +56,7 @@ impl DeadLetterQueue {
         let path = self.dir.join(filename);
 
         let json = serde_json::to_string_pretty(letter)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(std::io::Error::other)?;
 
         let mut file = fs::File::create(&path).await?;
         file.write_all(json.as_bytes()).await?;
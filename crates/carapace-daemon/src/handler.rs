@@ -5,13 +5,16 @@ use std::process::Command;
 use serde_json::json;
 use tracing::{info, warn};
 
-use crate::protocol::{self, JsonRpcRequest, JsonRpcResponse};
+use carapace_protocol::{HelloParams, HelloResult, ProtocolVersion, HELLO_METHOD, PROTOCOL_VERSION};
+
+use crate::protocol::{ErrorCode, JsonRpcRequest, JsonRpcResponse};
 
 /// Handle a validated JSON-RPC request and produce a response.
 pub fn handle_request(req: &JsonRpcRequest) -> JsonRpcResponse {
     info!(method = %req.method, id = %req.id, "handling request");
 
     match req.method.as_str() {
+        HELLO_METHOD => handle_hello(req).0,
         "ping" => handle_ping(req),
         "echo" => handle_echo(req),
         "whoami" => handle_whoami(req),
@@ -20,13 +23,63 @@ pub fn handle_request(req: &JsonRpcRequest) -> JsonRpcResponse {
             warn!(method = %req.method, "unknown method");
             JsonRpcResponse::error(
                 req.id.clone(),
-                protocol::METHOD_NOT_FOUND,
+                ErrorCode::MethodNotFound,
                 format!("Unknown method: {}", req.method),
             )
         }
     }
 }
 
+// ── hello ──────────────────────────────────────────────────────────────────
+
+/// Version handshake: accepts clients whose protocol major version matches
+/// ours and tells them which version and daemon they are talking to.
+///
+/// Also returns the client's version when it was accepted, for the
+/// connection to record.
+pub fn handle_hello(req: &JsonRpcRequest) -> (JsonRpcResponse, Option<ProtocolVersion>) {
+    let params: HelloParams = match serde_json::from_value(req.params.clone()) {
+        Ok(params) => params,
+        Err(e) => {
+            let response = JsonRpcResponse::error(
+                req.id.clone(),
+                ErrorCode::InvalidParams,
+                format!("Invalid hello params: {e}"),
+            );
+            return (response, None);
+        }
+    };
+
+    if !PROTOCOL_VERSION.is_compatible_with(params.protocol_version) {
+        warn!(
+            client = params.client.as_deref().unwrap_or("unknown"),
+            version = %params.protocol_version,
+            "rejecting client with incompatible protocol version"
+        );
+        let response = JsonRpcResponse::error_with_data(
+            req.id.clone(),
+            ErrorCode::IncompatibleVersion,
+            format!(
+                "Client speaks protocol {}, this daemon speaks {PROTOCOL_VERSION}",
+                params.protocol_version
+            ),
+            json!({ "server_version": PROTOCOL_VERSION }),
+        );
+        return (response, None);
+    }
+
+    info!(
+        client = params.client.as_deref().unwrap_or("unknown"),
+        version = %params.protocol_version,
+        "client hello"
+    );
+    let result = HelloResult {
+        protocol_version: PROTOCOL_VERSION,
+        server: concat!("carapace-daemon/", env!("CARGO_PKG_VERSION")).into(),
+    };
+    (JsonRpcResponse::success(req.id.clone(), json!(result)), Some(params.protocol_version))
+}
+
 // ── ping ───────────────────────────────────────────────────────────────────
 
 /// Responds with `{"pong": true}` – used to verify the daemon is alive.
@@ -99,7 +152,7 @@ fn handle_execute(req: &JsonRpcRequest) -> JsonRpcResponse {
         None => {
             return JsonRpcResponse::error(
                 req.id.clone(),
-                protocol::INVALID_PARAMS,
+                ErrorCode::InvalidParams,
                 "Missing required param: \"command\"",
             );
         }
@@ -135,7 +188,7 @@ fn handle_execute(req: &JsonRpcRequest) -> JsonRpcResponse {
         }
         Err(e) => JsonRpcResponse::error(
             req.id.clone(),
-            protocol::INTERNAL_ERROR,
+            ErrorCode::InternalError,
             format!("Failed to execute \"{command}\": {e}"),
         ),
     }
@@ -164,6 +217,27 @@ mod tests {
         assert_eq!(result["pong"], true);
     }
 
+    #[test]
+    fn hello_accepts_same_major_version() {
+        let req = make_request("hello", json!({"protocol_version": "1.7", "client": "test"}));
+        let result = handle_request(&req).result.unwrap();
+        assert_eq!(result["protocol_version"], PROTOCOL_VERSION.to_string());
+        assert!(result["server"].as_str().unwrap().starts_with("carapace-daemon/"));
+        assert_eq!(handle_hello(&req).1, Some(ProtocolVersion { major: 1, minor: 7 }));
+    }
+
+    #[test]
+    fn hello_rejects_other_major_version() {
+        let req = make_request("hello", json!({"protocol_version": "2.0"}));
+        let error = handle_request(&req).error.unwrap();
+        assert_eq!(error.code, ErrorCode::IncompatibleVersion.code());
+        assert_eq!(error.data.unwrap()["server_version"], PROTOCOL_VERSION.to_string());
+        assert_eq!(handle_hello(&req).1, None);
+
+        let req = make_request("hello", json!({"protocol_version": "one"}));
+        assert_eq!(handle_request(&req).error.unwrap().code, ErrorCode::InvalidParams.code());
+    }
+
     #[test]
     fn echo_returns_message() {
         let req = make_request("echo", json!({"message": "hello world"}));
@@ -198,7 +272,7 @@ mod tests {
         let req = make_request("execute", json!({}));
         let resp = handle_request(&req);
         assert!(resp.error.is_some());
-        assert_eq!(resp.error.unwrap().code, protocol::INVALID_PARAMS);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams.code());
     }
 
     #[test]
@@ -206,6 +280,6 @@ mod tests {
         let req = make_request("nonexistent.method", json!({}));
         let resp = handle_request(&req);
         assert!(resp.error.is_some());
-        assert_eq!(resp.error.unwrap().code, protocol::METHOD_NOT_FOUND);
+        assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound.code());
     }
 }
@@ -23,6 +23,7 @@ mod audit;
 mod channel_handler;
 mod config;
 mod content_filter;
+mod cursor_store;
 mod dead_letter;
 mod handler;
 mod middleware;
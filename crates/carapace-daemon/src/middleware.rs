@@ -7,7 +7,7 @@
 use crate::audit::{self, AuditLogger};
 use crate::content_filter::{ContentCheckResult, ContentFilter};
 use crate::dead_letter::{DeadLetter, DeadLetterQueue};
-use crate::protocol::{self, JsonRpcRequest, JsonRpcResponse};
+use crate::protocol::{ErrorCode, JsonRpcRequest, JsonRpcResponse};
 use crate::rate_limiter::{RateLimitResult, RateLimiter};
 
 /// The verdict from the middleware pipeline.
@@ -54,7 +54,7 @@ pub async fn run_pipeline(
 
             return MiddlewareVerdict::Reject(JsonRpcResponse::error(
                 req.id.clone(),
-                protocol::RATE_LIMITED,
+                ErrorCode::RateLimited,
                 reason,
             ));
         }
@@ -87,7 +87,7 @@ pub async fn run_pipeline(
 
             return MiddlewareVerdict::Reject(JsonRpcResponse::error(
                 req.id.clone(),
-                protocol::CONTENT_BLOCKED,
+                ErrorCode::ContentBlocked,
                 reason,
             ));
         }
@@ -194,7 +194,7 @@ mod tests {
         .await;
         match v {
             MiddlewareVerdict::Reject(resp) => {
-                assert_eq!(resp.error.unwrap().code, protocol::RATE_LIMITED);
+                assert_eq!(resp.error.unwrap().code, ErrorCode::RateLimited.code());
             }
             MiddlewareVerdict::Allow => panic!("should have been rejected"),
         }
@@ -224,7 +224,7 @@ mod tests {
         .await;
         match v {
             MiddlewareVerdict::Reject(resp) => {
-                assert_eq!(resp.error.unwrap().code, protocol::CONTENT_BLOCKED);
+                assert_eq!(resp.error.unwrap().code, ErrorCode::ContentBlocked.code());
             }
             MiddlewareVerdict::Allow => panic!("should have been rejected"),
         }
@@ -267,7 +267,7 @@ mod tests {
         match v {
             MiddlewareVerdict::Reject(resp) => {
                 // Should be rate limited, not content blocked.
-                assert_eq!(resp.error.unwrap().code, protocol::RATE_LIMITED);
+                assert_eq!(resp.error.unwrap().code, ErrorCode::RateLimited.code());
             }
             MiddlewareVerdict::Allow => panic!("should have been rejected"),
         }
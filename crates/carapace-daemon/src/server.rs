@@ -6,8 +6,12 @@ use std::time::Duration;
 
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tracing::{error, info, warn};
 
+use carapace_protocol::HELLO_METHOD;
+
 use std::collections::HashMap;
 
 use crate::adapters::gdocs::GDocsAdapter;
@@ -18,10 +22,13 @@ use crate::audit::AuditLogger;
 use crate::channel_handler::{self, ChannelContext};
 use crate::config::Config;
 use crate::content_filter::ContentFilter;
+use crate::cursor_store::CursorStore;
 use crate::dead_letter::DeadLetterQueue;
 use crate::handler;
 use crate::middleware::{self, MiddlewareVerdict};
-use crate::protocol::{self, JsonRpcRequest, JsonRpcResponse, ProcessResult};
+use crate::protocol::{
+    ErrorCode, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse, ProcessResult, ProtocolVersion,
+};
 use crate::rate_limiter::RateLimiter;
 
 /// Shared state available to every connection handler.
@@ -34,8 +41,8 @@ pub struct AppState {
     pub imsg_adapter: Option<ImsgAdapter>,
     pub imsg_outbound: Option<Allowlist>,
     pub imsg_inbound: Option<Allowlist>,
-    /// iMessage IDs already forwarded to a watch subscriber.
-    pub seen_message_ids: Arc<tokio::sync::Mutex<std::collections::HashSet<u64>>>,
+    /// Named watch cursors (`channel.watch` / `channel.ack`).
+    pub cursor_store: CursorStore,
     // Gmail channel — keyed by account name (e.g. "default", "primary", "wedding")
     pub gmail_adapters: HashMap<String, GmailAdapter>,
     pub gmail_inbound_allowlists: HashMap<String, Allowlist>,
@@ -134,7 +141,7 @@ impl AppState {
             imsg_adapter,
             imsg_outbound,
             imsg_inbound,
-            seen_message_ids: Arc::new(tokio::sync::Mutex::new(std::collections::HashSet::new())),
+            cursor_store: CursorStore::load(config.security.cursor_store_path.clone()),
             gmail_adapters,
             gmail_inbound_allowlists,
             gmail_default_account,
@@ -217,31 +224,47 @@ pub async fn run(socket_path: &Path, config: Config) -> std::io::Result<()> {
     }
 }
 
-/// Serialize a JSON-RPC response and write it as a newline-terminated line.
-async fn write_response(
-    writer: &mut tokio::net::unix::OwnedWriteHalf,
-    response: &JsonRpcResponse,
-) -> std::io::Result<()> {
-    let mut json = serde_json::to_string(response).unwrap_or_else(|e| {
+/// Serialize a JSON-RPC message as a newline-terminated line.
+fn to_line<T: serde::Serialize>(message: &T) -> String {
+    let mut json = serde_json::to_string(message).unwrap_or_else(|e| {
         format!(
             r#"{{"jsonrpc":"2.0","id":null,"error":{{"code":{},"message":"Serialization failed: {}"}}}}"#,
-            protocol::INTERNAL_ERROR,
+            ErrorCode::InternalError.code(),
             e
         )
     });
     json.push('\n');
-    writer.write_all(json.as_bytes()).await
+    json
 }
 
 /// Handle a single client connection.
 ///
 /// Reads newline-delimited JSON-RPC requests and writes back responses.
-/// The connection stays open until the client disconnects.
+/// Any number of `channel.watch` subscriptions can be active at once; their
+/// notifications are tagged with a `subscription` id and interleaved with
+/// ordinary responses. All output goes through a single writer task so lines
+/// never tear. The connection stays open until the client disconnects, at
+/// which point every subscription on it is cancelled.
 async fn handle_connection(stream: UnixStream, state: Arc<AppState>) -> std::io::Result<()> {
     let (reader, mut writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut line = String::new();
 
+    let (out_tx, mut out_rx) = mpsc::channel::<String>(256);
+    let writer_task = tokio::spawn(async move {
+        while let Some(line) = out_rx.recv().await {
+            if writer.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subscriptions: HashMap<String, JoinHandle<()>> = HashMap::new();
+    let mut next_subscription: u64 = 1;
+    // The protocol version agreed at `hello`; clients that skip it are
+    // served as version 1.
+    let mut negotiated: Option<ProtocolVersion> = None;
+
     info!("client connected");
 
     loop {
@@ -249,7 +272,7 @@ async fn handle_connection(stream: UnixStream, state: Arc<AppState>) -> std::io:
         let bytes_read = reader.read_line(&mut line).await?;
 
         if bytes_read == 0 {
-            info!("client disconnected");
+            info!(protocol = ?negotiated.map(|v| v.to_string()), "client disconnected");
             break;
         }
 
@@ -260,60 +283,89 @@ async fn handle_connection(stream: UnixStream, state: Arc<AppState>) -> std::io:
 
         let result = process_message(trimmed, &state).await;
 
-        match result {
-            ProcessResult::Response(response) => {
-                write_response(&mut writer, &response).await?;
-            }
+        let response = match result {
+            ProcessResult::Response(response) => response,
             ProcessResult::Subscription {
-                ack,
+                mut ack,
                 mut notifications,
             } => {
-                // Send the ack first.
-                write_response(&mut writer, &ack).await?;
-
-                // Enter streaming loop: forward notifications until
-                // the stream ends or the client disconnects.
-                loop {
-                    tokio::select! {
-                        maybe_notif = notifications.recv() => {
-                            match maybe_notif {
-                                Some(notif) => {
-                                    let mut json = serde_json::to_string(&notif)
-                                        .unwrap_or_default();
-                                    json.push('\n');
-                                    if writer.write_all(json.as_bytes()).await.is_err() {
-                                        info!("client disconnected during stream");
-                                        return Ok(());
-                                    }
-                                }
-                                None => {
-                                    // Notification channel closed (watch process exited).
-                                    info!("watch stream ended");
-                                    break;
-                                }
-                            }
+                subscriptions.retain(|_, task| !task.is_finished());
+                let subscription = next_subscription.to_string();
+                next_subscription += 1;
+                if let Some(serde_json::Value::Object(result)) = ack.result.as_mut() {
+                    result.insert("subscription".into(), subscription.clone().into());
+                }
+                // Queue the ack before any notification can be forwarded.
+                if out_tx.send(to_line(&ack)).await.is_err() {
+                    break;
+                }
+
+                let out_tx = out_tx.clone();
+                let id = subscription.clone();
+                let task = tokio::spawn(async move {
+                    while let Some(mut notif) = notifications.recv().await {
+                        if let serde_json::Value::Object(params) = &mut notif.params {
+                            params.insert("subscription".into(), id.clone().into());
                         }
-                        bytes = reader.read_line(&mut line) => {
-                            match bytes {
-                                Ok(0) | Err(_) => {
-                                    info!("client disconnected during stream");
-                                    return Ok(());
-                                }
-                                Ok(_) => {
-                                    // Client sent data during streaming — ignore it.
-                                    line.clear();
-                                }
-                            }
+                        if out_tx.send(to_line(&notif)).await.is_err() {
+                            return;
                         }
                     }
+                    // The source ended (e.g. the watch process exited).
+                    info!(subscription = %id, "watch stream ended");
+                    let end = JsonRpcNotification::new(
+                        "channel.watch_end",
+                        serde_json::json!({"subscription": id}),
+                    );
+                    let _ = out_tx.send(to_line(&end)).await;
+                });
+                subscriptions.insert(subscription, task);
+                continue;
+            }
+            ProcessResult::Unwatch { id, subscription } => match subscriptions.remove(&subscription) {
+                Some(task) => {
+                    task.abort();
+                    info!(%subscription, "subscription cancelled");
+                    JsonRpcResponse::success(
+                        id,
+                        serde_json::json!({"unsubscribed": true, "subscription": subscription}),
+                    )
+                }
+                None => JsonRpcResponse::error(
+                    id,
+                    ErrorCode::InvalidParams,
+                    format!("Unknown subscription: {subscription}"),
+                ),
+            },
+            ProcessResult::Hello { response, version } => {
+                let refused = response
+                    .error
+                    .as_ref()
+                    .is_some_and(|e| e.code == ErrorCode::IncompatibleVersion.code());
+                if refused {
+                    // Nothing else on this connection can be trusted to
+                    // parse; answer the hello and hang up.
+                    let _ = out_tx.send(to_line(&response)).await;
+                    info!("closing connection after an incompatible hello");
+                    break;
                 }
-                // Stream ended — close connection. The client's subscribe()
-                // consumed the GatewayClient, so no more requests will come.
-                return Ok(());
+                if let Some(version) = version {
+                    negotiated = Some(version);
+                }
+                response
             }
+        };
+
+        if out_tx.send(to_line(&response)).await.is_err() {
+            break;
         }
     }
 
+    for task in subscriptions.into_values() {
+        task.abort();
+    }
+    drop(out_tx);
+    let _ = writer_task.await;
     Ok(())
 }
 
@@ -326,7 +378,7 @@ async fn process_message(raw: &str, state: &AppState) -> ProcessResult {
             warn!(error = %e, "parse error");
             return ProcessResult::Response(JsonRpcResponse::error(
                 serde_json::Value::Null,
-                protocol::PARSE_ERROR,
+                ErrorCode::ParseError,
                 format!("Parse error: {e}"),
             ));
         }
@@ -336,7 +388,7 @@ async fn process_message(raw: &str, state: &AppState) -> ProcessResult {
     if let Err(e) = req.validate() {
         return ProcessResult::Response(JsonRpcResponse::error(
             req.id.clone(),
-            protocol::INVALID_REQUEST,
+            ErrorCode::InvalidRequest,
             format!("Invalid request: {e}"),
         ));
     }
@@ -365,7 +417,7 @@ async fn process_message(raw: &str, state: &AppState) -> ProcessResult {
             imsg_inbound: state.imsg_inbound.as_ref(),
             audit_logger: &state.audit_logger,
             dead_letter_queue: &state.dead_letter_queue,
-            seen_message_ids: Arc::clone(&state.seen_message_ids),
+            cursor_store: &state.cursor_store,
             gmail_adapters: &state.gmail_adapters,
             gmail_inbound_allowlists: &state.gmail_inbound_allowlists,
             gmail_default_account: &state.gmail_default_account,
@@ -373,6 +425,9 @@ async fn process_message(raw: &str, state: &AppState) -> ProcessResult {
             gdocs_default_account: &state.gdocs_default_account,
         };
         channel_handler::handle_channel_request(&req, &ctx).await
+    } else if req.method == HELLO_METHOD {
+        let (response, version) = handler::handle_hello(&req);
+        ProcessResult::Hello { response, version }
     } else {
         ProcessResult::Response(handler::handle_request(&req))
     }
@@ -8,7 +8,7 @@ use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
 use std::time::Duration;
 
-use carapace_client::GatewayClient;
+use carapace_client::{AsyncGatewayClient, GatewayClient};
 use serde_json::json;
 
 /// A test daemon that starts `carapace-daemon` with a temp socket + config
@@ -16,6 +16,7 @@ use serde_json::json;
 struct TestDaemon {
     child: Child,
     socket_path: PathBuf,
+    config_path: PathBuf,
     _temp_dir: tempfile::TempDir,
 }
 
@@ -80,37 +81,41 @@ allowlist = ["+1111111111"]
 
         std::fs::write(&config_path, config).expect("failed to write config");
 
-        // Find the daemon binary.
-        let daemon_bin = env!("CARGO_BIN_EXE_carapace-daemon");
-
-        let child = Command::new(daemon_bin)
-            .arg("--config")
-            .arg(&config_path)
-            .arg("--socket")
-            .arg(&socket_path)
-            .env("RUST_LOG", "warn")
-            .spawn()
-            .expect("failed to start daemon");
-
+        let child = spawn_daemon(&config_path, &socket_path);
         let daemon = TestDaemon {
             child,
-            socket_path: socket_path.clone(),
+            socket_path,
+            config_path,
             _temp_dir: temp_dir,
         };
+        daemon.wait_for_socket();
+        daemon
+    }
+
+    /// Kill the daemon and start it again on the same socket and config.
+    fn restart(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        // A killed daemon leaves its socket file behind.
+        let _ = std::fs::remove_file(&self.socket_path);
+        self.child = spawn_daemon(&self.config_path, &self.socket_path);
+        self.wait_for_socket();
+    }
 
+    fn wait_for_socket(&self) {
         // Poll until the socket appears (max 5 seconds).
         for _ in 0..50 {
-            if socket_path.exists() {
+            if self.socket_path.exists() {
                 // Give the daemon a moment to finish binding.
                 std::thread::sleep(Duration::from_millis(50));
-                return daemon;
+                return;
             }
             std::thread::sleep(Duration::from_millis(100));
         }
 
         panic!(
             "daemon socket did not appear at {} within 5s",
-            socket_path.display()
+            self.socket_path.display()
         );
     }
 
@@ -119,6 +124,17 @@ allowlist = ["+1111111111"]
     }
 }
 
+fn spawn_daemon(config_path: &Path, socket_path: &Path) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_carapace-daemon"))
+        .arg("--config")
+        .arg(config_path)
+        .arg("--socket")
+        .arg(socket_path)
+        .env("RUST_LOG", "warn")
+        .spawn()
+        .expect("failed to start daemon")
+}
+
 impl Drop for TestDaemon {
     fn drop(&mut self) {
         let _ = self.child.kill();
@@ -185,10 +201,10 @@ fn channel_send_blocked_by_allowlist() {
         )
         .unwrap_err();
     match err {
-        carapace_client::ClientError::Gateway { code, .. } => {
-            assert_eq!(code, -32001); // NOT_IN_ALLOWLIST
+        carapace_client::ClientError::NotInAllowlist { message, .. } => {
+            assert!(message.contains("+9999999999"), "got: {message}");
         }
-        other => panic!("expected Gateway error, got: {other}"),
+        other => panic!("expected NotInAllowlist error, got: {other}"),
     }
 }
 
@@ -206,12 +222,11 @@ fn channel_send_blocked_by_content_filter() {
             }),
         )
         .unwrap_err();
-    match err {
-        carapace_client::ClientError::Gateway { code, .. } => {
-            assert_eq!(code, -32003); // CONTENT_BLOCKED
-        }
-        other => panic!("expected Gateway error, got: {other}"),
-    }
+    assert!(
+        matches!(err, carapace_client::ClientError::ContentBlocked { .. }),
+        "expected ContentBlocked error, got: {err}"
+    );
+    assert_eq!(err.code(), Some(-32003));
 }
 
 #[test]
@@ -252,6 +267,34 @@ fn channel_status() {
     assert_eq!(result["configured"], true);
 }
 
+#[test]
+fn typed_channel_calls() {
+    let daemon = TestDaemon::start();
+    let mut client = daemon.client();
+
+    let status = client.imsg().status().unwrap();
+    assert!(status.configured);
+    assert_eq!(status.outbound.unwrap().entries, 2);
+
+    let caps = client.capabilities().unwrap();
+    assert_eq!(caps.channels.len(), 1);
+    assert_eq!(caps.channels[0].channel, "imsg");
+
+    let blocked = carapace_client::protocol::imsg::SendParams {
+        recipient: "+9999999999".into(),
+        message: "hello".into(),
+        attachments: vec![],
+    };
+    let err = client.imsg().send(&blocked).unwrap_err();
+    assert!(matches!(err, carapace_client::ClientError::NotInAllowlist { .. }), "got: {err}");
+
+    // Gmail is not configured on this gateway.
+    let gmail = client.gmail(None).status().unwrap();
+    assert!(!gmail.configured);
+    let err = client.gmail("work").inbox(Some(5)).unwrap_err();
+    assert!(matches!(err, carapace_client::ClientError::ChannelUnavailable { .. }), "got: {err}");
+}
+
 #[test]
 fn channel_watch_receives_filtered_messages() {
     let daemon = TestDaemon::start();
@@ -280,6 +323,141 @@ fn channel_watch_receives_filtered_messages() {
     assert_eq!(events[1]["sender"], "+1111111111");
 }
 
+#[test]
+fn multiple_watches_share_one_connection() {
+    let daemon = TestDaemon::start();
+    let mut client = daemon.client();
+
+    let first = client.watch(json!({"channel": "imsg"})).unwrap();
+    let second = client.watch(json!({"channel": "imsg"})).unwrap();
+    assert_ne!(first, second);
+
+    // Normal calls still work while both subscriptions stream.
+    let status = client.call("channel.status", json!({"channel": "imsg"})).unwrap();
+    assert_eq!(status["channel"], "imsg");
+
+    let err = client.unwatch("no-such-subscription").unwrap_err();
+    assert!(matches!(err, carapace_client::ClientError::Gateway { code: -32602, .. }));
+
+    let mut events: std::collections::HashMap<String, usize> = Default::default();
+    let mut ended = 0;
+    while ended < 2 {
+        let notif = client.next_notification().unwrap().expect("connection closed early");
+        let sub = notif.subscription.clone().expect("untagged notification");
+        if notif.method == "channel.watch_end" {
+            ended += 1;
+        } else {
+            *events.entry(sub).or_default() += 1;
+        }
+    }
+    assert_eq!(events.get(&first), Some(&2));
+    assert_eq!(events.get(&second), Some(&2));
+}
+
+#[test]
+fn client_reconnects_after_daemon_restart() {
+    let mut daemon = TestDaemon::start();
+    let mut client = daemon.client();
+    assert_eq!(client.call("ping", json!({})).unwrap()["pong"], true);
+
+    daemon.restart();
+
+    // Idempotent calls reconnect and retry without the caller noticing.
+    assert_eq!(client.call("ping", json!({})).unwrap()["pong"], true);
+    let status = client.call("channel.status", json!({"channel": "imsg"})).unwrap();
+    assert_eq!(status["channel"], "imsg");
+}
+
+#[test]
+fn writes_reach_a_restarted_daemon() {
+    let mut daemon = TestDaemon::start();
+    let mut client = daemon.client();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let async_client = runtime
+        .block_on(AsyncGatewayClient::connect(&daemon.socket_path))
+        .unwrap();
+
+    daemon.restart();
+
+    // The old connections are gone before anything is written, so even a
+    // send is retried on a new one and answered by the new daemon.
+    let send = json!({"channel": "imsg", "recipient": "+9999999999", "message": "hello"});
+    let err = client.call("channel.send", send.clone()).unwrap_err();
+    assert!(
+        matches!(err, carapace_client::ClientError::NotInAllowlist { .. }),
+        "got: {err}"
+    );
+    let err = runtime
+        .block_on(async_client.call("channel.send", send))
+        .unwrap_err();
+    assert!(
+        matches!(err, carapace_client::ClientError::NotInAllowlist { .. }),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn subscription_resubscribes_after_daemon_restart() {
+    let mut daemon = TestDaemon::start();
+    let (_ack, subscription) = daemon
+        .client()
+        .subscribe("channel.watch", json!({"channel": "imsg"}))
+        .unwrap();
+
+    // The mock waits before its first event, so the stream is cut short.
+    daemon.restart();
+
+    let events: Vec<serde_json::Value> = subscription.map(|r| r.unwrap()).collect();
+    assert!(events.len() >= 2, "expected the resubscribed stream's events, got {events:?}");
+    assert!(events.iter().all(|e| e["sender"] == "+1111111111"));
+}
+
+#[test]
+fn async_client_survives_daemon_restart() {
+    let mut daemon = TestDaemon::start();
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let client = runtime
+        .block_on(AsyncGatewayClient::connect(&daemon.socket_path))
+        .unwrap();
+
+    let (ping, status) = runtime.block_on(async {
+        tokio::join!(
+            client.call("ping", json!({})),
+            client.call("channel.status", json!({"channel": "imsg"})),
+        )
+    });
+    assert_eq!(ping.unwrap()["pong"], true);
+    assert_eq!(status.unwrap()["channel"], "imsg");
+
+    let (_ack, mut subscription) = runtime
+        .block_on(client.subscribe("channel.watch", json!({"channel": "imsg"})))
+        .unwrap();
+
+    daemon.restart();
+
+    runtime.block_on(async {
+        assert_eq!(client.call("ping", json!({})).await.unwrap()["pong"], true);
+        let mut events = Vec::new();
+        while let Some(event) = subscription.next().await {
+            events.push(event.unwrap());
+        }
+        assert!(events.len() >= 2, "expected the resubscribed stream's events, got {events:?}");
+    });
+}
+
+#[test]
+fn call_times_out() {
+    let daemon = TestDaemon::start();
+    let mut client = daemon.client();
+
+    // The late pong must not be taken as the next call's response.
+    let err = client
+        .call_with_timeout("ping", json!({}), Some(Duration::ZERO))
+        .unwrap_err();
+    assert!(matches!(err, carapace_client::ClientError::Timeout(_)), "got {err}");
+    assert_eq!(client.call("ping", json!({})).unwrap()["pong"], true);
+}
+
 #[test]
 fn unknown_method_returns_error() {
     let daemon = TestDaemon::start();
@@ -316,3 +494,47 @@ fn malformed_json_returns_parse_error() {
     let resp: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
     assert_eq!(resp["error"]["code"], -32700); // PARSE_ERROR
 }
+
+#[test]
+fn hello_rejects_incompatible_protocol_version() {
+    let daemon = TestDaemon::start();
+    let mut client = daemon.client();
+
+    let result = client.call("hello", json!({"protocol_version": "1.0", "client": "test"})).unwrap();
+    assert_eq!(result["protocol_version"], "1.0");
+
+    let err = client.call("hello", json!({"protocol_version": "2.0"})).unwrap_err();
+    match err {
+        carapace_client::ClientError::IncompatibleVersion { data, .. } => {
+            assert_eq!(data.unwrap()["server_version"], "1.0");
+        }
+        other => panic!("expected IncompatibleVersion, got: {other}"),
+    }
+}
+
+#[test]
+fn incompatible_hello_closes_the_connection() {
+    let daemon = TestDaemon::start();
+
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixStream;
+
+    let stream = UnixStream::connect(&daemon.socket_path).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    let mut reader = BufReader::new(stream);
+
+    writer
+        .write_all(b"{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"hello\",\"params\":{\"protocol_version\":\"2.0\"}}\n")
+        .unwrap();
+    writer.flush().unwrap();
+
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap();
+    let resp: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+    assert_eq!(resp["error"]["code"], -32006); // INCOMPATIBLE_VERSION
+
+    // Later requests are not served; the daemon hangs up instead.
+    let _ = writer.write_all(b"{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}\n");
+    line.clear();
+    assert_eq!(reader.read_line(&mut line).unwrap_or(0), 0, "got {line}");
+}
@@ -0,0 +1,490 @@
+//! Obtaining a refresh token: the loopback (browser) flow with PKCE and the
+//! device authorization flow.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use tokio::time::Instant;
+
+use crate::token::TOKEN_URL;
+
+/// Grant type for polling the token endpoint during the device flow.
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// The only scopes Google grants through the device flow.
+const DEVICE_FLOW_SCOPES: &[&str] = &[
+    "openid",
+    "email",
+    "profile",
+    "https://www.googleapis.com/auth/userinfo.email",
+    "https://www.googleapis.com/auth/userinfo.profile",
+    "https://www.googleapis.com/auth/drive.appdata",
+    "https://www.googleapis.com/auth/drive.file",
+    "https://www.googleapis.com/auth/youtube",
+    "https://www.googleapis.com/auth/youtube.readonly",
+];
+
+/// The OAuth endpoints a flow talks to. [`Endpoints::google`] in production;
+/// tests point them at a local server.
+#[derive(Debug, Clone)]
+pub struct Endpoints {
+    pub auth_url: String,
+    pub token_url: String,
+    pub device_url: String,
+    pub revoke_url: String,
+}
+
+impl Endpoints {
+    pub fn google() -> Self {
+        Self {
+            auth_url: "https://accounts.google.com/o/oauth2/v2/auth".into(),
+            token_url: TOKEN_URL.into(),
+            device_url: "https://oauth2.googleapis.com/device/code".into(),
+            revoke_url: "https://oauth2.googleapis.com/revoke".into(),
+        }
+    }
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self::google()
+    }
+}
+
+/// How the account owner grants consent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Flow {
+    /// Open a browser on this machine; Google redirects back to a local port.
+    #[default]
+    Browser,
+    /// Show a code to enter at Google's device page from any other device.
+    Device,
+}
+
+/// Flow selection shared by the proxies' `setup` and `reauth` subcommands.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct FlowArgs {
+    /// How to grant consent.
+    #[arg(long, value_enum, default_value_t = Flow::Browser)]
+    pub flow: Flow,
+    /// Local port for the browser flow's redirect (0 picks a free one).
+    /// Fix it to forward the port over SSH when setting up a headless host.
+    #[arg(long, default_value_t = 0)]
+    pub port: u16,
+}
+
+/// The OAuth client the proxy authenticates as.
+#[derive(Debug, Clone)]
+pub struct ClientCredentials {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// The result of a successful flow.
+#[derive(Debug, Clone)]
+pub struct Grant {
+    pub refresh_token: String,
+    /// Scopes the account owner actually granted.
+    pub scopes: Vec<String>,
+}
+
+/// Successful token endpoint response (the fields the flows need).
+#[derive(Debug, serde::Deserialize)]
+struct GrantResponse {
+    refresh_token: Option<String>,
+    scope: Option<String>,
+}
+
+/// Error body from the OAuth endpoints.
+#[derive(Debug, Default, serde::Deserialize)]
+struct OAuthError {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Response from the device authorization endpoint. Google calls the URL
+/// `verification_url`; RFC 8628 calls it `verification_uri`.
+#[derive(Debug, serde::Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    #[serde(alias = "verification_uri")]
+    verification_url: String,
+    expires_in: u64,
+    #[serde(default = "default_poll_interval")]
+    interval: u64,
+}
+
+fn default_poll_interval() -> u64 {
+    5
+}
+
+/// Fail before contacting Google when `flow` cannot grant `scopes`: the
+/// device flow only covers [`DEVICE_FLOW_SCOPES`].
+pub fn check_flow(flow: &FlowArgs, scopes: &[&str]) -> Result<()> {
+    if flow.flow != Flow::Device {
+        return Ok(());
+    }
+    let refused: Vec<&str> = scopes.iter().copied().filter(|s| !DEVICE_FLOW_SCOPES.contains(s)).collect();
+    if !refused.is_empty() {
+        anyhow::bail!(
+            "The device flow cannot grant {}.\n\
+             Google only allows a few scopes with device codes. Use --flow browser instead; \
+             on a headless host, pick a --port and forward it with `ssh -L`.",
+            refused.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Run the chosen flow and return the refresh token it produced.
+pub async fn authorize(
+    endpoints: &Endpoints,
+    client: &ClientCredentials,
+    scopes: &[&str],
+    flow: &FlowArgs,
+) -> Result<Grant> {
+    check_flow(flow, scopes)?;
+    match flow.flow {
+        Flow::Browser => loopback(endpoints, client, scopes, flow.port, open_browser).await,
+        Flow::Device => device(endpoints, client, scopes).await,
+    }
+}
+
+/// Revoke a refresh token. Google revokes the whole grant it belongs to,
+/// including access tokens issued from it.
+pub async fn revoke(endpoints: &Endpoints, token: &str) -> Result<()> {
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("token", token)
+        .finish();
+    let resp = post_form(&endpoints.revoke_url, body)
+        .await
+        .context("failed to revoke the previous refresh token")?;
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Token revocation failed with status {status}: {text}");
+    }
+    Ok(())
+}
+
+fn open_browser(auth_url: &str) {
+    println!("\nOpening browser for Google OAuth consent...");
+    println!("If the browser doesn't open automatically, visit:\n\n  {auth_url}\n");
+
+    if let Err(e) = open::that(auth_url) {
+        eprintln!("Warning: could not open browser: {e}");
+    }
+}
+
+/// A PKCE verifier and its S256 challenge (RFC 7636).
+struct Pkce {
+    verifier: String,
+    challenge: String,
+}
+
+impl Pkce {
+    fn new() -> Self {
+        Self::from_verifier(random_token())
+    }
+
+    fn from_verifier(verifier: String) -> Self {
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self { verifier, challenge }
+    }
+}
+
+/// 32 random bytes, base64url: a 43-character PKCE verifier or `state` value.
+fn random_token() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+/// The loopback flow: `visit` is handed the consent URL, and Google redirects
+/// the browser to a local listener with the code. The code is only usable
+/// with this run's PKCE verifier, and the redirect must echo `state`.
+async fn loopback(
+    endpoints: &Endpoints,
+    client: &ClientCredentials,
+    scopes: &[&str],
+    port: u16,
+    visit: impl FnOnce(&str),
+) -> Result<Grant> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("failed to listen on 127.0.0.1:{port}"))?;
+    let port = listener.local_addr()?.port();
+    let redirect_uri = format!("http://127.0.0.1:{port}");
+    let pkce = Pkce::new();
+    let state = random_token();
+
+    let auth_url = format!(
+        "{}?{}",
+        endpoints.auth_url,
+        form_urlencoded::Serializer::new(String::new())
+            .append_pair("client_id", &client.client_id)
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", &scopes.join(" "))
+            .append_pair("access_type", "offline")
+            .append_pair("prompt", "consent")
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", &state)
+            .finish()
+    );
+    visit(&auth_url);
+
+    let code = receive_code(listener, state).await?;
+
+    println!("Received authorization code. Exchanging for tokens...");
+
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("grant_type", "authorization_code")
+        .append_pair("code", &code)
+        .append_pair("client_id", &client.client_id)
+        .append_pair("client_secret", &client.client_secret)
+        .append_pair("redirect_uri", &redirect_uri)
+        .append_pair("code_verifier", &pkce.verifier)
+        .finish();
+    let resp = post_form(&endpoints.token_url, body)
+        .await
+        .context("failed to exchange authorization code")?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        anyhow::bail!("Token exchange failed with status {status}: {text}");
+    }
+    grant(resp.json().await.context("failed to parse token response")?)
+}
+
+/// Serve the OAuth redirect on `listener` until it delivers a code for
+/// `state`, or an error. Redirects with another `state` are ignored.
+async fn receive_code(listener: tokio::net::TcpListener, state: String) -> Result<String> {
+    type Outcome = std::result::Result<String, String>;
+    let (tx, rx) = tokio::sync::oneshot::channel::<Outcome>();
+    let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
+
+    let callback_handler = {
+        let tx = tx.clone();
+        move |axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>| {
+            let tx = tx.clone();
+            let state = state.clone();
+            async move {
+                if params.get("state") != Some(&state) {
+                    return axum::response::Html(
+                        "<html><body><h1>Authorization failed</h1>\
+                         <p>This response is not from the current setup run.</p></body></html>"
+                            .to_string(),
+                    );
+                }
+                let outcome = match params.get("code") {
+                    Some(code) => Ok(code.clone()),
+                    None => Err(params.get("error").cloned().unwrap_or_else(|| "unknown error".into())),
+                };
+                let page = match &outcome {
+                    Ok(_) => "<html><body><h1>Authorization successful!</h1>\
+                              <p>You can close this tab and return to the terminal.</p></body></html>"
+                        .to_string(),
+                    Err(error) => format!(
+                        "<html><body><h1>Authorization failed</h1><p>{error}</p></body></html>"
+                    ),
+                };
+                if let Some(sender) = tx.lock().await.take() {
+                    let _ = sender.send(outcome);
+                }
+                axum::response::Html(page)
+            }
+        }
+    };
+
+    let app = axum::Router::new().route("/", axum::routing::get(callback_handler));
+    let server = axum::serve(listener, app);
+    let outcome = tokio::select! {
+        result = server => {
+            result.context("OAuth callback server error")?;
+            anyhow::bail!("callback server exited unexpectedly");
+        }
+        outcome = rx => outcome.context("failed to receive authorization code")?,
+    };
+    outcome.map_err(|error| anyhow::anyhow!("Authorization failed: {error}"))
+}
+
+/// The device flow (RFC 8628): show a code, then poll the token endpoint
+/// until the account owner approves it elsewhere.
+async fn device(endpoints: &Endpoints, client: &ClientCredentials, scopes: &[&str]) -> Result<Grant> {
+    let body = form_urlencoded::Serializer::new(String::new())
+        .append_pair("client_id", &client.client_id)
+        .append_pair("scope", &scopes.join(" "))
+        .finish();
+    let resp = post_form(&endpoints.device_url, body)
+        .await
+        .context("failed to request a device code")?;
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        let error: OAuthError = serde_json::from_str(&text).unwrap_or_default();
+        if error.error == "invalid_scope" {
+            anyhow::bail!(
+                "Google refused these scopes for the device flow ({text}).\n\
+                 Google only allows a few scopes with device codes. Use --flow browser instead; \
+                 on a headless host, pick a --port and forward it with `ssh -L`."
+            );
+        }
+        anyhow::bail!("Device code request failed with status {status}: {text}");
+    }
+    let code: DeviceCodeResponse = resp.json().await.context("failed to parse device code response")?;
+
+    println!("\nOn any device, visit:\n\n  {}\n\nand enter the code:\n\n  {}\n", code.verification_url, code.user_code);
+    println!("Waiting for approval...");
+
+    let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = Duration::from_secs(code.interval);
+    loop {
+        tokio::time::sleep(interval).await;
+        if Instant::now() >= deadline {
+            anyhow::bail!("The device code expired before it was approved. Run the command again.");
+        }
+
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("grant_type", DEVICE_GRANT)
+            .append_pair("device_code", &code.device_code)
+            .append_pair("client_id", &client.client_id)
+            .append_pair("client_secret", &client.client_secret)
+            .finish();
+        let resp = post_form(&endpoints.token_url, body)
+            .await
+            .context("failed to poll for the device grant")?;
+        let status = resp.status();
+        if status.is_success() {
+            println!("Approved.");
+            return grant(resp.json().await.context("failed to parse token response")?);
+        }
+
+        let text = resp.text().await.unwrap_or_default();
+        let error: OAuthError = serde_json::from_str(&text).unwrap_or_default();
+        match error.error.as_str() {
+            "authorization_pending" => {}
+            "slow_down" => interval += Duration::from_secs(5),
+            "access_denied" => anyhow::bail!("Authorization was denied on the device page."),
+            "expired_token" => {
+                anyhow::bail!("The device code expired before it was approved. Run the command again.")
+            }
+            _ => anyhow::bail!(
+                "Device authorization failed with status {status}: {}",
+                error.error_description.unwrap_or(text)
+            ),
+        }
+    }
+}
+
+fn grant(resp: GrantResponse) -> Result<Grant> {
+    let refresh_token = resp.refresh_token.context(
+        "No refresh_token in response.\n\
+         Try revoking access at https://myaccount.google.com/permissions and re-running setup.",
+    )?;
+    let scopes = resp
+        .scope
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+    Ok(Grant { refresh_token, scopes })
+}
+
+async fn post_form(url: &str, body: String) -> reqwest::Result<reqwest::Response> {
+    reqwest::Client::new()
+        .post(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(body)
+        .send()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockGoogle;
+
+    fn client() -> ClientCredentials {
+        ClientCredentials { client_id: "cid".into(), client_secret: "csecret".into() }
+    }
+
+    #[test]
+    fn pkce_challenge_matches_rfc7636() {
+        let pkce = Pkce::from_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk".into());
+        assert_eq!(pkce.challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+        assert_eq!(Pkce::new().verifier.len(), 43);
+    }
+
+    #[tokio::test]
+    async fn device_flow_polls_until_approved() {
+        let mock = MockGoogle::start(2).await;
+        let grant = device(&mock.endpoints(), &client(), &["scope.a", "scope.b"]).await.unwrap();
+        assert_eq!(grant.refresh_token, "rt-new");
+        assert_eq!(grant.scopes, ["scope.a", "scope.b"]);
+
+        let requests = mock.token_requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|r| r["grant_type"] == DEVICE_GRANT && r["device_code"] == "dev-1"));
+        assert_eq!(mock.device_requests()[0]["scope"], "scope.a scope.b");
+    }
+
+    #[tokio::test]
+    async fn device_flow_reports_denial_and_refused_scopes() {
+        let mock = MockGoogle::start(0).await;
+        mock.deny();
+        let err = device(&mock.endpoints(), &client(), &["scope.a"]).await.unwrap_err();
+        assert!(err.to_string().contains("denied"), "{err}");
+
+        let err = device(&mock.endpoints(), &client(), &["https://mail.google.com/"]).await.unwrap_err();
+        assert!(err.to_string().contains("--flow browser"), "{err}");
+    }
+
+    #[test]
+    fn device_flow_is_refused_for_scopes_google_will_not_grant() {
+        let device = FlowArgs { flow: Flow::Device, port: 0 };
+        let err = check_flow(&device, &["openid", "https://www.googleapis.com/auth/gmail.readonly"]).unwrap_err();
+        assert!(err.to_string().contains("gmail.readonly"), "{err}");
+        assert!(!err.to_string().contains("openid"), "{err}");
+        assert!(err.to_string().contains("--flow browser"), "{err}");
+
+        assert!(check_flow(&device, &["https://www.googleapis.com/auth/drive.file"]).is_ok());
+        assert!(check_flow(&FlowArgs::default(), &["https://www.googleapis.com/auth/gmail.readonly"]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn loopback_flow_checks_state_and_sends_the_pkce_verifier() {
+        let mock = MockGoogle::start(0).await;
+        let (url_tx, url_rx) = tokio::sync::oneshot::channel();
+        let (endpoints, client) = (mock.endpoints(), client());
+        let flow = loopback(&endpoints, &client, &["scope.a"], 0, |url| {
+            url_tx.send(url.to_string()).unwrap();
+        });
+
+        let browser = async {
+            let url = reqwest::Url::parse(&url_rx.await.unwrap()).unwrap();
+            let param = |name: &str| url.query_pairs().find(|(k, _)| k == name).unwrap().1.into_owned();
+            assert_eq!(param("code_challenge_method"), "S256");
+            let redirect = param("redirect_uri");
+
+            // A redirect from another run is ignored; the real one completes the flow.
+            let http = reqwest::Client::new();
+            http.get(format!("{redirect}/?code=stale&state=other")).send().await.unwrap();
+            http.get(format!("{redirect}/?code=auth-1&state={}", param("state"))).send().await.unwrap();
+            param("code_challenge")
+        };
+
+        let (grant, challenge) = tokio::join!(flow, browser);
+        assert_eq!(grant.unwrap().refresh_token, "rt-new");
+
+        let requests = mock.token_requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0]["code"], "auth-1");
+        assert_eq!(Pkce::from_verifier(requests[0]["code_verifier"].clone()).challenge, challenge);
+    }
+}
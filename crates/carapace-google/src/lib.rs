@@ -0,0 +1,33 @@
+//! Google OAuth and shared file handling for the Carapace proxies.
+//!
+//! gmail-proxy and gdocs-proxy authenticate the same way: an installed-app
+//! OAuth client whose id and secret sit in the proxy's `config.toml`, and a
+//! refresh token in a separate 0600 secrets file written by `setup`. This
+//! crate holds that shared part:
+//!
+//!   - [`secrets`] — the `[auth]` config section and the secrets file
+//!   - [`store`] — [`SecretStore`]: the secrets file in plaintext or encrypted
+//!   - [`token`] — [`TokenManager`], which caches and refreshes access tokens
+//!   - [`flow`] — the consent flows: browser (loopback with PKCE) or device code
+//!   - [`setup`] — the `setup` and `reauth` subcommands that store the refresh token
+//!
+//! It also holds [`text`], the PDF text extraction and truncation both proxies
+//! apply to files they read.
+//!
+//! Each proxy picks its own scopes and secrets file name.
+
+pub mod flow;
+pub mod secrets;
+pub mod setup;
+pub mod store;
+pub mod text;
+pub mod token;
+
+#[cfg(test)]
+mod testing;
+
+pub use flow::{Endpoints, Flow, FlowArgs};
+pub use secrets::{load_secrets, write_secrets, AuthConfig, Secrets};
+pub use setup::{run_migrate_secrets, run_oauth_setup, run_reauth, SetupOptions};
+pub use store::{EncryptionConfig, SecretStore};
+pub use token::{TokenManager, TOKEN_URL};
@@ -0,0 +1,210 @@
+//! The `[auth]` config section and the refresh-token secrets file.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{open_store, EncryptionConfig, SecretStore};
+
+/// `[auth]` in a proxy's `config.toml`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Path of the secrets file, relative to the config file's directory.
+    pub secrets_file: String,
+    /// Encrypt the secrets file (see [`crate::store`]).
+    #[serde(default)]
+    pub encryption: Option<EncryptionConfig>,
+}
+
+impl AuthConfig {
+    /// The store holding this config's secrets. `config_dir` is the
+    /// directory of the config file.
+    pub fn secret_store(&self, config_dir: &Path, skip_permission_check: bool) -> Result<Box<dyn SecretStore>> {
+        open_store(config_dir, &self.secrets_file, self.encryption.as_ref(), skip_permission_check, false)
+    }
+}
+
+/// Contents of the secrets file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Secrets {
+    pub refresh_token: String,
+    /// Scopes granted with the token. Empty in files written before scopes
+    /// were recorded.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
+}
+
+impl Secrets {
+    /// Whether the token was granted `scope`. Tokens with no recorded scopes
+    /// are assumed to have everything the proxy asked for.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.is_empty() || self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Read a secrets file. On Unix, refuses files that are not 0600 unless
+/// `skip_permission_check` is set.
+pub fn load_secrets(path: &Path, skip_permission_check: bool) -> Result<Secrets> {
+    if !skip_permission_check {
+        check_private(path, "secrets file")?;
+    }
+
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read secrets file: {}", path.display()))?;
+    toml::from_str(&text).with_context(|| format!("failed to parse secrets file: {}", path.display()))
+}
+
+/// Write a secrets file with 0600 permissions. A new file is created 0600,
+/// so the token is never readable by others.
+pub fn write_secrets(path: &Path, secrets: &Secrets) -> Result<()> {
+    let content = toml::to_string(secrets).context("failed to serialize secrets")?;
+    write_private(path, content.as_bytes())
+}
+
+/// On Unix, fail unless `path` is 0600. `what` names the file in the error.
+pub(crate) fn check_private(path: &Path, what: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("failed to stat {what}: {}", path.display()))?;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode != 0o600 {
+            anyhow::bail!(
+                "{what} {} has permissions {:04o}, expected 0600",
+                path.display(),
+                mode
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write `content` to a 0600 file.
+///
+/// The content goes to a temporary file next to `path` that then replaces
+/// it, so a failed write leaves the old file whole. The setup guides
+/// pre-create the file in a directory the proxy user cannot write to; there
+/// the file is rewritten in place instead.
+pub(crate) fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = dir.join(format!(".{name}.{:016x}.tmp", rand::random::<u64>()));
+    match open_private(&temp, true) {
+        Ok(file) => {
+            let replaced = fill(file, content).and_then(|()| std::fs::rename(&temp, path));
+            if let Err(e) = replaced {
+                let _ = std::fs::remove_file(&temp);
+                return Err(e).with_context(|| format!("failed to write {}", path.display()));
+            }
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => write_in_place(path, content),
+        Err(e) => Err(e).with_context(|| format!("failed to write {}", path.display())),
+    }
+}
+
+/// Truncate and rewrite `path`. A failure part way loses the old content.
+fn write_in_place(path: &Path, content: &[u8]) -> Result<()> {
+    let file = open_private(path, false).with_context(|| format!("failed to write {}", path.display()))?;
+    fill(file, content).with_context(|| format!("failed to write {}", path.display()))?;
+
+    // An existing file keeps its old mode on open; tighten it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Open `path` for writing, created 0600: as a new file, or truncated.
+fn open_private(path: &Path, create_new: bool) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    if create_new {
+        options.create_new(true);
+    } else {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Write all of `content` and flush it to disk.
+fn fill(mut file: std::fs::File, content: &[u8]) -> std::io::Result<()> {
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_and_require_0600() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.toml");
+        let secrets = Secrets { refresh_token: "1//tok\"en".into(), scopes: Vec::new() };
+        write_secrets(&path, &secrets).unwrap();
+        assert_eq!(load_secrets(&path, false).unwrap().refresh_token, "1//tok\"en");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+            let err = load_secrets(&path, false).unwrap_err();
+            assert!(err.to_string().contains("expected 0600"), "{err}");
+            assert!(load_secrets(&path, true).is_ok());
+
+            write_secrets(&path, &secrets).unwrap();
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+            assert_eq!(mode, 0o600);
+        }
+    }
+
+    #[test]
+    fn private_writes_replace_the_file_or_rewrite_it_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.toml");
+        std::fs::write(&path, "old").unwrap();
+
+        write_private(&path, b"new").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        let names: Vec<_> = std::fs::read_dir(dir.path()).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, ["secrets.toml"], "temporary file left behind");
+
+        // The fallback for a directory the proxy user cannot write to.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        }
+        write_in_place(&path, b"newer").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "newer");
+        check_private(&path, "secrets file").unwrap();
+    }
+
+    #[test]
+    fn missing_scopes_mean_unknown() {
+        let old: Secrets = toml::from_str("refresh_token = \"rt\"\n").unwrap();
+        assert!(old.has_scope("https://www.googleapis.com/auth/gmail.compose"));
+
+        let read_only = Secrets { refresh_token: "rt".into(), scopes: vec!["a.readonly".into()] };
+        assert!(read_only.has_scope("a.readonly"));
+        assert!(!read_only.has_scope("a.compose"));
+        let text = toml::to_string(&read_only).unwrap();
+        assert_eq!(toml::from_str::<Secrets>(&text).unwrap().scopes, ["a.readonly"]);
+    }
+}
@@ -0,0 +1,434 @@
+//! The `setup` and `reauth` subcommands: obtain a refresh token and store it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use zeroize::Zeroizing;
+
+use crate::flow::{authorize, check_flow, revoke, ClientCredentials, Endpoints, FlowArgs};
+use crate::secrets::{load_secrets, write_private, Secrets};
+use crate::store::{is_encrypted, open_store, EncryptionConfig, SecretStore};
+
+/// What differs between the proxies' setup flows.
+pub struct SetupOptions<'a> {
+    /// The proxy binary, for the closing "start the proxy with" hint.
+    pub program: &'a str,
+    /// OAuth scopes to request.
+    pub scopes: &'a [&'a str],
+    /// Secrets file name used when the config has no `auth.secrets_file`.
+    pub default_secrets_file: &'a str,
+    /// Browser or device flow.
+    pub flow: FlowArgs,
+    pub endpoints: Endpoints,
+}
+
+/// Google client_secret JSON structure.
+#[derive(Debug, serde::Deserialize)]
+struct GoogleClientSecretFile {
+    installed: Option<GoogleClientCreds>,
+    web: Option<GoogleClientCreds>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct GoogleClientCreds {
+    client_id: String,
+    client_secret: String,
+}
+
+/// Run the OAuth setup flow.
+///
+/// 1. Reads credentials from --client-json or existing config
+/// 2. Has the account owner grant consent (browser or device flow)
+/// 3. Writes the refresh token to the config's `auth.secrets_file` (0600)
+pub async fn run_oauth_setup(
+    config_path: PathBuf,
+    client_json: Option<PathBuf>,
+    options: &SetupOptions<'_>,
+) -> Result<()> {
+    require_config(&config_path)?;
+    check_flow(&options.flow, options.scopes)?;
+    let client = client_credentials(&config_path, client_json)?;
+
+    let grant = authorize(&options.endpoints, &client, options.scopes, &options.flow).await?;
+
+    let store = secret_store(&config_path, options.default_secrets_file, false, true)?;
+    store.save(&Secrets { refresh_token: grant.refresh_token, scopes: grant.scopes })?;
+
+    println!("\nSetup complete!");
+    println!("  Config:  {}", config_path.display());
+    println!("  Secrets: {} (0600)", store.path().display());
+    println!(
+        "\nStart the proxy with:\n  {} serve --config {}",
+        options.program,
+        config_path.display()
+    );
+
+    Ok(())
+}
+
+/// Replace the stored refresh token with a freshly granted one, using the
+/// client credentials already in the config.
+///
+/// With `revoke_old`, the previous token is revoked first. Google revokes a
+/// grant as a whole, so this cannot wait until the new token exists; the
+/// running proxy loses access until it is restarted with the new token, and
+/// has none at all if consent then fails.
+pub async fn run_reauth(config_path: PathBuf, revoke_old: bool, options: &SetupOptions<'_>) -> Result<()> {
+    require_config(&config_path)?;
+    check_flow(&options.flow, options.scopes)?;
+    let client = client_credentials(&config_path, None)?;
+    let store = secret_store(&config_path, options.default_secrets_file, true, true)?;
+    let previous = store.load().ok();
+
+    let mut revoked = false;
+    if revoke_old {
+        match &previous {
+            Some(previous) => {
+                println!(
+                    "Revoking the previous refresh token. The proxy loses access now and has no \
+                     working token until the consent below completes."
+                );
+                revoke(&options.endpoints, &previous.refresh_token).await?;
+                revoked = true;
+                println!("Revoked the previous refresh token.");
+            }
+            None => println!("No previous refresh token in {}; nothing to revoke.", store.path().display()),
+        }
+    }
+
+    let saved = authorize(&options.endpoints, &client, options.scopes, &options.flow)
+        .await
+        .and_then(|grant| store.save(&Secrets { refresh_token: grant.refresh_token, scopes: grant.scopes }));
+    if let Err(e) = saved {
+        if !revoked {
+            return Err(e);
+        }
+        return Err(e.context(format!(
+            "the previous refresh token was already revoked, so the proxy has no working token; \
+             rerun `{} reauth --config {}` without --revoke-old",
+            options.program,
+            config_path.display()
+        )));
+    }
+
+    println!("\nRefresh token replaced in {} (0600).", store.path().display());
+    if previous.is_some() && !revoke_old {
+        println!("The previous token stays valid until revoked (rerun with --revoke-old to revoke it first).");
+    }
+    println!(
+        "\nRestart the proxy to use it:\n  {} serve --config {}",
+        options.program,
+        config_path.display()
+    );
+
+    Ok(())
+}
+
+/// Encrypt a plaintext secrets file in place, as the config's
+/// `[auth.encryption]` section describes. A missing or empty key file is
+/// filled with a new key.
+pub fn run_migrate_secrets(config_path: PathBuf, program: &str, default_secrets_file: &str) -> Result<()> {
+    require_config(&config_path)?;
+    let settings = store_settings(&config_path)?;
+    if settings.encryption.is_none() {
+        anyhow::bail!(
+            "{} has no [auth.encryption] section.\n\
+             Add one with key_file, passphrase_env or passphrase_fd, then run migrate-secrets again.",
+            config_path.display()
+        );
+    }
+    let store = secret_store(&config_path, default_secrets_file, false, true)?;
+
+    let text = std::fs::read_to_string(store.path())
+        .with_context(|| format!("failed to read secrets file: {}", store.path().display()))?;
+    if is_encrypted(&text) {
+        store.load()?;
+        println!("{} is already encrypted.", store.path().display());
+        return Ok(());
+    }
+
+    let secrets = load_secrets(store.path(), false)?;
+    encrypt_or_restore(store.as_ref(), &secrets, &Zeroizing::new(text))?;
+
+    println!("Encrypted {}.", store.path().display());
+    println!(
+        "Backups or snapshots taken earlier still hold the plaintext token; \
+         rotate it with:\n  {program} reauth --config {} --revoke-old",
+        config_path.display()
+    );
+    Ok(())
+}
+
+/// Save `secrets` encrypted and check they read back. On failure the file
+/// gets its `plaintext` back: it may have been rewritten in place, and it
+/// holds the only copy of the token.
+fn encrypt_or_restore(store: &dyn SecretStore, secrets: &Secrets, plaintext: &str) -> Result<()> {
+    let saved = store.save(secrets).and_then(|()| {
+        if store.load()?.refresh_token != secrets.refresh_token {
+            anyhow::bail!("encrypted secrets did not read back correctly");
+        }
+        Ok(())
+    });
+    let Err(e) = saved else {
+        return Ok(());
+    };
+    match write_private(store.path(), plaintext.as_bytes()) {
+        Ok(()) => Err(e.context(format!("{} was left unencrypted", store.path().display()))),
+        Err(restore) => Err(e.context(format!(
+            "{} could not be restored ({restore:#}) and may need `setup` again",
+            store.path().display()
+        ))),
+    }
+}
+
+fn require_config(config_path: &Path) -> Result<()> {
+    if !config_path.exists() {
+        anyhow::bail!(
+            "Config file not found at {}.\n\
+             Create it first (see the Carapace docs).",
+            config_path.display()
+        );
+    }
+    Ok(())
+}
+
+/// The OAuth client id and secret: imported from Google's client_secret JSON
+/// (and saved into the config), or read from the config.
+fn client_credentials(config_path: &Path, client_json: Option<PathBuf>) -> Result<ClientCredentials> {
+    let config_text = std::fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+
+    if let Some(json_path) = client_json {
+        let json_text = std::fs::read_to_string(&json_path)
+            .with_context(|| format!("failed to read {}", json_path.display()))?;
+        let parsed: GoogleClientSecretFile = serde_json::from_str(&json_text)
+            .context("failed to parse client_secret JSON")?;
+        let creds = parsed.installed.or(parsed.web)
+            .context("client_secret JSON has neither 'installed' nor 'web' key")?;
+
+        // Patch client_id / client_secret lines in the config file in-place.
+        let updated: String = config_text
+            .lines()
+            .map(|line| {
+                let t = line.trim_start();
+                if t.starts_with("client_id") && t.contains('=') {
+                    format!("client_id = \"{}\"", creds.client_id)
+                } else if t.starts_with("client_secret") && t.contains('=') {
+                    format!("client_secret = \"{}\"", creds.client_secret)
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        std::fs::write(config_path, &updated)
+            .with_context(|| format!("failed to update {}", config_path.display()))?;
+        println!("Updated client_id and client_secret in {}", config_path.display());
+        return Ok(ClientCredentials { client_id: creds.client_id, client_secret: creds.client_secret });
+    }
+
+    let parsed: toml::Value =
+        toml::from_str(&config_text).context("failed to parse config.toml")?;
+    let client_id = parsed
+        .get("auth").and_then(|a| a.get("client_id")).and_then(|v| v.as_str())
+        .context("client_id not found in config.toml")?.to_string();
+    let client_secret = parsed
+        .get("auth").and_then(|a| a.get("client_secret")).and_then(|v| v.as_str())
+        .context("client_secret not found in config.toml")?.to_string();
+
+    if client_id.contains("YOUR_CLIENT") || client_secret.contains("YOUR_CLIENT") {
+        anyhow::bail!(
+            "config.toml still has placeholder credentials.\n\
+             Either edit them manually or use --client-json to import from Google's JSON file."
+        );
+    }
+    Ok(ClientCredentials { client_id, client_secret })
+}
+
+/// The `[auth]` keys that say where secrets are stored, read on their own
+/// so that `setup` works before the rest of the config is filled in.
+#[derive(Debug, Default, serde::Deserialize)]
+struct StoreSettings {
+    secrets_file: Option<String>,
+    encryption: Option<EncryptionConfig>,
+}
+
+fn store_settings(config_path: &Path) -> Result<StoreSettings> {
+    let text = std::fs::read_to_string(config_path)
+        .with_context(|| format!("failed to read {}", config_path.display()))?;
+    let parsed: toml::Value = toml::from_str(&text).context("failed to parse config.toml")?;
+    match parsed.get("auth") {
+        Some(auth) => auth.clone().try_into().context("invalid [auth] section in config.toml"),
+        None => Ok(StoreSettings::default()),
+    }
+}
+
+/// The store `serve` will read: the config's `auth.secrets_file`, relative
+/// to the config directory, encrypted if `[auth.encryption]` is set.
+fn secret_store(
+    config_path: &Path,
+    default_file: &str,
+    skip_permission_check: bool,
+    create_key: bool,
+) -> Result<Box<dyn SecretStore>> {
+    let config_dir = config_path.parent().unwrap_or(Path::new("."));
+    let settings = store_settings(config_path)?;
+    let store = open_store(
+        config_dir,
+        settings.secrets_file.as_deref().unwrap_or(default_file),
+        settings.encryption.as_ref(),
+        skip_permission_check,
+        create_key,
+    )?;
+    if let Some(key_file) = store.created_key_file() {
+        println!("Wrote a new secrets key to {} (0600). Keep it out of backups of the secrets file.", key_file.display());
+    }
+    Ok(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flow::Flow;
+    use crate::testing::MockGoogle;
+
+    #[test]
+    fn secrets_path_follows_the_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("gmail-proxy.toml");
+        let path = |config: &Path| secret_store(config, "secrets.toml", true, false).unwrap().path().to_path_buf();
+
+        std::fs::write(&config, "[auth]\nsecrets_file = \"secrets-work.toml\"\n").unwrap();
+        assert_eq!(path(&config), dir.path().join("secrets-work.toml"));
+
+        std::fs::write(&config, "[auth]\nclient_id = \"x\"\n").unwrap();
+        assert_eq!(path(&config), dir.path().join("secrets.toml"));
+
+        // A malformed encryption section must not fall back to plaintext.
+        std::fs::write(&config, "[auth.encryption]\nkey_file = 7\n").unwrap();
+        assert!(secret_store(&config, "secrets.toml", true, true).is_err());
+    }
+
+    #[test]
+    fn migrate_secrets_encrypts_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("gmail-proxy.toml");
+        let secrets = dir.path().join("secrets.toml");
+        std::fs::write(&config, "[auth]\nsecrets_file = \"secrets.toml\"\n").unwrap();
+        crate::write_secrets(&secrets, &Secrets { refresh_token: "rt-plain".into(), scopes: Vec::new() }).unwrap();
+
+        let err = run_migrate_secrets(config.clone(), "gmail-proxy", "secrets.toml").unwrap_err();
+        assert!(err.to_string().contains("[auth.encryption]"), "{err}");
+
+        std::fs::write(
+            &config,
+            "[auth]\nsecrets_file = \"secrets.toml\"\n\n[auth.encryption]\nkey_file = \"secrets.key\"\n",
+        )
+        .unwrap();
+        run_migrate_secrets(config.clone(), "gmail-proxy", "secrets.toml").unwrap();
+        assert!(!std::fs::read_to_string(&secrets).unwrap().contains("rt-plain"));
+        // Running it again is harmless.
+        run_migrate_secrets(config.clone(), "gmail-proxy", "secrets.toml").unwrap();
+
+        let store = secret_store(&config, "secrets.toml", false, false).unwrap();
+        assert_eq!(store.load().unwrap().refresh_token, "rt-plain");
+    }
+
+    /// A store whose writes come out unreadable.
+    struct GarblingStore(PathBuf);
+
+    impl SecretStore for GarblingStore {
+        fn path(&self) -> &Path {
+            &self.0
+        }
+        fn load(&self) -> Result<Secrets> {
+            anyhow::bail!("unreadable")
+        }
+        fn save(&self, _: &Secrets) -> Result<()> {
+            std::fs::write(&self.0, "garbage")?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn migrate_secrets_restores_the_plaintext_when_the_check_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.toml");
+        let secrets = Secrets { refresh_token: "rt-plain".into(), scopes: Vec::new() };
+        crate::write_secrets(&path, &secrets).unwrap();
+        let plaintext = std::fs::read_to_string(&path).unwrap();
+
+        let err = encrypt_or_restore(&GarblingStore(path.clone()), &secrets, &plaintext).unwrap_err();
+        assert!(err.to_string().contains("left unencrypted"), "{err}");
+        assert!(format!("{err:#}").contains("unreadable"), "{err:#}");
+        assert_eq!(load_secrets(&path, false).unwrap().refresh_token, "rt-plain");
+    }
+
+    const DRIVE_FILE: &str = "https://www.googleapis.com/auth/drive.file";
+
+    /// A gdocs-proxy config with an old token, in `dir`.
+    fn reauth_config(dir: &Path) -> (PathBuf, PathBuf) {
+        let config = dir.join("gdocs-proxy.toml");
+        std::fs::write(
+            &config,
+            "[auth]\nclient_id = \"cid\"\nclient_secret = \"csecret\"\nsecrets_file = \"secrets-gdocs.toml\"\n",
+        )
+        .unwrap();
+        let secrets = dir.join("secrets-gdocs.toml");
+        crate::write_secrets(&secrets, &Secrets { refresh_token: "rt-old".into(), scopes: Vec::new() }).unwrap();
+        (config, secrets)
+    }
+
+    fn device_options<'a>(mock: &MockGoogle, scopes: &'a [&'a str]) -> SetupOptions<'a> {
+        SetupOptions {
+            program: "gdocs-proxy",
+            scopes,
+            default_secrets_file: "secrets-gdocs.toml",
+            flow: FlowArgs { flow: Flow::Device, port: 0 },
+            endpoints: mock.endpoints(),
+        }
+    }
+
+    #[tokio::test]
+    async fn reauth_revokes_and_replaces_the_token() {
+        let mock = MockGoogle::start(1).await;
+        let dir = tempfile::tempdir().unwrap();
+        let (config, secrets) = reauth_config(dir.path());
+
+        run_reauth(config, true, &device_options(&mock, &[DRIVE_FILE])).await.unwrap();
+
+        assert_eq!(mock.revoked(), ["rt-old"]);
+        assert_eq!(mock.device_requests()[0]["client_id"], "cid");
+        let rotated = load_secrets(&secrets, false).unwrap();
+        assert_eq!(rotated.refresh_token, "rt-new");
+        assert_eq!(rotated.scopes, [DRIVE_FILE]);
+    }
+
+    #[tokio::test]
+    async fn reauth_says_when_consent_fails_after_revoking() {
+        let mock = MockGoogle::start(0).await;
+        mock.deny();
+        let dir = tempfile::tempdir().unwrap();
+        let (config, _) = reauth_config(dir.path());
+
+        let err = run_reauth(config, true, &device_options(&mock, &[DRIVE_FILE])).await.unwrap_err();
+        assert_eq!(mock.revoked(), ["rt-old"]);
+        assert!(err.to_string().contains("already revoked"), "{err}");
+        assert!(format!("{err:#}").contains("denied"), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn device_flow_scopes_are_checked_before_revoking() {
+        let mock = MockGoogle::start(0).await;
+        let dir = tempfile::tempdir().unwrap();
+        let (config, secrets) = reauth_config(dir.path());
+
+        let scopes = ["https://www.googleapis.com/auth/drive.readonly"];
+        let err = run_reauth(config, true, &device_options(&mock, &scopes)).await.unwrap_err();
+        assert!(err.to_string().contains("--flow browser"), "{err}");
+        assert!(mock.revoked().is_empty());
+        assert!(mock.device_requests().is_empty());
+        assert_eq!(load_secrets(&secrets, false).unwrap().refresh_token, "rt-old");
+    }
+}
@@ -0,0 +1,501 @@
+//! Where a proxy keeps its refresh token: a plaintext TOML file, or the same
+//! TOML encrypted with XChaCha20-Poly1305.
+//!
+//! The encrypted file is selected by an `[auth.encryption]` config section.
+//! Its key is either read from a key file (32 random bytes, base64) or
+//! derived with Argon2id from a passphrase in an environment variable or on
+//! an inherited file descriptor. Either way the key must not sit next to the
+//! secrets file in the same backup for the encryption to be worth anything.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::secrets::{check_private, load_secrets, write_private, write_secrets, Secrets};
+
+/// Environment variable read when `[auth.encryption]` names no key source.
+pub const DEFAULT_PASSPHRASE_ENV: &str = "CARAPACE_SECRETS_PASSPHRASE";
+
+/// Authenticated with every ciphertext, so a file cannot be passed off as
+/// another format version.
+const AAD: &[u8] = b"carapace-secrets v1";
+const CIPHER: &str = "xchacha20poly1305";
+
+/// Loads and saves a proxy's [`Secrets`].
+pub trait SecretStore: Send + Sync {
+    /// The file the secrets live in, for messages.
+    fn path(&self) -> &Path;
+    fn load(&self) -> Result<Secrets>;
+    fn save(&self, secrets: &Secrets) -> Result<()>;
+    /// The key file written when the store was opened with `create_key`,
+    /// for the caller to report.
+    fn created_key_file(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// `[auth.encryption]`: encrypt the secrets file. Set at most one key source;
+/// with none, the passphrase is read from `CARAPACE_SECRETS_PASSPHRASE`.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EncryptionConfig {
+    /// Key file, relative to the config file's directory. Must be 0600.
+    pub key_file: Option<String>,
+    /// Environment variable holding the passphrase.
+    pub passphrase_env: Option<String>,
+    /// Inherited file descriptor to read the passphrase from (e.g. `3`).
+    pub passphrase_fd: Option<i32>,
+}
+
+/// Open the store a config describes: encrypted when `encryption` is set,
+/// plaintext otherwise. `secrets_file` and `key_file` are relative to
+/// `config_dir`.
+///
+/// With `create_key`, a missing or empty key file is filled with a new key
+/// (see [`SecretStore::created_key_file`]); only commands that are about to
+/// write the secrets should ask for that.
+pub fn open_store(
+    config_dir: &Path,
+    secrets_file: &str,
+    encryption: Option<&EncryptionConfig>,
+    skip_permission_check: bool,
+    create_key: bool,
+) -> Result<Box<dyn SecretStore>> {
+    let path = config_dir.join(secrets_file);
+    let Some(encryption) = encryption else {
+        return Ok(Box::new(FileStore { path, skip_permission_check }));
+    };
+
+    let sources = [
+        encryption.key_file.is_some(),
+        encryption.passphrase_env.is_some(),
+        encryption.passphrase_fd.is_some(),
+    ];
+    if sources.iter().filter(|&&set| set).count() > 1 {
+        anyhow::bail!("[auth.encryption] sets more than one of key_file, passphrase_env and passphrase_fd");
+    }
+
+    let (key, created_key_file) =
+        configured_key(config_dir, encryption, skip_permission_check, create_key, |var| std::env::var(var).ok())?;
+    Ok(Box::new(EncryptedFileStore { path, key, skip_permission_check, created_key_file }))
+}
+
+/// The key an `[auth.encryption]` section names, with environment variables
+/// looked up through `env`, and the key file if one was created for it.
+fn configured_key(
+    config_dir: &Path,
+    encryption: &EncryptionConfig,
+    skip_permission_check: bool,
+    create_key: bool,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<(Key, Option<PathBuf>)> {
+    if let Some(key_file) = &encryption.key_file {
+        let path = config_dir.join(key_file);
+        let (key, created) = Key::from_file(&path, skip_permission_check, create_key)?;
+        Ok((key, created.then_some(path)))
+    } else if let Some(fd) = encryption.passphrase_fd {
+        Ok((Key::Passphrase(read_passphrase_fd(fd)?), None))
+    } else {
+        let var = encryption.passphrase_env.as_deref().unwrap_or(DEFAULT_PASSPHRASE_ENV);
+        let passphrase = env(var)
+            .with_context(|| format!("secrets passphrase not set: environment variable {var} is empty or missing"))?;
+        Ok((Key::passphrase(passphrase)?, None))
+    }
+}
+
+/// Whether `text` is an encrypted secrets file rather than plaintext.
+pub fn is_encrypted(text: &str) -> bool {
+    toml::from_str::<Envelope>(text).is_ok()
+}
+
+/// Plaintext TOML, protected by its 0600 mode only.
+pub struct FileStore {
+    path: PathBuf,
+    skip_permission_check: bool,
+}
+
+impl FileStore {
+    pub fn new(path: PathBuf, skip_permission_check: bool) -> Self {
+        Self { path, skip_permission_check }
+    }
+}
+
+impl SecretStore for FileStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&self) -> Result<Secrets> {
+        load_secrets(&self.path, self.skip_permission_check)
+    }
+
+    fn save(&self, secrets: &Secrets) -> Result<()> {
+        write_secrets(&self.path, secrets)
+    }
+}
+
+/// The secrets TOML encrypted with XChaCha20-Poly1305. Still written 0600.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    key: Key,
+    skip_permission_check: bool,
+    created_key_file: Option<PathBuf>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: PathBuf, key: Key, skip_permission_check: bool) -> Self {
+        Self { path, key, skip_permission_check, created_key_file: None }
+    }
+}
+
+impl SecretStore for EncryptedFileStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&self) -> Result<Secrets> {
+        if !self.skip_permission_check {
+            check_private(&self.path, "secrets file")?;
+        }
+        let text = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("failed to read secrets file: {}", self.path.display()))?;
+        let envelope: Envelope = match toml::from_str(&text) {
+            Ok(envelope) => envelope,
+            Err(_) if toml::from_str::<Secrets>(&text).is_ok() => anyhow::bail!(
+                "secrets file {} is not encrypted, but [auth.encryption] is configured.\n\
+                 Run the proxy's `migrate-secrets` command to encrypt it.",
+                self.path.display()
+            ),
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to parse secrets file: {}", self.path.display()))
+            }
+        };
+        if envelope.version != 1 || envelope.cipher != CIPHER {
+            anyhow::bail!(
+                "secrets file {} uses unsupported format {} ({})",
+                self.path.display(),
+                envelope.version,
+                envelope.cipher
+            );
+        }
+
+        let key = self.key.derive(&envelope.kdf)?;
+        let nonce: [u8; 24] = decode(&envelope.nonce)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("secrets file {} has a malformed nonce", self.path.display()))?;
+        let plaintext = XChaCha20Poly1305::new(&(*key).into())
+            .decrypt(&XNonce::from(nonce), Payload { msg: &decode(&envelope.ciphertext)?, aad: AAD })
+            .map(Zeroizing::new)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "failed to decrypt {}: wrong key or passphrase, or the file is damaged",
+                    self.path.display()
+                )
+            })?;
+        let text = std::str::from_utf8(&plaintext).context("decrypted secrets are not UTF-8")?;
+        toml::from_str(text).with_context(|| format!("failed to parse secrets file: {}", self.path.display()))
+    }
+
+    fn save(&self, secrets: &Secrets) -> Result<()> {
+        let plaintext = Zeroizing::new(toml::to_string(secrets).context("failed to serialize secrets")?);
+        let kdf = self.key.fresh_kdf();
+        let key = self.key.derive(&kdf)?;
+        let nonce: [u8; 24] = rand::random();
+        let ciphertext = XChaCha20Poly1305::new(&(*key).into())
+            .encrypt(&XNonce::from(nonce), Payload { msg: plaintext.as_bytes(), aad: AAD })
+            .map_err(|_| anyhow::anyhow!("failed to encrypt secrets"))?;
+
+        let envelope = Envelope {
+            version: 1,
+            cipher: CIPHER.into(),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+            kdf,
+        };
+        let text = toml::to_string(&envelope).context("failed to serialize encrypted secrets")?;
+        write_private(&self.path, text.as_bytes())
+    }
+
+    fn created_key_file(&self) -> Option<&Path> {
+        self.created_key_file.as_deref()
+    }
+}
+
+/// Where the encryption key comes from.
+pub enum Key {
+    /// A 256-bit key, used as is.
+    Raw(Zeroizing<[u8; 32]>),
+    /// A passphrase, stretched with Argon2id and a per-file salt.
+    Passphrase(Zeroizing<String>),
+}
+
+impl Key {
+    pub fn passphrase(passphrase: String) -> Result<Self> {
+        let passphrase = Zeroizing::new(passphrase);
+        if passphrase.is_empty() {
+            anyhow::bail!("secrets passphrase is empty");
+        }
+        Ok(Key::Passphrase(passphrase))
+    }
+
+    /// Read a key file, or with `create`, write a new key to a missing or
+    /// empty one. Also returns whether the key was newly written.
+    fn from_file(path: &Path, skip_permission_check: bool, create: bool) -> Result<(Self, bool)> {
+        let existing = match std::fs::read_to_string(path) {
+            Ok(text) => Zeroizing::new(text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Zeroizing::new(String::new()),
+            Err(e) => return Err(e).with_context(|| format!("failed to read key file: {}", path.display())),
+        };
+        if existing.trim().is_empty() {
+            if !create {
+                anyhow::bail!("key file {} is missing or empty", path.display());
+            }
+            let key = Zeroizing::new(rand::random::<[u8; 32]>());
+            write_private(path, STANDARD.encode(*key).as_bytes())?;
+            return Ok((Key::Raw(key), true));
+        }
+
+        if !skip_permission_check {
+            check_private(path, "key file")?;
+        }
+        let bytes = Zeroizing::new(
+            STANDARD
+                .decode(existing.trim())
+                .with_context(|| format!("key file {} is not base64", path.display()))?,
+        );
+        let key: [u8; 32] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("key file {} must hold 32 bytes", path.display()))?;
+        Ok((Key::Raw(Zeroizing::new(key)), false))
+    }
+
+    /// Key derivation parameters for a new ciphertext.
+    fn fresh_kdf(&self) -> Kdf {
+        match self {
+            Key::Raw(_) => Kdf::KeyFile,
+            Key::Passphrase(_) => Kdf::Argon2id {
+                salt: STANDARD.encode(rand::random::<[u8; 16]>()),
+                m_cost: Params::DEFAULT_M_COST,
+                t_cost: Params::DEFAULT_T_COST,
+                p_cost: Params::DEFAULT_P_COST,
+            },
+        }
+    }
+
+    /// The cipher key for a file written with `kdf`.
+    fn derive(&self, kdf: &Kdf) -> Result<Zeroizing<[u8; 32]>> {
+        match (self, kdf) {
+            (Key::Raw(key), Kdf::KeyFile) => Ok(key.clone()),
+            (Key::Passphrase(passphrase), Kdf::Argon2id { salt, m_cost, t_cost, p_cost }) => {
+                let params = Params::new(*m_cost, *t_cost, *p_cost, Some(32))
+                    .map_err(|e| anyhow::anyhow!("invalid Argon2id parameters: {e}"))?;
+                let mut key = Zeroizing::new([0u8; 32]);
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(passphrase.as_bytes(), &decode(salt)?, key.as_mut())
+                    .map_err(|e| anyhow::anyhow!("failed to derive the secrets key: {e}"))?;
+                Ok(key)
+            }
+            (Key::Raw(_), Kdf::Argon2id { .. }) => {
+                anyhow::bail!("secrets file was encrypted with a passphrase, but a key file is configured")
+            }
+            (Key::Passphrase(_), Kdf::KeyFile) => {
+                anyhow::bail!("secrets file was encrypted with a key file, but a passphrase is configured")
+            }
+        }
+    }
+}
+
+/// On-disk form of an encrypted secrets file.
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    cipher: String,
+    nonce: String,
+    ciphertext: String,
+    // Last: TOML tables follow plain values.
+    kdf: Kdf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "kebab-case")]
+enum Kdf {
+    KeyFile,
+    Argon2id { salt: String, m_cost: u32, t_cost: u32, p_cost: u32 },
+}
+
+fn decode(value: &str) -> Result<Vec<u8>> {
+    STANDARD.decode(value).context("encrypted secrets file is not valid base64")
+}
+
+/// Longest passphrase read from `passphrase_fd`.
+#[cfg(unix)]
+const MAX_PASSPHRASE_BYTES: usize = 4096;
+
+/// Read a passphrase from an inherited file descriptor: one line, without
+/// waiting for the writer to close it.
+///
+/// The descriptor must be a pipe or a regular file. Anything else is
+/// refused and left open: a number nobody passed in may belong to the
+/// runtime (an epoll or eventfd), and closing it would break the process.
+/// The descriptor is closed once the passphrase has been read.
+#[cfg(unix)]
+fn read_passphrase_fd(fd: i32) -> Result<Zeroizing<String>> {
+    use std::io::Read;
+    use std::mem::ManuallyDrop;
+    use std::os::fd::FromRawFd;
+    use std::os::unix::fs::FileTypeExt;
+
+    if fd <= 2 {
+        anyhow::bail!("passphrase_fd must not be stdin, stdout or stderr");
+    }
+    // SAFETY: the File is never dropped unless fstat shows a pipe or a
+    // regular file, which is what whoever started the proxy handed over
+    // for this purpose; nothing else in the process reads it.
+    let file = ManuallyDrop::new(unsafe { std::fs::File::from_raw_fd(fd) });
+    let file_type = file
+        .metadata()
+        .with_context(|| format!("passphrase_fd {fd} is not an open file descriptor"))?
+        .file_type();
+    if !(file_type.is_fifo() || file_type.is_file()) {
+        anyhow::bail!("passphrase_fd {fd} must be a pipe or a regular file");
+    }
+
+    // Byte by byte, so nothing past the newline is consumed and a writer
+    // that keeps the pipe open does not block us.
+    let mut line = Zeroizing::new(Vec::new());
+    let mut byte = [0u8; 1];
+    loop {
+        let read = (&*file)
+            .read(&mut byte)
+            .with_context(|| format!("failed to read the secrets passphrase from fd {fd}"))?;
+        if read == 0 || byte[0] == b'\n' {
+            break;
+        }
+        if line.len() == MAX_PASSPHRASE_BYTES {
+            anyhow::bail!("secrets passphrase on fd {fd} is longer than {MAX_PASSPHRASE_BYTES} bytes");
+        }
+        line.push(byte[0]);
+    }
+    drop(ManuallyDrop::into_inner(file));
+
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    let line = std::str::from_utf8(&line).with_context(|| format!("secrets passphrase on fd {fd} is not UTF-8"))?;
+    if line.is_empty() {
+        anyhow::bail!("secrets passphrase on fd {fd} is empty");
+    }
+    Ok(Zeroizing::new(line.to_string()))
+}
+
+#[cfg(not(unix))]
+fn read_passphrase_fd(_fd: i32) -> Result<Zeroizing<String>> {
+    anyhow::bail!("passphrase_fd is only supported on Unix")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets() -> Secrets {
+        Secrets { refresh_token: "1//refresh-token".into(), scopes: vec!["scope.a".into()] }
+    }
+
+    #[test]
+    fn key_file_store_round_trips_without_plaintext_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let encryption = EncryptionConfig { key_file: Some("secrets.key".into()), ..Default::default() };
+
+        let err = open_store(dir.path(), "secrets.toml", Some(&encryption), false, false).err().unwrap();
+        assert!(err.to_string().contains("missing or empty"), "{err}");
+
+        let store = open_store(dir.path(), "secrets.toml", Some(&encryption), false, true).unwrap();
+        assert_eq!(store.created_key_file(), Some(dir.path().join("secrets.key").as_path()));
+        store.save(&secrets()).unwrap();
+        let text = std::fs::read_to_string(store.path()).unwrap();
+        assert!(!text.contains("refresh-token") && is_encrypted(&text), "{text}");
+
+        // Reopening reads the generated key back.
+        let store = open_store(dir.path(), "secrets.toml", Some(&encryption), false, true).unwrap();
+        assert_eq!(store.created_key_file(), None);
+        assert_eq!(store.load().unwrap().refresh_token, "1//refresh-token");
+
+        std::fs::write(dir.path().join("secrets.key"), STANDARD.encode([7u8; 32])).unwrap();
+        let store = open_store(dir.path(), "secrets.toml", Some(&encryption), true, false).unwrap();
+        assert!(store.load().unwrap_err().to_string().contains("wrong key"));
+    }
+
+    #[test]
+    fn passphrase_store_needs_the_same_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.toml");
+        let store = EncryptedFileStore::new(path.clone(), Key::passphrase("correct horse".into()).unwrap(), false);
+        store.save(&secrets()).unwrap();
+        assert_eq!(store.load().unwrap().scopes, ["scope.a"]);
+
+        let wrong = EncryptedFileStore::new(path.clone(), Key::passphrase("battery staple".into()).unwrap(), false);
+        assert!(wrong.load().is_err());
+        let key_file = EncryptedFileStore::new(path, Key::Raw(Zeroizing::new([1; 32])), false);
+        assert!(key_file.load().unwrap_err().to_string().contains("passphrase"));
+
+        let encryption = EncryptionConfig { passphrase_env: Some("CARAPACE_TEST_PASSPHRASE".into()), ..Default::default() };
+        let env = |var: &str| (var == "CARAPACE_TEST_PASSPHRASE").then(|| "correct horse".to_string());
+        let (key, _) = configured_key(dir.path(), &encryption, false, false, env).unwrap();
+        let store = EncryptedFileStore::new(dir.path().join("secrets.toml"), key, false);
+        assert_eq!(store.load().unwrap().refresh_token, "1//refresh-token");
+
+        let unset = configured_key(dir.path(), &EncryptionConfig::default(), false, false, |_| None);
+        assert!(unset.err().unwrap().to_string().contains(DEFAULT_PASSPHRASE_ENV));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn passphrase_fd_reads_one_line_from_a_pipe() {
+        use std::io::{Read, Write};
+        use std::os::fd::{AsRawFd, IntoRawFd};
+
+        // The writer stays open: only the first line may be waited for.
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"correct horse\nleft for someone else").unwrap();
+        let passphrase = read_passphrase_fd(reader.into_raw_fd()).unwrap();
+        assert_eq!(passphrase.as_str(), "correct horse");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passphrase");
+        std::fs::write(&path, "battery staple").unwrap();
+        let fd = std::fs::File::open(&path).unwrap().into_raw_fd();
+        assert_eq!(read_passphrase_fd(fd).unwrap().as_str(), "battery staple");
+
+        // A socket is refused and stays open.
+        let (mut ours, mut theirs) = std::os::unix::net::UnixStream::pair().unwrap();
+        let err = read_passphrase_fd(theirs.as_raw_fd()).unwrap_err();
+        assert!(err.to_string().contains("pipe or a regular file"), "{err}");
+        ours.write_all(b"x").unwrap();
+        let mut buf = [0u8; 1];
+        theirs.read_exact(&mut buf).unwrap();
+
+        let (reader, writer) = std::io::pipe().unwrap();
+        drop(writer);
+        let err = read_passphrase_fd(reader.into_raw_fd()).unwrap_err();
+        assert!(err.to_string().contains("empty"), "{err}");
+        assert!(read_passphrase_fd(2).is_err());
+    }
+
+    #[test]
+    fn encrypted_store_points_plaintext_files_at_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("secrets.toml");
+        write_secrets(&path, &secrets()).unwrap();
+        assert!(!is_encrypted(&std::fs::read_to_string(&path).unwrap()));
+
+        let store = EncryptedFileStore::new(path, Key::Raw(Zeroizing::new([1; 32])), false);
+        assert!(store.load().unwrap_err().to_string().contains("migrate-secrets"));
+    }
+}
@@ -0,0 +1,131 @@
+//! A local stand-in for Google's OAuth endpoints, for the flow tests.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{Form, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::flow::Endpoints;
+
+type Params = HashMap<String, String>;
+
+#[derive(Default)]
+struct Recorded {
+    /// Device-flow polls answered with `authorization_pending` before approval.
+    pending_polls: u32,
+    denied: bool,
+    scope: String,
+    device_requests: Vec<Params>,
+    token_requests: Vec<Params>,
+    revoked: Vec<String>,
+}
+
+pub struct MockGoogle {
+    base: String,
+    state: Arc<Mutex<Recorded>>,
+}
+
+impl MockGoogle {
+    pub async fn start(pending_polls: u32) -> Self {
+        let state = Arc::new(Mutex::new(Recorded {
+            pending_polls,
+            scope: "scope.a".into(),
+            ..Default::default()
+        }));
+        let app = axum::Router::new()
+            .route("/device/code", axum::routing::post(device_code))
+            .route("/token", axum::routing::post(token))
+            .route("/revoke", axum::routing::post(revoke))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        Self { base, state }
+    }
+
+    pub fn endpoints(&self) -> Endpoints {
+        Endpoints {
+            auth_url: format!("{}/auth", self.base),
+            token_url: format!("{}/token", self.base),
+            device_url: format!("{}/device/code", self.base),
+            revoke_url: format!("{}/revoke", self.base),
+        }
+    }
+
+    /// Answer device-flow polls with `access_denied`.
+    pub fn deny(&self) {
+        self.state.lock().unwrap().denied = true;
+    }
+
+    pub fn device_requests(&self) -> Vec<Params> {
+        self.state.lock().unwrap().device_requests.clone()
+    }
+
+    pub fn token_requests(&self) -> Vec<Params> {
+        self.state.lock().unwrap().token_requests.clone()
+    }
+
+    pub fn revoked(&self) -> Vec<String> {
+        self.state.lock().unwrap().revoked.clone()
+    }
+}
+
+async fn device_code(
+    State(state): State<Arc<Mutex<Recorded>>>,
+    Form(params): Form<Params>,
+) -> (StatusCode, Json<Value>) {
+    let mut state = state.lock().unwrap();
+    let scope = params.get("scope").cloned().unwrap_or_default();
+    state.device_requests.push(params);
+    // Like Google, refuse scopes outside the device-flow allowlist.
+    if scope.contains("mail.google.com") {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": "invalid_scope"})));
+    }
+    state.scope = scope;
+    (
+        StatusCode::OK,
+        Json(json!({
+            "device_code": "dev-1",
+            "user_code": "ABCD-EFGH",
+            "verification_url": "https://www.google.com/device",
+            "expires_in": 60,
+            "interval": 0
+        })),
+    )
+}
+
+async fn token(
+    State(state): State<Arc<Mutex<Recorded>>>,
+    Form(params): Form<Params>,
+) -> (StatusCode, Json<Value>) {
+    let mut state = state.lock().unwrap();
+    let device = params.get("grant_type").is_some_and(|g| g.ends_with("device_code"));
+    state.token_requests.push(params);
+    if device && state.denied {
+        return (StatusCode::FORBIDDEN, Json(json!({"error": "access_denied"})));
+    }
+    if device && state.pending_polls > 0 {
+        state.pending_polls -= 1;
+        return (StatusCode::PRECONDITION_REQUIRED, Json(json!({"error": "authorization_pending"})));
+    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "access_token": "at-new",
+            "expires_in": 3599,
+            "refresh_token": "rt-new",
+            "scope": state.scope,
+            "token_type": "Bearer"
+        })),
+    )
+}
+
+async fn revoke(State(state): State<Arc<Mutex<Recorded>>>, Form(params): Form<Params>) -> StatusCode {
+    if let Some(token) = params.get("token") {
+        state.lock().unwrap().revoked.push(token.clone());
+    }
+    StatusCode::OK
+}
@@ -0,0 +1,44 @@
+//! Text extraction helpers for files the proxies read on the agent's behalf
+//! (Gmail attachments, Drive files).
+
+use anyhow::{anyhow, Result};
+
+/// Extract the text layer of a PDF. Scanned PDFs yield little or nothing.
+///
+/// Parsing runs on a blocking thread and is isolated with `catch_unwind`,
+/// since malformed PDFs can make the parser panic.
+pub async fn extract_pdf_text(bytes: Vec<u8>) -> Result<String> {
+    tokio::task::spawn_blocking(move || {
+        std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem(&bytes))
+            .map_err(|_| anyhow!("PDF parser crashed on this file"))?
+            .map_err(|e| anyhow!("failed to extract PDF text: {e}"))
+    })
+    .await
+    .map_err(|e| anyhow!("PDF extraction task failed: {e}"))?
+}
+
+/// Truncate `text` to at most `max_chars` characters.
+///
+/// Returns the (possibly shortened) text and whether truncation happened.
+pub fn truncate_chars(text: String, max_chars: usize) -> (String, bool) {
+    match text.char_indices().nth(max_chars) {
+        Some((idx, _)) => (text[..idx].to_string(), true),
+        None => (text, false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("héllo".into(), 10), ("héllo".into(), false));
+        assert_eq!(truncate_chars("héllo".into(), 2), ("hé".into(), true));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_pdf_is_an_error() {
+        assert!(extract_pdf_text(b"not a pdf".to_vec()).await.is_err());
+    }
+}
@@ -0,0 +1,6 @@
+//! Print the protocol's JSON Schema (see `carapace_protocol::schema`).
+
+fn main() {
+    let schema = carapace_protocol::schema::export();
+    println!("{}", serde_json::to_string_pretty(&schema).expect("schema serializes"));
+}
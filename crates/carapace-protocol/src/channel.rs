@@ -0,0 +1,133 @@
+//! Types used by every channel: targeting, capabilities, watches and cursors.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Which channel (and account) a `channel.*` request is for. Its fields sit
+/// next to the method's own params.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Target {
+    /// `imsg`, `gmail` or `gdocs`; defaults to `imsg`.
+    #[serde(default = "default_channel")]
+    pub channel: String,
+    /// Gmail and Google Docs: the account; defaults to the configured default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
+
+fn default_channel() -> String {
+    "imsg".into()
+}
+
+/// Params of `channel.list_chats`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ListParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+/// Result of `channel.capabilities`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Capabilities {
+    pub channels: Vec<ChannelCapabilities>,
+}
+
+/// One configured channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ChannelCapabilities {
+    pub channel: String,
+    /// Configured account names, sorted. Empty for single-account channels.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_account: Option<String>,
+}
+
+/// Params of `channel.watch`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WatchParams {
+    /// Named cursor to resume from; events then carry a `position`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Google Docs only: restrict changes to these folder ids.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub folders: Vec<String>,
+}
+
+/// Acknowledgment of `channel.watch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct WatchAck {
+    pub subscribed: bool,
+    /// Assigned by the connection loop; identifies this stream's events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subscription: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Where a named cursor resumes from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+}
+
+/// Params of `channel.unwatch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UnwatchParams {
+    /// The id from the watch acknowledgment. A number is accepted too.
+    #[serde(deserialize_with = "string_or_number")]
+    pub subscription: String,
+}
+
+/// Result of `channel.unwatch`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct UnwatchResult {
+    pub unsubscribed: bool,
+    pub subscription: String,
+}
+
+/// Params of `channel.ack`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AckParams {
+    pub cursor: String,
+    /// The `position` of the last event handled. A number is accepted too.
+    #[serde(deserialize_with = "string_or_number")]
+    pub position: String,
+}
+
+/// Ids and positions are sent as strings; clients that parsed them into
+/// numbers may send them back that way.
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Number(u64),
+    }
+    Ok(match Id::deserialize(deserializer)? {
+        Id::String(s) => s,
+        Id::Number(n) => n.to_string(),
+    })
+}
+
+/// Result of `channel.ack`: the cursor's position after the ack. Cursors
+/// never move backwards, so this may be ahead of the acked position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AckResult {
+    pub cursor: String,
+    pub position: String,
+}
+
+/// An allowlist summary in `channel.status`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AllowlistInfo {
+    pub mode: String,
+    pub entries: usize,
+}
+
+/// Health of a proxy-backed channel (Gmail, Google Docs).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ProxyHealth {
+    pub proxy_reachable: bool,
+    pub token_valid: Option<bool>,
+    pub token_expires_in_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,114 @@
+//! JSON-RPC error codes returned by the daemon.
+
+/// A JSON-RPC error code the daemon can return.
+///
+/// Error objects keep the raw `i32` on the wire, so codes added by a newer
+/// daemon still parse; [`ErrorCode::from_code`] returns `None` for those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    // Standard JSON-RPC codes.
+    ParseError,
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    // Carapace codes.
+    /// The recipient is not on the channel's outbound allowlist.
+    NotInAllowlist,
+    /// The caller exceeded its request rate.
+    RateLimited,
+    /// The content filter blocked the message.
+    ContentBlocked,
+    /// The channel or account is not configured on this gateway.
+    ChannelUnavailable,
+    /// The channel accepted the request but delivery failed.
+    SendFailed,
+    /// The client's protocol version is not supported (see `hello`).
+    IncompatibleVersion,
+    /// A write policy refused the request (e.g. outside the writable folders).
+    WriteDenied,
+    /// The target changed since it was read; read it again and retry.
+    Conflict,
+}
+
+impl ErrorCode {
+    /// Every code, in table order.
+    pub const ALL: [ErrorCode; 13] = [
+        ErrorCode::ParseError,
+        ErrorCode::InvalidRequest,
+        ErrorCode::MethodNotFound,
+        ErrorCode::InvalidParams,
+        ErrorCode::InternalError,
+        ErrorCode::NotInAllowlist,
+        ErrorCode::RateLimited,
+        ErrorCode::ContentBlocked,
+        ErrorCode::ChannelUnavailable,
+        ErrorCode::SendFailed,
+        ErrorCode::IncompatibleVersion,
+        ErrorCode::WriteDenied,
+        ErrorCode::Conflict,
+    ];
+
+    /// The numeric code sent on the wire.
+    pub const fn code(self) -> i32 {
+        match self {
+            ErrorCode::ParseError => -32700,
+            ErrorCode::InvalidRequest => -32600,
+            ErrorCode::MethodNotFound => -32601,
+            ErrorCode::InvalidParams => -32602,
+            ErrorCode::InternalError => -32603,
+            ErrorCode::NotInAllowlist => -32001,
+            ErrorCode::RateLimited => -32002,
+            ErrorCode::ContentBlocked => -32003,
+            ErrorCode::ChannelUnavailable => -32004,
+            ErrorCode::SendFailed => -32005,
+            ErrorCode::IncompatibleVersion => -32006,
+            ErrorCode::WriteDenied => -32007,
+            ErrorCode::Conflict => -32008,
+        }
+    }
+
+    /// The code for a wire value, if it is one this version knows.
+    pub fn from_code(code: i32) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+
+    /// A stable snake_case name, for logs and generated clients.
+    pub const fn name(self) -> &'static str {
+        match self {
+            ErrorCode::ParseError => "parse_error",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::MethodNotFound => "method_not_found",
+            ErrorCode::InvalidParams => "invalid_params",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::NotInAllowlist => "not_in_allowlist",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::ContentBlocked => "content_blocked",
+            ErrorCode::ChannelUnavailable => "channel_unavailable",
+            ErrorCode::SendFailed => "send_failed",
+            ErrorCode::IncompatibleVersion => "incompatible_version",
+            ErrorCode::WriteDenied => "write_denied",
+            ErrorCode::Conflict => "conflict",
+        }
+    }
+}
+
+impl From<ErrorCode> for i32 {
+    fn from(code: ErrorCode) -> i32 {
+        code.code()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_code(code.code()), Some(code));
+        }
+        assert_eq!(ErrorCode::NotInAllowlist.code(), -32001);
+        assert_eq!(ErrorCode::from_code(-32099), None);
+    }
+}
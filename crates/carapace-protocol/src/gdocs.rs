@@ -0,0 +1,167 @@
+//! The `gdocs` (Google Docs, Sheets, Forms and Drive) channel.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::channel::ProxyHealth;
+
+/// Params of `channel.search`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "GDocsSearchParams")]
+pub struct SearchParams {
+    /// Drive search text; empty lists recent files.
+    #[serde(default)]
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    /// Only Google Docs, not Sheets, Forms or other files.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub docs_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+/// Params of `channel.get_history`: read a document, a sheet range, or form
+/// responses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ReadParams {
+    /// Document, spreadsheet or form id.
+    #[serde(rename = "chat_id")]
+    pub file_id: String,
+    /// `"json"` (default) or `"markdown"` for documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Include comment threads.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub comments: bool,
+    /// Sheets cell values: `"formatted"` (default), `"formula"` or `"raw"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render: Option<String>,
+    /// Read one A1 range of a spreadsheet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<String>,
+    /// Read form responses submitted after this RFC 3339 timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+}
+
+/// Params of `channel.status` that return one file's metadata instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FileInfoParams {
+    pub file_id: String,
+}
+
+/// Params of `channel.create_draft`, which creates a document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CreateDocumentParams {
+    pub title: String,
+    /// Initial text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder_id: Option<String>,
+}
+
+/// Params of `channel.send`: the write to perform, tagged by `action`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+#[schemars(rename = "GDocsAction")]
+pub enum Action {
+    Copy {
+        file_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        folder_id: Option<String>,
+    },
+    Append {
+        document_id: String,
+        text: String,
+    },
+    /// Apply edit operations against the revision that was read.
+    Edit {
+        document_id: String,
+        revision_id: String,
+        operations: Vec<Value>,
+    },
+    CreateFolder {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent_id: Option<String>,
+    },
+    CreateSheet {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        folder_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<Vec<Vec<String>>>,
+    },
+    /// Write one `range`, or several with `data`.
+    UpdateSheet {
+        spreadsheet_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        range: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        values: Option<Vec<Vec<String>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        data: Option<Vec<RangeValues>>,
+    },
+    AppendRows {
+        spreadsheet_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        sheet: Option<String>,
+        values: Vec<Vec<String>>,
+    },
+    CreateForm {
+        title: String,
+        /// Where to put the form; required under a write policy.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        folder_id: Option<String>,
+    },
+    EditForm {
+        form_id: String,
+        operations: Vec<Value>,
+    },
+}
+
+/// One range of a multi-range `update_sheet`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RangeValues {
+    pub range: String,
+    pub values: Vec<Vec<String>>,
+}
+
+/// Result of `channel.status`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "GDocsStatus")]
+pub struct Status {
+    pub channel: String,
+    pub account: String,
+    pub configured: bool,
+    pub health: Option<ProxyHealth>,
+    /// Every configured account.
+    pub accounts: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn actions_are_tagged_by_name() {
+        let action = Action::AppendRows {
+            spreadsheet_id: "s1".into(),
+            sheet: None,
+            values: vec![vec!["a".into(), "b".into()]],
+        };
+        assert_eq!(
+            serde_json::to_value(&action).unwrap(),
+            json!({"action": "append_rows", "spreadsheet_id": "s1", "values": [["a", "b"]]})
+        );
+
+        let read = ReadParams { file_id: "d1".into(), comments: true, ..Default::default() };
+        assert_eq!(serde_json::to_value(&read).unwrap(), json!({"chat_id": "d1", "comments": true}));
+    }
+}
@@ -0,0 +1,69 @@
+//! The `gmail` channel.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::channel::{AllowlistInfo, ProxyHealth};
+
+/// Params of `channel.search`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "GmailSearchParams")]
+pub struct SearchParams {
+    /// Gmail search syntax, e.g. `from:alice is:unread`.
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    /// `next_page_token` from the previous page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_token: Option<String>,
+}
+
+/// Params of `channel.get_history`: every message in a thread.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ThreadParams {
+    #[serde(rename = "chat_id")]
+    pub thread_id: String,
+}
+
+/// Params of `channel.create_draft`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DraftParams {
+    pub to: String,
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cc: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html_body: Option<String>,
+}
+
+/// Result of `channel.create_draft`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DraftResult {
+    pub draft_id: String,
+    pub message_id: String,
+    pub thread_id: String,
+}
+
+/// Params of `channel.read_attachment`. Without `attachment_id` the
+/// message's attachments are listed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct AttachmentParams {
+    pub message_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub attachment_id: Option<String>,
+}
+
+/// Result of `channel.status`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "GmailStatus")]
+pub struct Status {
+    pub channel: String,
+    pub account: String,
+    pub configured: bool,
+    pub health: Option<ProxyHealth>,
+    pub inbound: Option<AllowlistInfo>,
+    /// Every configured account.
+    pub accounts: Vec<String>,
+}
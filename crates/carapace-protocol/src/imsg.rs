@@ -0,0 +1,58 @@
+//! The `imsg` (iMessage) channel.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::channel::AllowlistInfo;
+
+/// Params of `channel.send`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SendParams {
+    /// Phone number or email handle.
+    pub recipient: String,
+    pub message: String,
+    /// Paths of files to attach. Not supported yet: the daemon rejects a
+    /// non-empty list with `InvalidParams`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<String>,
+}
+
+/// Result of `channel.send`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SendResult {
+    pub success: bool,
+    pub stdout: String,
+}
+
+/// Params of `channel.get_history`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryParams {
+    pub chat_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// Only messages before this ISO 8601 timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+}
+
+/// Health of the iMessage adapter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "ImsgHealth")]
+pub struct HealthStatus {
+    pub binary_exists: bool,
+    pub db_exists: bool,
+    pub smoke_test_ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of `channel.status`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[schemars(rename = "ImsgStatus")]
+pub struct Status {
+    pub channel: String,
+    pub configured: bool,
+    pub health: Option<HealthStatus>,
+    pub outbound: Option<AllowlistInfo>,
+    pub inbound: Option<AllowlistInfo>,
+}
@@ -0,0 +1,28 @@
+//! Types shared by the Carapace gateway daemon and its clients.
+//!
+//! The daemon speaks newline-delimited JSON-RPC 2.0 over a Unix socket (see
+//! `docs/06-protocol-spec.md`). This crate holds the message envelopes
+//! ([`rpc`]), the error codes ([`ErrorCode`]), the protocol version and
+//! `hello` handshake ([`version`]), and the params and results of the
+//! `channel.*` methods, one module per channel.
+//!
+//! Params structs leave out `channel` and `account` ([`channel::Target`]);
+//! clients add those when building a request. Results that are passed
+//! through unchanged from a proxy (Gmail threads, Docs content, ...) are not
+//! modelled and stay `serde_json::Value`.
+//!
+//! [`schema::export`] describes all of it as JSON Schema, for generating
+//! clients in other languages. The `carapace-protocol-schema` binary prints
+//! it; the checked-in copy is `docs/protocol.schema.json`.
+
+pub mod channel;
+pub mod codes;
+pub mod gdocs;
+pub mod gmail;
+pub mod imsg;
+pub mod rpc;
+pub mod schema;
+pub mod version;
+
+pub use codes::ErrorCode;
+pub use version::{HelloParams, HelloResult, ProtocolVersion, HELLO_METHOD, PROTOCOL_VERSION};
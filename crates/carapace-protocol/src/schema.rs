@@ -0,0 +1,157 @@
+//! JSON Schema export of the protocol.
+//!
+//! The document lists every method with the schema of its params and result,
+//! the error codes, and the envelope types, with all shared definitions under
+//! `$defs`. `channel.*` params are the [`Target`] fields plus the method's own.
+//! A result of `true` is passed through from a proxy and not described.
+
+use std::borrow::Cow;
+
+use schemars::generate::SchemaSettings;
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde_json::{json, Map, Value};
+
+use crate::channel::{
+    AckParams, AckResult, Capabilities, ListParams, Target, UnwatchParams, UnwatchResult, WatchAck, WatchParams,
+};
+use crate::rpc::{JsonRpcNotification, JsonRpcRequest, JsonRpcResponse};
+use crate::version::{HelloParams, HelloResult, HELLO_METHOD, PROTOCOL_VERSION};
+use crate::{gdocs, gmail, imsg, ErrorCode};
+
+/// The whole protocol as one JSON Schema (draft 2020-12) document.
+pub fn export() -> Value {
+    let mut methods = Methods { generator: SchemaSettings::draft2020_12().into_generator(), list: Vec::new() };
+
+    methods.add::<HelloParams, HelloResult>(HELLO_METHOD, None);
+    methods.add_untyped("ping", None);
+    methods.add_untyped("echo", None);
+    methods.add_untyped("whoami", None);
+    methods.add::<NoParams, Capabilities>("channel.capabilities", None);
+
+    methods.add_channel::<NoParams, imsg::Status>("channel.status", "imsg");
+    methods.add_channel::<NoParams, gmail::Status>("channel.status", "gmail");
+    methods.add_channel::<NoParams, gdocs::Status>("channel.status", "gdocs");
+    methods.add_channel::<gdocs::FileInfoParams, Value>("channel.status", "gdocs");
+
+    methods.add_channel::<imsg::SendParams, imsg::SendResult>("channel.send", "imsg");
+    methods.add_channel::<gdocs::Action, Value>("channel.send", "gdocs");
+
+    for channel in ["imsg", "gmail", "gdocs"] {
+        methods.add_channel::<ListParams, Value>("channel.list_chats", channel);
+    }
+    methods.add_channel::<imsg::HistoryParams, Value>("channel.get_history", "imsg");
+    methods.add_channel::<gmail::ThreadParams, Value>("channel.get_history", "gmail");
+    methods.add_channel::<gdocs::ReadParams, Value>("channel.get_history", "gdocs");
+
+    methods.add_channel::<gmail::SearchParams, Value>("channel.search", "gmail");
+    methods.add_channel::<gdocs::SearchParams, Value>("channel.search", "gdocs");
+    methods.add_channel::<gmail::DraftParams, gmail::DraftResult>("channel.create_draft", "gmail");
+    methods.add_channel::<gdocs::CreateDocumentParams, Value>("channel.create_draft", "gdocs");
+    methods.add_channel::<gmail::AttachmentParams, Value>("channel.read_attachment", "gmail");
+
+    for channel in ["imsg", "gmail", "gdocs"] {
+        methods.add_channel::<WatchParams, WatchAck>("channel.watch", channel);
+        methods.add_channel::<AckParams, AckResult>("channel.ack", channel);
+    }
+    methods.add::<UnwatchParams, UnwatchResult>("channel.unwatch", None);
+
+    let generator = &mut methods.generator;
+    let envelope = json!({
+        "request": generator.subschema_for::<JsonRpcRequest>(),
+        "response": generator.subschema_for::<JsonRpcResponse>(),
+        "notification": generator.subschema_for::<JsonRpcNotification>(),
+    });
+    let notifications = json!([
+        {
+            "method": "channel.watch",
+            "description": "An event on a watch stream; params carry `subscription` and, with a named cursor, `position`.",
+        },
+        {
+            "method": "channel.watch_end",
+            "description": "A watch stream ended; params carry `subscription` and `reason`.",
+        },
+    ]);
+    let error_codes: Vec<Value> = ErrorCode::ALL
+        .iter()
+        .map(|code| json!({"code": code.code(), "name": code.name()}))
+        .collect();
+
+    let definitions: Map<String, Value> = methods.generator.take_definitions(true).into_iter().collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Carapace gateway protocol",
+        "protocol_version": PROTOCOL_VERSION,
+        "envelope": envelope,
+        "methods": methods.list,
+        "notifications": notifications,
+        "error_codes": error_codes,
+        "$defs": definitions,
+    })
+}
+
+/// Params of methods that take none besides the target.
+struct NoParams;
+
+impl JsonSchema for NoParams {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "NoParams".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({"type": "object"})
+    }
+}
+
+struct Methods {
+    generator: SchemaGenerator,
+    list: Vec<Value>,
+}
+
+impl Methods {
+    fn add<P: JsonSchema, R: JsonSchema>(&mut self, method: &str, channel: Option<&str>) {
+        let params = self.generator.subschema_for::<P>();
+        let result = self.generator.subschema_for::<R>();
+        self.push(method, channel, params.to_value(), result.to_value());
+    }
+
+    fn add_channel<P: JsonSchema, R: JsonSchema>(&mut self, method: &str, channel: &str) {
+        let target = self.generator.subschema_for::<Target>();
+        let own = self.generator.subschema_for::<P>();
+        let result = self.generator.subschema_for::<R>();
+        let params = json!({"allOf": [target, own]});
+        self.push(method, Some(channel), params, result.to_value());
+    }
+
+    /// Diagnostic methods whose params and results are free-form objects.
+    fn add_untyped(&mut self, method: &str, channel: Option<&str>) {
+        self.push(method, channel, json!({"type": "object"}), json!({"type": "object"}));
+    }
+
+    fn push(&mut self, method: &str, channel: Option<&str>, params: Value, result: Value) {
+        let mut entry = json!({"method": method, "params": params, "result": result});
+        if let Some(channel) = channel {
+            entry["channel"] = json!(channel);
+        }
+        self.list.push(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_in_schema_is_current() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../docs/protocol.schema.json");
+        let checked_in: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert!(
+            checked_in == export(),
+            "docs/protocol.schema.json is out of date; regenerate it with \
+             `cargo run -p carapace-protocol --bin carapace-protocol-schema > docs/protocol.schema.json`"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Protocol versioning and the `hello` handshake.
+//!
+//! Versions are `major.minor`. A minor bump only adds methods, params or
+//! result fields, so any two versions with the same major interoperate; a
+//! major bump changes existing shapes. Clients send `hello` first on each
+//! connection and the daemon rejects an incompatible major with
+//! [`ErrorCode::IncompatibleVersion`](crate::ErrorCode::IncompatibleVersion).
+//! Clients that skip `hello` are served as version 1.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The protocol version implemented by this crate.
+pub const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion { major: 1, minor: 0 };
+
+/// Method name of the handshake.
+pub const HELLO_METHOD: &str = "hello";
+
+/// A `major.minor` protocol version, written as a string on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProtocolVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl ProtocolVersion {
+    /// Whether a peer speaking `other` can talk to one speaking `self`.
+    pub fn is_compatible_with(self, other: ProtocolVersion) -> bool {
+        self.major == other.major
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s.split_once('.').ok_or_else(|| format!("invalid protocol version {s:?}"))?;
+        let parse = |part: &str| part.parse::<u32>().map_err(|_| format!("invalid protocol version {s:?}"));
+        Ok(Self { major: parse(major)?, minor: parse(minor)? })
+    }
+}
+
+impl Serialize for ProtocolVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ProtocolVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for ProtocolVersion {
+    fn schema_name() -> Cow<'static, str> {
+        "ProtocolVersion".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "pattern": "^[0-9]+\\.[0-9]+$",
+            "description": "major.minor; peers with the same major are compatible."
+        })
+    }
+}
+
+/// Params of `hello`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HelloParams {
+    pub protocol_version: ProtocolVersion,
+    /// Client name and version, for the daemon's logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+}
+
+/// Result of `hello`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HelloResult {
+    pub protocol_version: ProtocolVersion,
+    /// Daemon name and version.
+    pub server: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_parse_and_compare_by_major() {
+        let v: ProtocolVersion = "1.4".parse().unwrap();
+        assert_eq!(v, ProtocolVersion { major: 1, minor: 4 });
+        assert_eq!(serde_json::to_value(v).unwrap(), serde_json::json!("1.4"));
+        assert!(PROTOCOL_VERSION.is_compatible_with(v));
+        assert!(!PROTOCOL_VERSION.is_compatible_with("2.0".parse().unwrap()));
+        assert!("1".parse::<ProtocolVersion>().is_err());
+    }
+}
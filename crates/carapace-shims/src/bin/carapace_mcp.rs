@@ -0,0 +1,77 @@
+//! carapace-mcp — MCP (Model Context Protocol) server for every Carapace channel.
+//!
+//! Speaks the MCP stdio transport protocol so that any MCP-capable agent
+//! (Claude Code, OpenClaw via acpx, etc.) can use the gateway's channels
+//! through one server. On the first request it asks the daemon which
+//! channels and accounts are configured (`channel.capabilities`) and offers
+//! the matching tools; every configured Gmail and Google Docs account is
+//! reachable through the tools' `account` argument.
+//!
+//! ## Options
+//! | Flag | Environment | Meaning |
+//! |------|-------------|---------|
+//! | `--channel gmail,gdocs,imsg` | | Only offer these channels (default: all configured) |
+//! | `--gmail-account NAME` | `GMAIL_ACCOUNT` | Pin Gmail to one account; others are unreachable |
+//! | `--gdocs-account NAME` | `GDOCS_ACCOUNT` | Pin Google Docs to one account |
+//! | `--imsg-cursor NAME` | `IMSG_MCP_CURSOR` | Named daemon cursor for the iMessage inbox |
+//! | `--log-payloads` | `CARAPACE_MCP_LOG_PAYLOADS=1` | Log redacted message payloads to stderr |
+//!
+//! Logs go to stderr; `RUST_LOG` sets the level (default `info`).
+//!
+//! ## Usage
+//! ```json
+//! {
+//!   "mcpServers": {
+//!     "carapace": {
+//!       "command": "sudo",
+//!       "args": ["-u", "carapace", "/usr/local/bin/carapace-mcp", "--channel", "gmail,gdocs"]
+//!     }
+//!   }
+//! }
+//! ```
+
+use carapace_shims::mcp::{self, Options};
+use clap::Parser;
+
+#[derive(Parser)]
+#[command(name = "carapace-mcp", version, about = "MCP server for the Carapace gateway")]
+struct Cli {
+    /// Channels to offer (gmail, gdocs, imsg); repeat or comma-separate.
+    /// Default: every channel the daemon has configured.
+    #[arg(long = "channel", value_delimiter = ',', value_parser = ["gmail", "gdocs", "imsg"])]
+    channels: Vec<String>,
+
+    /// Only expose this Gmail account [env: GMAIL_ACCOUNT]
+    #[arg(long)]
+    gmail_account: Option<String>,
+
+    /// Only expose this Google Docs account [env: GDOCS_ACCOUNT]
+    #[arg(long)]
+    gdocs_account: Option<String>,
+
+    /// Named daemon cursor for the iMessage inbox [env: IMSG_MCP_CURSOR]
+    #[arg(long)]
+    imsg_cursor: Option<String>,
+
+    /// Log redacted message payloads to stderr [env: CARAPACE_MCP_LOG_PAYLOADS=1]
+    #[arg(long)]
+    log_payloads: bool,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let mut options = Options::from_env("carapace", "carapace-mcp");
+    options.channels = cli.channels;
+    for (channel, account) in [("gmail", cli.gmail_account), ("gdocs", cli.gdocs_account)] {
+        if let Some(account) = account {
+            options.pinned_accounts.insert(channel.to_string(), Some(account));
+        }
+    }
+    if cli.imsg_cursor.is_some() {
+        options.imsg_cursor = cli.imsg_cursor;
+    }
+    options.log_payloads |= cli.log_payloads;
+
+    mcp::run(options);
+}
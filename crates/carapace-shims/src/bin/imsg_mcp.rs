@@ -0,0 +1,26 @@
+//! imsg-mcp — MCP (Model Context Protocol) server for the Carapace iMessage channel.
+//!
+//! Equivalent to `carapace-mcp --channel imsg` (see `carapace_shims::mcp`);
+//! kept so existing agent configurations keep working. `IMSG_MCP_CURSOR`
+//! names the daemon cursor for the `imsg://inbox` resource.
+//!
+//! ## Usage
+//! ```json
+//! {
+//!   "mcpServers": {
+//!     "imsg": {
+//!       "command": "sudo",
+//!       "args": ["-u", "carapace", "/usr/local/bin/imsg-mcp"],
+//!       "env": { "IMSG_MCP_CURSOR": "claude-inbox" }
+//!     }
+//!   }
+//! }
+//! ```
+
+use carapace_shims::mcp::{self, Options};
+
+fn main() {
+    let mut options = Options::from_env("carapace-imsg", "imsg-mcp");
+    options.channels = vec!["imsg".to_string()];
+    mcp::run(options);
+}
@@ -10,7 +10,8 @@
 //! imsg status
 //! ```
 
-use carapace_client::GatewayClient;
+use carapace_client::protocol::imsg;
+use carapace_client::{ClientError, GatewayClient};
 use clap::{Parser, Subcommand};
 use serde_json::json;
 
@@ -95,18 +96,15 @@ fn main() {
 
     match cli.command {
         Commands::Send { to, text, file } => {
-            let mut params = json!({
-                "channel": "imsg",
-                "recipient": to,
-                "message": text,
-            });
-            if !file.is_empty() {
-                params["attachments"] = json!(file);
-            }
+            let params = imsg::SendParams {
+                recipient: to.clone(),
+                message: text,
+                attachments: file,
+            };
 
-            match client.call("channel.send", params) {
+            match client.imsg().send(&params) {
                 Ok(result) => {
-                    if result.get("success") == Some(&json!(true)) {
+                    if result.success {
                         println!("Message sent to {to}");
                     } else {
                         println!("{}", serde_json::to_string_pretty(&result).unwrap());
@@ -120,12 +118,7 @@ fn main() {
         }
 
         Commands::Chats { limit, json: raw } => {
-            let mut params = json!({"channel": "imsg"});
-            if let Some(n) = limit {
-                params["limit"] = json!(n);
-            }
-
-            match client.call("channel.list_chats", params) {
+            match client.imsg().list_chats(limit) {
                 Ok(result) => {
                     if raw {
                         println!("{}", serde_json::to_string_pretty(&result).unwrap());
@@ -145,15 +138,9 @@ fn main() {
             limit,
             json: raw,
         } => {
-            let mut params = json!({
-                "channel": "imsg",
-                "chat_id": chat_id,
-            });
-            if let Some(n) = limit {
-                params["limit"] = json!(n);
-            }
+            let params = imsg::HistoryParams { chat_id, limit, before: None };
 
-            match client.call("channel.get_history", params) {
+            match client.imsg().history(&params) {
                 Ok(result) => {
                     if raw {
                         println!("{}", serde_json::to_string_pretty(&result).unwrap());
@@ -168,7 +155,7 @@ fn main() {
             }
         }
 
-        Commands::Status => match client.call("channel.status", json!({"channel": "imsg"})) {
+        Commands::Status => match client.imsg().status() {
             Ok(result) => {
                 println!("{}", serde_json::to_string_pretty(&result).unwrap());
             }
@@ -251,10 +238,12 @@ fn main() {
                         // Spawn a background thread so the main stdin loop keeps running
                         // and can handle messages.send while events are streaming.
                         let tx2 = Arc::clone(&tx);
+                        let mut watch_params = json!({"channel": "imsg"});
+                        if let Some(cursor) = params.get("cursor") { watch_params["cursor"] = cursor.clone(); }
                         std::thread::spawn(move || {
                             match GatewayClient::connect_default() {
                                 Ok(watch_client) => {
-                                    match watch_client.subscribe("channel.watch", json!({"channel": "imsg"})) {
+                                    match watch_client.subscribe("channel.watch", watch_params) {
                                         Ok((_ack, subscription)) => {
                                             let ok = json!({"jsonrpc":"2.0","id":id,"result":{"subscription":1}});
                                             tx2.lock().unwrap().send(ok.to_string()).ok();
@@ -305,12 +294,12 @@ fn main() {
 
                 let resp = match client.call(gw_method, gw_params) {
                     Ok(result) => json!({"jsonrpc":"2.0","id":id,"result":result}),
-                    Err(carapace_client::ClientError::Gateway { code, message }) => {
-                        json!({"jsonrpc":"2.0","id":id,"error":{"code":code,"message":message}})
-                    }
-                    Err(e) => {
-                        json!({"jsonrpc":"2.0","id":id,"error":{"code":-32603,"message":e.to_string()}})
-                    }
+                    Err(e) => match (e.code(), e.gateway_message()) {
+                        (Some(code), Some(message)) => {
+                            json!({"jsonrpc":"2.0","id":id,"error":{"code":code,"message":message}})
+                        }
+                        _ => json!({"jsonrpc":"2.0","id":id,"error":{"code":-32603,"message":e.to_string()}}),
+                    },
                 };
 
                 tx.lock().unwrap().send(resp.to_string()).ok();
@@ -361,28 +350,24 @@ fn main() {
 }
 
 /// Print a gateway error with a human-readable message.
-fn print_error(err: carapace_client::ClientError) {
-    match err {
-        carapace_client::ClientError::Gateway { code, message } => {
-            let hint = match code {
-                -32001 => "Recipient not in allowlist",
-                -32002 => "Rate limited, try again later",
-                -32003 => "Message blocked by content filter",
-                -32004 => "iMessage channel not available",
-                -32005 => "Send failed",
-                _ => "",
-            };
-            if hint.is_empty() {
-                eprintln!("Error ({code}): {message}");
-            } else {
-                eprintln!("Error: {hint}");
-                eprintln!("  Detail: {message}");
-            }
+fn print_error(err: ClientError) {
+    let hint = match &err {
+        ClientError::NotInAllowlist { .. } => "Recipient not in allowlist",
+        ClientError::RateLimited { .. } => "Rate limited, try again later",
+        ClientError::ContentBlocked { .. } => "Message blocked by content filter",
+        ClientError::ChannelUnavailable { .. } => "iMessage channel not available",
+        ClientError::SendFailed { .. } => "Send failed",
+        ClientError::Gateway { code, message, .. } => {
+            eprintln!("Error ({code}): {message}");
+            return;
         }
         other => {
             eprintln!("Error: {other}");
+            return;
         }
-    }
+    };
+    eprintln!("Error: {hint}");
+    eprintln!("  Detail: {}", err.gateway_message().unwrap_or_default());
 }
 
 /// Pretty-print chat list in human-readable form.
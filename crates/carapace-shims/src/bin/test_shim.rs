@@ -145,7 +145,7 @@ fn main() {
 
     print!("5. error case .... ");
     match client.call("nonexistent.method", json!({})) {
-        Err(carapace_client::ClientError::Gateway { code, message }) => {
+        Err(carapace_client::ClientError::Gateway { code, message, .. }) => {
             if code == -32601 {
                 println!("PASS ✓  (code: {code}, msg: \"{message}\")");
                 passed += 1;
@@ -0,0 +1,4 @@
+//! Shared code for the Carapace shim binaries.
+
+pub mod logging;
+pub mod mcp;
@@ -0,0 +1,160 @@
+//! Logging for the shim binaries.
+//!
+//! Logs go to **stderr** through `tracing`, filtered by `RUST_LOG` (default
+//! `info`). Whatever captures an agent's stderr sits outside the carapace
+//! boundary, so MCP message payloads — email bodies, document text, draft
+//! contents — are never logged unless payload logging is switched on, and
+//! even then they pass through [`redact`] first:
+//!
+//! - content fields (`body`, `text`, `snippet`, ...) are replaced by their length;
+//! - credential fields (`*token*`, `password`, ...) and bearer / OAuth tokens
+//!   inside strings are dropped;
+//! - email addresses and E.164 phone numbers are masked;
+//! - other strings longer than [`MAX_STRING_CHARS`] are truncated.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Longest string kept whole in a redacted payload.
+pub const MAX_STRING_CHARS: usize = 120;
+/// Longest redacted payload written as one log line.
+const MAX_PAYLOAD_CHARS: usize = 2000;
+
+/// Fields holding message or document content.
+const CONTENT_KEYS: &[&str] = &[
+    "body", "html_body", "text", "message", "snippet", "subject", "content", "data", "markdown", "html",
+    "values", "rows", "replacement", "structuredContent",
+];
+
+/// Field name fragments that mark credentials.
+const SECRET_KEY_PARTS: &[&str] = &["token", "secret", "password", "authorization", "cookie", "api_key"];
+
+static EMAIL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"([A-Za-z0-9._%+-])[A-Za-z0-9._%+-]*@([A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)+)").unwrap());
+static PHONE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\+\d{3,11}(\d{4})\b").unwrap());
+static BEARER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9._~+/=-]+").unwrap());
+/// Google OAuth access (`ya29.`) and refresh (`1//`) tokens.
+static GOOGLE_TOKEN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"ya29\.[A-Za-z0-9._-]+|1//[A-Za-z0-9._-]{20,}").unwrap());
+
+/// Send `tracing` output to stderr. `RUST_LOG` sets the level (default `info`).
+pub fn init() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .try_init()
+        .ok();
+}
+
+/// A copy of `value` that is safe to log (see the module docs).
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut out = Map::with_capacity(map.len());
+            for (key, value) in map {
+                let redacted = if is_secret_key(key) && value.is_string() {
+                    Value::String("[redacted]".into())
+                } else if CONTENT_KEYS.contains(&key.as_str()) && !value.is_null() {
+                    Value::String(format!("[{} chars]", content_len(value)))
+                } else {
+                    redact(value)
+                };
+                out.insert(key.clone(), redacted);
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        Value::String(s) => Value::String(redact_text(s)),
+        other => other.clone(),
+    }
+}
+
+/// Mask addresses and tokens in free text and truncate it.
+pub fn redact_text(text: &str) -> String {
+    let text = BEARER.replace_all(text, "Bearer [redacted]");
+    let text = GOOGLE_TOKEN.replace_all(&text, "[redacted]");
+    let text = EMAIL.replace_all(&text, "$1***@$2");
+    let text = PHONE.replace_all(&text, "+***$1");
+    truncate(&text, MAX_STRING_CHARS)
+}
+
+/// [`redact`] `value` and render it as one bounded log line.
+pub fn payload(value: &Value) -> String {
+    truncate(&redact(value).to_string(), MAX_PAYLOAD_CHARS)
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEY_PARTS.iter().any(|part| key.contains(part))
+}
+
+/// Size of a content field: characters of a string, or of its JSON.
+fn content_len(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.chars().count(),
+        other => other.to_string().chars().count(),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    let len = text.chars().count();
+    if len <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max).collect();
+    format!("{kept}… ({len} chars)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redact_hides_content_credentials_and_addresses() {
+        let call = json!({
+            "name": "gmail_create_draft",
+            "arguments": {
+                "to": "alice.smith@example.com",
+                "subject": "Quarterly numbers",
+                "body": "Revenue was up 12%",
+                "refresh_token": "1//abc",
+                "max": 20
+            },
+            "_meta": {"progressToken": 7}
+        });
+        assert_eq!(
+            redact(&call),
+            json!({
+                "name": "gmail_create_draft",
+                "arguments": {
+                    "to": "a***@example.com",
+                    "subject": "[17 chars]",
+                    "body": "[18 chars]",
+                    "refresh_token": "[redacted]",
+                    "max": 20
+                },
+                "_meta": {"progressToken": 7}
+            })
+        );
+    }
+
+    #[test]
+    fn redact_text_masks_tokens_and_phones_and_truncates() {
+        assert_eq!(
+            redact_text("send to +15551234567 with Bearer ya29.a0AfB_x"),
+            "send to +***4567 with Bearer [redacted]"
+        );
+        assert_eq!(redact_text("token ya29.a0AfB_xyz here"), "token [redacted] here");
+        // Dates are not phone numbers.
+        assert_eq!(redact_text("after:2025-03-01"), "after:2025-03-01");
+
+        let long = "x".repeat(MAX_STRING_CHARS + 5);
+        assert_eq!(redact_text(&long), format!("{}… ({} chars)", "x".repeat(MAX_STRING_CHARS), MAX_STRING_CHARS + 5));
+    }
+}
@@ -0,0 +1,1026 @@
+//! Google Docs / Drive tools for the Carapace MCP server.
+//!
+//! | Tool | Description |
+//! |------|-------------|
+//! | `gdocs_search` | Search Google Drive for files |
+//! | `gdocs_read` | Read a Google Doc, Sheet, Slides, Form or Drive file |
+//! | `gdocs_read_range` | Read one A1 range of a Sheet |
+//! | `gdocs_file_info` | Get file metadata |
+//! | `gdocs_create` | Create a new Google Doc |
+//! | `gdocs_copy` | Copy an existing file |
+//! | `gdocs_create_folder` | Create a Drive folder |
+//! | `gdocs_append` | Append text to a doc the agent created |
+//! | `gdocs_edit` | Structured edits (replace, insert, delete, headings, tables) |
+//! | `gdocs_update_sheet` | Write one or more Sheet ranges |
+//! | `gdocs_append_rows` | Append rows after a Sheet's last filled row |
+//! | `gdocs_form_add_questions` | Add questions to a Form |
+//! | `gdocs_form_edit_questions` | Reorder or delete Form questions |
+//! | `gdocs_status` | Check proxy health |
+
+use carapace_client::GatewayClient;
+use serde_json::{json, Value};
+
+use super::{object_schema, read_only, with_account, writes, ChannelTools, ToolResult};
+
+pub struct GDocsTools;
+
+impl ChannelTools for GDocsTools {
+    fn channel(&self) -> &'static str {
+        "gdocs"
+    }
+
+    fn has_accounts(&self) -> bool {
+        true
+    }
+
+    fn tools(&self) -> Value {
+        tool_definitions()
+    }
+
+    fn annotations(&self, tool: &str) -> Option<Value> {
+        Some(match tool {
+            "gdocs_search" | "gdocs_read" | "gdocs_read_range" | "gdocs_file_info" | "gdocs_status" => read_only(),
+            // These replace or delete existing content.
+            "gdocs_edit" | "gdocs_update_sheet" | "gdocs_form_edit_questions" => writes(true),
+            _ => writes(false),
+        })
+    }
+
+    fn output_schema(&self, tool: &str) -> Option<Value> {
+        let nullable_string = json!({"type": ["string", "null"]});
+        let file = object_schema(json!({
+            "id": {"type": "string"},
+            "name": {"type": "string"},
+            "mime_type": {"type": "string"},
+            "web_view_link": nullable_string
+        }));
+        Some(match tool {
+            "gdocs_search" => object_schema(json!({
+                "files": {"type": "array", "items": {"type": "object"}},
+                "next_page_token": nullable_string
+            })),
+            "gdocs_copy" | "gdocs_create_folder" => file,
+            "gdocs_append" => object_schema(json!({
+                "status": {"type": "string"},
+                "document_id": {"type": "string"}
+            })),
+            "gdocs_edit" => object_schema(json!({
+                "status": {"type": "string"},
+                "document_id": {"type": "string"},
+                "revision_id": nullable_string,
+                "applied": {"type": "integer"}
+            })),
+            "gdocs_append_rows" => object_schema(json!({
+                "spreadsheet_id": {"type": "string"},
+                "sheet": {"type": "string"},
+                "first_row": {"type": "integer"},
+                "rows_appended": {"type": "integer"},
+                "updated_range": nullable_string
+            })),
+            _ => return None,
+        })
+    }
+
+    fn call(&self, tool: &str, args: &Value, account: Option<&str>, gw: &mut GatewayClient) -> ToolResult {
+        call_tool(tool, args, account, gw)
+    }
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "gdocs_search",
+            "description": "\
+Search Google Drive for files. Uses Drive query syntax. \
+Returns a list of matching files with id, name, mime_type, created/modified times, and owner. \
+Supported query operators: name contains 'text', mimeType = '...', modifiedTime > '2024-01-01', \
+'text' in parents (search in folder), starred = true, sharedWithMe. \
+Set docs_only=true to restrict results to Google Docs only. \
+Example: name contains 'budget' — finds files with 'budget' in the name.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Google Drive search query (Drive query syntax). Leave empty to list recent files."
+                    },
+                    "max": {
+                        "type": "integer",
+                        "description": "Maximum number of results. Defaults to 20, max 100.",
+                        "default": 20
+                    },
+                    "docs_only": {
+                        "type": "boolean",
+                        "description": "If true, only return Google Docs (not sheets, slides, etc.). Defaults to false.",
+                        "default": false
+                    }
+                },
+                "required": []
+            }
+        },
+        {
+            "name": "gdocs_read",
+            "description": "\
+Read a Google Doc, Sheet, Slides presentation, or Form by its file ID. Auto-detects the file type. \
+For Docs: returns structured content with headings, paragraphs, list items, inline styles, links, \
+tables, images, footnotes, headers/footers and pending suggestions, plus the revision_id needed by \
+gdocs_edit. Set format to \"markdown\" to get the document as Markdown instead. \
+For Sheets: returns sheet names and cell data in rows; row n of rows is spreadsheet row n+1 \
+(empty rows are kept as []). Set render to \"formula\" or \"raw\" for formulas or unformatted values; \
+use gdocs_read_range for a single range. \
+For Slides: returns slide-by-slide text content. \
+For Forms: returns questions, options, and responses; pass since (RFC 3339) to get only \
+responses submitted after it, and use next_since from the result on the next call. \
+PDF, Word, Excel, PowerPoint, CSV and text files are returned with type file, their format, \
+extracted text (or rows for CSV and Excel) and a truncated flag; PDFs without a text layer (scans) come back empty. \
+Images and other binary files are not supported. \
+Use the file ID from gdocs_search results or extract it from a Google URL \
+(the part between /d/ and /edit).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "document_id": {
+                        "type": "string",
+                        "description": "The Google Docs document ID."
+                    },
+                    "format": {
+                        "type": "string",
+                        "enum": ["json", "markdown"],
+                        "description": "Output format for Google Docs. Defaults to json."
+                    },
+                    "include_comments": {
+                        "type": "boolean",
+                        "description": "Also return the document's comments and replies (Google Docs only)."
+                    },
+                    "render": {
+                        "type": "string",
+                        "enum": ["formatted", "formula", "raw"],
+                        "description": "Cell values for Google Sheets: as displayed (default), formulas, or unformatted values."
+                    },
+                    "since": {
+                        "type": "string",
+                        "description": "Google Forms only: return responses submitted after this RFC 3339 time."
+                    }
+                },
+                "required": ["document_id"]
+            }
+        },
+        {
+            "name": "gdocs_read_range",
+            "description": "\
+Read one range of a Google Sheet in A1 notation (e.g. 'Sheet1!A1:D20', 'Budget'!B:B). \
+Returns the resolved range, start_row and start_column, and the rows; rows[0] is start_row, \
+and empty rows inside the range are kept as []. All cell values are strings.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "spreadsheet_id": {
+                        "type": "string",
+                        "description": "The spreadsheet ID."
+                    },
+                    "range": {
+                        "type": "string",
+                        "description": "A1 range. Quote sheet names containing spaces: 'Q3 totals'!A1:C10."
+                    },
+                    "render": {
+                        "type": "string",
+                        "enum": ["formatted", "formula", "raw"],
+                        "description": "As displayed (default), formulas as entered, or unformatted values."
+                    }
+                },
+                "required": ["spreadsheet_id", "range"]
+            }
+        },
+        {
+            "name": "gdocs_file_info",
+            "description": "\
+Get metadata about a file in Google Drive (name, type, owner, dates, link). \
+Works for any file type, not just Google Docs.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file_id": {
+                        "type": "string",
+                        "description": "The Google Drive file ID."
+                    }
+                },
+                "required": ["file_id"]
+            }
+        },
+        {
+            "name": "gdocs_create",
+            "description": "\
+Create a new Google Doc with the given title and optional initial content. \
+By default the document is created in the Drive root, but you can specify a folder_id \
+to place it in a specific folder. \
+Returns the document_id and a link to open it in the browser. \
+The agent can later edit this document using gdocs_append.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Title for the new document."
+                    },
+                    "content": {
+                        "type": "string",
+                        "description": "Optional initial plain-text content for the document."
+                    },
+                    "folder_id": {
+                        "type": "string",
+                        "description": "Optional folder ID to create the document in. Use gdocs_create_folder to create folders, or gdocs_search to find existing ones."
+                    }
+                },
+                "required": ["title"]
+            }
+        },
+        {
+            "name": "gdocs_copy",
+            "description": "\
+Copy an existing file in Google Drive. Returns the new file's ID and metadata. \
+The copy is owned by the agent's account, so it can be edited freely. \
+This is useful for creating an editable version of a read-only document. \
+If the proxy restricts writes to certain folders, folder_id is required and must be one of them.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "file_id": {
+                        "type": "string",
+                        "description": "The ID of the file to copy."
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Optional title for the copy. Defaults to 'Copy of <original>'."
+                    },
+                    "folder_id": {
+                        "type": "string",
+                        "description": "Optional destination folder ID for the copy."
+                    }
+                },
+                "required": ["file_id"]
+            }
+        },
+        {
+            "name": "gdocs_append",
+            "description": "\
+Append plain text to the end of a Google Doc. Only works on documents inside the \
+proxy's writable folders or ones the agent created or copied. \
+Use this after gdocs_create or gdocs_copy to add content to a document.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "document_id": {
+                        "type": "string",
+                        "description": "The document ID to append text to."
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Plain text to append to the end of the document."
+                    }
+                },
+                "required": ["document_id", "text"]
+            }
+        },
+        {
+            "name": "gdocs_edit",
+            "description": "\
+Edit a Google Doc with a list of operations, applied together. First read the document with \
+gdocs_read and pass its revision_id; blocks are numbered by their position in the returned \
+content array (starting at 0). If someone else changed the document since, the edit is rejected \
+and nothing is applied — read it again and retry. Only works on documents inside the proxy's \
+writable folders or ones the agent created or copied.\n\
+Operations (field \"op\"):\n\
+- replace_text {find, replace, match_case?}: replace every occurrence (runs after the other operations)\n\
+- insert_after_heading {heading, text}: add a paragraph directly below a heading (must match exactly one heading)\n\
+- delete_range {from_block, to_block}: delete blocks, inclusive\n\
+- set_heading {block, level}: make a paragraph a heading (1-6) or body text (0)\n\
+- insert_table {after_block, rows}: insert a table of cell strings after a block",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "document_id": {
+                        "type": "string",
+                        "description": "The document ID to edit."
+                    },
+                    "revision_id": {
+                        "type": "string",
+                        "description": "The revision_id returned by gdocs_read."
+                    },
+                    "operations": {
+                        "type": "array",
+                        "description": "Edit operations, each an object with an \"op\" field.",
+                        "items": {"type": "object"}
+                    }
+                },
+                "required": ["document_id", "revision_id", "operations"]
+            }
+        },
+        {
+            "name": "gdocs_create_sheet",
+            "description": "\
+Create a new Google Sheet with the given name. Optionally provide initial data as rows of cell values \
+and a folder_id to place it in a specific folder. \
+Returns the spreadsheet ID and a link to open it. \
+The agent can later update this sheet using gdocs_update_sheet.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name for the new spreadsheet."
+                    },
+                    "folder_id": {
+                        "type": "string",
+                        "description": "Optional folder ID to create the sheet in."
+                    },
+                    "data": {
+                        "type": "array",
+                        "description": "Optional initial data. Array of rows, each row is an array of cell values as strings. First row is typically headers.",
+                        "items": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    }
+                },
+                "required": ["name"]
+            }
+        },
+        {
+            "name": "gdocs_update_sheet",
+            "description": "\
+Write values to a range in a Google Sheet. Only works on sheets the agent created or copied \
+(enforced by OAuth scope). Use A1 notation for the range (e.g. 'Sheet1!A1:C3', 'Sheet1!A1'). \
+To write several ranges in one request, pass data instead of range and values. \
+Values are parsed as if typed by a user (numbers, dates, formulas all work).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "spreadsheet_id": {
+                        "type": "string",
+                        "description": "The spreadsheet ID to update."
+                    },
+                    "range": {
+                        "type": "string",
+                        "description": "The A1 notation range to write to (e.g. 'Sheet1!A1:D10')."
+                    },
+                    "values": {
+                        "type": "array",
+                        "description": "Array of rows to write. Each row is an array of cell values as strings.",
+                        "items": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    },
+                    "data": {
+                        "type": "array",
+                        "description": "Several ranges to write at once, instead of range and values.",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "range": { "type": "string" },
+                                "values": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "array",
+                                        "items": { "type": "string" }
+                                    }
+                                }
+                            },
+                            "required": ["range", "values"]
+                        }
+                    }
+                },
+                "required": ["spreadsheet_id"]
+            }
+        },
+        {
+            "name": "gdocs_append_rows",
+            "description": "\
+Append rows to a Google Sheet, starting in column A of the row after the last filled row. \
+Only works on sheets the agent created or copied. Returns the first row written. \
+Values are parsed as if typed by a user.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "spreadsheet_id": {
+                        "type": "string",
+                        "description": "The spreadsheet ID to append to."
+                    },
+                    "sheet": {
+                        "type": "string",
+                        "description": "Sheet (tab) name. Defaults to the first sheet."
+                    },
+                    "values": {
+                        "type": "array",
+                        "description": "Rows to append. Each row is an array of cell values as strings.",
+                        "items": {
+                            "type": "array",
+                            "items": { "type": "string" }
+                        }
+                    }
+                },
+                "required": ["spreadsheet_id", "values"]
+            }
+        },
+        {
+            "name": "gdocs_create_form",
+            "description": "\
+Create a new Google Form with the given title. Returns the form ID, a link to edit it, \
+and the responder URI (shareable link for filling out the form). \
+Provide a folder_id to place it in a specific folder; it is required when the proxy restricts writes to certain folders. \
+The form starts empty; add questions with gdocs_form_add_questions.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "title": {
+                        "type": "string",
+                        "description": "Title for the new form."
+                    },
+                    "folder_id": {
+                        "type": "string",
+                        "description": "Optional folder ID to create the form in."
+                    }
+                },
+                "required": ["title"]
+            }
+        },
+        {
+            "name": "gdocs_form_add_questions",
+            "description": "\
+Add questions to a Google Form the agent created. Types: short_answer, paragraph, \
+multiple_choice, checkbox, dropdown (with options), scale (low/high), and date. \
+Questions are added in order at index (0 = first) or at the end; the index counts every form \
+item, including section headers and text blocks, as listed by gdocs_read. \
+Returns the new question IDs.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "form_id": {
+                        "type": "string",
+                        "description": "The form ID."
+                    },
+                    "questions": {
+                        "type": "array",
+                        "description": "Questions to add, in order.",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "title": { "type": "string" },
+                                "description": { "type": "string" },
+                                "type": {
+                                    "type": "string",
+                                    "enum": ["short_answer", "paragraph", "multiple_choice", "checkbox", "dropdown", "scale", "date"]
+                                },
+                                "required": { "type": "boolean" },
+                                "options": {
+                                    "type": "array",
+                                    "items": { "type": "string" },
+                                    "description": "Choices for multiple_choice, checkbox and dropdown."
+                                },
+                                "low": { "type": "integer", "description": "Scale start, 0 or 1." },
+                                "high": { "type": "integer", "description": "Scale end, 2-10." },
+                                "low_label": { "type": "string" },
+                                "high_label": { "type": "string" },
+                                "include_year": { "type": "boolean", "description": "Date questions; defaults to true." },
+                                "include_time": { "type": "boolean", "description": "Date questions; defaults to false." }
+                            },
+                            "required": ["title", "type"]
+                        }
+                    },
+                    "index": {
+                        "type": "integer",
+                        "description": "Item position of the first new question, counting section headers and text blocks. Defaults to the end of the form."
+                    }
+                },
+                "required": ["form_id", "questions"]
+            }
+        },
+        {
+            "name": "gdocs_form_edit_questions",
+            "description": "\
+Reorder or delete questions in a Google Form the agent created, by question_id (from gdocs_read). \
+Operations run in order: {\"op\": \"move_question\", \"question_id\", \"index\"} moves a question \
+to an item position (0 = first, counting section headers and text blocks); {\"op\": \"delete_question\", \"question_id\"} removes it.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "form_id": {
+                        "type": "string",
+                        "description": "The form ID."
+                    },
+                    "operations": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "op": { "type": "string", "enum": ["move_question", "delete_question"] },
+                                "question_id": { "type": "string" },
+                                "index": { "type": "integer" }
+                            },
+                            "required": ["op", "question_id"]
+                        }
+                    }
+                },
+                "required": ["form_id", "operations"]
+            }
+        },
+        {
+            "name": "gdocs_create_folder",
+            "description": "\
+Create a folder in Google Drive. Optionally specify a parent folder ID to nest it. \
+Returns the folder's ID and a link to open it in Drive. \
+Use this to organize documents into folders.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Name for the new folder."
+                    },
+                    "parent_id": {
+                        "type": "string",
+                        "description": "Optional parent folder ID. If omitted, the folder is created in the Drive root."
+                    }
+                },
+                "required": ["name"]
+            }
+        },
+        {
+            "name": "gdocs_status",
+            "description": "\
+Check the health of the Google Docs proxy. Returns whether the proxy is reachable \
+and whether the OAuth token is valid. \
+Use this to diagnose connection problems before calling other gdocs tools.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }
+    ])
+}
+
+fn call_tool(name: &str, args: &Value, account: Option<&str>, gw: &mut GatewayClient) -> ToolResult {
+    match name {
+        "gdocs_search" => {
+            let query = args.get("query").and_then(|v| v.as_str()).unwrap_or("");
+            let max = args.get("max").and_then(|v| v.as_u64()).unwrap_or(20);
+            let docs_only = args.get("docs_only").and_then(|v| v.as_bool()).unwrap_or(false);
+
+            let gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "query": query,
+                    "max": max,
+                    "docs_only": docs_only,
+                }),
+                account,
+            );
+
+            match gw.call("channel.search", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_search failed: {e}")),
+            }
+        }
+
+        "gdocs_read" => {
+            let doc_id = match args.get("document_id").and_then(|v| v.as_str()) {
+                Some(d) => d,
+                None => return Err("Missing required argument: \"document_id\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "chat_id": doc_id,
+                }),
+                account,
+            );
+            if let Some(format) = args.get("format").and_then(|v| v.as_str()) {
+                gw_params["format"] = json!(format);
+            }
+            if args.get("include_comments").and_then(|v| v.as_bool()).unwrap_or(false) {
+                gw_params["comments"] = json!(true);
+            }
+            if let Some(render) = args.get("render").and_then(|v| v.as_str()) {
+                gw_params["render"] = json!(render);
+            }
+            if let Some(since) = args.get("since").and_then(|v| v.as_str()) {
+                gw_params["since"] = json!(since);
+            }
+
+            match gw.call("channel.get_history", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_read failed: {e}")),
+            }
+        }
+
+        "gdocs_read_range" => {
+            let spreadsheet_id = match args.get("spreadsheet_id").and_then(|v| v.as_str()) {
+                Some(s) => s,
+                None => return Err("Missing required argument: \"spreadsheet_id\"".into()),
+            };
+            let range = match args.get("range").and_then(|v| v.as_str()) {
+                Some(r) => r,
+                None => return Err("Missing required argument: \"range\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "chat_id": spreadsheet_id,
+                    "range": range,
+                }),
+                account,
+            );
+            if let Some(render) = args.get("render").and_then(|v| v.as_str()) {
+                gw_params["render"] = json!(render);
+            }
+
+            match gw.call("channel.get_history", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_read_range failed: {e}")),
+            }
+        }
+
+        "gdocs_file_info" => {
+            let file_id = match args.get("file_id").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return Err("Missing required argument: \"file_id\"".into()),
+            };
+
+            let gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "file_info",
+                    "file_id": file_id,
+                }),
+                account,
+            );
+
+            match gw.call("channel.status", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_file_info failed: {e}")),
+            }
+        }
+
+        "gdocs_create" => {
+            let title = match args.get("title").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return Err("Missing required argument: \"title\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "create",
+                    "title": title,
+                }),
+                account,
+            );
+            if let Some(content) = args.get("content").and_then(|v| v.as_str()) {
+                gw_params["content"] = json!(content);
+            }
+            if let Some(folder_id) = args.get("folder_id").and_then(|v| v.as_str()) {
+                gw_params["folder_id"] = json!(folder_id);
+            }
+
+            match gw.call("channel.create_draft", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_create failed: {e}")),
+            }
+        }
+
+        "gdocs_copy" => {
+            let file_id = match args.get("file_id").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return Err("Missing required argument: \"file_id\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "copy",
+                    "file_id": file_id,
+                }),
+                account,
+            );
+            if let Some(title) = args.get("title").and_then(|v| v.as_str()) {
+                gw_params["title"] = json!(title);
+            }
+            if let Some(folder_id) = args.get("folder_id").and_then(|v| v.as_str()) {
+                gw_params["folder_id"] = json!(folder_id);
+            }
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_copy failed: {e}")),
+            }
+        }
+
+        "gdocs_append" => {
+            let doc_id = match args.get("document_id").and_then(|v| v.as_str()) {
+                Some(d) => d,
+                None => return Err("Missing required argument: \"document_id\"".into()),
+            };
+            let text = match args.get("text").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return Err("Missing required argument: \"text\"".into()),
+            };
+
+            let gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "append",
+                    "document_id": doc_id,
+                    "text": text,
+                }),
+                account,
+            );
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_append failed: {e}")),
+            }
+        }
+
+        "gdocs_edit" => {
+            let doc_id = match args.get("document_id").and_then(|v| v.as_str()) {
+                Some(d) => d,
+                None => return Err("Missing required argument: \"document_id\"".into()),
+            };
+            let revision_id = match args.get("revision_id").and_then(|v| v.as_str()) {
+                Some(r) => r,
+                None => return Err("Missing required argument: \"revision_id\"".into()),
+            };
+            let operations = match args.get("operations") {
+                Some(ops) if ops.is_array() => ops.clone(),
+                _ => return Err("Missing required argument: \"operations\" (array)".into()),
+            };
+
+            let gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "edit",
+                    "document_id": doc_id,
+                    "revision_id": revision_id,
+                    "operations": operations,
+                }),
+                account,
+            );
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_edit failed: {e}")),
+            }
+        }
+
+        "gdocs_create_sheet" => {
+            let name = match args.get("name").and_then(|v| v.as_str()) {
+                Some(n) => n,
+                None => return Err("Missing required argument: \"name\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "create_sheet",
+                    "name": name,
+                }),
+                account,
+            );
+            if let Some(folder_id) = args.get("folder_id").and_then(|v| v.as_str()) {
+                gw_params["folder_id"] = json!(folder_id);
+            }
+            if let Some(data) = args.get("data") {
+                gw_params["data"] = data.clone();
+            }
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_create_sheet failed: {e}")),
+            }
+        }
+
+        "gdocs_update_sheet" => {
+            let spreadsheet_id = match args.get("spreadsheet_id").and_then(|v| v.as_str()) {
+                Some(s) => s,
+                None => return Err("Missing required argument: \"spreadsheet_id\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "update_sheet",
+                    "spreadsheet_id": spreadsheet_id,
+                }),
+                account,
+            );
+            if let Some(data) = args.get("data") {
+                gw_params["data"] = data.clone();
+            } else {
+                let range = match args.get("range").and_then(|v| v.as_str()) {
+                    Some(r) => r,
+                    None => return Err("Missing required argument: \"range\" (or \"data\")".into()),
+                };
+                let values = match args.get("values") {
+                    Some(v) => v.clone(),
+                    None => return Err("Missing required argument: \"values\"".into()),
+                };
+                gw_params["range"] = json!(range);
+                gw_params["values"] = values;
+            }
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_update_sheet failed: {e}")),
+            }
+        }
+
+        "gdocs_append_rows" => {
+            let spreadsheet_id = match args.get("spreadsheet_id").and_then(|v| v.as_str()) {
+                Some(s) => s,
+                None => return Err("Missing required argument: \"spreadsheet_id\"".into()),
+            };
+            let values = match args.get("values") {
+                Some(v) => v.clone(),
+                None => return Err("Missing required argument: \"values\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "append_rows",
+                    "spreadsheet_id": spreadsheet_id,
+                    "values": values,
+                }),
+                account,
+            );
+            if let Some(sheet) = args.get("sheet").and_then(|v| v.as_str()) {
+                gw_params["sheet"] = json!(sheet);
+            }
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_append_rows failed: {e}")),
+            }
+        }
+
+        "gdocs_create_form" => {
+            let title = match args.get("title").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return Err("Missing required argument: \"title\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "create_form",
+                    "title": title,
+                }),
+                account,
+            );
+            if let Some(folder_id) = args.get("folder_id").and_then(|v| v.as_str()) {
+                gw_params["folder_id"] = json!(folder_id);
+            }
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_create_form failed: {e}")),
+            }
+        }
+
+        "gdocs_form_add_questions" => {
+            let form_id = match args.get("form_id").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return Err("Missing required argument: \"form_id\"".into()),
+            };
+            let questions = match args.get("questions").and_then(|v| v.as_array()) {
+                Some(q) if !q.is_empty() => q,
+                _ => return Err("Missing required argument: \"questions\" (expected non-empty array)".into()),
+            };
+            let start = args.get("index").and_then(|v| v.as_u64());
+
+            let operations: Vec<Value> = questions
+                .iter()
+                .enumerate()
+                .map(|(i, question)| {
+                    let mut op = json!({"op": "add_question", "question": question});
+                    if let Some(start) = start {
+                        op["index"] = json!(start + i as u64);
+                    }
+                    op
+                })
+                .collect();
+
+            let gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "edit_form",
+                    "form_id": form_id,
+                    "operations": operations,
+                }),
+                account,
+            );
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_form_add_questions failed: {e}")),
+            }
+        }
+
+        "gdocs_form_edit_questions" => {
+            let form_id = match args.get("form_id").and_then(|v| v.as_str()) {
+                Some(f) => f,
+                None => return Err("Missing required argument: \"form_id\"".into()),
+            };
+            let operations = match args.get("operations") {
+                Some(ops) if ops.as_array().is_some_and(|a| !a.is_empty()) => ops.clone(),
+                _ => return Err("Missing required argument: \"operations\" (expected non-empty array)".into()),
+            };
+
+            let gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "edit_form",
+                    "form_id": form_id,
+                    "operations": operations,
+                }),
+                account,
+            );
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_form_edit_questions failed: {e}")),
+            }
+        }
+
+        "gdocs_create_folder" => {
+            let name = match args.get("name").and_then(|v| v.as_str()) {
+                Some(n) => n,
+                None => return Err("Missing required argument: \"name\"".into()),
+            };
+
+            let mut gw_params = with_account(
+                json!({
+                    "channel": "gdocs",
+                    "action": "create_folder",
+                    "name": name,
+                }),
+                account,
+            );
+            if let Some(parent_id) = args.get("parent_id").and_then(|v| v.as_str()) {
+                gw_params["parent_id"] = json!(parent_id);
+            }
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_create_folder failed: {e}")),
+            }
+        }
+
+        "gdocs_status" => {
+            let gw_params = with_account(json!({"channel": "gdocs"}), account);
+            match gw.call("channel.status", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gdocs_status failed: {e}")),
+            }
+        }
+
+        _ => Err(format!("Unknown tool: {name}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::testing::FakeGateway;
+
+    #[test]
+    fn read_maps_options_onto_get_history() {
+        let gateway = FakeGateway::start(|_, _| Ok(json!({"type": "document"})));
+        let args = json!({"document_id": "d1", "format": "markdown", "include_comments": true});
+        call_tool("gdocs_read", &args, Some("hq"), &mut gateway.client()).unwrap();
+        assert_eq!(gateway.calls(), vec![(
+            "channel.get_history".to_string(),
+            json!({"channel": "gdocs", "chat_id": "d1", "format": "markdown", "comments": true, "account": "hq"}),
+        )]);
+    }
+
+    #[test]
+    fn added_questions_take_consecutive_indexes() {
+        let gateway = FakeGateway::start(|_, _| Ok(json!({"created_question_ids": ["a", "b"]})));
+        let q = json!({"title": "Q", "type": "short_answer"});
+        let args = json!({"form_id": "f1", "questions": [q, q], "index": 2});
+        call_tool("gdocs_form_add_questions", &args, None, &mut gateway.client()).unwrap();
+
+        let (method, params) = gateway.calls().remove(0);
+        assert_eq!(method, "channel.send");
+        assert_eq!(params["action"], "edit_form");
+        assert_eq!(params["operations"], json!([
+            {"op": "add_question", "question": q, "index": 2},
+            {"op": "add_question", "question": q, "index": 3},
+        ]));
+        assert!(params.get("account").is_none());
+    }
+}
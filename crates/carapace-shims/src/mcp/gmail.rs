@@ -0,0 +1,331 @@
+//! Gmail tools for the Carapace MCP server.
+//!
+//! | Tool | Description |
+//! |------|-------------|
+//! | `gmail_search` | Search emails using Gmail query syntax |
+//! | `gmail_read_thread` | Fetch all messages in a thread |
+//! | `gmail_read_attachment` | List a message's attachments or read one as text |
+//! | `gmail_create_draft` | Create a draft email (never sent automatically) |
+//! | `gmail_status` | Check gateway and OAuth token health |
+
+use carapace_client::GatewayClient;
+use serde_json::{json, Value};
+
+use super::{object_schema, read_only, with_account, writes, ChannelTools, ToolResult};
+
+pub struct GmailTools;
+
+impl ChannelTools for GmailTools {
+    fn channel(&self) -> &'static str {
+        "gmail"
+    }
+
+    fn has_accounts(&self) -> bool {
+        true
+    }
+
+    fn tools(&self) -> Value {
+        tool_definitions()
+    }
+
+    fn annotations(&self, tool: &str) -> Option<Value> {
+        Some(match tool {
+            // Drafts are only created, never sent or overwritten.
+            "gmail_create_draft" => writes(false),
+            _ => read_only(),
+        })
+    }
+
+    fn output_schema(&self, tool: &str) -> Option<Value> {
+        let nullable_string = json!({"type": ["string", "null"]});
+        Some(match tool {
+            "gmail_search" => object_schema(json!({
+                "messages": {"type": "array", "items": {"type": "object"}},
+                "next_page_token": nullable_string,
+                "result_size_estimate": {"type": ["integer", "null"]}
+            })),
+            "gmail_read_thread" => object_schema(json!({
+                "thread_id": {"type": "string"},
+                "messages": {"type": "array", "items": {"type": "object"}}
+            })),
+            // Without attachment_id: the attachment list. With it: the attachment.
+            "gmail_read_attachment" => object_schema(json!({
+                "message_id": {"type": "string"},
+                "attachments": {"type": "array", "items": {"type": "object"}}
+            })),
+            "gmail_create_draft" => object_schema(json!({
+                "draft_id": {"type": "string"},
+                "message_id": {"type": "string"},
+                "thread_id": {"type": "string"}
+            })),
+            _ => return None,
+        })
+    }
+
+    fn call(&self, tool: &str, args: &Value, account: Option<&str>, gw: &mut GatewayClient) -> ToolResult {
+        call_tool(tool, args, account, gw)
+    }
+}
+
+// ── Tool definitions (returned by tools/list) ────────────────────────────────
+//
+// These descriptions are what the agent reads to understand how to use each
+// tool. Be specific: name the query syntax, describe the return shape, note
+// constraints. Agents use this text as their only documentation.
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "gmail_search",
+            "description": "\
+Search emails in Gmail using Gmail's standard query syntax. \
+Returns a list of matching messages with id, thread_id, subject, from, date, and a \
+plain-text snippet of the body (OTP codes and auth URLs are pre-scrubbed). \
+Supported operators: from:, to:, subject:, is:unread, is:read, has:attachment, \
+after:, before:, older_than:, newer_than:, in:inbox, in:sent, label:, cc:, bcc:, filename:. \
+Operators that access trash, spam, or all-mail are blocked. \
+Example queries: \"from:boss@company.com is:unread\", \"subject:invoice newer_than:7d\".",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Gmail search query (same syntax as the Gmail search box). Required."
+                    },
+                    "max": {
+                        "type": "integer",
+                        "description": "Maximum number of messages to return. Defaults to 20, max 50.",
+                        "default": 20
+                    }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "gmail_read_thread",
+            "description": "\
+Fetch all messages in a Gmail thread by its thread_id. \
+Use the thread_id from gmail_search results to retrieve the full conversation. \
+Returns an array of messages in chronological order, each with from, to, date, subject, \
+and plain-text body (scrubbed). This is the right tool when you want to read a full \
+email conversation rather than just the snippet from search.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "thread_id": {
+                        "type": "string",
+                        "description": "The Gmail thread ID to fetch (from gmail_search result's thread_id field)."
+                    }
+                },
+                "required": ["thread_id"]
+            }
+        },
+        {
+            "name": "gmail_read_attachment",
+            "description": "\
+List or read the attachments of a Gmail message. \
+Call with only message_id to list attachments: each entry has attachment_id, filename, \
+mime_type, size, and readable (whether its text can be extracted). \
+Call again with an attachment_id from that list to get the attachment's text. \
+Only text, CSV/TSV, markdown and PDF (text layer only — scanned PDFs return little or no \
+text) can be read; other types and files over the size limit return an error. \
+Extracted text is scrubbed like message bodies and may be truncated (see the truncated field).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "message_id": {
+                        "type": "string",
+                        "description": "The Gmail message ID (the id field from gmail_search or gmail_read_thread)."
+                    },
+                    "attachment_id": {
+                        "type": "string",
+                        "description": "Attachment to read, from the listing. Omit to list attachments."
+                    }
+                },
+                "required": ["message_id"]
+            }
+        },
+        {
+            "name": "gmail_create_draft",
+            "description": "\
+Create a Gmail draft email. The draft is saved to the Drafts folder and is NOT sent \
+automatically — a human must open Gmail and send it manually. \
+Use this when you need to compose an email for review before sending. \
+Optionally pass html_body to include a formatted HTML version; it is reduced to basic \
+formatting tags (paragraphs, bold/italic, lists, links, tables) and scripts, styles, \
+images and attributes other than link targets are removed. \
+Returns the draft_id of the created draft.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "to": {
+                        "type": "string",
+                        "description": "Recipient email address."
+                    },
+                    "subject": {
+                        "type": "string",
+                        "description": "Email subject line."
+                    },
+                    "body": {
+                        "type": "string",
+                        "description": "Plain-text email body."
+                    },
+                    "html_body": {
+                        "type": "string",
+                        "description": "Optional HTML version of the body, sent as a multipart/alternative part."
+                    },
+                    "cc": {
+                        "type": "string",
+                        "description": "Optional CC email address."
+                    }
+                },
+                "required": ["to", "subject", "body"]
+            }
+        },
+        {
+            "name": "gmail_status",
+            "description": "\
+Check the health of the Gmail channel. Returns whether the gmail-proxy is reachable \
+and whether the OAuth token is valid and when it expires. \
+Use this to diagnose connection problems before calling other Gmail tools.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }
+    ])
+}
+
+// ── Tool call dispatcher ─────────────────────────────────────────────────────
+
+fn call_tool(name: &str, args: &Value, account: Option<&str>, gw: &mut GatewayClient) -> ToolResult {
+    match name {
+        "gmail_search" => {
+            let query = match args.get("query").and_then(|v| v.as_str()) {
+                Some(q) => q,
+                None => return Err("Missing required argument: \"query\"".into()),
+            };
+            let max = args.get("max").and_then(|v| v.as_u64()).unwrap_or(20);
+
+            let gw_params = with_account(json!({
+                "channel": "gmail",
+                "query": query,
+                "max": max,
+            }), account);
+
+            match gw.call("channel.search", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gmail_search failed: {e}")),
+            }
+        }
+
+        "gmail_read_thread" => {
+            let thread_id = match args.get("thread_id").and_then(|v| v.as_str()) {
+                Some(t) => t,
+                None => return Err("Missing required argument: \"thread_id\"".into()),
+            };
+
+            let gw_params = with_account(json!({
+                "channel": "gmail",
+                "chat_id": thread_id,
+            }), account);
+
+            match gw.call("channel.get_history", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gmail_read_thread failed: {e}")),
+            }
+        }
+
+        "gmail_read_attachment" => {
+            let message_id = match args.get("message_id").and_then(|v| v.as_str()) {
+                Some(m) => m,
+                None => return Err("Missing required argument: \"message_id\"".into()),
+            };
+
+            let mut gw_params = with_account(json!({
+                "channel": "gmail",
+                "message_id": message_id,
+            }), account);
+            if let Some(attachment_id) = args.get("attachment_id").and_then(|v| v.as_str()) {
+                gw_params["attachment_id"] = json!(attachment_id);
+            }
+
+            match gw.call("channel.read_attachment", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gmail_read_attachment failed: {e}")),
+            }
+        }
+
+        "gmail_create_draft" => {
+            let to = match args.get("to").and_then(|v| v.as_str()) {
+                Some(v) => v,
+                None => return Err("Missing required argument: \"to\"".into()),
+            };
+            let subject = match args.get("subject").and_then(|v| v.as_str()) {
+                Some(v) => v,
+                None => return Err("Missing required argument: \"subject\"".into()),
+            };
+            let body = match args.get("body").and_then(|v| v.as_str()) {
+                Some(v) => v,
+                None => return Err("Missing required argument: \"body\"".into()),
+            };
+
+            let mut gw_params = with_account(json!({
+                "channel": "gmail",
+                "to": to,
+                "subject": subject,
+                "body": body,
+            }), account);
+            if let Some(cc) = args.get("cc").and_then(|v| v.as_str()) {
+                gw_params["cc"] = json!(cc);
+            }
+            if let Some(html) = args.get("html_body").and_then(|v| v.as_str()) {
+                gw_params["html_body"] = json!(html);
+            }
+
+            match gw.call("channel.create_draft", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gmail_create_draft failed: {e}")),
+            }
+        }
+
+        "gmail_status" => {
+            let gw_params = with_account(json!({"channel": "gmail"}), account);
+            match gw.call("channel.status", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(format!("gmail_status failed: {e}")),
+            }
+        }
+
+        _ => Err(format!("Unknown tool: {name}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::testing::FakeGateway;
+
+    #[test]
+    fn tools_map_to_gateway_calls_with_the_account() {
+        let gateway = FakeGateway::start(|_, _| Ok(json!({"ok": true})));
+        let mut gw = gateway.client();
+        call_tool("gmail_search", &json!({"query": "from:ana"}), Some("work"), &mut gw).unwrap();
+        call_tool("gmail_read_thread", &json!({"thread_id": "t1"}), None, &mut gw).unwrap();
+
+        assert_eq!(gateway.calls(), vec![
+            ("channel.search".to_string(), json!({"channel": "gmail", "query": "from:ana", "max": 20, "account": "work"})),
+            ("channel.get_history".to_string(), json!({"channel": "gmail", "chat_id": "t1"})),
+        ]);
+    }
+
+    #[test]
+    fn gateway_errors_name_the_tool() {
+        let gateway = FakeGateway::start(|_, _| Err((-32001, "Recipient blocked".into())));
+        let args = json!({"to": "a@example.com", "subject": "s", "body": "b"});
+        let err = call_tool("gmail_create_draft", &args, None, &mut gateway.client()).unwrap_err();
+        assert!(err.starts_with("gmail_create_draft failed"), "{err}");
+        assert!(call_tool("gmail_send", &json!({}), None, &mut gateway.client()).is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! iMessage tools and inbox resource for the Carapace MCP server.
+//!
+//! | Tool | Description |
+//! |------|-------------|
+//! | `imsg_send` | Send a message (recipient must be on the outbound allowlist) |
+//! | `imsg_list_chats` | List recent chats |
+//! | `imsg_history` | Read the messages of one chat |
+//! | `imsg_status` | Check gateway and iMessage channel health |
+//!
+//! ## Resources
+//! `imsg://inbox` holds the most recent inbound messages received through
+//! `channel.watch` since the server started (already filtered by the inbound
+//! allowlist). Clients that call `resources/subscribe` on it get a
+//! `notifications/resources/updated` notification for every new message.
+//!
+//! With a named daemon cursor ([`super::Options::imsg_cursor`]), messages that
+//! arrived while the server was not running are replayed into the inbox, and
+//! the cursor is acknowledged each time the inbox is read.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+use carapace_client::{ClientError, GatewayClient};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use super::{read_only, writes, ChannelTools, Context, ToolResult};
+
+const INBOX_URI: &str = "imsg://inbox";
+/// Inbound messages kept in the inbox resource; older ones are dropped.
+const INBOX_CAPACITY: usize = 100;
+
+// ── Inbox (shared with the watch thread) ─────────────────────────────────────
+
+#[derive(Default)]
+struct Inbox {
+    messages: VecDeque<Value>,
+    /// Whether the client subscribed to `imsg://inbox` updates.
+    subscribed: bool,
+    /// Whether a watch thread is currently running.
+    watching: bool,
+    /// Position of the newest message, to acknowledge on the named cursor.
+    position: Option<String>,
+}
+
+type SharedInbox = Arc<Mutex<Inbox>>;
+
+pub struct ImsgTools {
+    cursor: Option<String>,
+    inbox: SharedInbox,
+}
+
+impl ImsgTools {
+    pub fn new(cursor: Option<String>) -> Self {
+        Self { cursor, inbox: Arc::default() }
+    }
+
+    /// Acknowledge `position` on the named cursor, if one is configured.
+    fn ack(&self, position: Option<String>, ctx: &mut Context<'_>) {
+        let (Some(cursor), Some(position)) = (self.cursor.clone(), position) else {
+            return;
+        };
+        let params = json!({"channel": "imsg", "cursor": cursor, "position": position});
+        let result = ctx.gateway().and_then(|gw| gw.call("channel.ack", params).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!(error = %e, "cursor ack failed");
+        }
+    }
+
+    /// Start the watch thread unless one is already running.
+    ///
+    /// The watch uses its own connection so tool calls are never blocked
+    /// behind the stream. If it fails or the stream ends, the next read or
+    /// subscribe starts it again.
+    fn ensure_watch(&self, ctx: &Context<'_>) {
+        {
+            let mut inbox = self.inbox.lock().unwrap();
+            if inbox.watching {
+                return;
+            }
+            inbox.watching = true;
+        }
+
+        let inbox = Arc::clone(&self.inbox);
+        let out = ctx.notifier();
+        let socket_path = ctx.socket_path();
+        let span = tracing::Span::current();
+        let mut params = json!({"channel": "imsg"});
+        if let Some(ref cursor) = self.cursor {
+            params["cursor"] = json!(cursor);
+        }
+        std::thread::spawn(move || {
+            let _entered = span.enter();
+            let result = super::connect(socket_path.as_deref())
+                .and_then(|c| c.subscribe("channel.watch", params).map_err(|e| e.to_string()));
+            match result {
+                Ok((_ack, subscription)) => {
+                    info!("watching for inbound messages");
+                    for event in subscription {
+                        match event {
+                            Ok(message) => on_message(&inbox, &out, message),
+                            Err(e) => {
+                                warn!(error = %e, "watch error");
+                                break;
+                            }
+                        }
+                    }
+                    info!("watch stream ended");
+                }
+                Err(e) => warn!(error = %e, "watch failed to start"),
+            }
+            inbox.lock().unwrap().watching = false;
+        });
+    }
+}
+
+impl ChannelTools for ImsgTools {
+    fn channel(&self) -> &'static str {
+        "imsg"
+    }
+
+    fn tools(&self) -> Value {
+        tool_definitions()
+    }
+
+    fn annotations(&self, tool: &str) -> Option<Value> {
+        Some(match tool {
+            "imsg_send" => writes(false),
+            _ => read_only(),
+        })
+    }
+
+    fn call(&self, tool: &str, args: &Value, _account: Option<&str>, gw: &mut GatewayClient) -> ToolResult {
+        call_tool(tool, args, gw)
+    }
+
+    fn resources(&self) -> Vec<Value> {
+        vec![json!({
+            "uri": INBOX_URI,
+            "name": "iMessage inbox",
+            "description": "Most recent inbound iMessages (newest last), received since the \
+                            server started. Only senders on the inbound allowlist appear. \
+                            Subscribe to be notified when a new message arrives.",
+            "mimeType": "application/json"
+        })]
+    }
+
+    fn read_resource(&self, uri: &str, ctx: &mut Context<'_>) -> Option<Result<String, String>> {
+        if uri != INBOX_URI {
+            return None;
+        }
+        self.ensure_watch(ctx);
+        let (messages, position) = {
+            let inbox = self.inbox.lock().unwrap();
+            (inbox.messages.iter().cloned().collect::<Vec<_>>(), inbox.position.clone())
+        };
+        self.ack(position, ctx);
+        Some(serde_json::to_string_pretty(&messages).map_err(|e| e.to_string()))
+    }
+
+    fn subscribe(&self, uri: &str, on: bool, ctx: &mut Context<'_>) -> bool {
+        if uri != INBOX_URI {
+            return false;
+        }
+        self.inbox.lock().unwrap().subscribed = on;
+        if on {
+            self.ensure_watch(ctx);
+        }
+        true
+    }
+
+    // Start collecting inbound messages right away so the inbox is populated
+    // before the agent first looks at it.
+    fn initialized(&self, ctx: &mut Context<'_>) {
+        if let Some(ref cursor) = self.cursor {
+            info!(cursor = %cursor, "watching with named cursor");
+        }
+        self.ensure_watch(ctx);
+    }
+}
+
+/// Add an inbound message to the inbox and notify a subscribed client.
+fn on_message(inbox: &SharedInbox, out: &Sender<Value>, mut message: Value) {
+    let subscribed = {
+        let mut inbox = inbox.lock().unwrap();
+        if let Some(obj) = message.as_object_mut() {
+            obj.remove("subscription");
+            if let Some(position) = obj.remove("position").and_then(|p| p.as_str().map(String::from)) {
+                inbox.position = Some(position);
+            }
+        }
+        if inbox.messages.len() == INBOX_CAPACITY {
+            inbox.messages.pop_front();
+        }
+        inbox.messages.push_back(message);
+        inbox.subscribed
+    };
+    if subscribed {
+        out.send(json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/resources/updated",
+            "params": {"uri": INBOX_URI}
+        }))
+        .ok();
+    }
+}
+
+// ── Tool definitions (returned by tools/list) ────────────────────────────────
+//
+// These descriptions are what the agent reads to understand how to use each
+// tool. Be specific about what the gateway enforces: agents cannot see the
+// allowlists, so they need to know what a rejection means and what not to
+// retry.
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "imsg_send",
+            "description": "\
+Send an iMessage through the Carapace gateway. \
+The recipient must be on the operator's outbound allowlist; any other recipient is \
+rejected with a not-in-allowlist error. The allowlist cannot be changed or bypassed \
+from here, so do not retry with a different spelling of the same handle. Ask the \
+user to add the contact instead. \
+Sends are also rate limited (retry later if told so) and checked by a content \
+filter (a blocked message must be rephrased, not resent). \
+Only text can be sent; files cannot be attached. \
+Returns success and the recipient on delivery to Messages.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "to": {
+                        "type": "string",
+                        "description": "Recipient handle: a phone number in E.164 form (+15551234567) or an Apple ID email."
+                    },
+                    "text": {
+                        "type": "string",
+                        "description": "Message text."
+                    }
+                },
+                "required": ["to", "text"]
+            }
+        },
+        {
+            "name": "imsg_list_chats",
+            "description": "\
+List recent iMessage chats. Each chat has an id to pass as chat_id to imsg_history, \
+and a display name or the participant handles.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of chats to return."
+                    }
+                },
+                "required": []
+            }
+        },
+        {
+            "name": "imsg_history",
+            "description": "\
+Read the messages of one iMessage chat by chat_id (from imsg_list_chats). \
+Returns messages with sender, text and date, newest first.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "chat_id": {
+                        "type": "string",
+                        "description": "Chat identifier from imsg_list_chats."
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Maximum number of messages to return."
+                    },
+                    "before": {
+                        "type": "string",
+                        "description": "Optional ISO 8601 timestamp; only messages sent before it are returned. Use it to page back."
+                    }
+                },
+                "required": ["chat_id"]
+            }
+        },
+        {
+            "name": "imsg_status",
+            "description": "\
+Check the health of the iMessage channel: whether the daemon can reach Messages \
+and whether the channel is configured. Use this to diagnose connection problems \
+before calling other iMessage tools.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {},
+                "required": []
+            }
+        }
+    ])
+}
+
+// ── Tool call dispatcher ─────────────────────────────────────────────────────
+
+fn call_tool(name: &str, args: &Value, gw: &mut GatewayClient) -> ToolResult {
+    match name {
+        "imsg_send" => {
+            let to = match args.get("to").and_then(|v| v.as_str()) {
+                Some(v) => v,
+                None => return Err("Missing required argument: \"to\"".into()),
+            };
+            let text = match args.get("text").and_then(|v| v.as_str()) {
+                Some(v) => v,
+                None => return Err("Missing required argument: \"text\"".into()),
+            };
+
+            if args.get("files").and_then(|f| f.as_array()).is_some_and(|a| !a.is_empty()) {
+                return Err("imsg_send cannot attach files; send the text only.".into());
+            }
+
+            let gw_params = json!({
+                "channel": "imsg",
+                "recipient": to,
+                "message": text,
+            });
+
+            match gw.call("channel.send", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(gateway_error("imsg_send", e)),
+            }
+        }
+
+        "imsg_list_chats" => {
+            let mut gw_params = json!({"channel": "imsg"});
+            if let Some(limit) = args.get("limit").and_then(|v| v.as_u64()) {
+                gw_params["limit"] = json!(limit);
+            }
+            match gw.call("channel.list_chats", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(gateway_error("imsg_list_chats", e)),
+            }
+        }
+
+        "imsg_history" => {
+            // Chat IDs may come back from imsg_list_chats as numbers.
+            let chat_id = match args.get("chat_id") {
+                Some(Value::String(s)) if !s.is_empty() => s.clone(),
+                Some(Value::Number(n)) => n.to_string(),
+                _ => return Err("Missing required argument: \"chat_id\"".into()),
+            };
+
+            let mut gw_params = json!({
+                "channel": "imsg",
+                "chat_id": chat_id,
+            });
+            if let Some(limit) = args.get("limit").and_then(|v| v.as_u64()) {
+                gw_params["limit"] = json!(limit);
+            }
+            if let Some(before) = args.get("before").and_then(|v| v.as_str()) {
+                gw_params["before"] = json!(before);
+            }
+
+            match gw.call("channel.get_history", gw_params) {
+                Ok(result) => Ok(result),
+                Err(e) => Err(gateway_error("imsg_history", e)),
+            }
+        }
+
+        "imsg_status" => match gw.call("channel.status", json!({"channel": "imsg"})) {
+            Ok(result) => Ok(result),
+            Err(e) => Err(gateway_error("imsg_status", e)),
+        },
+
+        _ => Err(format!("Unknown tool: {name}")),
+    }
+}
+
+/// Describe a gateway error, explaining the policy rejections an agent can hit.
+fn gateway_error(tool: &str, err: ClientError) -> String {
+    let hint = match &err {
+        ClientError::NotInAllowlist { .. } => {
+            "The recipient is not on the outbound allowlist. Only the operator can add \
+             contacts; ask the user instead of retrying."
+        }
+        ClientError::RateLimited { .. } => "Rate limited. Wait before sending again.",
+        ClientError::ContentBlocked { .. } => {
+            "Blocked by the content filter. Rephrase the message; resending it unchanged \
+             will be blocked again."
+        }
+        ClientError::ChannelUnavailable { .. } => {
+            "The iMessage channel is not configured or unavailable on this gateway."
+        }
+        ClientError::SendFailed { .. } => "Messages failed to send. Check imsg_status.",
+        ClientError::Gateway { message, .. } => return format!("{tool} failed: {message}"),
+        other => return format!("{tool} failed: {other}"),
+    };
+    format!("{tool} failed: {hint} (detail: {})", err.gateway_message().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp::testing::FakeGateway;
+
+    #[test]
+    fn send_forwards_text_to_the_gateway() {
+        let gateway = FakeGateway::start(|_, _| Ok(json!({"success": true, "stdout": ""})));
+        let result = call_tool("imsg_send", &json!({"to": "+15551234567", "text": "hi"}), &mut gateway.client());
+        assert_eq!(result.unwrap()["success"], true);
+        assert_eq!(
+            gateway.calls(),
+            vec![("channel.send".to_string(), json!({"channel": "imsg", "recipient": "+15551234567", "message": "hi"}))]
+        );
+    }
+
+    #[test]
+    fn send_refuses_files_without_calling_the_gateway() {
+        let gateway = FakeGateway::start(|_, _| Ok(json!({"success": true, "stdout": ""})));
+        let args = json!({"to": "+15551234567", "text": "hi", "files": ["/tmp/photo.jpg"]});
+        let err = call_tool("imsg_send", &args, &mut gateway.client()).unwrap_err();
+        assert!(err.contains("cannot attach files"), "{err}");
+        assert!(gateway.calls().is_empty());
+        assert!(tool_definitions()[0]["inputSchema"]["properties"].get("files").is_none());
+    }
+}
@@ -0,0 +1,1158 @@
+//! Shared MCP (Model Context Protocol) server for the Carapace gateway.
+//!
+//! ## Transport
+//! Reads newline-delimited JSON-RPC 2.0 from **stdin**, writes responses to
+//! **stdout**. Diagnostic messages go to **stderr** only — stdout is reserved
+//! for the MCP wire protocol. A dedicated writer thread owns stdout so that
+//! background watches can push notifications while a tool call is running.
+//!
+//! Logging goes through [`crate::logging`]: message metadata at `debug`,
+//! payloads only with [`Options::log_payloads`], and then redacted.
+//!
+//! ## Channels and accounts
+//! Each channel contributes its tools (and resources) through
+//! [`ChannelTools`]. The tool list is built from the channels the daemon
+//! reports in `channel.capabilities`, narrowed to [`Options::channels`] when
+//! set. Channels with named accounts (Gmail, Google Docs) get an `account`
+//! argument listing every configured account, unless the server is pinned to
+//! one account, in which case only that account is reachable. The
+//! single-channel wrappers (`gmail-mcp`, `gdocs-mcp`) are pinned to the
+//! daemon's default account unless `GMAIL_ACCOUNT`/`GDOCS_ACCOUNT` names one.
+//!
+//! The gateway connection is made lazily, so the MCP handshake succeeds even
+//! if the daemon isn't running yet. Until the daemon can be asked, every
+//! selected channel is offered; once it answers, clients are told to refresh
+//! with `notifications/tools/list_changed`.
+//!
+//! ## Protocol revisions
+//! `initialize` agrees on the newest revision both sides support (see
+//! [`SUPPORTED_VERSIONS`]). Tool annotations are sent from 2025-03-26 on;
+//! `outputSchema` and `structuredContent` from 2025-06-18 on.
+//!
+//! ## Tool calls
+//! Tool calls run on a fixed set of worker threads ([`MAX_CONCURRENT_CALLS`]),
+//! each with a pooled gateway connection, so the server keeps reading stdin
+//! meanwhile; further calls wait for a free worker. `notifications/cancelled`
+//! stops waiting for the call: its connection is shut down and no response is
+//! sent. The daemon is not told, so a call it has already received (a send,
+//! an edit) may still complete. If the request carries a `progressToken`,
+//! `notifications/progress` is sent every few seconds until the call ends.
+
+pub mod gdocs;
+pub mod gmail;
+pub mod imsg;
+#[cfg(test)]
+mod testing;
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use carapace_client::{GatewayClient, InterruptHandle};
+use serde_json::{json, Value};
+use tracing::{debug, error, info, warn};
+
+use crate::logging;
+
+/// MCP revisions this server speaks, newest first.
+pub const SUPPORTED_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+/// First revision with tool annotations.
+const ANNOTATIONS_SINCE: &str = "2025-03-26";
+/// First revision with `outputSchema` and `structuredContent`.
+const STRUCTURED_OUTPUT_SINCE: &str = "2025-06-18";
+const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Tools per `tools/list` page.
+const TOOLS_PAGE_SIZE: usize = 50;
+/// Tool calls run at once; further calls queue for a free worker thread.
+pub const MAX_CONCURRENT_CALLS: usize = 8;
+/// How often a running tool call reports progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Outcome of a tool call: the gateway result, or a message for the agent.
+pub type ToolResult = Result<Value, String>;
+
+/// The tools (and optionally resources) one channel exposes over MCP.
+///
+/// Tool calls run on worker threads, hence `Send + Sync`.
+pub trait ChannelTools: Send + Sync {
+    /// Daemon channel name (`gmail`, `gdocs`, `imsg`). Tool names start with
+    /// this followed by `_`.
+    fn channel(&self) -> &'static str;
+
+    /// Whether the channel has named accounts, selected with `account`.
+    fn has_accounts(&self) -> bool {
+        false
+    }
+
+    /// Tool definitions, as returned by `tools/list`, without `account`,
+    /// `annotations` or `outputSchema`.
+    fn tools(&self) -> Value;
+
+    /// Behaviour hints for a tool (see [`read_only`] and [`writes`]).
+    fn annotations(&self, _tool: &str) -> Option<Value> {
+        None
+    }
+
+    /// JSON Schema of a tool's `structuredContent`. Defaults to any object.
+    fn output_schema(&self, _tool: &str) -> Option<Value> {
+        None
+    }
+
+    /// Run one of this channel's tools. `account` is already resolved; `None`
+    /// means the daemon's default account.
+    fn call(&self, tool: &str, args: &Value, account: Option<&str>, gw: &mut GatewayClient) -> ToolResult;
+
+    /// Resource descriptors, as returned by `resources/list`.
+    fn resources(&self) -> Vec<Value> {
+        Vec::new()
+    }
+
+    /// Read a resource as JSON text. `None` if the URI is not this channel's.
+    fn read_resource(&self, _uri: &str, _ctx: &mut Context<'_>) -> Option<Result<String, String>> {
+        None
+    }
+
+    /// Turn update notifications for a resource on or off. Returns false if
+    /// the URI is not this channel's.
+    fn subscribe(&self, _uri: &str, _on: bool, _ctx: &mut Context<'_>) -> bool {
+        false
+    }
+
+    /// Called once the client has finished the handshake.
+    fn initialized(&self, _ctx: &mut Context<'_>) {}
+}
+
+/// Per-request access to the gateway and the stdout writer.
+pub struct Context<'a> {
+    client: &'a mut Option<GatewayClient>,
+    out: &'a Sender<Value>,
+    socket_path: Option<&'a Path>,
+}
+
+impl Context<'_> {
+    /// The gateway connection, made on first use.
+    pub fn gateway(&mut self) -> Result<&mut GatewayClient, String> {
+        if self.client.is_none() {
+            *self.client = Some(connect(self.socket_path)?);
+        }
+        Ok(self.client.as_mut().unwrap())
+    }
+
+    /// A sender for messages (notifications) written to stdout.
+    pub fn notifier(&self) -> Sender<Value> {
+        self.out.clone()
+    }
+
+    /// The daemon socket ([`Options::socket_path`]), for connections of
+    /// your own.
+    pub fn socket_path(&self) -> Option<PathBuf> {
+        self.socket_path.map(Path::to_path_buf)
+    }
+}
+
+/// Connect to the daemon at `socket_path`, or the client's default path.
+pub fn connect(socket_path: Option<&Path>) -> Result<GatewayClient, String> {
+    let result = match socket_path {
+        Some(path) => GatewayClient::connect(path),
+        None => GatewayClient::connect_default(),
+    };
+    let client = result.map_err(|e| {
+        format!("Cannot connect to Carapace daemon: {e}. Make sure carapace-daemon is running.")
+    })?;
+    info!("connected to gateway");
+    Ok(client)
+}
+
+/// Annotations for a tool that only reads.
+pub fn read_only() -> Value {
+    json!({"readOnlyHint": true, "openWorldHint": true})
+}
+
+/// Annotations for a tool that changes something. `destructive` means it can
+/// overwrite or delete existing content rather than only add to it.
+pub fn writes(destructive: bool) -> Value {
+    json!({"readOnlyHint": false, "destructiveHint": destructive, "openWorldHint": true})
+}
+
+/// An object output schema with the given (all optional) properties.
+pub fn object_schema(properties: Value) -> Value {
+    json!({"type": "object", "properties": properties})
+}
+
+/// Inject the `account` field into a gateway params object if an account is set.
+pub fn with_account(mut params: Value, account: Option<&str>) -> Value {
+    if let Some(acct) = account {
+        params["account"] = json!(acct);
+    }
+    params
+}
+
+// ── Server options ───────────────────────────────────────────────────────────
+
+/// How a server instance is set up.
+pub struct Options {
+    /// `serverInfo.name` reported in the handshake.
+    pub server_name: &'static str,
+    /// Name attached to every log line (the `server` span field).
+    pub log_tag: &'static str,
+    /// Channels to offer. Empty means every channel the daemon has configured.
+    pub channels: Vec<String>,
+    /// Accounts the server is pinned to, by channel. A pinned channel exposes
+    /// only that account and has no `account` argument; `None` pins it to the
+    /// daemon's default account.
+    pub pinned_accounts: HashMap<String, Option<String>>,
+    /// Named daemon cursor for the iMessage inbox watch.
+    pub imsg_cursor: Option<String>,
+    /// Log (redacted) message payloads, not just their metadata.
+    pub log_payloads: bool,
+    /// Daemon socket. `None` uses the client default (`CARAPACE_SOCKET_PATH`
+    /// or `/var/run/carapace/gateway.sock`).
+    pub socket_path: Option<PathBuf>,
+}
+
+impl Options {
+    /// Options with account pins, the inbox cursor and payload logging read
+    /// from the environment (`GMAIL_ACCOUNT`, `GDOCS_ACCOUNT`,
+    /// `IMSG_MCP_CURSOR`, `CARAPACE_MCP_LOG_PAYLOADS=1`).
+    pub fn from_env(server_name: &'static str, log_tag: &'static str) -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let mut pinned_accounts = HashMap::new();
+        for (channel, name) in [("gmail", "GMAIL_ACCOUNT"), ("gdocs", "GDOCS_ACCOUNT")] {
+            if let Some(account) = var(name) {
+                pinned_accounts.insert(channel.to_string(), Some(account));
+            }
+        }
+        Self {
+            server_name,
+            log_tag,
+            channels: Vec::new(),
+            pinned_accounts,
+            imsg_cursor: var("IMSG_MCP_CURSOR"),
+            log_payloads: var("CARAPACE_MCP_LOG_PAYLOADS").is_some_and(|v| v == "1" || v == "true"),
+            socket_path: None,
+        }
+    }
+
+    /// Pin `channel` to the daemon's default account, unless it is already
+    /// pinned to a named one.
+    pub fn pin_default_account(&mut self, channel: &str) {
+        self.pinned_accounts.entry(channel.to_string()).or_insert(None);
+    }
+}
+
+// ── Server ───────────────────────────────────────────────────────────────────
+
+/// A channel as reported by `channel.capabilities`.
+struct ChannelInfo {
+    accounts: Vec<String>,
+    default_account: Option<String>,
+}
+
+struct Server {
+    options: Options,
+    providers: Vec<Arc<dyn ChannelTools>>,
+    /// Configured channels, once the daemon has been asked.
+    configured: Option<HashMap<String, ChannelInfo>>,
+    /// A tool list was served before the daemon could be asked.
+    served_fallback: bool,
+    /// Protocol revision agreed in `initialize`.
+    protocol: &'static str,
+    /// Tools per `tools/list` page.
+    page_size: usize,
+    /// Idle gateway connections for tool calls.
+    pool: Arc<Mutex<Vec<GatewayClient>>>,
+    /// Queued and running tool calls, by JSON-RPC request id.
+    in_flight: Arc<Mutex<HashMap<String, Arc<Call>>>>,
+    /// Queue of the worker threads, started by the first tool call.
+    jobs: Option<Sender<CallJob>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// A queued or running tool call, as seen by `notifications/cancelled`.
+#[derive(Default)]
+struct Call {
+    cancelled: AtomicBool,
+    interrupt: Mutex<Option<InterruptHandle>>,
+}
+
+impl Call {
+    /// Stop waiting for the call by shutting its connection down. The daemon
+    /// is not told, so a request it already received still runs.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.interrupt.lock().unwrap().as_ref() {
+            handle.interrupt();
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Everything a worker thread needs to run one `tools/call`.
+struct CallJob {
+    id: Value,
+    call: Arc<Call>,
+    tool: String,
+    args: Value,
+    account: Option<String>,
+    provider: Arc<dyn ChannelTools>,
+    progress_token: Option<Value>,
+    structured: bool,
+}
+
+/// Run the MCP server on stdin/stdout until stdin closes.
+pub fn run(options: Options) {
+    logging::init();
+    let span = tracing::info_span!("mcp", server = options.log_tag);
+    let _entered = span.enter();
+    let log_payloads = options.log_payloads;
+
+    info!("starting — waiting for MCP messages on stdin");
+    if log_payloads {
+        warn!("payload logging enabled (redacted)");
+    }
+    for (channel, account) in &options.pinned_accounts {
+        let account = account.as_deref().unwrap_or("(gateway default)");
+        info!(channel = %channel, account = %account, "pinned to account");
+    }
+
+    let mut server = Server::new(options);
+
+    // Dedicated writer thread owns stdout. A Null message stops it (watch
+    // threads may still hold a sender at exit).
+    let (out_tx, out_rx) = std::sync::mpsc::channel::<Value>();
+    let writer = std::thread::spawn(move || {
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        for msg in out_rx {
+            if msg.is_null() {
+                break;
+            }
+            writeln!(out, "{msg}").ok();
+            out.flush().ok();
+        }
+    });
+
+    let mut client: Option<GatewayClient> = None;
+    let socket_path = server.options.socket_path.clone();
+
+    let stdin = std::io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(l) if l.trim().is_empty() => continue,
+            Ok(l) => l,
+            Err(e) => {
+                error!(error = %e, "stdin read error");
+                break;
+            }
+        };
+
+        let req: Value = match serde_json::from_str(&line) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!(error = %e, "unparseable message");
+                out_tx.send(rpc_error(Value::Null, -32700, format!("Parse error: {e}"))).ok();
+                continue;
+            }
+        };
+
+        let id = req.get("id").cloned().unwrap_or(Value::Null);
+        let method = req.get("method").and_then(|v| v.as_str()).unwrap_or("");
+        let params = req.get("params").cloned().unwrap_or(json!({}));
+        if log_payloads {
+            info!(payload = %logging::payload(&req), "recv");
+        } else {
+            debug!(method, id = %id, "recv");
+        }
+
+        let mut ctx = Context { client: &mut client, out: &out_tx, socket_path: socket_path.as_deref() };
+        let response = server.handle_message(method, &params, id, &mut ctx);
+        // Notifications (and tool calls, answered by their worker) return
+        // Null — nothing to write back now.
+        if response.is_null() {
+            continue;
+        }
+        log_send(&response, log_payloads);
+        out_tx.send(response).ok();
+    }
+
+    info!("stdin closed — exiting");
+    // Let queued and running tool calls answer, then the writer flush
+    // everything queued before the process exits.
+    server.finish();
+    out_tx.send(Value::Null).ok();
+    writer.join().ok();
+}
+
+impl Server {
+    fn new(options: Options) -> Self {
+        let mut providers: Vec<Arc<dyn ChannelTools>> = vec![
+            Arc::new(gmail::GmailTools),
+            Arc::new(gdocs::GDocsTools),
+            Arc::new(imsg::ImsgTools::new(options.imsg_cursor.clone())),
+        ];
+        if !options.channels.is_empty() {
+            providers.retain(|p| options.channels.iter().any(|c| c == p.channel()));
+        }
+        Self {
+            options,
+            providers,
+            configured: None,
+            served_fallback: false,
+            protocol: SUPPORTED_VERSIONS[SUPPORTED_VERSIONS.len() - 1],
+            page_size: TOOLS_PAGE_SIZE,
+            pool: Arc::default(),
+            in_flight: Arc::default(),
+            jobs: None,
+            workers: Vec::new(),
+        }
+    }
+
+    /// Wait for every queued and running tool call to finish.
+    fn finish(&mut self) {
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            worker.join().ok();
+        }
+    }
+
+    fn handle_message(&mut self, method: &str, params: &Value, id: Value, ctx: &mut Context<'_>) -> Value {
+        match method {
+            // ── MCP handshake ────────────────────────────────────────────────
+            "initialize" => {
+                // Echo the client's revision if we speak it, else offer our newest.
+                let requested = params.get("protocolVersion").and_then(|v| v.as_str());
+                self.protocol = SUPPORTED_VERSIONS
+                    .into_iter()
+                    .find(|v| Some(*v) == requested)
+                    .unwrap_or(SUPPORTED_VERSIONS[0]);
+                info!(version = self.protocol, "protocol version agreed");
+
+                let mut capabilities = json!({"tools": {"listChanged": true}});
+                if self.providers.iter().any(|p| !p.resources().is_empty()) {
+                    capabilities["resources"] = json!({"subscribe": true});
+                }
+                rpc_result(id, json!({
+                    "protocolVersion": self.protocol,
+                    "capabilities": capabilities,
+                    "serverInfo": {
+                        "name": self.options.server_name,
+                        "version": SERVER_VERSION
+                    }
+                }))
+            }
+
+            // Notification — learn the configured channels and let them start
+            // background work (the iMessage inbox watch).
+            "notifications/initialized" => {
+                debug!("initialized");
+                self.discover(ctx);
+                for provider in self.enabled() {
+                    provider.initialized(ctx);
+                }
+                Value::Null
+            }
+
+            // Notification — abort a running tool call.
+            "notifications/cancelled" => {
+                let request_id = params.get("requestId").map(|v| v.to_string()).unwrap_or_default();
+                if let Some(call) = self.in_flight.lock().unwrap().get(&request_id) {
+                    let reason = params.get("reason").and_then(|v| v.as_str()).unwrap_or("no reason given");
+                    info!(request_id = %request_id, reason = %logging::redact_text(reason), "cancelling tool call");
+                    call.cancel();
+                }
+                Value::Null
+            }
+
+            // ── Tools ────────────────────────────────────────────────────────
+            "tools/list" => {
+                self.discover(ctx);
+                if self.configured.is_none() {
+                    self.served_fallback = true;
+                }
+                let tools = self.tool_definitions();
+                let start = match params.get("cursor") {
+                    None | Some(Value::Null) => 0,
+                    Some(cursor) => match cursor.as_str().and_then(|c| c.parse::<usize>().ok()) {
+                        Some(start) if start <= tools.len() => start,
+                        _ => return rpc_error(id, -32602, format!("Invalid cursor: {cursor}")),
+                    },
+                };
+                let end = tools.len().min(start + self.page_size);
+                let mut result = json!({"tools": &tools[start..end]});
+                if end < tools.len() {
+                    result["nextCursor"] = json!(end.to_string());
+                }
+                rpc_result(id, result)
+            }
+
+            "tools/call" => {
+                let tool = params.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                self.discover(ctx);
+                let (provider, account) = match self.route(&tool, &args) {
+                    Ok(route) => route,
+                    Err(message) => {
+                        warn!(tool = %tool, error = %logging::redact_text(&message), "tool call rejected");
+                        return tool_error(id, message);
+                    }
+                };
+                let progress_token = params.get("_meta").and_then(|m| m.get("progressToken")).cloned();
+                let job = CallJob {
+                    id,
+                    call: Arc::default(),
+                    tool,
+                    args,
+                    account,
+                    provider,
+                    progress_token,
+                    structured: self.protocol >= STRUCTURED_OUTPUT_SINCE,
+                };
+                self.spawn_call(job, ctx);
+                Value::Null
+            }
+
+            // ── Resources ────────────────────────────────────────────────────
+            "resources/list" => {
+                let resources: Vec<Value> = self.enabled().flat_map(|p| p.resources()).collect();
+                rpc_result(id, json!({"resources": resources}))
+            }
+
+            "resources/read" => {
+                let uri = params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
+                match self.enabled().find_map(|p| p.read_resource(uri, ctx)) {
+                    Some(Ok(text)) => rpc_result(id, json!({
+                        "contents": [{"uri": uri, "mimeType": "application/json", "text": text}]
+                    })),
+                    Some(Err(message)) => rpc_error(id, -32603, message),
+                    None => rpc_error(id, -32002, format!("Resource not found: {uri}")),
+                }
+            }
+
+            "resources/subscribe" | "resources/unsubscribe" => {
+                let uri = params.get("uri").and_then(|v| v.as_str()).unwrap_or("");
+                let on = method == "resources/subscribe";
+                if self.enabled().any(|p| p.subscribe(uri, on, ctx)) {
+                    rpc_result(id, json!({}))
+                } else {
+                    rpc_error(id, -32002, format!("Resource not found: {uri}"))
+                }
+            }
+
+            // Other client notifications need no reply.
+            m if m.starts_with("notifications/") => Value::Null,
+
+            // ── Unknown method ───────────────────────────────────────────────
+            _ => rpc_error(id, -32601, format!("Method not found: {method}")),
+        }
+    }
+
+    /// Ask the daemon which channels and accounts are configured, once.
+    ///
+    /// On failure every selected channel stays offered and the next request
+    /// tries again. If a fallback tool list was already served, the client is
+    /// told to fetch it again.
+    fn discover(&mut self, ctx: &mut Context<'_>) {
+        if self.configured.is_some() {
+            return;
+        }
+        let result = ctx
+            .gateway()
+            .and_then(|gw| gw.call("channel.capabilities", json!({})).map_err(|e| e.to_string()));
+        let caps = match result {
+            Ok(caps) => caps,
+            Err(e) => {
+                warn!(error = %e, "could not read gateway capabilities");
+                // Don't keep a broken connection around for the next attempt.
+                *ctx.client = None;
+                return;
+            }
+        };
+
+        let mut configured = HashMap::new();
+        for entry in caps.get("channels").and_then(|c| c.as_array()).into_iter().flatten() {
+            let Some(channel) = entry.get("channel").and_then(|c| c.as_str()) else {
+                continue;
+            };
+            let accounts = entry
+                .get("accounts")
+                .and_then(|a| a.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|a| a.as_str().map(String::from))
+                .collect();
+            let default_account = entry.get("default_account").and_then(|d| d.as_str()).map(String::from);
+            configured.insert(channel.to_string(), ChannelInfo { accounts, default_account });
+        }
+        let names: Vec<&String> = configured.keys().collect();
+        info!(channels = ?names, "gateway capabilities");
+        self.configured = Some(configured);
+
+        if self.served_fallback {
+            ctx.out
+                .send(json!({"jsonrpc": "2.0", "method": "notifications/tools/list_changed"}))
+                .ok();
+        }
+    }
+
+    /// Selected channels that the daemon has configured (all selected
+    /// channels while that is not yet known).
+    fn enabled(&self) -> impl Iterator<Item = &Arc<dyn ChannelTools>> {
+        self.providers
+            .iter()
+            .filter(|p| self.configured.as_ref().is_none_or(|c| c.contains_key(p.channel())))
+    }
+
+    fn tool_definitions(&self) -> Vec<Value> {
+        let annotated = self.protocol >= ANNOTATIONS_SINCE;
+        let structured = self.protocol >= STRUCTURED_OUTPUT_SINCE;
+        let mut tools = Vec::new();
+        for provider in self.enabled() {
+            let account_property = self.account_property(provider.as_ref());
+            for mut tool in provider.tools().as_array().cloned().unwrap_or_default() {
+                let name = tool["name"].as_str().unwrap_or("").to_string();
+                if let Some(ref property) = account_property {
+                    tool["inputSchema"]["properties"]["account"] = property.clone();
+                }
+                if annotated {
+                    if let Some(annotations) = provider.annotations(&name) {
+                        tool["annotations"] = annotations;
+                    }
+                }
+                if structured {
+                    tool["outputSchema"] = provider.output_schema(&name).unwrap_or_else(|| json!({"type": "object"}));
+                }
+                tools.push(tool);
+            }
+        }
+        tools
+    }
+
+    /// Schema of the `account` argument, or `None` if the channel has no
+    /// accounts or is pinned to one.
+    fn account_property(&self, provider: &dyn ChannelTools) -> Option<Value> {
+        if !provider.has_accounts() || self.options.pinned_accounts.contains_key(provider.channel()) {
+            return None;
+        }
+        let info = self.configured.as_ref().and_then(|c| c.get(provider.channel()));
+        Some(match info {
+            Some(info) => {
+                let default = info.default_account.as_deref().unwrap_or("the gateway default");
+                json!({
+                    "type": "string",
+                    "description": format!("Account to use. Defaults to {default}."),
+                    "enum": info.accounts
+                })
+            }
+            None => json!({
+                "type": "string",
+                "description": "Account to use, as named in the gateway config. Defaults to the gateway's default account."
+            }),
+        })
+    }
+
+    /// Find the channel that owns `name` and the account to call it with.
+    fn route(&self, name: &str, args: &Value) -> Result<(Arc<dyn ChannelTools>, Option<String>), String> {
+        let Some(provider) = self
+            .enabled()
+            .find(|p| name.strip_prefix(p.channel()).is_some_and(|rest| rest.starts_with('_')))
+        else {
+            return Err(format!("Unknown tool: {name}"));
+        };
+
+        let account = match self.options.pinned_accounts.get(provider.channel()) {
+            Some(pinned) => pinned.clone(),
+            None if provider.has_accounts() => {
+                let requested = args.get("account").and_then(|v| v.as_str()).filter(|a| !a.is_empty());
+                let known = self.configured.as_ref().and_then(|c| c.get(provider.channel()));
+                if let (Some(account), Some(info)) = (requested, known) {
+                    if !info.accounts.iter().any(|a| a == account) {
+                        return Err(format!(
+                            "Unknown {} account \"{account}\". Available: {}",
+                            provider.channel(),
+                            info.accounts.join(", ")
+                        ));
+                    }
+                }
+                requested.map(String::from)
+            }
+            None => None,
+        };
+
+        Ok((Arc::clone(provider), account))
+    }
+
+    /// Queue a tool call for the worker threads, which write the response.
+    fn spawn_call(&mut self, job: CallJob, ctx: &Context<'_>) {
+        self.in_flight.lock().unwrap().insert(job.id.to_string(), Arc::clone(&job.call));
+        if self.jobs.is_none() {
+            self.jobs = Some(self.start_workers(ctx.notifier()));
+        }
+        self.jobs.as_ref().unwrap().send(job).ok();
+    }
+
+    /// Start [`MAX_CONCURRENT_CALLS`] worker threads on one job queue.
+    fn start_workers(&mut self, out: Sender<Value>) -> Sender<CallJob> {
+        let (jobs, queue) = std::sync::mpsc::channel::<CallJob>();
+        let queue = Arc::new(Mutex::new(queue));
+        let worker = Worker {
+            pool: Arc::clone(&self.pool),
+            in_flight: Arc::clone(&self.in_flight),
+            out,
+            socket_path: self.options.socket_path.clone(),
+            log_payloads: self.options.log_payloads,
+        };
+        for _ in 0..MAX_CONCURRENT_CALLS {
+            let (worker, queue) = (worker.clone(), Arc::clone(&queue));
+            let span = tracing::Span::current();
+            self.workers.push(std::thread::spawn(move || {
+                let _entered = span.enter();
+                worker.run(&queue);
+            }));
+        }
+        jobs
+    }
+}
+
+/// What a worker thread shares with the server.
+#[derive(Clone)]
+struct Worker {
+    pool: Arc<Mutex<Vec<GatewayClient>>>,
+    in_flight: Arc<Mutex<HashMap<String, Arc<Call>>>>,
+    out: Sender<Value>,
+    socket_path: Option<PathBuf>,
+    log_payloads: bool,
+}
+
+impl Worker {
+    /// Answer queued tool calls until the queue is closed.
+    fn run(&self, queue: &Mutex<Receiver<CallJob>>) {
+        loop {
+            let job = queue.lock().unwrap().recv();
+            match job {
+                Ok(job) => self.answer(job),
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn answer(&self, job: CallJob) {
+        let started = Instant::now();
+        let result = self.run_call(&job);
+        self.in_flight.lock().unwrap().remove(&job.id.to_string());
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        if job.call.is_cancelled() {
+            info!(tool = %job.tool, elapsed_ms, "tool call cancelled — no response sent");
+            return;
+        }
+        let response = match result {
+            Ok(result) => {
+                info!(tool = %job.tool, elapsed_ms, "tool call succeeded");
+                tool_success(job.id, result, job.structured)
+            }
+            Err(message) => {
+                warn!(tool = %job.tool, elapsed_ms, error = %logging::redact_text(&message), "tool call failed");
+                tool_error(job.id, message)
+            }
+        };
+        log_send(&response, self.log_payloads);
+        self.out.send(response).ok();
+    }
+
+    /// Make the gateway call for `job` on a pooled connection.
+    ///
+    /// The connection goes back to the pool unless the call was cancelled,
+    /// which shuts it down; a client whose daemon restarted reconnects itself.
+    fn run_call(&self, job: &CallJob) -> ToolResult {
+        let call = &job.call;
+        if call.is_cancelled() {
+            return Err("Cancelled".into());
+        }
+        let idle = self.pool.lock().unwrap().pop();
+        let mut gw = match idle {
+            Some(gw) => gw,
+            None => connect(self.socket_path.as_deref())?,
+        };
+        *call.interrupt.lock().unwrap() = gw.interrupt_handle().ok();
+        if call.is_cancelled() {
+            return Err("Cancelled".into());
+        }
+
+        let progress = job
+            .progress_token
+            .clone()
+            .map(|token| Progress::start(token, job.tool.clone(), self.out.clone(), PROGRESS_INTERVAL));
+        let result = job.provider.call(&job.tool, &job.args, job.account.as_deref(), &mut gw);
+        drop(progress);
+
+        if !call.is_cancelled() {
+            self.pool.lock().unwrap().push(gw);
+        }
+        result
+    }
+}
+
+/// Sends `notifications/progress` every `interval` ([`PROGRESS_INTERVAL`])
+/// while a tool call runs. Dropping it stops the updates, after the last one
+/// is queued.
+struct Progress {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Progress {
+    fn start(token: Value, tool: String, out: Sender<Value>, interval: Duration) -> Self {
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let thread = std::thread::spawn(move || {
+            let started = Instant::now();
+            let mut progress = 0u64;
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                progress += 1;
+                out.send(json!({
+                    "jsonrpc": "2.0",
+                    "method": "notifications/progress",
+                    "params": {
+                        "progressToken": token,
+                        "progress": progress,
+                        "message": format!("{tool} still running ({}s)", started.elapsed().as_secs())
+                    }
+                }))
+                .ok();
+            }
+        });
+        Self { stop: Some(stop), thread: Some(thread) }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        // Closing the channel wakes the thread; joining keeps every update
+        // ahead of the tool's response on stdout.
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+/// Log an outgoing message: its payload (redacted) or just its id.
+fn log_send(response: &Value, log_payloads: bool) {
+    if log_payloads {
+        info!(payload = %logging::payload(response), "send");
+    } else {
+        debug!(id = %response["id"], "send");
+    }
+}
+
+// ── MCP response helpers ─────────────────────────────────────────────────────
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+/// A JSON-RPC error response, for protocol-level failures.
+fn rpc_error(id: Value, code: i32, message: String) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+/// Wrap a successful tool result in the MCP `tools/call` response envelope.
+///
+/// MCP expects `content` to be an array of content blocks. We serialize the
+/// gateway result as a single `text` block so the agent can read it directly.
+/// With `structured`, the result is also sent as `structuredContent`, which
+/// must be an object: other values are wrapped as `{"result": ...}`.
+fn tool_success(id: Value, result: Value, structured: bool) -> Value {
+    let text = serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string());
+    let mut response = json!({
+        "content": [
+            {"type": "text", "text": text}
+        ],
+        "isError": false
+    });
+    if structured {
+        response["structuredContent"] = if result.is_object() { result } else { json!({"result": result}) };
+    }
+    rpc_result(id, response)
+}
+
+/// Wrap an error message in the MCP `tools/call` error response envelope.
+///
+/// Using `isError: true` (rather than a JSON-RPC error object) tells the agent
+/// that the tool ran but failed gracefully — it can decide how to proceed.
+fn tool_error(id: Value, message: String) -> Value {
+    rpc_result(id, json!({
+        "content": [
+            {"type": "text", "text": message}
+        ],
+        "isError": true
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::mpsc::Receiver;
+
+    use super::*;
+    use crate::mcp::testing::{FakeGateway, Reply};
+
+    fn options(channels: &[&str]) -> Options {
+        Options {
+            server_name: "test",
+            log_tag: "test",
+            channels: channels.iter().map(|c| c.to_string()).collect(),
+            pinned_accounts: HashMap::new(),
+            imsg_cursor: None,
+            log_payloads: false,
+            socket_path: None,
+        }
+    }
+
+    /// A server that already knows the daemon has Gmail (`personal`, the
+    /// default, and `work`), Google Docs (`hq`) and iMessage.
+    fn server(options: Options) -> Server {
+        let mut server = Server::new(options);
+        let info = |accounts: &[&str]| ChannelInfo {
+            accounts: accounts.iter().map(|a| a.to_string()).collect(),
+            default_account: accounts.first().map(|a| a.to_string()),
+        };
+        server.configured = Some(HashMap::from([
+            ("gmail".to_string(), info(&["personal", "work"])),
+            ("gdocs".to_string(), info(&["hq"])),
+            ("imsg".to_string(), info(&[])),
+        ]));
+        server
+    }
+
+    fn account_of(server: &Server, tool: &str, args: Value) -> Result<Option<String>, String> {
+        server.route(tool, &args).map(|(_, account)| account)
+    }
+
+    #[test]
+    fn unpinned_server_offers_every_account() {
+        let server = server(options(&[]));
+        let tools = server.tool_definitions();
+        let search = tools.iter().find(|t| t["name"] == "gmail_search").unwrap();
+        assert_eq!(search["inputSchema"]["properties"]["account"]["enum"], json!(["personal", "work"]));
+
+        assert_eq!(account_of(&server, "gmail_search", json!({"account": "work"})), Ok(Some("work".into())));
+        assert_eq!(account_of(&server, "gmail_search", json!({})), Ok(None));
+        let err = account_of(&server, "gmail_search", json!({"account": "other"})).unwrap_err();
+        assert!(err.contains("Available: personal, work"), "{err}");
+    }
+
+    #[test]
+    fn default_pin_hides_other_accounts() {
+        let mut options = options(&["gmail"]);
+        options.pin_default_account("gmail");
+        let server = server(options);
+
+        let tools = server.tool_definitions();
+        assert!(tools.iter().all(|t| t["inputSchema"]["properties"].get("account").is_none()));
+        // A requested account is ignored: the call goes to the daemon default.
+        assert_eq!(account_of(&server, "gmail_search", json!({"account": "work"})), Ok(None));
+    }
+
+    #[test]
+    fn named_pin_outranks_the_default_pin() {
+        let mut options = options(&["gdocs"]);
+        options.pinned_accounts.insert("gdocs".into(), Some("hq".into()));
+        options.pin_default_account("gdocs");
+        let server = server(options);
+        assert_eq!(account_of(&server, "gdocs_read", json!({})), Ok(Some("hq".into())));
+    }
+
+    #[test]
+    fn calls_route_to_the_owning_selected_channel() {
+        let server = server(options(&["gmail", "imsg"]));
+        let channel = |tool: &str| server.route(tool, &json!({})).map(|(p, _)| p.channel());
+        assert_eq!(channel("gmail_status"), Ok("gmail"));
+        assert_eq!(channel("imsg_send"), Ok("imsg"));
+        assert_eq!(channel("gdocs_read"), Err("Unknown tool: gdocs_read".into()));
+        assert_eq!(channel("gmailsearch"), Err("Unknown tool: gmailsearch".into()));
+    }
+
+    /// A server talking to a fake daemon that has every channel configured.
+    struct Harness {
+        server: Server,
+        gateway: FakeGateway,
+        client: Option<GatewayClient>,
+        out: Sender<Value>,
+        written: Receiver<Value>,
+    }
+
+    impl Harness {
+        fn start(handler: impl Fn(&str, &Value) -> Reply + Send + Sync + 'static) -> Self {
+            let gateway = FakeGateway::start(move |method, params| match method {
+                "channel.capabilities" => Ok(json!({"channels": [
+                    {"channel": "gmail", "accounts": ["personal"], "default_account": "personal"},
+                    {"channel": "gdocs", "accounts": ["hq"], "default_account": "hq"},
+                    {"channel": "imsg"}
+                ]})),
+                _ => handler(method, params),
+            });
+            let (out, written) = std::sync::mpsc::channel();
+            let mut options = options(&[]);
+            options.socket_path = Some(gateway.path().to_path_buf());
+            Self { server: Server::new(options), gateway, client: None, out, written }
+        }
+
+        fn request(&mut self, method: &str, params: Value, id: Value) -> Value {
+            let socket_path = self.server.options.socket_path.clone();
+            let mut ctx = Context { client: &mut self.client, out: &self.out, socket_path: socket_path.as_deref() };
+            self.server.handle_message(method, &params, id, &mut ctx)
+        }
+
+        fn initialize(&mut self, version: &str) -> Value {
+            self.request("initialize", json!({"protocolVersion": version}), json!(0))["result"].clone()
+        }
+
+        /// The next message written to stdout.
+        fn written(&self) -> Value {
+            self.written.recv_timeout(Duration::from_secs(5)).expect("nothing written")
+        }
+    }
+
+    #[test]
+    fn initialize_agrees_on_a_revision_both_sides_speak() {
+        let mut harness = Harness::start(|_, _| Ok(json!({})));
+        let tool = |harness: &mut Harness| {
+            let tools = harness.request("tools/list", json!({}), json!(1))["result"]["tools"].clone();
+            tools.as_array().unwrap().iter().find(|t| t["name"] == "gmail_search").cloned().unwrap()
+        };
+
+        assert_eq!(harness.initialize("2024-11-05")["protocolVersion"], "2024-11-05");
+        let search = tool(&mut harness);
+        assert!(search.get("annotations").is_none() && search.get("outputSchema").is_none());
+
+        assert_eq!(harness.initialize("2025-03-26")["protocolVersion"], "2025-03-26");
+        let search = tool(&mut harness);
+        assert_eq!(search["annotations"]["readOnlyHint"], true);
+        assert!(search.get("outputSchema").is_none());
+
+        // An unknown revision is answered with the newest this server speaks.
+        assert_eq!(harness.initialize("2099-01-01")["protocolVersion"], SUPPORTED_VERSIONS[0]);
+        assert!(tool(&mut harness).get("outputSchema").is_some());
+    }
+
+    #[test]
+    fn tools_list_pages_through_every_tool() {
+        let mut harness = Harness::start(|_, _| Ok(json!({})));
+        harness.server.page_size = 10;
+        let mut names = Vec::new();
+        let mut cursor = Value::Null;
+        loop {
+            let result = harness.request("tools/list", json!({"cursor": cursor}), json!(1))["result"].clone();
+            let page = result["tools"].as_array().unwrap();
+            assert!(page.len() <= 10);
+            names.extend(page.iter().map(|t| t["name"].as_str().unwrap().to_string()));
+            match result.get("nextCursor") {
+                Some(next) => cursor = next.clone(),
+                None => break,
+            }
+        }
+        let all: Vec<String> = harness.server.tool_definitions().iter().map(|t| t["name"].as_str().unwrap().to_string()).collect();
+        assert!(all.len() > 20);
+        assert_eq!(names, all);
+
+        for bad in [json!("x"), json!("999"), json!(3)] {
+            let response = harness.request("tools/list", json!({"cursor": bad}), json!(2));
+            assert_eq!(response["error"]["code"], -32602, "{bad}");
+        }
+    }
+
+    #[test]
+    fn structured_content_is_sent_from_2025_06_18() {
+        let mut harness = Harness::start(|_, _| Ok(json!({"healthy": true})));
+        harness.initialize("2025-06-18");
+        assert!(harness.request("tools/call", json!({"name": "gmail_status"}), json!(5)).is_null());
+        let response = harness.written();
+        assert_eq!(response["id"], 5);
+        assert_eq!(response["result"]["structuredContent"], json!({"healthy": true}));
+        assert!(response["result"]["content"][0]["text"].as_str().unwrap().contains("healthy"));
+
+        harness.initialize("2025-03-26");
+        harness.request("tools/call", json!({"name": "gmail_status"}), json!(6));
+        assert!(harness.written()["result"].get("structuredContent").is_none());
+
+        // structuredContent must be an object.
+        let wrapped = tool_success(json!(7), json!(["a"]), true);
+        assert_eq!(wrapped["result"]["structuredContent"], json!({"result": ["a"]}));
+    }
+
+    #[test]
+    fn cancelled_calls_get_no_response() {
+        let mut harness = Harness::start(|method, _| {
+            if method == "channel.send" {
+                std::thread::sleep(Duration::from_secs(3));
+            }
+            Ok(json!({"success": true}))
+        });
+        harness.initialize("2025-06-18");
+        harness.request("tools/call", json!({"name": "imsg_send", "arguments": {"to": "+1", "text": "hi"}}), json!(9));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !harness.gateway.calls().iter().any(|(m, _)| m == "channel.send") {
+            assert!(Instant::now() < deadline, "the call never reached the daemon");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let started = Instant::now();
+        harness.request("notifications/cancelled", json!({"requestId": 9, "reason": "user"}), Value::Null);
+        harness.server.finish();
+        assert!(started.elapsed() < Duration::from_secs(2), "the worker kept waiting for the daemon");
+        assert!(harness.server.in_flight.lock().unwrap().is_empty());
+        assert!(harness.written.try_recv().is_err(), "a cancelled call was answered");
+    }
+
+    #[test]
+    fn concurrent_calls_are_bounded_by_the_worker_pool() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (r, m) = (Arc::clone(&running), Arc::clone(&most));
+        let mut harness = Harness::start(move |_, _| {
+            let now = r.fetch_add(1, Ordering::SeqCst) + 1;
+            m.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(100));
+            r.fetch_sub(1, Ordering::SeqCst);
+            Ok(json!({}))
+        });
+        let calls = MAX_CONCURRENT_CALLS + 4;
+        for id in 0..calls {
+            harness.request("tools/call", json!({"name": "gmail_status"}), json!(id));
+        }
+        harness.server.finish();
+
+        let mut answered: Vec<u64> = (0..calls).map(|_| harness.written()["id"].as_u64().unwrap()).collect();
+        answered.sort();
+        assert_eq!(answered, (0..calls as u64).collect::<Vec<_>>());
+        assert_eq!(harness.server.workers.len(), 0);
+        let most = most.load(Ordering::SeqCst);
+        assert!(most > 1 && most <= MAX_CONCURRENT_CALLS, "{most} calls ran at once");
+    }
+
+    #[test]
+    fn progress_is_reported_until_dropped() {
+        let (out, written) = std::sync::mpsc::channel();
+        let progress = Progress::start(json!("tok"), "gdocs_read".into(), out, Duration::from_millis(20));
+        std::thread::sleep(Duration::from_millis(110));
+        drop(progress);
+
+        let updates: Vec<Value> = written.try_iter().collect();
+        assert!(updates.len() >= 2, "{updates:?}");
+        for (i, update) in updates.iter().enumerate() {
+            assert_eq!(update["method"], "notifications/progress");
+            assert_eq!(update["params"]["progressToken"], "tok");
+            assert_eq!(update["params"]["progress"], i as u64 + 1);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(written.try_recv().is_err(), "progress continued after the call ended");
+    }
+}
@@ -0,0 +1,78 @@
+//! A stand-in for the gateway daemon, for the MCP server tests.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use carapace_client::GatewayClient;
+use serde_json::{json, Value};
+
+/// How the fake daemon answers a call: a result, or a JSON-RPC error code
+/// and message.
+pub type Reply = Result<Value, (i32, String)>;
+
+type Handler = dyn Fn(&str, &Value) -> Reply + Send + Sync;
+
+/// A daemon on a private socket that answers `hello` and passes every other
+/// call to a handler, recording it first.
+pub struct FakeGateway {
+    _dir: tempfile::TempDir,
+    path: PathBuf,
+    calls: Arc<Mutex<Vec<(String, Value)>>>,
+}
+
+impl FakeGateway {
+    pub fn start(handler: impl Fn(&str, &Value) -> Reply + Send + Sync + 'static) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gateway.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let recorded = Arc::clone(&calls);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { break };
+                let (calls, handler) = (Arc::clone(&recorded), Arc::clone(&handler));
+                std::thread::spawn(move || serve(stream, &calls, handler.as_ref()));
+            }
+        });
+        Self { _dir: dir, path, calls }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A connected client.
+    pub fn client(&self) -> GatewayClient {
+        GatewayClient::connect(&self.path).unwrap()
+    }
+
+    /// Every call received other than `hello`, as `(method, params)`.
+    pub fn calls(&self) -> Vec<(String, Value)> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+fn serve(stream: UnixStream, calls: &Mutex<Vec<(String, Value)>>, handler: &Handler) {
+    let mut writer = stream.try_clone().unwrap();
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        let req: Value = serde_json::from_str(&line).unwrap();
+        let method = req["method"].as_str().unwrap_or("").to_string();
+        let reply = if method == "hello" {
+            Ok(json!({"protocol_version": "1.0"}))
+        } else {
+            calls.lock().unwrap().push((method.clone(), req["params"].clone()));
+            handler(&method, &req["params"])
+        };
+        let response = match reply {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": req["id"], "result": result}),
+            Err((code, message)) => json!({"jsonrpc": "2.0", "id": req["id"], "error": {"code": code, "message": message}}),
+        };
+        if writeln!(writer, "{response}").is_err() {
+            break;
+        }
+    }
+}
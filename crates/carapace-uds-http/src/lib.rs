@@ -0,0 +1,318 @@
+//! HTTP/1.1 over a Unix domain socket, for talking to the Carapace proxies.
+//!
+//! gmail-proxy and gdocs-proxy serve a small JSON API on a Unix socket.
+//! [`UdsClient`] sends requests to one of them and returns the parsed JSON
+//! body. Connections are kept alive and reused across requests (and across
+//! clones of the client), and responses are parsed by hyper, so chunked and
+//! fixed-length bodies of any size are handled the same way.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::client::conn::http1::{self, SendRequest};
+use hyper::header::{CONTENT_TYPE, HOST};
+use hyper::{Method, Request};
+use hyper_util::rt::TokioIo;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value;
+use tokio::net::UnixStream;
+use tracing::{debug, warn};
+
+/// Idle connections kept per client; more than this are closed when done.
+const MAX_IDLE: usize = 4;
+
+/// Characters left as-is by [`encode`]: the RFC 3986 unreserved set.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+/// Percent-encode a path segment or query value.
+pub fn encode(s: &str) -> String {
+    utf8_percent_encode(s, COMPONENT).to_string()
+}
+
+/// Errors from a request to a proxy. `service` names the proxy in messages.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{service} socket not found at {}", .path.display())]
+    SocketNotFound { service: &'static str, path: PathBuf },
+
+    #[error("failed to connect to {service}: {source}")]
+    Connect { service: &'static str, source: std::io::Error },
+
+    /// A non-2xx response. `message` is the proxy's `{"error": ...}` text.
+    #[error("HTTP error from {service}: status {status}{}", fmt_detail(.message))]
+    Status { service: &'static str, status: u16, message: Option<String> },
+
+    #[error("failed to parse {service} response: {message}")]
+    Parse { service: &'static str, message: String },
+
+    #[error("I/O error communicating with {service}: {source}")]
+    Http { service: &'static str, source: hyper::Error },
+}
+
+fn fmt_detail(message: &Option<String>) -> String {
+    message.as_ref().map(|m| format!(" ({m})")).unwrap_or_default()
+}
+
+/// A JSON client for an HTTP server on a Unix socket.
+///
+/// Cheap to clone; clones share the pool of idle connections.
+#[derive(Clone)]
+pub struct UdsClient {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    service: &'static str,
+    socket_path: PathBuf,
+    idle: Mutex<Vec<SendRequest<Full<Bytes>>>>,
+}
+
+impl UdsClient {
+    /// A client for the server at `socket_path`. Nothing is opened until the
+    /// first request.
+    pub fn new(service: &'static str, socket_path: PathBuf) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                service,
+                socket_path,
+                idle: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.inner.socket_path
+    }
+
+    pub async fn get(&self, path: &str) -> Result<Value, Error> {
+        self.send(Method::GET, path, None).await
+    }
+
+    pub async fn post(&self, path: &str, body: &Value) -> Result<Value, Error> {
+        self.send(Method::POST, path, Some(body)).await
+    }
+
+    pub async fn put(&self, path: &str, body: &Value) -> Result<Value, Error> {
+        self.send(Method::PUT, path, Some(body)).await
+    }
+
+    /// Send a request and parse the JSON response body.
+    ///
+    /// A request that fails on a reused connection because the server had
+    /// already closed it is sent once more on a new connection, if
+    /// [`retryable`].
+    async fn send(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Value, Error> {
+        let service = self.inner.service;
+        if !self.inner.socket_path.exists() {
+            return Err(Error::SocketNotFound { service, path: self.inner.socket_path.clone() });
+        }
+        let body = match body {
+            Some(body) => Some(
+                serde_json::to_vec(body).map_err(|e| Error::Parse { service, message: e.to_string() })?,
+            ),
+            None => None,
+        };
+
+        let mut retried = false;
+        loop {
+            let (mut sender, reused) = self.checkout().await?;
+            let request = build_request(&method, path, body.clone());
+            let response = match sender.send_request(request).await {
+                Ok(response) => response,
+                Err(e) if reused && !retried && retryable(&e, &method) => {
+                    debug!(service, error = %e, "reused connection was closed; retrying on a new one");
+                    retried = true;
+                    continue;
+                }
+                Err(source) => return Err(Error::Http { service, source }),
+            };
+
+            let status = response.status();
+            let bytes = response
+                .into_body()
+                .collect()
+                .await
+                .map_err(|source| Error::Http { service, source })?
+                .to_bytes();
+            self.checkin(sender);
+
+            if !status.is_success() {
+                let text = String::from_utf8_lossy(&bytes);
+                warn!(service, status = status.as_u16(), body = %text, "proxy returned error");
+                // Surface the proxy's own error message (e.g. a policy denial).
+                let message = serde_json::from_slice::<Value>(&bytes)
+                    .ok()
+                    .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(String::from));
+                return Err(Error::Status { service, status: status.as_u16(), message });
+            }
+
+            return serde_json::from_slice(&bytes).map_err(|e| Error::Parse {
+                service,
+                message: format!("{e}: {}", String::from_utf8_lossy(&bytes)),
+            });
+        }
+    }
+
+    /// An idle connection that is still open, or a new one. The flag says
+    /// whether it was reused.
+    async fn checkout(&self) -> Result<(SendRequest<Full<Bytes>>, bool), Error> {
+        loop {
+            let idle = self.inner.idle.lock().unwrap().pop();
+            let Some(mut sender) = idle else { break };
+            if !sender.is_closed() && sender.ready().await.is_ok() {
+                return Ok((sender, true));
+            }
+        }
+
+        let service = self.inner.service;
+        let stream = UnixStream::connect(&self.inner.socket_path)
+            .await
+            .map_err(|source| Error::Connect { service, source })?;
+        let (sender, connection) = http1::handshake(TokioIo::new(stream))
+            .await
+            .map_err(|source| Error::Http { service, source })?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!(service, error = %e, "proxy connection closed");
+            }
+        });
+        Ok((sender, false))
+    }
+
+    fn checkin(&self, sender: SendRequest<Full<Bytes>>) {
+        if sender.is_closed() {
+            return;
+        }
+        let mut idle = self.inner.idle.lock().unwrap();
+        if idle.len() < MAX_IDLE {
+            idle.push(sender);
+        }
+    }
+}
+
+/// Whether a request that failed on a reused connection may be resent:
+/// it was never written, or the method is idempotent.
+fn retryable(e: &hyper::Error, method: &Method) -> bool {
+    e.is_canceled() || (e.is_incomplete_message() && method.is_idempotent())
+}
+
+fn build_request(method: &Method, path: &str, body: Option<Vec<u8>>) -> Request<Full<Bytes>> {
+    let builder = Request::builder().method(method).uri(path).header(HOST, "localhost");
+    let request = match body {
+        Some(body) => builder.header(CONTENT_TYPE, "application/json").body(Full::from(body)),
+        None => builder.body(Full::default()),
+    };
+    request.expect("request parts are valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+
+    /// Serve raw HTTP responses: `respond` maps each request head to a
+    /// response. Counts accepted connections.
+    fn serve(listener: UnixListener, respond: fn(&str) -> String) -> Arc<AtomicUsize> {
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let count = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                count.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    loop {
+                        let mut chunk = [0u8; 1024];
+                        let Ok(n) = stream.read(&mut chunk).await else { return };
+                        if n == 0 {
+                            return;
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                        let text = String::from_utf8_lossy(&buf).into_owned();
+                        let Some(end) = text.find("\r\n\r\n") else { continue };
+                        let length = text[..end]
+                            .to_ascii_lowercase()
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                            .unwrap_or(0);
+                        if buf.len() < end + 4 + length {
+                            continue;
+                        }
+                        let response = respond(&text[..end]);
+                        buf.drain(..end + 4 + length);
+                        stream.write_all(response.as_bytes()).await.unwrap();
+                        if response.contains("Connection: close") {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        accepted
+    }
+
+    fn bind() -> (tempfile::TempDir, PathBuf, UnixListener) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxy.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        (dir, path, listener)
+    }
+
+    #[tokio::test]
+    async fn reuses_connection_and_decodes_chunked_bodies() {
+        let (_dir, path, listener) = bind();
+        let accepted = serve(listener, |head| {
+            if head.starts_with("POST") {
+                return "HTTP/1.1 200 OK\r\nContent-Length: 11\r\n\r\n{\"ok\":true}".into();
+            }
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\n{\"a\":\r\n3\r\n[1]\r\n1\r\n}\r\n0\r\n\r\n".into()
+        });
+
+        let client = UdsClient::new("test-proxy", path);
+        assert_eq!(client.get("/a").await.unwrap(), serde_json::json!({"a": [1]}));
+        assert_eq!(client.clone().post("/b", &serde_json::json!({"x": 1})).await.unwrap()["ok"], true);
+        assert_eq!(client.get("/a").await.unwrap()["a"][0], 1);
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn error_status_carries_proxy_message() {
+        let (_dir, path, listener) = bind();
+        serve(listener, |_| {
+            "HTTP/1.1 403 Forbidden\r\nContent-Length: 18\r\n\r\n{\"error\":\"denied\"}".into()
+        });
+
+        let err = UdsClient::new("test-proxy", path).get("/doc/1").await.unwrap_err();
+        assert!(matches!(&err, Error::Status { status: 403, message: Some(m), .. } if m == "denied"));
+        assert_eq!(err.to_string(), "HTTP error from test-proxy: status 403 (denied)");
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_the_server_closes_a_connection() {
+        let (_dir, path, listener) = bind();
+        let accepted = serve(listener, |_| {
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\n{}".into()
+        });
+
+        let client = UdsClient::new("test-proxy", path);
+        client.get("/health").await.unwrap();
+        client.get("/health").await.unwrap();
+        assert_eq!(accepted.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn missing_socket_is_reported() {
+        let client = UdsClient::new("test-proxy", PathBuf::from("/nonexistent/proxy.sock"));
+        let err = client.get("/health").await.unwrap_err();
+        assert_eq!(err.to_string(), "test-proxy socket not found at /nonexistent/proxy.sock");
+    }
+
+    #[test]
+    fn encode_escapes_utf8_bytes() {
+        assert_eq!(encode("a b/c?d=e&f"), "a%20b%2Fc%3Fd%3De%26f");
+        assert_eq!(encode("Zoë-1_2.3~"), "Zo%C3%AB-1_2.3~");
+    }
+}
@@ -1,363 +1,79 @@
-//! OAuth 2.0 token management and interactive setup flow for Google Docs/Drive.
-
-use std::path::{Path, PathBuf};
-use std::sync::Arc;
-
-use anyhow::{Context, Result, anyhow};
-use tokio::sync::RwLock;
-use tokio::time::Instant;
-
-/// Response from the OAuth token exchange endpoint.
-#[derive(Debug, serde::Deserialize)]
-struct OAuthTokenResponse {
-    refresh_token: Option<String>,
-    access_token: String,
-    expires_in: u64,
-}
-
-/// Google token refresh response.
-#[derive(Debug, serde::Deserialize)]
-pub struct TokenResponse {
-    pub access_token: String,
-    pub expires_in: u64,
-    pub token_type: String,
-}
-
-/// Thread-safe OAuth access token manager with automatic refresh.
-pub struct TokenManager {
-    client_id: String,
-    client_secret: String,
-    refresh_token: String,
-    token_url: String,
-    http_client: reqwest::Client,
-    cached: Arc<RwLock<Option<CachedToken>>>,
-}
-
-struct CachedToken {
-    access_token: String,
-    expires_at: Instant,
+//! OAuth for Google Docs/Drive: the scopes this proxy requests and its setup flow.
+//! Token refresh and secrets handling are shared with the other proxies in
+//! `carapace-google`.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use carapace_google::{FlowArgs, SetupOptions};
+
+pub use carapace_google::TokenManager;
+
+/// Scopes for reading: drive (list/search), docs/sheets/slides/forms content
+/// and form responses.
+pub const READ_SCOPES: &[&str] = &[
+    "https://www.googleapis.com/auth/drive.readonly",
+    "https://www.googleapis.com/auth/documents.readonly",
+    "https://www.googleapis.com/auth/spreadsheets.readonly",
+    "https://www.googleapis.com/auth/presentations.readonly",
+    "https://www.googleapis.com/auth/forms.body.readonly",
+    "https://www.googleapis.com/auth/forms.responses.readonly",
+];
+
+/// Scopes for writing: forms.body (create and edit forms), drive.file
+/// (create files and edit the ones this app created or was given).
+pub const WRITE_SCOPES: &[&str] = &[
+    "https://www.googleapis.com/auth/forms.body",
+    "https://www.googleapis.com/auth/drive.file",
+];
+
+/// What the proxy may do with Drive, which decides the scopes it asks for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Access {
+    /// Search and read only; every write route is refused.
+    ReadOnly,
+    /// Also create and edit files (within the `[write]` policy).
+    #[default]
+    Write,
 }
 
-/// Refresh tokens 5 minutes before they actually expire.
-const SAFETY_MARGIN_SECS: u64 = 5 * 60;
-
-impl TokenManager {
-    pub fn new(
-        client_id: String,
-        client_secret: String,
-        refresh_token: String,
-        token_url: String,
-    ) -> Self {
-        Self {
-            client_id,
-            client_secret,
-            refresh_token,
-            token_url,
-            http_client: reqwest::Client::new(),
-            cached: Arc::new(RwLock::new(None)),
-        }
-    }
-
-    /// Return a valid access token, refreshing if the cached one is expired.
-    pub async fn get_token(&self) -> Result<String> {
-        {
-            let guard = self.cached.read().await;
-            if let Some(cached) = guard.as_ref() {
-                if Instant::now() < cached.expires_at {
-                    return Ok(cached.access_token.clone());
-                }
-            }
-        }
-        self.refresh().await
-    }
-
-    async fn refresh(&self) -> Result<String> {
-        let body = format!(
-            "grant_type=refresh_token&client_id={}&client_secret={}&refresh_token={}",
-            url_encode(&self.client_id),
-            url_encode(&self.client_secret),
-            url_encode(&self.refresh_token),
-        );
-
-        let resp = self
-            .http_client
-            .post(&self.token_url)
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .body(body)
-            .send()
-            .await?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("Token refresh failed with status {status}: {text}"));
-        }
-
-        let token_resp: TokenResponse = resp.json().await?;
-
-        let expires_at = if token_resp.expires_in > SAFETY_MARGIN_SECS {
-            Instant::now()
-                + std::time::Duration::from_secs(token_resp.expires_in - SAFETY_MARGIN_SECS)
-        } else {
-            Instant::now()
-        };
-
-        let access_token = token_resp.access_token.clone();
-        {
-            let mut guard = self.cached.write().await;
-            *guard = Some(CachedToken {
-                access_token: access_token.clone(),
-                expires_at,
-            });
-        }
-        Ok(access_token)
-    }
-
-    /// Remaining seconds until the cached token expires.
-    pub async fn expires_in_secs(&self) -> Option<u64> {
-        let guard = self.cached.read().await;
-        guard.as_ref().map(|c| {
-            let now = Instant::now();
-            if c.expires_at > now { (c.expires_at - now).as_secs() } else { 0 }
-        })
-    }
-
-    /// Whether the cached token is present and not yet expired.
-    pub async fn is_valid(&self) -> bool {
-        let guard = self.cached.read().await;
-        match guard.as_ref() {
-            Some(cached) => Instant::now() < cached.expires_at,
-            None => false,
+impl Access {
+    pub fn scopes(self) -> Vec<&'static str> {
+        match self {
+            Access::ReadOnly => READ_SCOPES.to_vec(),
+            Access::Write => READ_SCOPES.iter().chain(WRITE_SCOPES).copied().collect(),
         }
     }
 }
 
-// ---------------------------------------------------------------------------
-// OAuth setup flow
-// ---------------------------------------------------------------------------
-
-/// Google client_secret JSON structure.
-#[derive(Debug, serde::Deserialize)]
-struct GoogleClientSecretFile {
-    installed: Option<GoogleClientCreds>,
-    web: Option<GoogleClientCreds>,
+fn options<'a>(scopes: &'a [&'a str], flow: FlowArgs) -> SetupOptions<'a> {
+    SetupOptions {
+        program: "gdocs-proxy",
+        scopes,
+        default_secrets_file: "secrets-gdocs.toml",
+        flow,
+        endpoints: Default::default(),
+    }
 }
 
-#[derive(Debug, serde::Deserialize)]
-struct GoogleClientCreds {
-    client_id: String,
-    client_secret: String,
+/// Run the OAuth setup flow (see [`carapace_google::run_oauth_setup`]).
+pub async fn run_oauth_setup(
+    config_path: PathBuf,
+    client_json: Option<PathBuf>,
+    access: Access,
+    flow: FlowArgs,
+) -> Result<()> {
+    let scopes = access.scopes();
+    carapace_google::run_oauth_setup(config_path, client_json, &options(&scopes, flow)).await
 }
 
-/// Run the interactive OAuth setup flow for Google Docs/Drive.
-///
-/// Scopes requested:
-///   - `drive.readonly`   — list/search/read files
-///   - `documents.readonly` — read Google Docs content
-///   - `drive.file`       — create new files + edit files the app created
-pub async fn run_oauth_setup(config_path: PathBuf, client_json: Option<PathBuf>) -> Result<()> {
-    if !config_path.exists() {
-        anyhow::bail!(
-            "Config file not found at {}.\n\
-             Create it first (see the Carapace docs).",
-            config_path.display()
-        );
-    }
-
-    let config_dir = config_path
-        .parent()
-        .unwrap_or(Path::new("."))
-        .to_path_buf();
-
-    let config_text = std::fs::read_to_string(&config_path)
-        .with_context(|| format!("failed to read {}", config_path.display()))?;
-
-    let client_id: String;
-    let client_secret: String;
-
-    if let Some(json_path) = client_json {
-        let json_text = std::fs::read_to_string(&json_path)
-            .with_context(|| format!("failed to read {}", json_path.display()))?;
-        let parsed: GoogleClientSecretFile = serde_json::from_str(&json_text)
-            .context("failed to parse client_secret JSON")?;
-        let creds = parsed.installed.or(parsed.web)
-            .context("client_secret JSON has neither 'installed' nor 'web' key")?;
-        client_id = creds.client_id;
-        client_secret = creds.client_secret;
-
-        // Patch client_id / client_secret lines in the config file.
-        let updated: String = config_text
-            .lines()
-            .map(|line| {
-                let t = line.trim_start();
-                if t.starts_with("client_id") && t.contains('=') {
-                    format!("client_id = \"{}\"", client_id)
-                } else if t.starts_with("client_secret") && t.contains('=') {
-                    format!("client_secret = \"{}\"", client_secret)
-                } else {
-                    line.to_string()
-                }
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        std::fs::write(&config_path, &updated)
-            .with_context(|| format!("failed to update {}", config_path.display()))?;
-        println!("Updated client_id and client_secret in {}", config_path.display());
-    } else {
-        let parsed: toml::Value =
-            toml::from_str(&config_text).context("failed to parse config.toml")?;
-        client_id = parsed
-            .get("auth").and_then(|a| a.get("client_id")).and_then(|v| v.as_str())
-            .context("client_id not found in config.toml")?.to_string();
-        client_secret = parsed
-            .get("auth").and_then(|a| a.get("client_secret")).and_then(|v| v.as_str())
-            .context("client_secret not found in config.toml")?.to_string();
-
-        if client_id.contains("YOUR_CLIENT") || client_secret.contains("YOUR_CLIENT") {
-            anyhow::bail!(
-                "config.toml still has placeholder credentials.\n\
-                 Either edit them manually or use --client-json to import from Google's JSON file."
-            );
-        }
-    }
-
-    // Spin up ephemeral local HTTP server for the OAuth callback.
-    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
-    let port = listener.local_addr()?.port();
-    let redirect_uri = format!("http://127.0.0.1:{port}");
-
-    let (tx, rx) = tokio::sync::oneshot::channel::<String>();
-    let tx = Arc::new(tokio::sync::Mutex::new(Some(tx)));
-
-    let callback_handler = {
-        let tx = tx.clone();
-        move |axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>| {
-            let tx = tx.clone();
-            async move {
-                if let Some(code) = params.get("code") {
-                    if let Some(sender) = tx.lock().await.take() {
-                        let _ = sender.send(code.clone());
-                    }
-                    axum::response::Html(
-                        "<html><body><h1>Authorization successful!</h1>\
-                         <p>You can close this tab and return to the terminal.</p></body></html>"
-                            .to_string(),
-                    )
-                } else {
-                    let error = params.get("error").cloned().unwrap_or_else(|| "unknown error".into());
-                    axum::response::Html(format!(
-                        "<html><body><h1>Authorization failed</h1><p>{error}</p></body></html>"
-                    ))
-                }
-            }
-        }
-    };
-
-    let app = axum::Router::new().route("/", axum::routing::get(callback_handler));
-
-    // Scopes: drive (list/search/create), docs/sheets/slides/forms (read + create), drive.file (edit own files)
-    let scopes = "https://www.googleapis.com/auth/drive.readonly%20\
-                  https://www.googleapis.com/auth/documents.readonly%20\
-                  https://www.googleapis.com/auth/spreadsheets.readonly%20\
-                  https://www.googleapis.com/auth/presentations.readonly%20\
-                  https://www.googleapis.com/auth/forms.body.readonly%20\
-                  https://www.googleapis.com/auth/forms.body%20\
-                  https://www.googleapis.com/auth/forms.responses.readonly%20\
-                  https://www.googleapis.com/auth/drive.file";
-
-    let auth_url = format!(
-        "https://accounts.google.com/o/oauth2/v2/auth?\
-         client_id={}&redirect_uri={}&response_type=code&\
-         scope={scopes}&access_type=offline&prompt=consent",
-        url_encode(&client_id),
-        url_encode(&redirect_uri),
-    );
-
-    println!("\nOpening browser for Google OAuth consent...");
-    println!("If the browser doesn't open automatically, visit:\n\n  {auth_url}\n");
-
-    if let Err(e) = open::that(&auth_url) {
-        eprintln!("Warning: could not open browser: {e}");
-    }
-
-    let server = axum::serve(listener, app);
-    let code = tokio::select! {
-        result = server => {
-            result.context("OAuth callback server error")?;
-            anyhow::bail!("callback server exited unexpectedly");
-        }
-        code = rx => { code.context("failed to receive authorization code")? }
-    };
-
-    println!("Received authorization code. Exchanging for tokens...");
-
-    let http = reqwest::Client::new();
-    let token_body = format!(
-        "grant_type=authorization_code&code={}&client_id={}&client_secret={}&redirect_uri={}",
-        url_encode(&code),
-        url_encode(&client_id),
-        url_encode(&client_secret),
-        url_encode(&redirect_uri),
-    );
-
-    let resp = http
-        .post("https://oauth2.googleapis.com/token")
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(token_body)
-        .send()
-        .await
-        .context("failed to exchange authorization code")?;
-
-    let status = resp.status();
-    if !status.is_success() {
-        let text = resp.text().await.unwrap_or_default();
-        anyhow::bail!("Token exchange failed with status {status}: {text}");
-    }
-
-    let token_resp: OAuthTokenResponse =
-        resp.json().await.context("failed to parse token response")?;
-
-    let refresh_token = token_resp.refresh_token.context(
-        "No refresh_token in response.\n\
-         Try revoking access at https://myaccount.google.com/permissions and re-running setup.",
-    )?;
-
-    // Write secrets file with 0600 permissions.
-    // Read the secrets_file path from the config so it matches what `serve` expects.
-    let parsed_config: toml::Value = toml::from_str(
-        &std::fs::read_to_string(&config_path).unwrap_or_default()
-    ).unwrap_or(toml::Value::Table(Default::default()));
-    let secrets_filename = parsed_config
-        .get("auth").and_then(|a| a.get("secrets_file")).and_then(|v| v.as_str())
-        .unwrap_or("secrets-gdocs.toml");
-    let secrets_path = config_dir.join(secrets_filename);
-    let secrets_content = format!("refresh_token = \"{refresh_token}\"\n");
-    std::fs::write(&secrets_path, &secrets_content)
-        .with_context(|| format!("failed to write {}", secrets_path.display()))?;
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(&secrets_path, std::fs::Permissions::from_mode(0o600))?;
-    }
-
-    println!("\nSetup complete!");
-    println!("  Config:  {}", config_path.display());
-    println!("  Secrets: {} (0600)", secrets_path.display());
-    println!("\nStart the proxy with:\n  gdocs-proxy serve --config {}", config_path.display());
-
-    Ok(())
+/// Replace the stored refresh token (see [`carapace_google::run_reauth`]).
+pub async fn run_reauth(config_path: PathBuf, access: Access, flow: FlowArgs, revoke_old: bool) -> Result<()> {
+    let scopes = access.scopes();
+    carapace_google::run_reauth(config_path, revoke_old, &options(&scopes, flow)).await
 }
 
-/// Percent-encode a string for use in URL query parameters.
-fn url_encode(s: &str) -> String {
-    s.chars()
-        .flat_map(|c| match c {
-            'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => {
-                vec![c]
-            }
-            c => format!("%{:02X}", c as u32).chars().collect(),
-        })
-        .collect()
+/// Encrypt the plaintext secrets file (see [`carapace_google::run_migrate_secrets`]).
+pub fn run_migrate_secrets(config_path: PathBuf) -> Result<()> {
+    carapace_google::run_migrate_secrets(config_path, "gdocs-proxy", "secrets-gdocs.toml")
 }
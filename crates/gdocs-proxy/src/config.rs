@@ -2,18 +2,13 @@
 //!
 //! Two-file structure (same pattern as gmail-proxy):
 //!   - `config.toml` — non-secret settings (paths, scrub rules, etc.)
-//!   - `secrets.toml` — OAuth refresh token; must be 0600
+//!   - `secrets.toml` — OAuth refresh token; must be 0600, optionally encrypted
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct AuthConfig {
-    pub client_id: String,
-    pub client_secret: String,
-    pub secrets_file: String,
-}
+pub use carapace_google::{AuthConfig, Secrets};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct AccountConfig {
@@ -29,32 +24,86 @@ pub struct ScrubConfig {
     /// Regex patterns to redact from document content (e.g. OTPs, tokens).
     #[serde(default)]
     pub redact_patterns: Vec<String>,
+    /// Mask form answers to questions asking for a respondent's name, email,
+    /// phone number or address.
+    #[serde(default)]
+    pub mask_respondent_identity: bool,
     /// Folder IDs that are blocked from search results and reads.
     /// Files anywhere inside these folders (including nested subfolders) are hidden.
     #[serde(default)]
     pub blocked_folders: Vec<String>,
+    /// Also exclude direct children of blocked folders in the Drive search
+    /// query itself, so fewer results need an ancestry check.
+    #[serde(default)]
+    pub exclude_blocked_in_query: bool,
+    /// How long folder parent links are cached for ancestry checks.
+    /// Set to 0 to disable caching.
+    #[serde(default = "default_ancestry_cache_ttl_secs")]
+    pub ancestry_cache_ttl_secs: u64,
+    /// Treat files whose folder ancestry cannot be read as blocked: hide them
+    /// from search and refuse reads and copies.
+    #[serde(default = "default_true")]
+    pub fail_closed: bool,
 }
 
 impl Default for ScrubConfig {
     fn default() -> Self {
         Self {
             strip_links: false,
-            redact_patterns: vec![],
-            blocked_folders: vec![],
+            redact_patterns: Vec::new(),
+            mask_respondent_identity: false,
+            blocked_folders: Vec::new(),
+            exclude_blocked_in_query: false,
+            ancestry_cache_ttl_secs: default_ancestry_cache_ttl_secs(),
+            fail_closed: true,
         }
     }
 }
 
+fn default_ancestry_cache_ttl_secs() -> u64 {
+    300
+}
+
+/// Write-scope policy. When this section is present, writes are confined to
+/// `writable_folders` (and, optionally, files the agent created).
+#[derive(Debug, Deserialize, Clone)]
+pub struct WriteConfig {
+    /// Folder IDs the agent may write into, including nested subfolders.
+    #[serde(default)]
+    pub writable_folders: Vec<String>,
+    /// Also allow writes to files created through this proxy, and to
+    /// anything inside folders it created.
+    #[serde(default = "default_true")]
+    pub allow_created_files: bool,
+    /// Where IDs of created files are recorded. Relative paths are resolved
+    /// against the config directory.
+    #[serde(default = "default_created_files_file")]
+    pub created_files_file: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_created_files_file() -> String {
+    "gdocs-created-files.json".into()
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProxyConfig {
     #[serde(default = "default_socket_path")]
     pub socket_path: String,
+    /// Where the `/changes` page token is persisted. Relative paths are
+    /// resolved against the config directory.
+    #[serde(default = "default_changes_cursor_file")]
+    pub changes_cursor_file: String,
 }
 
 impl Default for ProxyConfig {
     fn default() -> Self {
         Self {
             socket_path: default_socket_path(),
+            changes_cursor_file: default_changes_cursor_file(),
         }
     }
 }
@@ -63,9 +112,41 @@ fn default_socket_path() -> String {
     "/var/run/carapace/gdocs-proxy.sock".into()
 }
 
+fn default_changes_cursor_file() -> String {
+    "changes-cursor.json".into()
+}
+
+/// Limits for reading non-Google files (PDF, Office, text).
 #[derive(Debug, Deserialize, Clone)]
-pub struct Secrets {
-    pub refresh_token: String,
+pub struct FilesConfig {
+    /// Files larger than this are refused without being downloaded.
+    #[serde(default = "default_file_max_bytes")]
+    pub max_bytes: u64,
+    /// Extracted text is truncated to this many characters.
+    #[serde(default = "default_file_max_chars")]
+    pub max_text_chars: usize,
+    /// Folder for the temporary Google copies used to read Office files.
+    /// Defaults to My Drive's root; required when `[write]` is configured.
+    #[serde(default)]
+    pub convert_folder: Option<String>,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_file_max_bytes(),
+            max_text_chars: default_file_max_chars(),
+            convert_folder: None,
+        }
+    }
+}
+
+fn default_file_max_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_file_max_chars() -> usize {
+    100_000
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -76,6 +157,10 @@ pub struct ConfigFile {
     pub scrub: ScrubConfig,
     #[serde(default)]
     pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub write: Option<WriteConfig>,
+    #[serde(default)]
+    pub files: FilesConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +169,8 @@ pub struct Config {
     pub gdocs: AccountConfig,
     pub scrub: ScrubConfig,
     pub proxy: ProxyConfig,
+    pub write: Option<WriteConfig>,
+    pub files: FilesConfig,
     pub secrets: Secrets,
 }
 
@@ -98,8 +185,9 @@ pub fn validate_patterns(config: &ConfigFile) -> Result<()> {
 
 /// Load configuration from the given TOML config file path.
 ///
-/// Resolves the secrets file relative to the config file's parent directory.
-/// On Unix, enforces that the secrets file has 0600 permissions.
+/// Resolves the secrets file relative to the config file's parent directory,
+/// decrypting it if `[auth.encryption]` is set. On Unix, enforces that the
+/// secrets file has 0600 permissions.
 pub fn load_config(path: &Path, skip_permission_check: bool) -> Result<Config> {
     let text = std::fs::read_to_string(path)
         .with_context(|| format!("failed to read config file: {}", path.display()))?;
@@ -110,36 +198,28 @@ pub fn load_config(path: &Path, skip_permission_check: bool) -> Result<Config> {
     validate_patterns(&file).context("config pattern validation failed")?;
 
     let config_dir = path.parent().unwrap_or(Path::new("."));
-    let secrets_path = config_dir.join(&file.auth.secrets_file);
-
-    if !skip_permission_check {
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let metadata = std::fs::metadata(&secrets_path)
-                .with_context(|| format!("failed to stat secrets file: {}", secrets_path.display()))?;
-            let mode = metadata.permissions().mode() & 0o777;
-            if mode != 0o600 {
-                anyhow::bail!(
-                    "secrets file {} has permissions {:04o}, expected 0600",
-                    secrets_path.display(),
-                    mode
-                );
-            }
-        }
-    }
-
-    let secrets_text = std::fs::read_to_string(&secrets_path)
-        .with_context(|| format!("failed to read secrets file: {}", secrets_path.display()))?;
-
-    let secrets: Secrets = toml::from_str(&secrets_text)
-        .with_context(|| format!("failed to parse secrets file: {}", secrets_path.display()))?;
+    let write = file.write.map(|mut w| {
+        w.created_files_file = config_dir
+            .join(&w.created_files_file)
+            .to_string_lossy()
+            .into_owned();
+        w
+    });
+    let mut proxy = file.proxy;
+    proxy.changes_cursor_file = config_dir
+        .join(&proxy.changes_cursor_file)
+        .to_string_lossy()
+        .into_owned();
+
+    let secrets = file.auth.secret_store(config_dir, skip_permission_check)?.load()?;
 
     Ok(Config {
         auth: file.auth,
         gdocs: file.gdocs,
         scrub: file.scrub,
-        proxy: file.proxy,
+        proxy,
+        write,
+        files: file.files,
         secrets,
     })
 }
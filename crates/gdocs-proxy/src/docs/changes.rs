@@ -0,0 +1,165 @@
+//! Classify Drive `changes.list` entries for `GET /changes`.
+//!
+//! A Drive change only says that something about a file changed at `time`.
+//! What happened is inferred from the file's own timestamps: a creation,
+//! share or content edit stamps `createdTime`, `sharedWithMeTime` or
+//! `modifiedTime` with (almost) the same time as the change. Comments update
+//! none of them, so a change that the timestamps do not explain is reported
+//! as a comment only if the file's comment list confirms one; anything else
+//! (renames by others, permission tweaks, starring) is dropped.
+
+use chrono::{DateTime, Duration, FixedOffset};
+
+use crate::docs::types::{DriveChange, DriveComment};
+
+/// How far apart a file timestamp and the change time may be and still count
+/// as the same event.
+pub const MATCH_WINDOW: Duration = Duration::seconds(60);
+
+const FOLDER_MIME_TYPE: &str = "application/vnd.google-apps.folder";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Shared,
+    Commented,
+}
+
+impl ChangeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Shared => "shared",
+            ChangeKind::Commented => "commented",
+        }
+    }
+}
+
+/// Who wrote the most recent comment or reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommentActivity {
+    pub author: Option<String>,
+    pub by_me: bool,
+}
+
+fn parse_time(value: Option<&str>) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value?).ok()
+}
+
+/// Change time minus [`MATCH_WINDOW`], as RFC 3339: the earliest comment
+/// modification that can explain the change.
+pub fn comment_window_start(change: &DriveChange) -> Option<String> {
+    parse_time(change.time.as_deref()).map(|t| (t - MATCH_WINDOW).to_rfc3339())
+}
+
+/// Decide what a change reports.
+///
+/// Returns `None` for changes that are never reported: removals, trashed
+/// files and folders. `Some(ChangeKind::Commented)` means the timestamps do
+/// not explain the change and the comment list must confirm it.
+pub fn classify(change: &DriveChange) -> Option<ChangeKind> {
+    let file = change.file.as_ref()?;
+    if change.removed || file.trashed || file.mime_type == FOLDER_MIME_TYPE {
+        return None;
+    }
+    let time = parse_time(change.time.as_deref())?;
+    let near = |stamp: Option<&str>| {
+        parse_time(stamp).is_some_and(|s| (s - time).abs() <= MATCH_WINDOW)
+    };
+
+    if !file.owned_by_me && near(file.shared_with_me_time.as_deref()) {
+        Some(ChangeKind::Shared)
+    } else if near(file.created_time.as_deref()) {
+        Some(ChangeKind::Created)
+    } else if near(file.modified_time.as_deref()) {
+        Some(ChangeKind::Modified)
+    } else {
+        Some(ChangeKind::Commented)
+    }
+}
+
+/// The latest non-deleted comment or reply modified at or after `since`.
+pub fn latest_comment(comments: &[DriveComment], since: &str) -> Option<CommentActivity> {
+    let since = DateTime::parse_from_rfc3339(since).ok()?;
+    comments
+        .iter()
+        .filter(|c| !c.deleted)
+        .flat_map(|c| {
+            let replies = c
+                .replies
+                .iter()
+                .filter(|r| !r.deleted)
+                .map(|r| (r.modified_time.as_deref(), r.author.as_ref()));
+            std::iter::once((c.modified_time.as_deref(), c.author.as_ref())).chain(replies)
+        })
+        .filter_map(|(time, author)| parse_time(time).map(|t| (t, author)))
+        .filter(|(t, _)| *t >= since)
+        .max_by_key(|(t, _)| *t)
+        .map(|(_, author)| CommentActivity {
+            author: author.and_then(|a| a.display_name.clone()),
+            by_me: author.is_some_and(|a| a.me),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(file: serde_json::Value) -> DriveChange {
+        serde_json::from_value(json!({
+            "fileId": "f1",
+            "time": "2025-03-01T10:00:30Z",
+            "file": file
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_classify_from_file_timestamps() {
+        let base = json!({
+            "id": "f1", "name": "Plan", "mimeType": "application/vnd.google-apps.document",
+            "createdTime": "2024-01-01T00:00:00Z", "modifiedTime": "2024-06-01T00:00:00Z",
+            "ownedByMe": true
+        });
+        let with = |extra: serde_json::Value| {
+            let mut file = base.clone();
+            file.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+            change(file)
+        };
+
+        assert_eq!(classify(&with(json!({"modifiedTime": "2025-03-01T10:00:00Z"}))), Some(ChangeKind::Modified));
+        assert_eq!(
+            classify(&with(json!({"createdTime": "2025-03-01T10:00:29Z", "modifiedTime": "2025-03-01T10:00:29Z"}))),
+            Some(ChangeKind::Created)
+        );
+        assert_eq!(
+            classify(&with(json!({"ownedByMe": false, "sharedWithMeTime": "2025-03-01T10:01:00Z"}))),
+            Some(ChangeKind::Shared)
+        );
+        assert_eq!(classify(&with(json!({}))), Some(ChangeKind::Commented));
+        assert_eq!(classify(&with(json!({"trashed": true}))), None);
+        assert_eq!(classify(&with(json!({"mimeType": FOLDER_MIME_TYPE}))), None);
+    }
+
+    #[test]
+    fn test_latest_comment_includes_replies() {
+        let comments: Vec<DriveComment> = serde_json::from_value(json!([
+            {"id": "c1", "modifiedTime": "2025-03-01T09:00:00Z", "author": {"displayName": "Old"}},
+            {"id": "c2", "modifiedTime": "2025-03-01T10:00:10Z", "author": {"displayName": "Ana"},
+             "replies": [
+                {"modifiedTime": "2025-03-01T10:00:20Z", "author": {"displayName": "Me", "me": true}},
+                {"modifiedTime": "2025-03-01T10:00:25Z", "deleted": true, "author": {"displayName": "Gone"}}
+             ]}
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            latest_comment(&comments, "2025-03-01T09:59:30Z"),
+            Some(CommentActivity { author: Some("Me".into()), by_me: true })
+        );
+        assert_eq!(latest_comment(&comments, "2025-03-01T10:00:21Z"), None);
+    }
+}
@@ -1,18 +1,37 @@
 //! Google Drive + Docs API HTTP client.
 
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use serde_json::json;
 
 use crate::auth::TokenManager;
+use crate::docs::edit::RevisionConflict;
+use crate::docs::files::FileTooLarge;
+use crate::docs::parents::ParentCache;
+use crate::docs::sheets::{SheetValueRange, ValueRender};
 use crate::docs::types::*;
 
+/// Default lifetime of cached parent links.
+const DEFAULT_PARENT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Tries at deleting a temporary conversion copy before giving up.
+const DELETE_ATTEMPTS: u32 = 3;
+
+/// Upper bound on folders visited while walking one file's ancestry.
+const MAX_ANCESTORS: usize = 256;
+
 pub struct DocsClient {
     http_client: reqwest::Client,
     token_manager: Arc<TokenManager>,
     drive_base_url: String,
     docs_base_url: String,
+    sheets_base_url: String,
+    slides_base_url: String,
+    forms_base_url: String,
+    parent_cache: ParentCache,
     pub account: String,
 }
 
@@ -26,10 +45,32 @@ impl DocsClient {
             token_manager,
             drive_base_url: "https://www.googleapis.com/drive/v3".into(),
             docs_base_url: "https://docs.googleapis.com/v1".into(),
+            sheets_base_url: "https://sheets.googleapis.com/v4".into(),
+            slides_base_url: "https://slides.googleapis.com/v1".into(),
+            forms_base_url: "https://forms.googleapis.com/v1".into(),
+            parent_cache: ParentCache::new(DEFAULT_PARENT_CACHE_TTL),
             account,
         }
     }
 
+    /// Set how long parent links are cached for ancestry checks.
+    pub fn with_parent_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.parent_cache = ParentCache::new(ttl);
+        self
+    }
+
+    /// Send every API request to `root` (e.g. a local mock server) instead of
+    /// Google, keeping each API's path prefix.
+    #[cfg(test)]
+    pub fn with_api_root(mut self, root: &str) -> Self {
+        self.drive_base_url = format!("{root}/drive/v3");
+        self.docs_base_url = format!("{root}/docs/v1");
+        self.sheets_base_url = format!("{root}/sheets/v4");
+        self.slides_base_url = format!("{root}/slides/v1");
+        self.forms_base_url = format!("{root}/forms/v1");
+        self
+    }
+
     async fn auth_header(&self) -> Result<String> {
         let token = self.token_manager.get_token().await?;
         Ok(format!("Bearer {token}"))
@@ -63,7 +104,7 @@ impl DocsClient {
             .query(&[
                 ("q", full_query.as_str()),
                 ("pageSize", &max_results.to_string()),
-                ("fields", "files(id,name,mimeType,createdTime,modifiedTime,owners,webViewLink,starred,trashed),nextPageToken"),
+                ("fields", "files(id,name,mimeType,createdTime,modifiedTime,owners,webViewLink,starred,trashed,parents),nextPageToken"),
                 ("orderBy", "modifiedTime desc"),
             ]);
         if let Some(pt) = page_token {
@@ -82,7 +123,7 @@ impl DocsClient {
             .http_client
             .get(format!("{}/files/{file_id}", self.drive_base_url))
             .header("Authorization", &auth)
-            .query(&[("fields", "id,name,mimeType,createdTime,modifiedTime,owners,webViewLink,starred,trashed,parents")])
+            .query(&[("fields", "id,name,mimeType,size,createdTime,modifiedTime,owners,webViewLink,starred,trashed,parents")])
             .send()
             .await
             .context("Drive get_file request failed")?;
@@ -90,39 +131,227 @@ impl DocsClient {
         resp.json().await.context("failed to deserialize file metadata")
     }
 
-    /// Check if a file is inside any of the blocked folders by walking up the parent chain.
-    /// Returns true if blocked.
-    pub async fn is_in_blocked_folder(&self, file_id: &str, blocked_folders: &[String]) -> bool {
+    /// List the non-deleted comments on a file, following pagination.
+    pub async fn list_comments(&self, file_id: &str) -> Result<Vec<Comment>> {
+        let auth = self.auth_header().await?;
+        let mut comments = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut req = self
+                .http_client
+                .get(format!("{}/files/{file_id}/comments", self.drive_base_url))
+                .header("Authorization", &auth)
+                .query(&[
+                    ("pageSize", "100"),
+                    ("fields", "comments(id,author(displayName),content,quotedFileContent(value),resolved,deleted,replies(author(displayName),content,deleted)),nextPageToken"),
+                ]);
+            if let Some(ref pt) = page_token {
+                req = req.query(&[("pageToken", pt.as_str())]);
+            }
+            let resp = req.send().await.context("Drive comments request failed")?;
+            let resp = check_status(resp, "Drive comments").await?;
+            let page: CommentListResponse = resp
+                .json()
+                .await
+                .context("failed to deserialize Drive comments")?;
+            comments.extend(page.comments.iter().filter(|c| !c.deleted).map(|c| c.to_comment()));
+            match page.next_page_token {
+                Some(pt) => page_token = Some(pt),
+                None => return Ok(comments),
+            }
+        }
+    }
+
+    /// Comments (with replies) modified at or after `since` (RFC 3339).
+    ///
+    /// Returns a single page: enough to tell whether and by whom a file was
+    /// recently commented on.
+    pub async fn recent_comments(&self, file_id: &str, since: &str) -> Result<Vec<DriveComment>> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/files/{file_id}/comments", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[
+                ("startModifiedTime", since),
+                ("pageSize", "20"),
+                ("fields", "comments(id,author(displayName,me),deleted,modifiedTime,replies(author(displayName,me),deleted,modifiedTime))"),
+            ])
+            .send()
+            .await
+            .context("Drive comments request failed")?;
+        let resp = check_status(resp, "Drive comments").await?;
+        let page: CommentListResponse = resp
+            .json()
+            .await
+            .context("failed to deserialize Drive comments")?;
+        Ok(page.comments)
+    }
+
+    /// Page token for changes made from now on.
+    pub async fn start_page_token(&self) -> Result<String> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/changes/startPageToken", self.drive_base_url))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .context("Drive startPageToken request failed")?;
+        let resp = check_status(resp, "Drive startPageToken").await?;
+        let body: serde_json::Value = resp
+            .json()
+            .await
+            .context("failed to deserialize startPageToken response")?;
+        body.get("startPageToken")
+            .and_then(|t| t.as_str())
+            .map(str::to_string)
+            .context("startPageToken missing from Drive response")
+    }
+
+    /// One page of changes from `page_token`.
+    ///
+    /// Returns `Ok(None)` when Drive rejects the token as invalid or expired
+    /// — the caller must start again from [`Self::start_page_token`].
+    pub async fn list_changes(&self, page_token: &str, page_size: u32) -> Result<Option<ChangeListResponse>> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/changes", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[
+                ("pageToken", page_token),
+                ("pageSize", &page_size.to_string()),
+                ("spaces", "drive"),
+                ("includeRemoved", "false"),
+                ("fields", "changes(fileId,time,removed,file(id,name,mimeType,createdTime,modifiedTime,sharedWithMeTime,ownedByMe,trashed,webViewLink,lastModifyingUser(displayName,me))),nextPageToken,newStartPageToken"),
+            ])
+            .send()
+            .await
+            .context("Drive changes request failed")?;
+        if matches!(
+            resp.status(),
+            reqwest::StatusCode::BAD_REQUEST | reqwest::StatusCode::NOT_FOUND | reqwest::StatusCode::GONE
+        ) {
+            return Ok(None);
+        }
+        let resp = check_status(resp, "Drive changes").await?;
+        resp.json().await.map(Some).context("failed to deserialize Drive changes")
+    }
+
+    /// Parent IDs of a file or folder, served from the parent cache when fresh.
+    pub async fn parents(&self, file_id: &str) -> Result<Vec<String>> {
+        if let Some(parents) = self.parent_cache.get(file_id).await {
+            return Ok(parents);
+        }
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/files/{file_id}", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[("fields", "parents")])
+            .send()
+            .await
+            .context("Drive get parents request failed")?;
+        let resp = check_status(resp, "Drive get parents").await?;
+        let file: serde_json::Value = resp
+            .json()
+            .await
+            .context("failed to deserialize file parents")?;
+        let parents: Vec<String> = file
+            .get("parents")
+            .and_then(|p| p.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.parent_cache.insert(file_id, parents.clone()).await;
+        Ok(parents)
+    }
+
+    /// Seed the parent cache from file metadata that already lists parents
+    /// (e.g. search results), saving a lookup per file.
+    pub async fn remember_parents(&self, files: &[DriveFile]) {
+        for f in files {
+            if let Some(ref parents) = f.parents {
+                self.parent_cache.insert(&f.id, parents.clone()).await;
+            }
+        }
+    }
+
+    /// Check if a file is inside any of the blocked folders by walking up
+    /// every parent chain. Returns true if blocked.
+    ///
+    /// A file reachable from a blocked folder through any readable path is
+    /// blocked. Otherwise, if some part of the hierarchy could not be read, the
+    /// lookup error is returned and the caller decides how to treat it.
+    pub async fn is_in_blocked_folder(&self, file_id: &str, blocked_folders: &[String]) -> Result<bool> {
         if blocked_folders.is_empty() {
-            return false;
+            return Ok(false);
         }
-        // Check if this file IS a blocked folder.
-        if blocked_folders.iter().any(|f| f == file_id) {
-            return true;
+        let mut seen = HashSet::new();
+        let mut frontier = vec![file_id.to_string()];
+        let mut lookup_error = None;
+        while let Some(id) = frontier.pop() {
+            if blocked_folders.contains(&id) {
+                return Ok(true);
+            }
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            if seen.len() > MAX_ANCESTORS {
+                anyhow::bail!("folder hierarchy of {file_id} has more than {MAX_ANCESTORS} ancestors");
+            }
+            match self.parents(&id).await {
+                Ok(parents) => frontier.extend(parents),
+                Err(e) => {
+                    lookup_error.get_or_insert(e.context(format!("failed to read parents of {id}")));
+                }
+            }
         }
-        // Walk up the parent chain.
-        let mut current_id = file_id.to_string();
-        let mut seen = std::collections::HashSet::new();
-        loop {
-            if !seen.insert(current_id.clone()) {
-                return false; // cycle protection
+        match lookup_error {
+            Some(e) => Err(e),
+            None => Ok(false),
+        }
+    }
+
+    /// Check many files against the blocked folders concurrently. Results are
+    /// returned in the same order as `files`.
+    pub async fn check_blocked(
+        &self,
+        files: &[DriveFile],
+        blocked_folders: &[String],
+    ) -> Vec<Result<bool>> {
+        self.remember_parents(files).await;
+        let checks = files
+            .iter()
+            .map(|f| self.is_in_blocked_folder(&f.id, blocked_folders));
+        futures::future::join_all(checks).await
+    }
+
+    /// Return the file's ancestry: the file ID followed by every folder above
+    /// it, across all parents, up to the Drive root.
+    ///
+    /// Unlike [`is_in_blocked_folder`](Self::is_in_blocked_folder), lookup
+    /// failures are returned as errors so write checks can fail closed.
+    pub async fn ancestry(&self, file_id: &str) -> Result<Vec<String>> {
+        let mut chain = vec![file_id.to_string()];
+        let mut next = 0;
+        while next < chain.len() {
+            if chain.len() > MAX_ANCESTORS {
+                anyhow::bail!("folder hierarchy of {file_id} has more than {MAX_ANCESTORS} ancestors");
             }
-            let file = match self.get_file(&current_id).await {
-                Ok(f) => f,
-                Err(_) => return false, // can't read → not blocked
-            };
-            let parents = match file.parents {
-                Some(ref p) if !p.is_empty() => p.clone(),
-                _ => return false, // reached root
-            };
-            for parent in &parents {
-                if blocked_folders.iter().any(|f| f == parent) {
-                    return true;
+            let parents = self.parents(&chain[next]).await?;
+            for parent in parents {
+                if !chain.contains(&parent) {
+                    chain.push(parent);
                 }
             }
-            // Continue up with the first parent.
-            current_id = parents[0].clone();
+            next += 1;
         }
+        Ok(chain)
     }
 
     /// Create a folder in Google Drive. Optionally place it inside a parent folder.
@@ -186,9 +415,7 @@ impl DocsClient {
         });
         let resp = self
             .http_client
-            .put(format!(
-                "https://sheets.googleapis.com/v4/spreadsheets/{spreadsheet_id}/values/{range}",
-            ))
+            .put(sheets_values_url(&self.sheets_base_url, spreadsheet_id, range)?)
             .header("Authorization", &auth)
             .query(&[("valueInputOption", "USER_ENTERED")])
             .json(&body)
@@ -199,15 +426,22 @@ impl DocsClient {
         resp.json().await.context("failed to deserialize sheets update response")
     }
 
-    /// Copy a file. Returns the new file's metadata.
-    pub async fn copy_file(&self, file_id: &str, new_title: Option<&str>) -> Result<DriveFile> {
+    /// Copy a file, optionally into `parent_id`. Returns the new file's metadata.
+    pub async fn copy_file(
+        &self,
+        file_id: &str,
+        new_title: Option<&str>,
+        parent_id: Option<&str>,
+    ) -> Result<DriveFile> {
         let auth = self.auth_header().await?;
 
-        let body = if let Some(title) = new_title {
-            json!({"name": title})
-        } else {
-            json!({})
-        };
+        let mut body = json!({});
+        if let Some(title) = new_title {
+            body["name"] = json!(title);
+        }
+        if let Some(pid) = parent_id {
+            body["parents"] = json!([pid]);
+        }
 
         let resp = self
             .http_client
@@ -222,6 +456,117 @@ impl DocsClient {
         resp.json().await.context("failed to deserialize copy response")
     }
 
+    // ── Drive file content ─────────────────────────────────────────────────
+
+    /// Download a file's raw bytes, failing with [`FileTooLarge`] once more
+    /// than `max_bytes` have been received.
+    pub async fn download(&self, file_id: &str, max_bytes: u64) -> Result<Vec<u8>> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/files/{file_id}", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[("alt", "media")])
+            .send()
+            .await
+            .context("Drive download request failed")?;
+        let resp = check_status(resp, "Drive download").await?;
+        read_limited(resp, max_bytes).await
+    }
+
+    /// Export a Google file in `mime_type`, with the same limit as [`download`](Self::download).
+    pub async fn export(&self, file_id: &str, mime_type: &str, max_bytes: u64) -> Result<Vec<u8>> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/files/{file_id}/export", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[("mimeType", mime_type)])
+            .send()
+            .await
+            .context("Drive export request failed")?;
+        let resp = check_status(resp, "Drive export").await?;
+        read_limited(resp, max_bytes).await
+    }
+
+    /// Read an Office file by converting it to `google_mime` in a temporary
+    /// copy, exporting that as `export_mime`, and deleting the copy.
+    ///
+    /// The copy is placed in `folder_id` (My Drive's root when `None`), never
+    /// next to the original, which may be in a shared folder other people
+    /// watch. The caller checks the folder against the write policy. If the
+    /// copy cannot be deleted the read fails, naming the copy.
+    pub async fn export_converted(
+        &self,
+        file_id: &str,
+        google_mime: &str,
+        export_mime: &str,
+        max_bytes: u64,
+        folder_id: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .post(format!("{}/files/{file_id}/copy", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[("fields", "id")])
+            .json(&json!({
+                "name": format!("carapace-convert-{file_id}"),
+                "mimeType": google_mime,
+                "parents": [folder_id.unwrap_or("root")]
+            }))
+            .send()
+            .await
+            .context("Drive convert request failed")?;
+        let resp = check_status(resp, "Drive convert").await?;
+        let copy: serde_json::Value = resp.json().await.context("failed to deserialize Drive file")?;
+        let copy_id = copy
+            .get("id")
+            .and_then(|v| v.as_str())
+            .context("missing id in Drive copy response")?
+            .to_string();
+
+        let exported = self.export(&copy_id, export_mime, max_bytes).await;
+        self.delete_temporary(&copy_id).await?;
+        exported
+    }
+
+    /// Delete a temporary conversion copy, retrying transient failures.
+    async fn delete_temporary(&self, copy_id: &str) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            match self.delete_file(copy_id).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < DELETE_ATTEMPTS => {
+                    tracing::warn!(copy_id, attempt, "failed to delete temporary conversion copy: {e:#}");
+                    tokio::time::sleep(Duration::from_millis(200 * attempt as u64)).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    tracing::error!(copy_id, "giving up deleting temporary conversion copy: {e:#}");
+                    return Err(e.context(format!(
+                        "temporary conversion copy {copy_id} could not be deleted and must be removed by hand"
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Permanently delete a file. Only used for files the proxy itself just
+    /// made: temporary copies, and forms it could not move into place.
+    async fn delete_file(&self, file_id: &str) -> Result<()> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .delete(format!("{}/files/{file_id}", self.drive_base_url))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .context("Drive delete request failed")?;
+        check_status(resp, "Drive delete").await?;
+        Ok(())
+    }
+
     // ── Google Docs API ────────────────────────────────────────────────────
 
     /// Read a Google Doc by document ID. Returns the full structured content.
@@ -246,7 +591,8 @@ impl DocsClient {
         let resp = self
             .http_client
             .get(format!(
-                "https://sheets.googleapis.com/v4/spreadsheets/{spreadsheet_id}",
+                "{}/spreadsheets/{spreadsheet_id}",
+                self.sheets_base_url,
             ))
             .header("Authorization", &auth)
             .query(&[("includeGridData", "true")])
@@ -257,6 +603,74 @@ impl DocsClient {
         resp.json().await.context("failed to deserialize spreadsheet")
     }
 
+    /// Read one A1 range. Returns the values API response (`range`, `values`).
+    pub async fn get_sheet_values(
+        &self,
+        spreadsheet_id: &str,
+        range: &str,
+        render: ValueRender,
+    ) -> Result<serde_json::Value> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(sheets_values_url(&self.sheets_base_url, spreadsheet_id, range)?)
+            .header("Authorization", &auth)
+            .query(&[("valueRenderOption", render.api_option()), ("majorDimension", "ROWS")])
+            .send()
+            .await
+            .context("Sheets get values request failed")?;
+        let resp = check_status(resp, "Sheets get values").await?;
+        resp.json().await.context("failed to deserialize sheet values")
+    }
+
+    /// Write several ranges in one request.
+    pub async fn batch_update_sheet_values(
+        &self,
+        spreadsheet_id: &str,
+        data: &[SheetValueRange],
+    ) -> Result<serde_json::Value> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .post(format!(
+                "{}/spreadsheets/{spreadsheet_id}/values:batchUpdate",
+                self.sheets_base_url,
+            ))
+            .header("Authorization", &auth)
+            .json(&json!({"valueInputOption": "USER_ENTERED", "data": data}))
+            .send()
+            .await
+            .context("Sheets batch update request failed")?;
+        let resp = check_status(resp, "Sheets batch update").await?;
+        resp.json().await.context("failed to deserialize sheets batch update response")
+    }
+
+    /// Titles of a spreadsheet's sheets, in tab order.
+    pub async fn sheet_titles(&self, spreadsheet_id: &str) -> Result<Vec<String>> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!(
+                "{}/spreadsheets/{spreadsheet_id}",
+                self.sheets_base_url,
+            ))
+            .header("Authorization", &auth)
+            .query(&[("fields", "sheets.properties.title")])
+            .send()
+            .await
+            .context("Sheets get titles request failed")?;
+        let resp = check_status(resp, "Sheets get titles").await?;
+        let body: serde_json::Value = resp.json().await.context("failed to deserialize spreadsheet")?;
+        Ok(body
+            .get("sheets")
+            .and_then(|s| s.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|s| s.pointer("/properties/title").and_then(|t| t.as_str()))
+            .map(str::to_string)
+            .collect())
+    }
+
     // ── Google Slides API ────────────────────────────────────────────────
 
     /// Read a Google Slides presentation. Returns slide content.
@@ -265,7 +679,8 @@ impl DocsClient {
         let resp = self
             .http_client
             .get(format!(
-                "https://slides.googleapis.com/v1/presentations/{presentation_id}",
+                "{}/presentations/{presentation_id}",
+                self.slides_base_url,
             ))
             .header("Authorization", &auth)
             .send()
@@ -283,7 +698,8 @@ impl DocsClient {
         let resp = self
             .http_client
             .get(format!(
-                "https://forms.googleapis.com/v1/forms/{form_id}",
+                "{}/forms/{form_id}",
+                self.forms_base_url,
             ))
             .header("Authorization", &auth)
             .send()
@@ -294,34 +710,139 @@ impl DocsClient {
     }
 
     /// Create a Google Form with the given title.
-    pub async fn create_form(&self, title: &str) -> Result<serde_json::Value> {
+    ///
+    /// The Forms API always creates in the Drive root, so with `folder_id`
+    /// the new form is then moved into that folder. If the move fails the
+    /// form is deleted again rather than left in the root.
+    pub async fn create_form(&self, title: &str, folder_id: Option<&str>) -> Result<serde_json::Value> {
         let auth = self.auth_header().await?;
         let resp = self
             .http_client
-            .post("https://forms.googleapis.com/v1/forms")
+            .post(format!("{}/forms", self.forms_base_url))
             .header("Authorization", &auth)
             .json(&json!({"info": {"title": title}}))
             .send()
             .await
             .context("Forms create request failed")?;
         let resp = check_status(resp, "Forms create").await?;
-        resp.json().await.context("failed to deserialize form")
+        let form: serde_json::Value = resp.json().await.context("failed to deserialize form")?;
+
+        let (Some(folder_id), Some(form_id)) = (folder_id, form.get("formId").and_then(|v| v.as_str())) else {
+            return Ok(form);
+        };
+        if let Err(e) = self.move_file(form_id, folder_id).await {
+            if let Err(delete_err) = self.delete_file(form_id).await {
+                tracing::error!(form_id, "failed to delete form left in the Drive root: {delete_err:#}");
+                return Err(e.context(format!(
+                    "form {form_id} could not be moved into {folder_id} or deleted and must be removed from the Drive root by hand"
+                )));
+            }
+            return Err(e.context(format!("form could not be moved into {folder_id} and was deleted")));
+        }
+        Ok(form)
+    }
+
+    /// Move a file into `folder_id`, out of every folder it is in now.
+    async fn move_file(&self, file_id: &str, folder_id: &str) -> Result<()> {
+        let current = self.parents(file_id).await?;
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .patch(format!("{}/files/{file_id}", self.drive_base_url))
+            .header("Authorization", &auth)
+            .query(&[
+                ("addParents", folder_id),
+                ("removeParents", &current.join(",")),
+                ("fields", "id,parents"),
+            ])
+            .json(&json!({}))
+            .send()
+            .await
+            .context("Drive move request failed")?;
+        check_status(resp, "Drive move").await?;
+        self.parent_cache.insert(file_id, vec![folder_id.to_string()]).await;
+        Ok(())
     }
 
     /// Read form responses.
     pub async fn get_form_responses(&self, form_id: &str) -> Result<serde_json::Value> {
+        self.list_form_responses(form_id, None).await
+    }
+
+    /// Read form responses submitted after `since` (RFC 3339), following
+    /// pagination. Returns `{"responses": [...]}` like a single page.
+    pub async fn list_form_responses(&self, form_id: &str, since: Option<&str>) -> Result<serde_json::Value> {
+        let auth = self.auth_header().await?;
+        let filter = since.map(|t| format!("timestamp > {t}"));
+        let mut responses = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query: Vec<(&str, &str)> = Vec::new();
+            if let Some(ref f) = filter {
+                query.push(("filter", f));
+            }
+            if let Some(ref pt) = page_token {
+                query.push(("pageToken", pt));
+            }
+            let resp = self
+                .http_client
+                .get(format!(
+                    "{}/forms/{form_id}/responses",
+                self.forms_base_url,
+                ))
+                .header("Authorization", &auth)
+                .query(&query)
+                .send()
+                .await
+                .context("Forms get_form_responses request failed")?;
+            let resp = check_status(resp, "Forms get_form_responses").await?;
+            let page: serde_json::Value = resp.json().await.context("failed to deserialize form responses")?;
+            if let Some(items) = page.get("responses").and_then(|r| r.as_array()) {
+                responses.extend(items.iter().cloned());
+            }
+            page_token = page.get("nextPageToken").and_then(|t| t.as_str()).map(str::to_string);
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(json!({"responses": responses}))
+    }
+
+    /// Apply Forms `batchUpdate` requests, failing with [`RevisionConflict`]
+    /// if the form is no longer at `required_revision_id`.
+    pub async fn form_batch_update(
+        &self,
+        form_id: &str,
+        requests: Vec<serde_json::Value>,
+        required_revision_id: &str,
+    ) -> Result<serde_json::Value> {
         let auth = self.auth_header().await?;
+        let body = json!({
+            "requests": requests,
+            "includeFormInResponse": false,
+            "writeControl": {"requiredRevisionId": required_revision_id}
+        });
         let resp = self
             .http_client
-            .get(format!(
-                "https://forms.googleapis.com/v1/forms/{form_id}/responses",
+            .post(format!(
+                "{}/forms/{form_id}:batchUpdate",
+                self.forms_base_url,
             ))
             .header("Authorization", &auth)
+            .json(&body)
             .send()
             .await
-            .context("Forms get_form_responses request failed")?;
-        let resp = check_status(resp, "Forms get_form_responses").await?;
-        resp.json().await.context("failed to deserialize form responses")
+            .context("Forms batchUpdate request failed")?;
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST {
+            let text = resp.text().await.unwrap_or_default();
+            if is_revision_conflict(&text) {
+                return Err(RevisionConflict.into());
+            }
+            anyhow::bail!("Forms batchUpdate: API error 400 Bad Request: {text}");
+        }
+        let resp = check_status(resp, "Forms batchUpdate").await?;
+        resp.json().await.context("failed to deserialize Forms batchUpdate response")
     }
 
     /// Create a new Google Doc with the given title, optionally in a specific folder.
@@ -403,6 +924,46 @@ impl DocsClient {
         Ok(())
     }
 
+    /// Apply `batchUpdate` requests, failing with [`RevisionConflict`] if the
+    /// document is no longer at `required_revision_id`. Returns the new
+    /// revision ID.
+    pub async fn batch_update(
+        &self,
+        doc_id: &str,
+        requests: Vec<serde_json::Value>,
+        required_revision_id: &str,
+    ) -> Result<Option<String>> {
+        let auth = self.auth_header().await?;
+        let body = json!({
+            "requests": requests,
+            "writeControl": {"requiredRevisionId": required_revision_id}
+        });
+        let resp = self
+            .http_client
+            .post(format!("{}/documents/{doc_id}:batchUpdate", self.docs_base_url))
+            .header("Authorization", &auth)
+            .json(&body)
+            .send()
+            .await
+            .context("Docs batchUpdate request failed")?;
+        if resp.status() == reqwest::StatusCode::BAD_REQUEST {
+            let text = resp.text().await.unwrap_or_default();
+            if is_revision_conflict(&text) {
+                return Err(RevisionConflict.into());
+            }
+            anyhow::bail!("Docs batchUpdate: API error 400 Bad Request: {text}");
+        }
+        let resp = check_status(resp, "Docs batchUpdate").await?;
+        let result: serde_json::Value = resp
+            .json()
+            .await
+            .context("failed to deserialize batchUpdate response")?;
+        Ok(result
+            .pointer("/writeControl/requiredRevisionId")
+            .and_then(|v| v.as_str())
+            .map(String::from))
+    }
+
     /// Find the end-of-body index of a document (for insertions).
     fn doc_end_index(doc: &Document) -> i64 {
         if let Some(ref body) = doc.body {
@@ -473,3 +1034,137 @@ async fn check_status(resp: reqwest::Response, context: &str) -> Result<reqwest:
         Err(anyhow::anyhow!("{context}: API error {status}: {body}"))
     }
 }
+
+/// Whether a 400 body is Google's stale `requiredRevisionId` rejection. The
+/// Docs and Forms APIs report it with status `FAILED_PRECONDITION`; malformed
+/// requests (including a malformed revision ID) are `INVALID_ARGUMENT`.
+fn is_revision_conflict(body: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.pointer("/error/status").and_then(|s| s.as_str()).map(|s| s == "FAILED_PRECONDITION"))
+        .unwrap_or(false)
+}
+
+/// Values API URL for `range`, with the range percent-encoded as one path
+/// segment (sheet names may contain spaces, `#` or `/`).
+fn sheets_values_url(sheets_base_url: &str, spreadsheet_id: &str, range: &str) -> Result<reqwest::Url> {
+    let mut url = reqwest::Url::parse(&format!(
+        "{sheets_base_url}/spreadsheets/{spreadsheet_id}/values"
+    ))
+    .context("invalid spreadsheet id")?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("invalid Sheets URL"))?
+        .push(range);
+    Ok(url)
+}
+
+/// Read a response body, failing with [`FileTooLarge`] past `max_bytes`.
+async fn read_limited(mut resp: reqwest::Response, max_bytes: u64) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await.context("failed to read file content")? {
+        if (body.len() + chunk.len()) as u64 > max_bytes {
+            return Err(FileTooLarge { limit: max_bytes }.into());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::MockApi;
+
+    fn blocked() -> Vec<String> {
+        vec!["blocked".to_string()]
+    }
+
+    #[tokio::test]
+    async fn blocked_folder_is_found_through_any_parent() {
+        let mock = MockApi::start().await;
+        mock.parents("doc", &["open", "shared"]);
+        mock.parents("open", &["root"]);
+        mock.parents("root", &[]);
+        mock.parents("shared", &["blocked"]);
+        let docs = mock.client();
+
+        assert!(docs.is_in_blocked_folder("doc", &blocked()).await.unwrap());
+        mock.parents("shared", &["root"]);
+        let docs = mock.client();
+        assert!(!docs.is_in_blocked_folder("doc", &blocked()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn unreadable_branch_is_an_error_unless_another_path_is_blocked() {
+        let mock = MockApi::start().await;
+        mock.parents("doc", &["gone", "open"]);
+        mock.parents("open", &[]);
+        let docs = mock.client();
+        assert!(docs.is_in_blocked_folder("doc", &blocked()).await.is_err());
+
+        mock.parents("open", &["blocked"]);
+        let docs = mock.client();
+        assert!(docs.is_in_blocked_folder("doc", &blocked()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn ancestry_covers_every_parent_and_looks_each_folder_up_once() {
+        let mock = MockApi::start().await;
+        mock.parents("doc", &["a", "b"]);
+        mock.parents("a", &["top"]);
+        mock.parents("b", &["top"]);
+        mock.parents("top", &[]);
+        let docs = mock.client();
+
+        let chain = docs.ancestry("doc").await.unwrap();
+        assert_eq!(chain, vec!["doc", "a", "b", "top"]);
+        assert_eq!(mock.count("GET /drive/v3/files/top"), 1);
+
+        // Cached links are reused by later checks.
+        docs.ancestry("a").await.unwrap();
+        assert_eq!(mock.count("GET /drive/v3/files/a"), 1);
+    }
+
+    #[tokio::test]
+    async fn check_blocked_keeps_order_and_uses_listed_parents() {
+        let mock = MockApi::start().await;
+        mock.parents("root", &[]);
+        let docs = mock.client();
+        let file = |id: &str, parent: &str| -> crate::docs::types::DriveFile {
+            serde_json::from_value(serde_json::json!({
+                "id": id, "name": id, "mimeType": "text/plain", "parents": [parent]
+            }))
+            .unwrap()
+        };
+        let files = vec![file("a", "blocked"), file("b", "root"), file("c", "missing")];
+
+        let results = docs.check_blocked(&files, &blocked()).await;
+        assert!(results[0].as_ref().unwrap());
+        assert!(!results[1].as_ref().unwrap());
+        assert!(results[2].is_err());
+        // Parents came with the listing; only folders were looked up.
+        assert_eq!(mock.count("GET /drive/v3/files/a"), 0);
+        assert_eq!(mock.count("GET /drive/v3/files/b"), 0);
+    }
+
+    #[tokio::test]
+    async fn only_failed_precondition_is_a_revision_conflict() {
+        let mock = MockApi::start().await;
+        let docs = mock.client();
+        let route = "POST /docs/v1/documents/d:batchUpdate";
+
+        mock.on(route, 400, serde_json::json!({"error": {
+            "code": 400, "status": "FAILED_PRECONDITION",
+            "message": "The document was modified after the required revision."
+        }}));
+        let err = docs.batch_update("d", vec![], "r1").await.unwrap_err();
+        assert!(err.is::<crate::docs::edit::RevisionConflict>());
+
+        mock.on(route, 400, serde_json::json!({"error": {
+            "code": 400, "status": "INVALID_ARGUMENT",
+            "message": "Invalid requests[0].insertText: index must be less than the end index of the referenced revision."
+        }}));
+        let err = docs.batch_update("d", vec![], "r1").await.unwrap_err();
+        assert!(!err.is::<crate::docs::edit::RevisionConflict>(), "{err}");
+    }
+}
+
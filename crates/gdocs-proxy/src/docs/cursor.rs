@@ -0,0 +1,114 @@
+//! Persisted Drive changes page token for `GET /changes`.
+//!
+//! The token marks the first change not yet handed to the daemon. It is
+//! stored as a small JSON file so a proxy restart resumes where it left off
+//! instead of re-delivering (or silently skipping) changes.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorFile {
+    page_token: String,
+}
+
+pub struct ChangesCursor {
+    path: PathBuf,
+    current: Mutex<Option<String>>,
+}
+
+impl ChangesCursor {
+    /// Load the cursor from `path`. A missing file yields an empty cursor.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let current = match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let file: CursorFile = serde_json::from_str(&text)
+                    .with_context(|| format!("failed to parse changes cursor: {}", path.display()))?;
+                Some(file.page_token)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to read changes cursor: {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            current: Mutex::new(current),
+        })
+    }
+
+    pub async fn get(&self) -> Option<String> {
+        self.current.lock().await.clone()
+    }
+
+    /// Move the cursor to `page_token`.
+    ///
+    /// Drive page tokens are numeric in practice; when both tokens are, the
+    /// cursor never moves backwards (a subscriber replaying from an older
+    /// named cursor must not rewind the proxy's own position).
+    pub async fn advance(&self, page_token: &str) -> Result<()> {
+        let mut current = self.current.lock().await;
+        let behind = |c: &String| match (c.parse::<u64>(), page_token.parse::<u64>()) {
+            (Ok(c), Ok(new)) => c >= new,
+            _ => c == page_token,
+        };
+        if current.as_ref().is_some_and(behind) {
+            return Ok(());
+        }
+        write_atomic(&self.path, page_token)?;
+        *current = Some(page_token.to_string());
+        Ok(())
+    }
+
+    /// Replace the cursor unconditionally (used when Drive rejects a token).
+    pub async fn reset(&self, page_token: &str) -> Result<()> {
+        let mut current = self.current.lock().await;
+        write_atomic(&self.path, page_token)?;
+        *current = Some(page_token.to_string());
+        Ok(())
+    }
+}
+
+fn write_atomic(path: &Path, page_token: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create cursor dir {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let body = serde_json::to_string(&CursorFile {
+        page_token: page_token.to_string(),
+    })?;
+    std::fs::write(&tmp, body)
+        .with_context(|| format!("failed to write changes cursor: {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("failed to replace changes cursor: {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cursor_persists_and_only_advances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursor.json");
+
+        let cursor = ChangesCursor::load(&path).unwrap();
+        assert_eq!(cursor.get().await, None);
+
+        cursor.advance("100").await.unwrap();
+        cursor.advance("50").await.unwrap();
+        assert_eq!(cursor.get().await.as_deref(), Some("100"));
+
+        let reloaded = ChangesCursor::load(&path).unwrap();
+        assert_eq!(reloaded.get().await.as_deref(), Some("100"));
+
+        reloaded.reset("7").await.unwrap();
+        assert_eq!(ChangesCursor::load(&path).unwrap().get().await.as_deref(), Some("7"));
+    }
+}
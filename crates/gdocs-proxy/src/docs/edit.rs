@@ -0,0 +1,346 @@
+//! Translate high-level edit operations into Docs API `batchUpdate` requests.
+//!
+//! Operations refer to blocks by their position in the structured `content`
+//! of the revision the agent read. Planning resolves each block to its
+//! character range in that revision, then orders the requests so that no
+//! request shifts the indices of another:
+//!
+//!   - index-based requests run from the end of the document backwards;
+//!   - `replace_text` runs last, so it also applies to newly inserted text.
+//!
+//! The caller sends the plan with `requiredRevisionId` set, so Google rejects
+//! it if the document changed after it was read.
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+
+use crate::docs::types::{ContentBlock, Document, EditOperation};
+
+/// The document changed after the edit was planned.
+#[derive(Debug)]
+pub struct RevisionConflict;
+
+impl std::fmt::Display for RevisionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("document was modified since it was read")
+    }
+}
+
+impl std::error::Error for RevisionConflict {}
+
+/// Character range of a structured block (Docs API indices, UTF-16 units).
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: i64,
+    end: i64,
+    is_table: bool,
+}
+
+/// Build the `batchUpdate` requests for `ops` against `doc`.
+///
+/// Returns an error describing the first invalid operation.
+pub fn plan(doc: &Document, ops: &[EditOperation]) -> Result<Vec<Value>> {
+    if ops.is_empty() {
+        bail!("no operations given");
+    }
+
+    let blocks = doc.blocks();
+    let mut spans = Vec::with_capacity(blocks.len());
+    for (block, element) in &blocks {
+        let (Some(start), Some(end)) = (element.start_index, element.end_index) else {
+            bail!("document is missing structural indices");
+        };
+        spans.push(Span {
+            start,
+            end,
+            is_table: matches!(block, ContentBlock::Table { .. }),
+        });
+    }
+    let body_end = doc.body_end_index().unwrap_or(0);
+
+    let block_span = |n: usize, what: &str| -> Result<Span> {
+        spans.get(n).copied().ok_or_else(|| {
+            anyhow::anyhow!("{what} {n} is out of range (document has {} blocks)", spans.len())
+        })
+    };
+
+    // Deleted block ranges, validated up front so other operations can be
+    // checked against them.
+    let mut deleted: Vec<(usize, usize)> = Vec::new();
+    for op in ops {
+        if let EditOperation::DeleteRange { from_block, to_block } = *op {
+            if from_block > to_block {
+                bail!("delete_range: from_block {from_block} is after to_block {to_block}");
+            }
+            block_span(to_block, "delete_range: block")?;
+            if deleted.iter().any(|&(f, t)| from_block <= t && f <= to_block) {
+                bail!("delete_range: blocks {from_block}-{to_block} overlap another deleted range");
+            }
+            deleted.push((from_block, to_block));
+        }
+    }
+    let check_not_deleted = |n: usize, op: &str| -> Result<()> {
+        if deleted.iter().any(|&(f, t)| (f..=t).contains(&n)) {
+            bail!("{op}: block {n} is deleted by another operation");
+        }
+        Ok(())
+    };
+
+    // (anchor index, operation number, requests)
+    let mut indexed: Vec<(i64, usize, Vec<Value>)> = Vec::new();
+    let mut replacements = Vec::new();
+
+    for (seq, op) in ops.iter().enumerate() {
+        match op {
+            EditOperation::ReplaceText { find, replace, match_case } => {
+                if find.is_empty() {
+                    bail!("replace_text: find must not be empty");
+                }
+                replacements.push(json!({
+                    "replaceAllText": {
+                        "containsText": {"text": find, "matchCase": match_case},
+                        "replaceText": replace
+                    }
+                }));
+            }
+            EditOperation::InsertAfterHeading { heading, text } => {
+                let wanted = heading.trim();
+                let matches: Vec<usize> = blocks
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (block, _))| {
+                        matches!(block, ContentBlock::Heading { text, .. } if text.trim().eq_ignore_ascii_case(wanted))
+                    })
+                    .map(|(n, _)| n)
+                    .collect();
+                let n = match matches[..] {
+                    [] => bail!("insert_after_heading: no heading \"{wanted}\""),
+                    [n] => n,
+                    _ => bail!("insert_after_heading: heading \"{wanted}\" is ambiguous (blocks {matches:?})"),
+                };
+                check_not_deleted(n, "insert_after_heading")?;
+                let text = text.trim_end_matches('\n');
+                if text.is_empty() {
+                    bail!("insert_after_heading: text must not be empty");
+                }
+                // Split the heading paragraph before its trailing newline, then
+                // reset the new paragraph (which inherits the heading style).
+                let at = spans[n].end - 1;
+                indexed.push((
+                    at,
+                    seq,
+                    vec![
+                        json!({"insertText": {"text": format!("\n{text}"), "location": {"index": at}}}),
+                        json!({"updateParagraphStyle": {
+                            "range": {"startIndex": at + 1, "endIndex": at + 2 + utf16_len(text)},
+                            "paragraphStyle": {"namedStyleType": "NORMAL_TEXT"},
+                            "fields": "namedStyleType"
+                        }}),
+                    ],
+                ));
+            }
+            EditOperation::DeleteRange { from_block, to_block } => {
+                let start = spans[*from_block].start;
+                // The final newline of the body can never be deleted.
+                let end = spans[*to_block].end.min(body_end - 1);
+                indexed.push((
+                    start,
+                    seq,
+                    vec![json!({"deleteContentRange": {"range": {"startIndex": start, "endIndex": end}}})],
+                ));
+            }
+            EditOperation::SetHeading { block, level } => {
+                let span = block_span(*block, "set_heading: block")?;
+                check_not_deleted(*block, "set_heading")?;
+                if span.is_table {
+                    bail!("set_heading: block {block} is a table");
+                }
+                let style = match level {
+                    0 => "NORMAL_TEXT".to_string(),
+                    1..=6 => format!("HEADING_{level}"),
+                    _ => bail!("set_heading: level must be 0-6, got {level}"),
+                };
+                // Stop before the trailing newline so text inserted after this
+                // block by another operation is not restyled.
+                indexed.push((
+                    span.start,
+                    seq,
+                    vec![json!({"updateParagraphStyle": {
+                        "range": {"startIndex": span.start, "endIndex": span.end - 1},
+                        "paragraphStyle": {"namedStyleType": style},
+                        "fields": "namedStyleType"
+                    }})],
+                ));
+            }
+            EditOperation::InsertTable { after_block, rows } => {
+                let span = block_span(*after_block, "insert_table: after_block")?;
+                check_not_deleted(*after_block, "insert_table")?;
+                let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+                if columns == 0 {
+                    bail!("insert_table: rows must contain at least one cell");
+                }
+                // Tables are always followed by a paragraph, so insert at its
+                // start; after a paragraph, insert before its newline.
+                let at = if span.is_table { span.end } else { span.end - 1 };
+                let mut requests = vec![json!({"insertTable": {
+                    "rows": rows.len(),
+                    "columns": columns,
+                    "location": {"index": at}
+                }})];
+                requests.extend(table_cell_inserts(at, rows, columns));
+                indexed.push((at, seq, requests));
+            }
+        }
+    }
+
+    indexed.sort_by_key(|&(anchor, seq, _)| std::cmp::Reverse((anchor, seq)));
+    let mut requests: Vec<Value> = indexed.into_iter().flat_map(|(_, _, r)| r).collect();
+    requests.extend(replacements);
+    Ok(requests)
+}
+
+/// `insertText` requests filling a table just inserted at `at`, last cell
+/// first so earlier cell indices stay valid.
+///
+/// An empty table inserted at `at` starts at `at + 1` (after the newline the
+/// API adds). Each row takes one index plus two per cell (cell start and the
+/// cell's empty paragraph).
+fn table_cell_inserts(at: i64, rows: &[Vec<String>], columns: usize) -> Vec<Value> {
+    let row_len = 1 + 2 * columns as i64;
+    let mut requests = Vec::new();
+    for (r, row) in rows.iter().enumerate().rev() {
+        for (c, cell) in row.iter().enumerate().rev() {
+            if cell.is_empty() {
+                continue;
+            }
+            let index = at + 4 + r as i64 * row_len + 2 * c as i64;
+            requests.push(json!({"insertText": {"text": cell, "location": {"index": index}}}));
+        }
+    }
+    requests
+}
+
+fn utf16_len(text: &str) -> i64 {
+    text.encode_utf16().count() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Blocks: 0 heading "Notes" [1,7), 1 paragraph "hello" [7,13), 2 paragraph "bye" [13,17).
+    fn doc() -> Document {
+        let para = |start: i64, text: &str, style: &str| {
+            json!({
+                "startIndex": start,
+                "endIndex": start + text.len() as i64 + 1,
+                "paragraph": {
+                    "elements": [{"textRun": {"content": format!("{text}\n")}}],
+                    "paragraphStyle": {"namedStyleType": style}
+                }
+            })
+        };
+        serde_json::from_value(json!({
+            "documentId": "d",
+            "title": "t",
+            "revisionId": "r1",
+            "body": {"content": [
+                {"startIndex": 0, "endIndex": 1, "sectionBreak": {}},
+                para(1, "Notes", "HEADING_1"),
+                para(7, "hello", "NORMAL_TEXT"),
+                para(13, "bye", "NORMAL_TEXT"),
+            ]}
+        }))
+        .unwrap()
+    }
+
+    fn ops(value: Value) -> Vec<EditOperation> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_index_edits_run_back_to_front_and_replacements_last() {
+        let requests = plan(
+            &doc(),
+            &ops(json!([
+                {"op": "replace_text", "find": "a", "replace": "b"},
+                {"op": "insert_after_heading", "heading": "notes", "text": "intro"},
+                {"op": "delete_range", "from_block": 2, "to_block": 2},
+            ])),
+        )
+        .unwrap();
+
+        // Deleting the last block keeps the body's final newline.
+        assert_eq!(requests[0]["deleteContentRange"]["range"], json!({"startIndex": 13, "endIndex": 16}));
+        assert_eq!(requests[1]["insertText"]["location"]["index"], 6);
+        assert_eq!(requests[1]["insertText"]["text"], "\nintro");
+        assert_eq!(requests[2]["updateParagraphStyle"]["range"], json!({"startIndex": 7, "endIndex": 13}));
+        assert!(requests[3].get("replaceAllText").is_some());
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_and_conflicting_blocks() {
+        let d = doc();
+        assert!(plan(&d, &ops(json!([{"op": "set_heading", "block": 3, "level": 1}]))).is_err());
+        assert!(plan(&d, &ops(json!([{"op": "set_heading", "block": 1, "level": 7}]))).is_err());
+        assert!(plan(
+            &d,
+            &ops(json!([
+                {"op": "delete_range", "from_block": 0, "to_block": 1},
+                {"op": "delete_range", "from_block": 1, "to_block": 2},
+            ]))
+        )
+        .is_err());
+        assert!(plan(
+            &d,
+            &ops(json!([
+                {"op": "delete_range", "from_block": 1, "to_block": 2},
+                {"op": "insert_table", "after_block": 1, "rows": [["a"]]},
+            ]))
+        )
+        .is_err());
+        assert!(plan(&d, &ops(json!([{"op": "insert_after_heading", "heading": "Missing", "text": "x"}]))).is_err());
+    }
+
+    #[test]
+    fn test_insert_after_ambiguous_heading_is_rejected() {
+        let mut d = doc();
+        let second = serde_json::from_value(json!({
+            "startIndex": 17,
+            "endIndex": 23,
+            "paragraph": {
+                "elements": [{"textRun": {"content": "notes\n"}}],
+                "paragraphStyle": {"namedStyleType": "HEADING_2"}
+            }
+        }))
+        .unwrap();
+        d.body.as_mut().unwrap().content.as_mut().unwrap().push(second);
+
+        let err = plan(&d, &ops(json!([{"op": "insert_after_heading", "heading": "NOTES", "text": "x"}])))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("ambiguous (blocks [0, 3])"), "{err}");
+    }
+
+    #[test]
+    fn test_insert_table_fills_cells_last_first() {
+        let requests = plan(
+            &doc(),
+            &ops(json!([{"op": "insert_table", "after_block": 1, "rows": [["a", "b"], ["c"]]}])),
+        )
+        .unwrap();
+
+        assert_eq!(requests[0]["insertTable"], json!({"rows": 2, "columns": 2, "location": {"index": 12}}));
+        let cells: Vec<(i64, &str)> = requests[1..]
+            .iter()
+            .map(|r| {
+                (
+                    r["insertText"]["location"]["index"].as_i64().unwrap(),
+                    r["insertText"]["text"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        // Table starts at 13; first cell paragraph at 16, rows are 5 long.
+        assert_eq!(cells, vec![(21, "c"), (18, "b"), (16, "a")]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Reading Drive files that are not native Google Docs/Sheets/Slides/Forms.
+//!
+//! Only an allowlist of types is ever read:
+//!
+//!   - plain text and markdown are downloaded as-is;
+//!   - CSV/TSV are downloaded and parsed into rows;
+//!   - PDFs are downloaded and their text layer extracted (scans yield nothing);
+//!   - Word, Excel and PowerPoint files (and their OpenDocument equivalents)
+//!     are converted by Drive into a temporary Google file, exported as text
+//!     or CSV, and the temporary copy is deleted.
+//!
+//! Everything else is rejected without being downloaded.
+
+pub use carapace_google::text::{extract_pdf_text, truncate_chars};
+
+/// How a file is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Text,
+    /// Delimited text with the given separator.
+    Delimited(char),
+    Pdf,
+    OfficeDocument,
+    OfficeSpreadsheet,
+    OfficePresentation,
+}
+
+/// The download exceeded the configured size limit.
+#[derive(Debug)]
+pub struct FileTooLarge {
+    pub limit: u64,
+}
+
+impl std::fmt::Display for FileTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "file exceeds the {} byte limit", self.limit)
+    }
+}
+
+impl std::error::Error for FileTooLarge {}
+
+const TEXT_MIME_TYPES: &[&str] = &["text/plain", "text/markdown", "text/x-markdown", "application/json"];
+
+const DOCUMENT_MIME_TYPES: &[&str] = &[
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/msword",
+    "application/vnd.oasis.opendocument.text",
+    "application/rtf",
+    "text/rtf",
+];
+
+const SPREADSHEET_MIME_TYPES: &[&str] = &[
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.ms-excel",
+    "application/vnd.oasis.opendocument.spreadsheet",
+];
+
+const PRESENTATION_MIME_TYPES: &[&str] = &[
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.oasis.opendocument.presentation",
+];
+
+impl FileKind {
+    /// Decide whether a file can be read, from its MIME type and name.
+    ///
+    /// Files uploaded as `application/octet-stream` are classified by
+    /// extension; a specific MIME type is never overridden by the name.
+    pub fn classify(mime_type: &str, name: &str) -> Option<FileKind> {
+        let mime = mime_type.to_ascii_lowercase();
+        let by_mime = match mime.as_str() {
+            "application/pdf" => Some(FileKind::Pdf),
+            "text/csv" | "application/csv" => Some(FileKind::Delimited(',')),
+            "text/tab-separated-values" => Some(FileKind::Delimited('\t')),
+            m if TEXT_MIME_TYPES.contains(&m) => Some(FileKind::Text),
+            m if DOCUMENT_MIME_TYPES.contains(&m) => Some(FileKind::OfficeDocument),
+            m if SPREADSHEET_MIME_TYPES.contains(&m) => Some(FileKind::OfficeSpreadsheet),
+            m if PRESENTATION_MIME_TYPES.contains(&m) => Some(FileKind::OfficePresentation),
+            _ => None,
+        };
+        if by_mime.is_some() || !(mime.is_empty() || mime == "application/octet-stream") {
+            return by_mime;
+        }
+
+        let ext = name
+            .rsplit_once('.')
+            .map(|(_, e)| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "pdf" => Some(FileKind::Pdf),
+            "csv" => Some(FileKind::Delimited(',')),
+            "tsv" => Some(FileKind::Delimited('\t')),
+            "txt" | "md" | "json" => Some(FileKind::Text),
+            "docx" | "doc" | "odt" | "rtf" => Some(FileKind::OfficeDocument),
+            "xlsx" | "xls" | "ods" => Some(FileKind::OfficeSpreadsheet),
+            "pptx" | "ppt" | "odp" => Some(FileKind::OfficePresentation),
+            _ => None,
+        }
+    }
+
+    /// For Office files: the Google type to convert into and the export format.
+    pub fn conversion(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            FileKind::OfficeDocument => Some(("application/vnd.google-apps.document", "text/plain")),
+            // Drive exports only the first sheet as CSV.
+            FileKind::OfficeSpreadsheet => Some(("application/vnd.google-apps.spreadsheet", "text/csv")),
+            FileKind::OfficePresentation => Some(("application/vnd.google-apps.presentation", "text/plain")),
+            _ => None,
+        }
+    }
+
+    /// Separator for files returned as rows.
+    pub fn delimiter(self) -> Option<char> {
+        match self {
+            FileKind::Delimited(d) => Some(d),
+            FileKind::OfficeSpreadsheet => Some(','),
+            _ => None,
+        }
+    }
+
+    /// Short name reported as `format` in the response.
+    pub fn name(self) -> &'static str {
+        match self {
+            FileKind::Text => "text",
+            FileKind::Delimited(_) => "csv",
+            FileKind::Pdf => "pdf",
+            FileKind::OfficeDocument => "document",
+            FileKind::OfficeSpreadsheet => "spreadsheet",
+            FileKind::OfficePresentation => "presentation",
+        }
+    }
+}
+
+/// Parse delimited text into rows. Handles quoted fields with embedded
+/// separators, newlines and doubled quotes.
+pub fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            c if in_quotes => field.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Keep leading rows while their total cell text fits in `max_chars`.
+pub fn limit_rows(mut rows: Vec<Vec<String>>, max_chars: usize) -> (Vec<Vec<String>>, bool) {
+    let mut used = 0;
+    for (i, row) in rows.iter().enumerate() {
+        used += row.iter().map(|c| c.chars().count()).sum::<usize>();
+        if used > max_chars {
+            rows.truncate(i);
+            return (rows, true);
+        }
+    }
+    (rows, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_mime_type_and_extension() {
+        assert_eq!(FileKind::classify("application/pdf", "x"), Some(FileKind::Pdf));
+        assert_eq!(
+            FileKind::classify(
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+                "memo.docx"
+            ),
+            Some(FileKind::OfficeDocument)
+        );
+        assert_eq!(
+            FileKind::classify("application/octet-stream", "Budget.XLSX"),
+            Some(FileKind::OfficeSpreadsheet)
+        );
+        assert_eq!(FileKind::classify("application/octet-stream", "run.exe"), None);
+        // A specific non-readable MIME type is not overridden by the name.
+        assert_eq!(FileKind::classify("image/png", "notes.txt"), None);
+    }
+
+    #[test]
+    fn test_parse_delimited_handles_quotes() {
+        let rows = parse_delimited("name,note\r\n\"Smith, J\",\"said \"\"hi\"\"\nbye\"\n", ',');
+        assert_eq!(
+            rows,
+            vec![
+                vec!["name".to_string(), "note".to_string()],
+                vec!["Smith, J".to_string(), "said \"hi\"\nbye".to_string()],
+            ]
+        );
+        assert_eq!(parse_delimited("a\tb", '\t'), vec![vec!["a".to_string(), "b".to_string()]]);
+    }
+
+    #[test]
+    fn test_limit_rows_stops_at_budget() {
+        let rows = vec![vec!["abc".to_string()], vec!["de".to_string()], vec!["f".to_string()]];
+        assert_eq!(limit_rows(rows.clone(), 10), (rows.clone(), false));
+        assert_eq!(limit_rows(rows.clone(), 5), (rows[..2].to_vec(), true));
+    }
+}
@@ -0,0 +1,191 @@
+//! Translate question operations into Forms API `batchUpdate` requests.
+//!
+//! Forms requests address items by index and run in order, each seeing the
+//! effect of the previous one. Planning replays the operations against the
+//! form's current item list so every request gets the index that is correct
+//! at the moment it runs. The caller sends the plan with the form's
+//! `revisionId` as `requiredRevisionId`, so a concurrent change makes the
+//! whole batch fail instead of moving or deleting the wrong item.
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+
+use crate::docs::types::{FormOperation, NewQuestion, QuestionKind};
+
+/// Build the `batchUpdate` requests for `ops` against `form` (a Forms API
+/// `Form` resource).
+pub fn plan(form: &Value, ops: &[FormOperation]) -> Result<Vec<Value>> {
+    if ops.is_empty() {
+        bail!("no operations given");
+    }
+
+    // Question ID of each item in order; non-question items (section
+    // headers, images) and newly added questions have none.
+    let mut items: Vec<Option<String>> = form
+        .get("items")
+        .and_then(|i| i.as_array())
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            item.pointer("/questionItem/question/questionId")
+                .and_then(|q| q.as_str())
+                .map(str::to_string)
+        })
+        .collect();
+
+    let position = |items: &[Option<String>], op: &str, question_id: &str| -> Result<usize> {
+        items
+            .iter()
+            .position(|q| q.as_deref() == Some(question_id))
+            .ok_or_else(|| anyhow::anyhow!("{op}: no question with question_id \"{question_id}\""))
+    };
+
+    let mut requests = Vec::with_capacity(ops.len());
+    for op in ops {
+        match op {
+            FormOperation::AddQuestion { question, index } => {
+                let index = index.unwrap_or(items.len());
+                if index > items.len() {
+                    bail!("add_question: index {index} is out of range (form has {} items)", items.len());
+                }
+                requests.push(json!({"createItem": {
+                    "item": question_item(question)?,
+                    "location": {"index": index}
+                }}));
+                items.insert(index, None);
+            }
+            FormOperation::MoveQuestion { question_id, index } => {
+                let from = position(&items, "move_question", question_id)?;
+                if *index >= items.len() {
+                    bail!("move_question: index {index} is out of range (form has {} items)", items.len());
+                }
+                requests.push(json!({"moveItem": {
+                    "originalLocation": {"index": from},
+                    "newLocation": {"index": index}
+                }}));
+                let moved = items.remove(from);
+                items.insert(*index, moved);
+            }
+            FormOperation::DeleteQuestion { question_id } => {
+                let at = position(&items, "delete_question", question_id)?;
+                requests.push(json!({"deleteItem": {"location": {"index": at}}}));
+                items.remove(at);
+            }
+        }
+    }
+    Ok(requests)
+}
+
+/// Forms API `Item` for a new question.
+fn question_item(q: &NewQuestion) -> Result<Value> {
+    if q.title.trim().is_empty() {
+        bail!("add_question: title must not be empty");
+    }
+    let choice = |kind: &str, options: &[String]| -> Result<Value> {
+        if options.iter().all(|o| o.trim().is_empty()) {
+            bail!("add_question: \"{}\" needs at least one option", q.title);
+        }
+        let options: Vec<Value> = options
+            .iter()
+            .filter(|o| !o.trim().is_empty())
+            .map(|o| json!({"value": o}))
+            .collect();
+        Ok(json!({"choiceQuestion": {"type": kind, "options": options}}))
+    };
+
+    let mut question = match &q.kind {
+        QuestionKind::ShortAnswer => json!({"textQuestion": {"paragraph": false}}),
+        QuestionKind::Paragraph => json!({"textQuestion": {"paragraph": true}}),
+        QuestionKind::MultipleChoice { options } => choice("RADIO", options)?,
+        QuestionKind::Checkbox { options } => choice("CHECKBOX", options)?,
+        QuestionKind::Dropdown { options } => choice("DROP_DOWN", options)?,
+        QuestionKind::Scale { low, high, low_label, high_label } => {
+            if *low > 1 || !(2..=10).contains(high) {
+                bail!("add_question: scale must run from 0 or 1 to 2-10, got {low}-{high}");
+            }
+            let mut scale = json!({"low": low, "high": high});
+            if let Some(label) = low_label {
+                scale["lowLabel"] = json!(label);
+            }
+            if let Some(label) = high_label {
+                scale["highLabel"] = json!(label);
+            }
+            json!({"scaleQuestion": scale})
+        }
+        QuestionKind::Date { include_year, include_time } => {
+            json!({"dateQuestion": {"includeYear": include_year, "includeTime": include_time}})
+        }
+    };
+    question["required"] = json!(q.required);
+
+    let mut item = json!({"title": q.title, "questionItem": {"question": question}});
+    if let Some(ref description) = q.description {
+        item["description"] = json!(description);
+    }
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form() -> Value {
+        let question = |id: &str| json!({"questionItem": {"question": {"questionId": id}}});
+        json!({"items": [question("q1"), {"pageBreakItem": {}}, question("q2")]})
+    }
+
+    fn ops(value: Value) -> Vec<FormOperation> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_indices_follow_earlier_operations() {
+        let requests = plan(
+            &form(),
+            &ops(json!([
+                {"op": "add_question", "index": 0, "question": {"title": "Name", "type": "short_answer", "required": true}},
+                {"op": "move_question", "question_id": "q2", "index": 1},
+                {"op": "delete_question", "question_id": "q1"},
+                {"op": "add_question", "question": {"title": "Rating", "type": "scale", "low": 1, "high": 5}},
+            ])),
+        )
+        .unwrap();
+
+        assert_eq!(requests[0]["createItem"]["location"]["index"], 0);
+        assert_eq!(
+            requests[0]["createItem"]["item"]["questionItem"]["question"],
+            json!({"textQuestion": {"paragraph": false}, "required": true})
+        );
+        // q2 was at 2, shifted to 3 by the insert.
+        assert_eq!(requests[1]["moveItem"], json!({"originalLocation": {"index": 3}, "newLocation": {"index": 1}}));
+        // Items are now [new, q2, q1, page break].
+        assert_eq!(requests[2]["deleteItem"]["location"]["index"], 2);
+        assert_eq!(requests[3]["createItem"]["location"]["index"], 3);
+    }
+
+    #[test]
+    fn test_choice_questions_and_validation() {
+        let requests = plan(
+            &form(),
+            &ops(json!([{"op": "add_question", "question": {
+                "title": "Diet", "type": "dropdown", "options": ["None", "", "Vegan"]
+            }}])),
+        )
+        .unwrap();
+        assert_eq!(
+            requests[0]["createItem"]["item"]["questionItem"]["question"]["choiceQuestion"],
+            json!({"type": "DROP_DOWN", "options": [{"value": "None"}, {"value": "Vegan"}]})
+        );
+
+        let invalid = [
+            json!([{"op": "add_question", "question": {"title": "x", "type": "checkbox", "options": []}}]),
+            json!([{"op": "add_question", "question": {"title": "x", "type": "scale", "low": 1, "high": 11}}]),
+            json!([{"op": "add_question", "index": 4, "question": {"title": "x", "type": "paragraph"}}]),
+            json!([{"op": "delete_question", "question_id": "missing"}]),
+            json!([{"op": "move_question", "question_id": "q1", "index": 3}]),
+        ];
+        for ops_json in invalid {
+            assert!(plan(&form(), &ops(ops_json.clone())).is_err(), "{ops_json}");
+        }
+    }
+}
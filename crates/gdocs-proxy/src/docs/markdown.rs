@@ -0,0 +1,248 @@
+//! Render a Google Doc as Markdown for `GET /doc/{id}?format=markdown`.
+//!
+//! Headings, nested bulleted/numbered lists, bold/italic/strikethrough, links,
+//! tables, images (as `![alt](object_id)`) and footnotes are kept. Underline
+//! has no Markdown equivalent and is dropped. Headers, footers, suggestions
+//! and comments are returned alongside the Markdown, not inside it.
+
+use crate::docs::types::{heading_level, Document, Paragraph, StructuralElement, TextStyle};
+
+pub fn render(doc: &Document, strip_links: bool) -> String {
+    let mut out = String::new();
+    let mut previous_was_list = false;
+
+    for element in doc.body_elements() {
+        let (block, is_list) = match render_element(doc, element, strip_links) {
+            Some(rendered) => rendered,
+            None => continue,
+        };
+        if !out.is_empty() {
+            out.push_str(if previous_was_list && is_list { "\n" } else { "\n\n" });
+        }
+        out.push_str(&block);
+        previous_was_list = is_list;
+    }
+
+    let footnotes = doc.footnote_texts();
+    if !footnotes.is_empty() {
+        out.push_str("\n\n");
+        let lines: Vec<String> = footnotes
+            .iter()
+            .map(|f| format!("[^{}]: {}", f.number, f.text))
+            .collect();
+        out.push_str(&lines.join("\n"));
+    }
+
+    out.push('\n');
+    out
+}
+
+/// Render one body element; returns the Markdown and whether it is a list item.
+fn render_element(doc: &Document, element: &StructuralElement, strip_links: bool) -> Option<(String, bool)> {
+    if let Some(ref paragraph) = element.paragraph {
+        let text = render_inline(doc, paragraph, strip_links);
+        if text.trim().is_empty() {
+            return None;
+        }
+        if let Some(level) = heading_level(paragraph) {
+            return Some((format!("{} {}", "#".repeat(level as usize), text.trim()), false));
+        }
+        if let Some(ref bullet) = paragraph.bullet {
+            let indent = "  ".repeat(bullet.nesting_level.unwrap_or(0) as usize);
+            let marker = if doc.is_ordered_list(bullet) { "1." } else { "-" };
+            return Some((format!("{indent}{marker} {}", text.trim()), true));
+        }
+        return Some((text, false));
+    }
+    if let Some(ref table) = element.table {
+        let rows: Vec<Vec<String>> = table
+            .table_rows
+            .iter()
+            .flatten()
+            .map(|row| {
+                row.table_cells
+                    .iter()
+                    .flatten()
+                    .map(|cell| {
+                        let paragraphs: Vec<String> = cell
+                            .content
+                            .iter()
+                            .flatten()
+                            .filter_map(|e| e.paragraph.as_ref())
+                            .map(|p| render_inline(doc, p, strip_links))
+                            .filter(|t| !t.is_empty())
+                            .collect();
+                        paragraphs.join("<br>").replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .collect();
+        return render_table(&rows).map(|t| (t, false));
+    }
+    None
+}
+
+fn render_table(rows: &[Vec<String>]) -> Option<String> {
+    let columns = rows.iter().map(Vec::len).max().filter(|&c| c > 0)?;
+    let line = |cells: &[String]| {
+        let mut padded: Vec<&str> = cells.iter().map(String::as_str).collect();
+        padded.resize(columns, "");
+        format!("| {} |", padded.join(" | "))
+    };
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(|r| line(r)));
+    Some(lines.join("\n"))
+}
+
+/// Inline Markdown for a paragraph's elements, without the trailing newline.
+fn render_inline(doc: &Document, paragraph: &Paragraph, strip_links: bool) -> String {
+    let mut out = String::new();
+    for elem in paragraph.elements.iter().flatten() {
+        if let Some(ref run) = elem.text_run {
+            let content = run.content.as_deref().unwrap_or("").trim_end_matches('\n');
+            // Soft line breaks are vertical tabs in the Docs API.
+            let content = content.replace('\u{000b}', "  \n");
+            out.push_str(&match run.text_style {
+                Some(ref style) => styled(&content, style, strip_links),
+                None => content,
+            });
+        }
+        if let Some(id) = elem
+            .inline_object_element
+            .as_ref()
+            .and_then(|o| o.inline_object_id.as_deref())
+        {
+            let image = doc.image_ref(id);
+            out.push_str(&format!(
+                "![{}]({})",
+                image.alt_text.as_deref().unwrap_or(""),
+                image.object_id
+            ));
+        }
+        if let Some(number) = elem
+            .footnote_reference
+            .as_ref()
+            .and_then(|f| f.footnote_number.as_deref())
+        {
+            out.push_str(&format!("[^{number}]"));
+        }
+    }
+    out
+}
+
+fn styled(text: &str, style: &TextStyle, strip_links: bool) -> String {
+    let mut result = text.to_string();
+    if style.strikethrough == Some(true) {
+        result = wrap(&result, "~~");
+    }
+    if style.italic == Some(true) {
+        result = wrap(&result, "_");
+    }
+    if style.bold == Some(true) {
+        result = wrap(&result, "**");
+    }
+    if !strip_links {
+        if let Some(url) = style.link.as_ref().and_then(|l| l.url.as_deref()) {
+            result = wrap_with(&result, "[", &format!("]({url})"));
+        }
+    }
+    result
+}
+
+fn wrap(text: &str, marker: &str) -> String {
+    wrap_with(text, marker, marker)
+}
+
+/// Wrap the non-whitespace core of `text`, keeping surrounding whitespace
+/// outside the markers so the Markdown stays valid.
+fn wrap_with(text: &str, open: &str, close: &str) -> String {
+    let core = text.trim();
+    if core.is_empty() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    format!("{leading}{open}{core}{close}{trailing}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(text: &str, style: serde_json::Value) -> serde_json::Value {
+        json!({"textRun": {"content": text, "textStyle": style}})
+    }
+
+    #[test]
+    fn test_renders_headings_lists_styles_and_tables() {
+        let doc: Document = serde_json::from_value(json!({
+            "documentId": "d",
+            "title": "t",
+            "lists": {
+                "num": {"listProperties": {"nestingLevels": [{"glyphType": "DECIMAL"}]}},
+                "dot": {"listProperties": {"nestingLevels": [{"glyphSymbol": "●"}, {"glyphSymbol": "○"}]}}
+            },
+            "inlineObjects": {
+                "img1": {"inlineObjectProperties": {"embeddedObject": {"description": "Logo"}}}
+            },
+            "body": {"content": [
+                {"paragraph": {"elements": [run("Plan\n", json!({}))],
+                               "paragraphStyle": {"namedStyleType": "HEADING_2"}}},
+                {"paragraph": {"elements": [
+                    run("Read ", json!({})),
+                    run("this ", json!({"bold": true})),
+                    run("doc", json!({"link": {"url": "https://example.com"}})),
+                    run("\n", json!({}))
+                ]}},
+                {"paragraph": {"elements": [run("first\n", json!({}))],
+                               "bullet": {"listId": "num"}}},
+                {"paragraph": {"elements": [run("nested\n", json!({}))],
+                               "bullet": {"listId": "dot", "nestingLevel": 1}}},
+                {"paragraph": {"elements": [{"inlineObjectElement": {"inlineObjectId": "img1"}}, run("\n", json!({}))]}},
+                {"table": {"rows": 2, "columns": 2, "tableRows": [
+                    {"tableCells": [
+                        {"content": [{"paragraph": {"elements": [run("A\n", json!({}))]}}]},
+                        {"content": [{"paragraph": {"elements": [run("B\n", json!({}))]}}]}
+                    ]},
+                    {"tableCells": [
+                        {"content": [{"paragraph": {"elements": [run("1|2\n", json!({}))]}}]}
+                    ]}
+                ]}}
+            ]}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            render(&doc, false),
+            "## Plan\n\n\
+             Read **this** [doc](https://example.com)\n\n\
+             1. first\n  \
+             - nested\n\n\
+             ![Logo](img1)\n\n\
+             | A | B |\n| --- | --- |\n| 1\\|2 |  |\n"
+        );
+        assert!(render(&doc, true).contains("Read **this** doc\n"));
+    }
+
+    #[test]
+    fn test_footnotes_are_listed_at_the_end() {
+        let doc: Document = serde_json::from_value(json!({
+            "documentId": "d",
+            "title": "t",
+            "footnotes": {
+                "fn1": {"content": [{"paragraph": {"elements": [run("Source.\n", json!({}))]}}]}
+            },
+            "body": {"content": [
+                {"paragraph": {"elements": [
+                    run("Claim", json!({})),
+                    {"footnoteReference": {"footnoteId": "fn1", "footnoteNumber": "1"}},
+                    run("\n", json!({}))
+                ]}}
+            ]}
+        }))
+        .unwrap();
+
+        assert_eq!(render(&doc, false), "Claim[^1]\n\n[^1]: Source.\n");
+    }
+}
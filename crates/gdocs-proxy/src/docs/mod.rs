@@ -1,2 +1,10 @@
+pub mod changes;
 pub mod client;
+pub mod cursor;
+pub mod edit;
+pub mod files;
+pub mod forms;
+pub mod markdown;
+pub mod parents;
+pub mod sheets;
 pub mod types;
@@ -0,0 +1,70 @@
+//! TTL cache of Drive parent links used for folder ancestry checks.
+//!
+//! Blocked-folder and write-scope checks walk a file's parents up to the
+//! Drive root. Folders near the root are shared by almost every file, so
+//! caching each item's parent list keeps a search page from re-fetching the
+//! same folders for every result.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Mutex;
+
+pub struct ParentCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Vec<String>, Instant)>>,
+}
+
+impl ParentCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cached parents of `id`, if present and not expired.
+    pub async fn get(&self, id: &str) -> Option<Vec<String>> {
+        let mut entries = self.entries.lock().await;
+        match entries.get(id) {
+            Some((parents, at)) if at.elapsed() < self.ttl => Some(parents.clone()),
+            Some(_) => {
+                entries.remove(id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub async fn insert(&self, id: &str, parents: Vec<String>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.entries
+            .lock()
+            .await
+            .insert(id.to_string(), (parents, Instant::now()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_entries_expire_after_ttl() {
+        let cache = ParentCache::new(Duration::from_millis(20));
+        cache.insert("doc", vec!["folder".into()]).await;
+        assert_eq!(cache.get("doc").await, Some(vec!["folder".to_string()]));
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(cache.get("doc").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_caching() {
+        let cache = ParentCache::new(Duration::ZERO);
+        cache.insert("doc", vec!["folder".into()]).await;
+        assert_eq!(cache.get("doc").await, None);
+    }
+}
@@ -0,0 +1,212 @@
+//! Google Sheets value handling shared by whole-spreadsheet and range reads.
+//!
+//! Cells are always returned as strings, whatever the render mode, so numbers
+//! go through the same redaction patterns as text. Row positions are kept
+//! stable: empty rows inside the data come back as `[]` instead of being
+//! dropped, and range reads report the row and column they start at.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::OwnedMutexGuard;
+
+/// How cell values are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueRender {
+    /// Values as displayed in the Sheets UI (the default).
+    Formatted,
+    /// Formulas as entered (`=SUM(A1:A3)`); other cells as raw values.
+    Formula,
+    /// Underlying values without number formatting.
+    Raw,
+}
+
+impl ValueRender {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value {
+            None | Some("formatted") => Ok(ValueRender::Formatted),
+            Some("formula") => Ok(ValueRender::Formula),
+            Some("raw") => Ok(ValueRender::Raw),
+            Some(other) => bail!("Unknown render '{other}'. Use 'formatted', 'formula' or 'raw'."),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueRender::Formatted => "formatted",
+            ValueRender::Formula => "formula",
+            ValueRender::Raw => "raw",
+        }
+    }
+
+    /// `valueRenderOption` for the Sheets values API.
+    pub fn api_option(self) -> &'static str {
+        match self {
+            ValueRender::Formatted => "FORMATTED_VALUE",
+            ValueRender::Formula => "FORMULA",
+            ValueRender::Raw => "UNFORMATTED_VALUE",
+        }
+    }
+
+    /// Text of one `CellData` from a grid-data read.
+    pub fn grid_cell_text(self, cell: &Value) -> String {
+        let formatted = || cell.get("formattedValue").map(value_text).unwrap_or_default();
+        match self {
+            ValueRender::Formatted => formatted(),
+            ValueRender::Formula => match cell.pointer("/userEnteredValue/formulaValue") {
+                Some(formula) => value_text(formula),
+                None => extended_value_text(cell.get("effectiveValue")).unwrap_or_else(formatted),
+            },
+            ValueRender::Raw => extended_value_text(cell.get("effectiveValue")).unwrap_or_else(formatted),
+        }
+    }
+}
+
+/// One range and its values, as used by batch updates.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SheetValueRange {
+    pub range: String,
+    pub values: Vec<Vec<String>>,
+}
+
+/// Text of an `ExtendedValue` (`{"numberValue": 3}`, `{"stringValue": "x"}`, ...).
+fn extended_value_text(value: Option<&Value>) -> Option<String> {
+    let map = value?.as_object()?;
+    map.values().next().map(value_text)
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        Value::Object(map) => map
+            .get("message")
+            .map(value_text)
+            .unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+/// Rows of a values API response as strings; empty rows are kept.
+pub fn value_rows(values: Option<&Value>) -> Vec<Vec<String>> {
+    values
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .map(|row| {
+            row.as_array()
+                .into_iter()
+                .flatten()
+                .map(value_text)
+                .collect()
+        })
+        .collect()
+}
+
+/// Drop trailing empty rows, keeping the ones between filled rows.
+pub fn trim_trailing_empty(rows: &mut Vec<Vec<String>>) {
+    while rows.last().is_some_and(|r| r.iter().all(String::is_empty)) {
+        rows.pop();
+    }
+}
+
+/// Quote a sheet name for A1 notation (`My Sheet` → `'My Sheet'`).
+pub fn quote_sheet_name(name: &str) -> String {
+    format!("'{}'", name.replace('\'', "''"))
+}
+
+/// First column and 1-based row of a range as returned by the Sheets API
+/// (`'Q3'!B5:D9` → `("B", 5)`). Ranges without a row (`Sheet1!C:C`) start
+/// at row 1.
+pub fn range_start(range: &str) -> (String, u64) {
+    let cells = match range.rfind('!') {
+        Some(i) => &range[i + 1..],
+        None if range.starts_with('\'') => "",
+        None => range,
+    };
+    let first = cells.split(':').next().unwrap_or("");
+    let column: String = first.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    let row = first[column.len()..].parse().unwrap_or(1);
+    let column = if column.is_empty() { "A".to_string() } else { column.to_ascii_uppercase() };
+    (column, row)
+}
+
+/// Per-spreadsheet locks for `append_rows`, which reads the last filled row
+/// and then writes below it. Holding the lock across both keeps two appends
+/// to the same spreadsheet from picking the same row.
+#[derive(Default)]
+pub struct AppendLocks {
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl AppendLocks {
+    pub async fn lock(&self, spreadsheet_id: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(spreadsheet_id.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_grid_cell_text_per_render_mode() {
+        let cell = json!({
+            "formattedValue": "$1,200.00",
+            "userEnteredValue": {"formulaValue": "=SUM(B2:B4)"},
+            "effectiveValue": {"numberValue": 1200}
+        });
+        assert_eq!(ValueRender::Formatted.grid_cell_text(&cell), "$1,200.00");
+        assert_eq!(ValueRender::Formula.grid_cell_text(&cell), "=SUM(B2:B4)");
+        assert_eq!(ValueRender::Raw.grid_cell_text(&cell), "1200");
+
+        let plain = json!({"formattedValue": "TRUE", "effectiveValue": {"boolValue": true}});
+        assert_eq!(ValueRender::Formula.grid_cell_text(&plain), "true");
+        assert_eq!(ValueRender::Raw.grid_cell_text(&json!({})), "");
+    }
+
+    #[test]
+    fn test_value_rows_keep_empty_rows_and_stringify() {
+        let mut rows = value_rows(Some(&json!([["a", 1], [], [true], []])));
+        trim_trailing_empty(&mut rows);
+        assert_eq!(
+            rows,
+            vec![vec!["a".to_string(), "1".to_string()], vec![], vec!["true".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_range_start() {
+        assert_eq!(range_start("Sheet1!B5:D9"), ("B".to_string(), 5));
+        assert_eq!(range_start("'Q3 ! totals'!aa10"), ("AA".to_string(), 10));
+        assert_eq!(range_start("Sheet1!C:C"), ("C".to_string(), 1));
+        assert_eq!(range_start("'My Sheet'"), ("A".to_string(), 1));
+        assert_eq!(quote_sheet_name("Bob's"), "'Bob''s'");
+    }
+
+    #[tokio::test]
+    async fn test_append_locks_serialize_per_spreadsheet() {
+        let locks = AppendLocks::default();
+        let held = locks.lock("s1").await;
+        // Another spreadsheet is not blocked.
+        drop(locks.lock("s2").await);
+
+        let waiting = locks.lock("s1");
+        tokio::pin!(waiting);
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        drop(held);
+        waiting.await;
+    }
+}
+
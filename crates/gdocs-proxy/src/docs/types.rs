@@ -1,5 +1,7 @@
 //! Data types for Google Drive and Docs API responses.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
@@ -19,6 +21,9 @@ pub struct DriveFile {
     pub id: String,
     pub name: String,
     pub mime_type: String,
+    /// Size in bytes (a decimal string); absent for Google-native files.
+    #[serde(default)]
+    pub size: Option<String>,
     #[serde(default)]
     pub created_time: Option<String>,
     #[serde(default)]
@@ -42,6 +47,133 @@ pub struct FileOwner {
     pub email_address: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeListResponse {
+    #[serde(default)]
+    pub changes: Vec<DriveChange>,
+    /// Set when more changes are pending; the cursor for the next page.
+    pub next_page_token: Option<String>,
+    /// Set on the last page; the cursor for future changes.
+    pub new_start_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveChange {
+    pub file_id: Option<String>,
+    /// When the change was recorded (RFC 3339).
+    pub time: Option<String>,
+    #[serde(default)]
+    pub removed: bool,
+    pub file: Option<ChangedFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedFile {
+    pub id: String,
+    pub name: String,
+    pub mime_type: String,
+    #[serde(default)]
+    pub created_time: Option<String>,
+    #[serde(default)]
+    pub modified_time: Option<String>,
+    /// When the file was shared with the user; absent for their own files.
+    #[serde(default)]
+    pub shared_with_me_time: Option<String>,
+    #[serde(default)]
+    pub owned_by_me: bool,
+    #[serde(default)]
+    pub trashed: bool,
+    #[serde(default)]
+    pub web_view_link: Option<String>,
+    #[serde(default)]
+    pub last_modifying_user: Option<DriveUser>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveUser {
+    pub display_name: Option<String>,
+    /// Whether this is the authenticated user.
+    #[serde(default)]
+    pub me: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentListResponse {
+    #[serde(default)]
+    pub comments: Vec<DriveComment>,
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveComment {
+    pub id: String,
+    pub author: Option<CommentAuthor>,
+    #[serde(default)]
+    pub content: String,
+    pub quoted_file_content: Option<QuotedFileContent>,
+    #[serde(default)]
+    pub resolved: bool,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub modified_time: Option<String>,
+    #[serde(default)]
+    pub replies: Vec<DriveReply>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentAuthor {
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub me: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QuotedFileContent {
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveReply {
+    pub author: Option<CommentAuthor>,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub modified_time: Option<String>,
+}
+
+impl DriveComment {
+    pub fn to_comment(&self) -> Comment {
+        let author = |a: &Option<CommentAuthor>| a.as_ref().and_then(|a| a.display_name.clone());
+        Comment {
+            comment_id: self.id.clone(),
+            author: author(&self.author),
+            content: self.content.clone(),
+            quoted_text: self.quoted_file_content.as_ref().and_then(|q| q.value.clone()),
+            resolved: self.resolved,
+            replies: self
+                .replies
+                .iter()
+                .filter(|r| !r.deleted)
+                .map(|r| CommentReply {
+                    author: author(&r.author),
+                    content: r.content.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Google Docs API types
 // ---------------------------------------------------------------------------
@@ -54,6 +186,16 @@ pub struct Document {
     pub title: String,
     pub body: Option<DocBody>,
     pub revision_id: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, DocBody>,
+    #[serde(default)]
+    pub footers: HashMap<String, DocBody>,
+    #[serde(default)]
+    pub footnotes: HashMap<String, DocBody>,
+    #[serde(default)]
+    pub lists: HashMap<String, List>,
+    #[serde(default)]
+    pub inline_objects: HashMap<String, InlineObject>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +207,10 @@ pub struct DocBody {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StructuralElement {
+    #[serde(default)]
+    pub start_index: Option<i64>,
+    #[serde(default)]
+    pub end_index: Option<i64>,
     pub paragraph: Option<Paragraph>,
     pub table: Option<Table>,
     pub section_break: Option<serde_json::Value>,
@@ -75,6 +221,51 @@ pub struct StructuralElement {
 pub struct Paragraph {
     pub elements: Option<Vec<ParagraphElement>>,
     pub paragraph_style: Option<ParagraphStyle>,
+    pub bullet: Option<Bullet>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bullet {
+    pub list_id: Option<String>,
+    pub nesting_level: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    pub list_properties: Option<ListProperties>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListProperties {
+    pub nesting_levels: Option<Vec<NestingLevel>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NestingLevel {
+    /// Set for numbered levels (e.g. `DECIMAL`); bulleted levels use a glyph symbol.
+    pub glyph_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineObject {
+    pub inline_object_properties: Option<InlineObjectProperties>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineObjectProperties {
+    pub embedded_object: Option<EmbeddedObject>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EmbeddedObject {
+    pub title: Option<String>,
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -88,7 +279,8 @@ pub struct ParagraphStyle {
 #[serde(rename_all = "camelCase")]
 pub struct ParagraphElement {
     pub text_run: Option<TextRun>,
-    pub inline_object_element: Option<serde_json::Value>,
+    pub inline_object_element: Option<InlineObjectElement>,
+    pub footnote_reference: Option<FootnoteReference>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -96,6 +288,23 @@ pub struct ParagraphElement {
 pub struct TextRun {
     pub content: Option<String>,
     pub text_style: Option<TextStyle>,
+    #[serde(default)]
+    pub suggested_insertion_ids: Vec<String>,
+    #[serde(default)]
+    pub suggested_deletion_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlineObjectElement {
+    pub inline_object_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FootnoteReference {
+    pub footnote_id: Option<String>,
+    pub footnote_number: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -144,6 +353,18 @@ pub struct StructuredDoc {
     pub title: String,
     pub revision_id: Option<String>,
     pub content: Vec<ContentBlock>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub footers: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub footnotes: Vec<FootnoteText>,
+    /// Pending suggested edits, shown inline in `content` and listed here.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<Suggestion>,
+    /// Drive comments, only when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<Comment>>,
 }
 
 /// A single content block in a structured document.
@@ -160,11 +381,32 @@ pub enum ContentBlock {
         text: String,
         #[serde(skip_serializing_if = "Vec::is_empty")]
         links: Vec<InlineLink>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        styles: Vec<StyledRange>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        images: Vec<ImageRef>,
+    },
+    #[serde(rename = "list_item")]
+    ListItem {
+        text: String,
+        /// Nesting depth, starting at 0.
+        level: u8,
+        ordered: bool,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        links: Vec<InlineLink>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        styles: Vec<StyledRange>,
     },
     #[serde(rename = "table")]
     Table {
         rows: Vec<Vec<String>>,
     },
+    #[serde(rename = "image")]
+    Image {
+        object_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        alt_text: Option<String>,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -173,105 +415,372 @@ pub struct InlineLink {
     pub url: String,
 }
 
+/// Inline formatting over `text[start..end]` (character offsets).
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StyledRange {
+    pub start: usize,
+    pub end: usize,
+    /// Any of `bold`, `italic`, `underline`, `strikethrough`.
+    pub styles: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageRef {
+    pub object_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alt_text: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FootnoteText {
+    pub number: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Suggestion {
+    pub suggestion_id: String,
+    /// `insertion` or `deletion`.
+    pub kind: &'static str,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Comment {
+    pub comment_id: String,
+    pub author: Option<String>,
+    pub content: String,
+    /// The document text the comment is anchored to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quoted_text: Option<String>,
+    pub resolved: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<CommentReply>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentReply {
+    pub author: Option<String>,
+    pub content: String,
+}
+
 // ---------------------------------------------------------------------------
 // Conversion: Document → StructuredDoc
 // ---------------------------------------------------------------------------
 
+/// Plain text of a paragraph with its inline structure.
+struct ParagraphText {
+    text: String,
+    links: Vec<InlineLink>,
+    styles: Vec<StyledRange>,
+    images: Vec<ImageRef>,
+}
+
 impl Document {
     /// Convert a raw Google Docs API response into a structured representation.
     pub fn to_structured(&self) -> StructuredDoc {
-        let mut blocks = Vec::new();
-
-        if let Some(ref body) = self.body {
-            if let Some(ref content) = body.content {
-                for element in content {
-                    if let Some(block) = convert_element(element) {
-                        blocks.push(block);
-                    }
-                }
-            }
-        }
-
         StructuredDoc {
             document_id: self.document_id.clone(),
             title: self.title.clone(),
             revision_id: self.revision_id.clone(),
-            content: blocks,
+            content: self.blocks().into_iter().map(|(block, _)| block).collect(),
+            headers: self.segment_texts(&self.headers),
+            footers: self.segment_texts(&self.footers),
+            footnotes: self.footnote_texts(),
+            suggestions: self.suggestions(),
+            comments: None,
         }
     }
-}
 
-fn convert_element(element: &StructuralElement) -> Option<ContentBlock> {
-    if let Some(ref paragraph) = element.paragraph {
-        return convert_paragraph(paragraph);
+    /// The structured blocks together with the body elements they came from.
+    ///
+    /// Block numbering matches [`StructuredDoc::content`], so edit operations
+    /// can refer to blocks by position.
+    pub fn blocks(&self) -> Vec<(ContentBlock, &StructuralElement)> {
+        self.body_elements()
+            .iter()
+            .filter_map(|element| self.convert_element(element).map(|block| (block, element)))
+            .collect()
     }
-    if let Some(ref table) = element.table {
-        return Some(convert_table(table));
+
+    /// Index just past the last character of the body.
+    pub fn body_end_index(&self) -> Option<i64> {
+        self.body_elements().last()?.end_index
     }
-    None
-}
 
-fn convert_paragraph(paragraph: &Paragraph) -> Option<ContentBlock> {
-    let mut text = String::new();
-    let mut links = Vec::new();
+    pub(crate) fn body_elements(&self) -> &[StructuralElement] {
+        self.body
+            .as_ref()
+            .and_then(|b| b.content.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Whether a list level is numbered rather than bulleted.
+    pub(crate) fn is_ordered_list(&self, bullet: &Bullet) -> bool {
+        let level = bullet.nesting_level.unwrap_or(0) as usize;
+        bullet
+            .list_id
+            .as_ref()
+            .and_then(|id| self.lists.get(id))
+            .and_then(|l| l.list_properties.as_ref())
+            .and_then(|p| p.nesting_levels.as_ref())
+            .and_then(|levels| levels.get(level))
+            .and_then(|l| l.glyph_type.as_deref())
+            .is_some_and(|g| !matches!(g, "GLYPH_TYPE_UNSPECIFIED" | "NONE"))
+    }
+
+    pub(crate) fn image_ref(&self, object_id: &str) -> ImageRef {
+        let embedded = self
+            .inline_objects
+            .get(object_id)
+            .and_then(|o| o.inline_object_properties.as_ref())
+            .and_then(|p| p.embedded_object.as_ref());
+        let alt_text = embedded
+            .and_then(|e| e.description.as_deref().or(e.title.as_deref()))
+            .filter(|t| !t.trim().is_empty())
+            .map(|t| t.trim().to_string());
+        ImageRef {
+            object_id: object_id.to_string(),
+            alt_text,
+        }
+    }
+
+    fn convert_element(&self, element: &StructuralElement) -> Option<ContentBlock> {
+        if let Some(ref paragraph) = element.paragraph {
+            return self.convert_paragraph(paragraph);
+        }
+        if let Some(ref table) = element.table {
+            return Some(convert_table(table));
+        }
+        None
+    }
+
+    fn convert_paragraph(&self, paragraph: &Paragraph) -> Option<ContentBlock> {
+        let ParagraphText { text, links, styles, mut images } = self.paragraph_text(paragraph);
+
+        if text.is_empty() {
+            // Image-only paragraphs become image blocks; other empty
+            // paragraphs (section breaks, spacing) are skipped.
+            return match images.len() {
+                0 => None,
+                1 => {
+                    let image = images.remove(0);
+                    Some(ContentBlock::Image {
+                        object_id: image.object_id,
+                        alt_text: image.alt_text,
+                    })
+                }
+                _ => Some(ContentBlock::Paragraph { text, links, styles, images }),
+            };
+        }
+
+        if let Some(level) = heading_level(paragraph) {
+            return Some(ContentBlock::Heading { level, text });
+        }
 
-    if let Some(ref elements) = paragraph.elements {
-        for elem in elements {
+        if let Some(ref bullet) = paragraph.bullet {
+            return Some(ContentBlock::ListItem {
+                text,
+                level: bullet.nesting_level.unwrap_or(0),
+                ordered: self.is_ordered_list(bullet),
+                links,
+                styles,
+            });
+        }
+
+        Some(ContentBlock::Paragraph { text, links, styles, images })
+    }
+
+    fn paragraph_text(&self, paragraph: &Paragraph) -> ParagraphText {
+        let mut out = ParagraphText {
+            text: String::new(),
+            links: Vec::new(),
+            styles: Vec::new(),
+            images: Vec::new(),
+        };
+
+        for elem in paragraph.elements.iter().flatten() {
             if let Some(ref text_run) = elem.text_run {
                 let content = text_run.content.as_deref().unwrap_or("");
-                text.push_str(content);
+                let start = out.text.chars().count();
+                out.text.push_str(content);
 
-                // Collect links.
                 if let Some(ref style) = text_run.text_style {
-                    if let Some(ref link) = style.link {
-                        if let Some(ref url) = link.url {
-                            let link_text = content.trim().to_string();
-                            if !link_text.is_empty() {
-                                links.push(InlineLink {
-                                    text: link_text,
-                                    url: url.clone(),
-                                });
-                            }
+                    let end = start + content.trim_end_matches('\n').chars().count();
+                    let names = style_names(style);
+                    if end > start && !names.is_empty() {
+                        out.styles.push(StyledRange { start, end, styles: names });
+                    }
+
+                    // Collect links.
+                    if let Some(url) = style.link.as_ref().and_then(|l| l.url.as_ref()) {
+                        let link_text = content.trim().to_string();
+                        if !link_text.is_empty() {
+                            out.links.push(InlineLink {
+                                text: link_text,
+                                url: url.clone(),
+                            });
                         }
                     }
                 }
             }
+            if let Some(id) = elem
+                .inline_object_element
+                .as_ref()
+                .and_then(|o| o.inline_object_id.as_deref())
+            {
+                out.images.push(self.image_ref(id));
+            }
+            if let Some(number) = elem
+                .footnote_reference
+                .as_ref()
+                .and_then(|f| f.footnote_number.as_deref())
+            {
+                out.text.push_str(&format!("[^{number}]"));
+            }
         }
+
+        // Trim trailing newline that Google Docs adds to every paragraph.
+        out.text = out.text.trim_end_matches('\n').to_string();
+        out
     }
 
-    // Trim trailing newline that Google Docs adds to every paragraph.
-    let text = text.trim_end_matches('\n').to_string();
+    /// Plain text of a header/footer/footnote segment, one line per paragraph.
+    fn segment_text(&self, segment: &DocBody) -> String {
+        segment
+            .content
+            .iter()
+            .flatten()
+            .filter_map(|element| match self.convert_element(element)? {
+                ContentBlock::Heading { text, .. }
+                | ContentBlock::Paragraph { text, .. }
+                | ContentBlock::ListItem { text, .. } => Some(text),
+                ContentBlock::Table { rows } => Some(
+                    rows.iter()
+                        .map(|r| r.join(" | "))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                ),
+                ContentBlock::Image { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-    // Skip empty paragraphs (section breaks, etc.).
-    if text.is_empty() {
-        return None;
+    fn segment_texts(&self, segments: &HashMap<String, DocBody>) -> Vec<String> {
+        let mut ids: Vec<&String> = segments.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .map(|id| self.segment_text(&segments[id]))
+            .filter(|t| !t.is_empty())
+            .collect()
     }
 
-    // Determine if this is a heading.
-    if let Some(ref style) = paragraph.paragraph_style {
-        if let Some(ref named_style) = style.named_style_type {
-            let heading_level = match named_style.as_str() {
-                "HEADING_1" => Some(1),
-                "HEADING_2" => Some(2),
-                "HEADING_3" => Some(3),
-                "HEADING_4" => Some(4),
-                "HEADING_5" => Some(5),
-                "HEADING_6" => Some(6),
-                "TITLE" => Some(1),
-                "SUBTITLE" => Some(2),
-                _ => None,
-            };
+    /// Footnote IDs and their display numbers, in reading order.
+    pub(crate) fn footnote_numbers(&self) -> Vec<(String, String)> {
+        let mut numbers = Vec::new();
+        for paragraph in paragraphs(self.body_elements()) {
+            for elem in paragraph.elements.iter().flatten() {
+                if let Some(FootnoteReference {
+                    footnote_id: Some(id),
+                    footnote_number: Some(number),
+                }) = elem.footnote_reference.as_ref()
+                {
+                    numbers.push((id.clone(), number.clone()));
+                }
+            }
+        }
+        numbers
+    }
+
+    pub(crate) fn footnote_texts(&self) -> Vec<FootnoteText> {
+        self.footnote_numbers()
+            .into_iter()
+            .filter_map(|(id, number)| {
+                let text = self.segment_text(self.footnotes.get(&id)?);
+                Some(FootnoteText { number, text })
+            })
+            .collect()
+    }
 
-            if let Some(level) = heading_level {
-                return Some(ContentBlock::Heading { level, text });
+    /// Suggested insertions and deletions in the body, grouped by suggestion.
+    pub(crate) fn suggestions(&self) -> Vec<Suggestion> {
+        let mut out: Vec<Suggestion> = Vec::new();
+        for paragraph in paragraphs(self.body_elements()) {
+            for run in paragraph.elements.iter().flatten().filter_map(|e| e.text_run.as_ref()) {
+                let content = run.content.as_deref().unwrap_or("");
+                let tagged = run
+                    .suggested_insertion_ids
+                    .iter()
+                    .map(|id| (id, "insertion"))
+                    .chain(run.suggested_deletion_ids.iter().map(|id| (id, "deletion")));
+                for (id, kind) in tagged {
+                    match out.iter_mut().find(|s| s.suggestion_id == *id && s.kind == kind) {
+                        Some(existing) => existing.text.push_str(content),
+                        None => out.push(Suggestion {
+                            suggestion_id: id.clone(),
+                            kind,
+                            text: content.to_string(),
+                        }),
+                    }
+                }
             }
         }
+        for s in &mut out {
+            s.text = s.text.trim_end_matches('\n').to_string();
+        }
+        out
+    }
+}
+
+/// Heading level of a paragraph's named style, if it is a heading.
+pub(crate) fn heading_level(paragraph: &Paragraph) -> Option<u8> {
+    let named_style = paragraph.paragraph_style.as_ref()?.named_style_type.as_deref()?;
+    match named_style {
+        "HEADING_1" => Some(1),
+        "HEADING_2" => Some(2),
+        "HEADING_3" => Some(3),
+        "HEADING_4" => Some(4),
+        "HEADING_5" => Some(5),
+        "HEADING_6" => Some(6),
+        "TITLE" => Some(1),
+        "SUBTITLE" => Some(2),
+        _ => None,
     }
+}
 
-    Some(ContentBlock::Paragraph { text, links })
+pub(crate) fn style_names(style: &TextStyle) -> Vec<&'static str> {
+    [
+        (style.bold, "bold"),
+        (style.italic, "italic"),
+        (style.underline, "underline"),
+        (style.strikethrough, "strikethrough"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.unwrap_or(false).then_some(name))
+    .collect()
 }
 
-fn convert_table(table: &Table) -> ContentBlock {
+/// All paragraphs in `elements`, including those inside table cells.
+pub(crate) fn paragraphs(elements: &[StructuralElement]) -> Vec<&Paragraph> {
+    let mut out = Vec::new();
+    for element in elements {
+        if let Some(ref paragraph) = element.paragraph {
+            out.push(paragraph);
+        }
+        let cells = element
+            .table
+            .iter()
+            .flat_map(|t| t.table_rows.iter().flatten())
+            .flat_map(|row| row.table_cells.iter().flatten());
+        for cell in cells {
+            out.extend(paragraphs(cell.content.as_deref().unwrap_or_default()));
+        }
+    }
+    out
+}
+
+pub(crate) fn convert_table(table: &Table) -> ContentBlock {
     let mut rows = Vec::new();
 
     if let Some(ref table_rows) = table.table_rows {
@@ -305,17 +814,6 @@ fn convert_table(table: &Table) -> ContentBlock {
     ContentBlock::Table { rows }
 }
 
-// ---------------------------------------------------------------------------
-// Token types (shared with auth)
-// ---------------------------------------------------------------------------
-
-#[derive(Debug, Deserialize)]
-pub struct TokenResponse {
-    pub access_token: String,
-    pub expires_in: u64,
-    pub token_type: String,
-}
-
 // ---------------------------------------------------------------------------
 // Request types
 // ---------------------------------------------------------------------------
@@ -340,6 +838,103 @@ pub struct UpdateDocRequest {
     pub append_text: Option<String>,
 }
 
+/// Request body for `POST /doc/{id}/edit`.
+#[derive(Debug, Deserialize)]
+pub struct EditDocRequest {
+    /// Revision the edit was planned against (from `GET /doc/{id}`). The edit
+    /// is rejected if the document has changed since.
+    pub revision_id: String,
+    pub operations: Vec<EditOperation>,
+}
+
+/// A high-level document edit. Block numbers are positions in the `content`
+/// array returned by `GET /doc/{id}` at `revision_id`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EditOperation {
+    /// Replace every occurrence of `find` with `replace`.
+    ReplaceText {
+        find: String,
+        replace: String,
+        #[serde(default = "default_match_case")]
+        match_case: bool,
+    },
+    /// Insert a paragraph directly below the first heading with this text.
+    InsertAfterHeading { heading: String, text: String },
+    /// Delete blocks `from_block` through `to_block` (inclusive).
+    DeleteRange { from_block: usize, to_block: usize },
+    /// Turn a paragraph into a heading (`level` 1–6) or back into body text (0).
+    SetHeading { block: usize, level: u8 },
+    /// Insert a table after a block, filled with `rows`.
+    InsertTable { after_block: usize, rows: Vec<Vec<String>> },
+}
+
+fn default_match_case() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EditFormRequest {
+    pub operations: Vec<FormOperation>,
+}
+
+/// A change to a form's questions. Questions are identified by the
+/// `question_id` returned by `GET /doc/{id}`; `index` is a position in the
+/// form's question list (0 = first) at the time the operation runs.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FormOperation {
+    /// Add a question at `index`, or at the end.
+    AddQuestion {
+        question: NewQuestion,
+        #[serde(default)]
+        index: Option<usize>,
+    },
+    /// Move a question to `index`.
+    MoveQuestion { question_id: String, index: usize },
+    DeleteQuestion { question_id: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NewQuestion {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(flatten)]
+    pub kind: QuestionKind,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QuestionKind {
+    ShortAnswer,
+    Paragraph,
+    MultipleChoice { options: Vec<String> },
+    Checkbox { options: Vec<String> },
+    Dropdown { options: Vec<String> },
+    /// A linear scale from `low` (0 or 1) to `high` (2–10).
+    Scale {
+        low: u8,
+        high: u8,
+        #[serde(default)]
+        low_label: Option<String>,
+        #[serde(default)]
+        high_label: Option<String>,
+    },
+    Date {
+        #[serde(default = "default_true")]
+        include_year: bool,
+        #[serde(default)]
+        include_time: bool,
+    },
+}
+
+fn default_true() -> bool {
+    true
+}
+
 /// Sanitized file listing for search results.
 #[derive(Debug, Serialize)]
 pub struct FileResult {
@@ -373,3 +968,57 @@ impl DriveFile {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_structured_keeps_lists_styles_images_and_suggestions() {
+        let doc: Document = serde_json::from_value(json!({
+            "documentId": "d",
+            "title": "t",
+            "lists": {"num": {"listProperties": {"nestingLevels": [{"glyphType": "DECIMAL"}]}}},
+            "inlineObjects": {
+                "img1": {"inlineObjectProperties": {"embeddedObject": {"title": "Chart"}}}
+            },
+            "body": {"content": [
+                {"paragraph": {"elements": [
+                    {"textRun": {"content": "Step ", "textStyle": {}}},
+                    {"textRun": {"content": "one", "textStyle": {"bold": true, "italic": true},
+                                 "suggestedInsertionIds": ["s1"]}},
+                    {"textRun": {"content": "\n", "textStyle": {}}}
+                ], "bullet": {"listId": "num", "nestingLevel": 0}}},
+                {"paragraph": {"elements": [
+                    {"inlineObjectElement": {"inlineObjectId": "img1"}},
+                    {"textRun": {"content": "\n"}}
+                ]}}
+            ]}
+        }))
+        .unwrap();
+
+        let structured = doc.to_structured();
+        match &structured.content[0] {
+            ContentBlock::ListItem { text, level, ordered, styles, .. } => {
+                assert_eq!(text, "Step one");
+                assert_eq!((*level, *ordered), (0, true));
+                assert_eq!(
+                    styles,
+                    &vec![StyledRange { start: 5, end: 8, styles: vec!["bold", "italic"] }]
+                );
+            }
+            other => panic!("expected list item, got {other:?}"),
+        }
+        match &structured.content[1] {
+            ContentBlock::Image { object_id, alt_text } => {
+                assert_eq!(object_id, "img1");
+                assert_eq!(alt_text.as_deref(), Some("Chart"));
+            }
+            other => panic!("expected image, got {other:?}"),
+        }
+        assert_eq!(structured.suggestions.len(), 1);
+        assert_eq!(structured.suggestions[0].kind, "insertion");
+        assert_eq!(structured.suggestions[0].text, "one");
+    }
+}
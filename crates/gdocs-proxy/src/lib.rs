@@ -2,3 +2,6 @@ pub mod auth;
 pub mod config;
 pub mod docs;
 pub mod proxy;
+
+#[cfg(test)]
+mod testing;
@@ -2,18 +2,28 @@
 //!
 //! Usage:
 //!   gdocs-proxy setup  --config /etc/carapace/gdocs-proxy.toml [--client-json /path/to/client_secret.json]
+//!   gdocs-proxy reauth --config /etc/carapace/gdocs-proxy.toml [--revoke-old]
+//!   gdocs-proxy migrate-secrets --config /etc/carapace/gdocs-proxy.toml
 //!   gdocs-proxy serve  [--config /path/to/config.toml]
+//!
+//! `setup` and `reauth` take `--flow browser|device` and `--access`.
 
 use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Context;
 use clap::{Parser, Subcommand};
 
-use gdocs_proxy::auth::{run_oauth_setup, TokenManager};
+use carapace_google::FlowArgs;
+use gdocs_proxy::auth::{run_migrate_secrets, run_oauth_setup, run_reauth, Access, TokenManager, WRITE_SCOPES};
 use gdocs_proxy::config;
 use gdocs_proxy::docs::client::DocsClient;
+use gdocs_proxy::docs::cursor::ChangesCursor;
+use gdocs_proxy::proxy::policy::{CreatedFiles, WritePolicy};
 use gdocs_proxy::proxy::routes::{build_router, AppState};
+use gdocs_proxy::proxy::scrub::Scrubber;
 
 #[derive(Parser)]
 #[command(name = "gdocs-proxy", about = "Secure Google Docs/Drive proxy for Carapace")]
@@ -24,7 +34,7 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Command {
-    /// Interactive OAuth setup — runs once to obtain and store the refresh token.
+    /// OAuth setup — runs once to obtain and store the refresh token.
     Setup {
         /// Path to config.toml
         #[arg(long, default_value = "/etc/carapace/gdocs-proxy.toml")]
@@ -32,6 +42,31 @@ enum Command {
         /// Path to the Google client_secret JSON downloaded from Google Cloud Console.
         #[arg(long)]
         client_json: Option<PathBuf>,
+        /// What the proxy may do; only the scopes this needs are requested.
+        #[arg(long, value_enum, default_value_t = Access::Write)]
+        access: Access,
+        #[command(flatten)]
+        flow: FlowArgs,
+    },
+    /// Replace the stored refresh token, e.g. to rotate it or change --access.
+    Reauth {
+        /// Path to config.toml
+        #[arg(long, default_value = "/etc/carapace/gdocs-proxy.toml")]
+        config: PathBuf,
+        #[arg(long, value_enum, default_value_t = Access::Write)]
+        access: Access,
+        #[command(flatten)]
+        flow: FlowArgs,
+        /// Revoke the current token before requesting a new one. The running
+        /// proxy loses access until it is restarted with the new token.
+        #[arg(long)]
+        revoke_old: bool,
+    },
+    /// Encrypt the plaintext secrets file as configured in [auth.encryption].
+    MigrateSecrets {
+        /// Path to config.toml
+        #[arg(long, default_value = "/etc/carapace/gdocs-proxy.toml")]
+        config: PathBuf,
     },
     /// Run the proxy server.
     Serve {
@@ -45,8 +80,14 @@ enum Command {
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     match cli.command {
-        Command::Setup { config, client_json } => {
-            run_oauth_setup(config, client_json).await?;
+        Command::Setup { config, client_json, access, flow } => {
+            run_oauth_setup(config, client_json, access, flow).await?;
+        }
+        Command::Reauth { config, access, flow, revoke_old } => {
+            run_reauth(config, access, flow, revoke_old).await?;
+        }
+        Command::MigrateSecrets { config } => {
+            run_migrate_secrets(config)?;
         }
         Command::Serve { config } => {
             serve(config).await?;
@@ -76,7 +117,7 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
         cfg.auth.client_id.clone(),
         cfg.auth.client_secret.clone(),
         cfg.secrets.refresh_token.clone(),
-        "https://oauth2.googleapis.com/token".into(),
+        carapace_google::TOKEN_URL.into(),
     ));
     token_manager
         .get_token()
@@ -85,10 +126,10 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
     tracing::info!("OAuth token valid");
 
     // Create Docs/Drive client.
-    let docs = Arc::new(DocsClient::new(
-        token_manager.clone(),
-        cfg.gdocs.account.clone(),
-    ));
+    let docs = Arc::new(
+        DocsClient::new(token_manager.clone(), cfg.gdocs.account.clone())
+            .with_parent_cache_ttl(Duration::from_secs(cfg.scrub.ancestry_cache_ttl_secs)),
+    );
 
     // Compile scrub patterns.
     let scrub_patterns: Vec<regex::Regex> = cfg
@@ -101,16 +142,61 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
     if !cfg.scrub.blocked_folders.is_empty() {
         tracing::info!(
             count = cfg.scrub.blocked_folders.len(),
+            fail_closed = cfg.scrub.fail_closed,
             "Blocked folders configured"
         );
     }
 
+    let write_policy = match cfg.write {
+        Some(ref w) => {
+            let created = if w.allow_created_files {
+                Some(CreatedFiles::load(&w.created_files_file)
+                    .context("Failed to load created files list")?)
+            } else {
+                None
+            };
+            tracing::info!(
+                writable_folders = w.writable_folders.len(),
+                allow_created_files = w.allow_created_files,
+                "Write policy enforced"
+            );
+            WritePolicy::new(w.writable_folders.clone(), created)
+        }
+        None => {
+            tracing::warn!("No [write] section configured — writes are allowed anywhere outside blocked folders");
+            WritePolicy::unrestricted()
+        }
+    };
+
+    let read_only = !WRITE_SCOPES.iter().all(|s| cfg.secrets.has_scope(s));
+    if read_only {
+        tracing::info!(granted = ?cfg.secrets.scopes, "Token is read-only — writes are refused");
+    }
+
+    let changes_cursor = Arc::new(
+        ChangesCursor::load(&cfg.proxy.changes_cursor_file)
+            .context("Failed to load changes cursor")?,
+    );
+
     let state = Arc::new(AppState {
         docs,
         token_manager: token_manager.clone(),
-        scrub_patterns,
-        strip_links: cfg.scrub.strip_links,
+        scrubber: Scrubber::new(
+            scrub_patterns,
+            cfg.scrub.strip_links,
+            cfg.scrub.mask_respondent_identity,
+        ),
         blocked_folders: cfg.scrub.blocked_folders.clone(),
+        exclude_blocked_in_query: cfg.scrub.exclude_blocked_in_query,
+        fail_closed: cfg.scrub.fail_closed,
+        ancestry_failures: AtomicU64::new(0),
+        write_policy,
+        read_only,
+        file_max_bytes: cfg.files.max_bytes,
+        file_max_chars: cfg.files.max_text_chars,
+        convert_folder: cfg.files.convert_folder.clone(),
+        append_locks: Default::default(),
+        changes_cursor,
         start_time: std::time::Instant::now(),
     });
 
@@ -1 +1,3 @@
+pub mod policy;
 pub mod routes;
+pub mod scrub;
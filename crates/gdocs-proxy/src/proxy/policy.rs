@@ -0,0 +1,209 @@
+//! Write-scope policy — decides which files the agent may modify.
+//!
+//! Every write (append, sheet update, copy destination, create parent) is
+//! checked against the target's ancestry: the file itself followed by its
+//! parent chain up to the Drive root.
+//!
+//!   - Anything inside a blocked folder is never writable.
+//!   - When a `[write]` section is configured, the target must sit inside one
+//!     of `writable_folders`, or (with `allow_created_files`) be a file the
+//!     agent created through this proxy or lie inside a folder it created.
+//!   - Without a `[write]` section, only the blocked-folder rule applies.
+
+use std::collections::{BTreeSet, HashSet};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use tokio::sync::Mutex;
+
+/// Outcome of a write-scope check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteDecision {
+    Allowed,
+    /// The target is inside a blocked folder (reported as not found).
+    Blocked,
+    /// The target is outside every writable zone.
+    OutsideWritableZone,
+}
+
+/// IDs of files and folders created by the agent, persisted as a JSON array.
+pub struct CreatedFiles {
+    path: PathBuf,
+    ids: Mutex<BTreeSet<String>>,
+}
+
+impl CreatedFiles {
+    /// Load the set from `path`. A missing file yields an empty set.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let ids = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("failed to parse created files list: {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to read created files list: {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            ids: Mutex::new(ids),
+        })
+    }
+
+    pub async fn contains_any(&self, ids: &[String]) -> bool {
+        let set = self.ids.lock().await;
+        ids.iter().any(|id| set.contains(id))
+    }
+
+    /// Record a newly created file and persist the set.
+    pub async fn insert(&self, id: &str) -> Result<()> {
+        let mut set = self.ids.lock().await;
+        if !set.insert(id.to_string()) {
+            return Ok(());
+        }
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create dir {}", parent.display()))?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&*set)?)
+            .with_context(|| format!("failed to write created files list: {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path)
+            .with_context(|| format!("failed to replace created files list: {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+pub struct WritePolicy {
+    /// `None` when no `[write]` section is configured (writes unrestricted
+    /// apart from blocked folders).
+    writable_folders: Option<HashSet<String>>,
+    created: Option<CreatedFiles>,
+}
+
+impl WritePolicy {
+    /// A policy that only enforces blocked folders.
+    pub fn unrestricted() -> Self {
+        Self {
+            writable_folders: None,
+            created: None,
+        }
+    }
+
+    /// A policy confining writes to `writable_folders` and, if given, files
+    /// recorded in `created`.
+    pub fn new(writable_folders: Vec<String>, created: Option<CreatedFiles>) -> Self {
+        Self {
+            writable_folders: Some(writable_folders.into_iter().collect()),
+            created,
+        }
+    }
+
+    /// Whether writes are confined to writable zones.
+    pub fn is_enforced(&self) -> bool {
+        self.writable_folders.is_some()
+    }
+
+    pub fn writable_folders(&self) -> Vec<String> {
+        let mut folders: Vec<String> = self
+            .writable_folders
+            .iter()
+            .flatten()
+            .cloned()
+            .collect();
+        folders.sort();
+        folders
+    }
+
+    pub fn allows_created_files(&self) -> bool {
+        self.created.is_some()
+    }
+
+    /// Decide whether a file with the given ancestry (`[file, parent, grandparent, ...]`)
+    /// may be written.
+    pub async fn decide(&self, ancestry: &[String], blocked_folders: &[String]) -> WriteDecision {
+        if ancestry.iter().any(|id| blocked_folders.contains(id)) {
+            return WriteDecision::Blocked;
+        }
+        let Some(ref writable) = self.writable_folders else {
+            return WriteDecision::Allowed;
+        };
+        if ancestry.iter().any(|id| writable.contains(id)) {
+            return WriteDecision::Allowed;
+        }
+        if let Some(ref created) = self.created {
+            if created.contains_any(ancestry).await {
+                return WriteDecision::Allowed;
+            }
+        }
+        WriteDecision::OutsideWritableZone
+    }
+
+    /// Record a file created by the agent so it joins the implicit writable zone.
+    pub async fn record_created(&self, id: &str) {
+        if let Some(ref created) = self.created {
+            if let Err(e) = created.insert(id).await {
+                tracing::warn!(id, error = %e, "failed to record created file");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_unrestricted_only_enforces_blocked_folders() {
+        let policy = WritePolicy::unrestricted();
+        let blocked = chain(&["secret"]);
+        assert_eq!(policy.decide(&chain(&["doc", "root"]), &blocked).await, WriteDecision::Allowed);
+        assert_eq!(
+            policy.decide(&chain(&["doc", "secret", "root"]), &blocked).await,
+            WriteDecision::Blocked
+        );
+    }
+
+    #[tokio::test]
+    async fn test_writable_folder_covers_nested_files() {
+        let policy = WritePolicy::new(chain(&["agent"]), None);
+        assert_eq!(
+            policy.decide(&chain(&["doc", "sub", "agent", "root"]), &[]).await,
+            WriteDecision::Allowed
+        );
+        assert_eq!(
+            policy.decide(&chain(&["doc", "other", "root"]), &[]).await,
+            WriteDecision::OutsideWritableZone
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocked_folder_wins_over_writable_folder() {
+        let policy = WritePolicy::new(chain(&["agent"]), None);
+        assert_eq!(
+            policy.decide(&chain(&["doc", "secret", "agent"]), &chain(&["secret"])).await,
+            WriteDecision::Blocked
+        );
+    }
+
+    #[tokio::test]
+    async fn test_created_files_zone_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("created.json");
+
+        let policy = WritePolicy::new(vec![], Some(CreatedFiles::load(&path).unwrap()));
+        let target = chain(&["doc", "made-by-agent", "root"]);
+        assert_eq!(policy.decide(&target, &[]).await, WriteDecision::OutsideWritableZone);
+
+        policy.record_created("made-by-agent").await;
+        assert_eq!(policy.decide(&target, &[]).await, WriteDecision::Allowed);
+
+        let reloaded = WritePolicy::new(vec![], Some(CreatedFiles::load(&path).unwrap()));
+        assert_eq!(reloaded.decide(&target, &[]).await, WriteDecision::Allowed);
+    }
+}
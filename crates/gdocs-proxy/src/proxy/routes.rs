@@ -2,35 +2,85 @@
 //!
 //! Endpoints:
 //!   GET  /search?q=<query>&max=<n>&page_token=<token>  — Search Drive for files
-//!   GET  /doc/{id}                                      — Read a Google Doc (structured)
+//!   GET  /doc/{id}                                      — Read a Google file (structured) or a PDF/Office/text file
 //!   GET  /file/{id}                                     — Get file metadata
 //!   POST /docs                                          — Create a new Google Doc
 //!   POST /docs/copy/{id}                                — Copy a file
 //!   PUT  /doc/{id}                                      — Append text to a doc
+//!   GET  /sheets/{id}/values?range=<a1>&render=<mode>   — Read a sheet range
+//!   PUT  /sheets/{id}/values                            — Write one range
+//!   POST /sheets/{id}/values/batch                      — Write several ranges
+//!   POST /sheets/{id}/append                            — Append rows after the last filled row
+//!   POST /forms/{id}/questions                          — Add, move or delete form questions
+//!   GET  /forms/{id}/responses?since=<rfc3339>          — Form responses submitted after a time
+//!   GET  /changes?since=<token>&max=<n>&folders=<ids>   — Files created, modified, shared or commented on
 //!   GET  /health                                        — Token health check
 //!
 //! NOT exposed: delete, share, permission changes, move to trash.
+//!
+//! Every write is checked against the write policy (see [`super::policy`]):
+//! writes into blocked folders return 404, writes outside the writable zones
+//! return 403. A proxy set up with `--access read-only` refuses all writes
+//! with 403.
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
-use axum::extract::{Path, Query, State};
-use axum::http::StatusCode;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Deserialize;
 
 use crate::auth::TokenManager;
+use crate::docs::changes::{self, ChangeKind};
 use crate::docs::client::DocsClient;
-use crate::docs::types::{CreateDocRequest, UpdateDocRequest};
+use crate::docs::cursor::ChangesCursor;
+use crate::docs::edit::{self, RevisionConflict};
+use crate::docs::files::{self, FileKind, FileTooLarge};
+use crate::docs::forms;
+use crate::docs::markdown;
+use crate::docs::sheets::{self, AppendLocks, SheetValueRange, ValueRender};
+use crate::docs::types::{CreateDocRequest, DriveFile, EditDocRequest, EditFormRequest, UpdateDocRequest};
+use crate::proxy::policy::{WriteDecision, WritePolicy};
+use crate::proxy::scrub::Scrubber;
 
 pub struct AppState {
     pub docs: Arc<DocsClient>,
     pub token_manager: Arc<TokenManager>,
-    pub scrub_patterns: Vec<regex::Regex>,
-    pub strip_links: bool,
+    pub scrubber: Scrubber,
     pub blocked_folders: Vec<String>,
+    pub exclude_blocked_in_query: bool,
+    /// Treat files whose location cannot be verified as blocked.
+    pub fail_closed: bool,
+    /// Ancestry lookups that failed during blocked-folder or write checks.
+    pub ancestry_failures: AtomicU64,
+    pub write_policy: WritePolicy,
+    /// The token lacks the write scopes (`--access read-only`).
+    pub read_only: bool,
+    /// Largest non-Google file `GET /doc/{id}` will download or export.
+    pub file_max_bytes: u64,
+    /// Characters of extracted text (or CSV cells) returned per file.
+    pub file_max_chars: usize,
+    /// Where Office files are copied for conversion (`None` = My Drive root).
+    pub convert_folder: Option<String>,
+    pub append_locks: AppendLocks,
+    /// Drive changes page token for `GET /changes`.
+    pub changes_cursor: Arc<ChangesCursor>,
     pub start_time: std::time::Instant,
 }
 
+#[derive(Deserialize)]
+pub struct ReadParams {
+    /// `json` (default) or `markdown`. Markdown applies to Google Docs only.
+    pub format: Option<String>,
+    /// Include Drive comments (Google Docs only).
+    pub comments: Option<bool>,
+    /// Cell rendering for Sheets: `formatted` (default), `formula` or `raw`.
+    pub render: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct SearchParams {
     pub q: Option<String>,
@@ -43,6 +93,8 @@ pub struct SearchParams {
 #[derive(Deserialize)]
 pub struct CopyParams {
     pub title: Option<String>,
+    /// Destination folder. Required when the write policy is enforced.
+    pub folder_id: Option<String>,
 }
 
 pub fn build_router(state: Arc<AppState>) -> axum::Router {
@@ -50,17 +102,147 @@ pub fn build_router(state: Arc<AppState>) -> axum::Router {
         .route("/search", axum::routing::get(search_handler))
         .route("/doc/{id}", axum::routing::get(get_doc_handler))
         .route("/doc/{id}", axum::routing::put(update_doc_handler))
+        .route("/doc/{id}/edit", axum::routing::post(edit_doc_handler))
         .route("/file/{id}", axum::routing::get(get_file_handler))
         .route("/docs", axum::routing::post(create_doc_handler))
         .route("/docs/copy/{id}", axum::routing::post(copy_handler))
         .route("/folders", axum::routing::post(create_folder_handler))
         .route("/sheets", axum::routing::post(create_sheet_handler))
+        .route("/sheets/{id}/values", axum::routing::get(get_sheet_values_handler))
         .route("/sheets/{id}/values", axum::routing::put(update_sheet_values_handler))
+        .route("/sheets/{id}/values/batch", axum::routing::post(batch_update_sheet_values_handler))
+        .route("/sheets/{id}/append", axum::routing::post(append_rows_handler))
         .route("/forms", axum::routing::post(create_form_handler))
+        .route("/forms/{id}/questions", axum::routing::post(edit_form_questions_handler))
+        .route("/forms/{id}/responses", axum::routing::get(form_responses_handler))
+        .route("/changes", axum::routing::get(changes_handler))
         .route("/health", axum::routing::get(health_handler))
+        .route_layer(axum::middleware::from_fn_with_state(state.clone(), read_only_gate))
         .with_state(state)
 }
 
+/// Refuse writes when the token is read-only. Every write route is a POST
+/// or PUT, so the method decides.
+async fn read_only_gate(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if state.read_only && request.method() != Method::GET {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Write denied: gdocs-proxy is authorized read-only",
+                "hint": "Run `gdocs-proxy reauth --access write` to allow writes."
+            })),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+// ---------------------------------------------------------------------------
+// Write policy
+// ---------------------------------------------------------------------------
+
+fn write_denied(message: String, state: &AppState) -> (StatusCode, Json<serde_json::Value>) {
+    let mut zones = state
+        .write_policy
+        .writable_folders()
+        .iter()
+        .map(|f| format!("folder {f}"))
+        .collect::<Vec<_>>();
+    if state.write_policy.allows_created_files() {
+        zones.push("files created by the agent".into());
+    }
+    let hint = if zones.is_empty() {
+        "No writable folders are configured.".to_string()
+    } else {
+        format!("Writable zones: {}.", zones.join(", "))
+    };
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({"error": message, "hint": hint})),
+    )
+}
+
+/// Check that an existing file (or a folder being written into) may be modified.
+///
+/// Fails closed: if the ancestry cannot be read, the write is denied.
+async fn check_write_target(
+    state: &AppState,
+    target_id: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    if !state.write_policy.is_enforced() && state.blocked_folders.is_empty() {
+        return Ok(());
+    }
+    let ancestry = state.docs.ancestry(target_id).await.map_err(|e| {
+        state.ancestry_failures.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(target_id, error = %e, "write check: ancestry lookup failed");
+        write_denied(format!("Write denied: could not verify the location of {target_id}"), state)
+    })?;
+    match state.write_policy.decide(&ancestry, &state.blocked_folders).await {
+        WriteDecision::Allowed => Ok(()),
+        WriteDecision::Blocked => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "File not found"})),
+        )),
+        WriteDecision::OutsideWritableZone => Err(write_denied(
+            format!("Write denied: {target_id} is outside the writable folders"),
+            state,
+        )),
+    }
+}
+
+/// Decide whether a failed blocked-folder lookup hides the file.
+///
+/// The failure is logged and counted either way; in fail-closed mode the file
+/// is treated as blocked.
+fn blocked_lookup_failed(state: &AppState, file_id: &str, error: &anyhow::Error) -> bool {
+    state.ancestry_failures.fetch_add(1, Ordering::Relaxed);
+    tracing::warn!(
+        file_id,
+        error = %error,
+        fail_closed = state.fail_closed,
+        "blocked-folder check: ancestry lookup failed"
+    );
+    state.fail_closed
+}
+
+/// Reject reads of files inside blocked folders (404). In fail-closed mode, a
+/// file whose location cannot be verified is rejected with 503.
+async fn check_not_blocked(
+    state: &AppState,
+    file_id: &str,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    match state.docs.is_in_blocked_folder(file_id, &state.blocked_folders).await {
+        Ok(false) => Ok(()),
+        Ok(true) => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({"error": "File not found"})),
+        )),
+        Err(e) if blocked_lookup_failed(state, file_id, &e) => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({
+                "error": format!("Could not verify that {file_id} is outside blocked folders"),
+                "hint": "The Drive folder lookup failed. Retry later."
+            })),
+        )),
+        Err(_) => Ok(()),
+    }
+}
+
+/// Check the parent folder of a file about to be created.
+async fn check_create_parent(
+    state: &AppState,
+    parent_id: Option<&str>,
+) -> Result<(), (StatusCode, Json<serde_json::Value>)> {
+    match parent_id {
+        Some(pid) => check_write_target(state, pid).await,
+        None if state.write_policy.is_enforced() => Err(write_denied(
+            "Write denied: a destination folder is required (new files cannot be placed in the Drive root)".into(),
+            state,
+        )),
+        None => Ok(()),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // GET /search
 // ---------------------------------------------------------------------------
@@ -83,6 +265,11 @@ async fn search_handler(
     } else {
         raw_query
     };
+    let query = if state.exclude_blocked_in_query {
+        exclude_folders(&query, &state.blocked_folders)
+    } else {
+        query
+    };
 
     let result = state
         .docs
@@ -95,19 +282,25 @@ async fn search_handler(
             )
         })?;
 
-    let raw_files = result.files.unwrap_or_default();
-    let mut files = Vec::new();
-    for f in &raw_files {
-        if f.trashed.unwrap_or(false) {
-            continue;
-        }
-        if !state.blocked_folders.is_empty()
-            && state.docs.is_in_blocked_folder(&f.id, &state.blocked_folders).await
-        {
-            continue;
-        }
-        files.push(f.to_result());
-    }
+    let raw_files: Vec<_> = result
+        .files
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|f| !f.trashed.unwrap_or(false))
+        .collect();
+    let checks = state
+        .docs
+        .check_blocked(&raw_files, &state.blocked_folders)
+        .await;
+    let files: Vec<_> = raw_files
+        .iter()
+        .zip(checks)
+        .filter(|(f, check)| match check {
+            Ok(blocked) => !blocked,
+            Err(e) => !blocked_lookup_failed(&state, &f.id, e),
+        })
+        .map(|(f, _)| f.to_result())
+        .collect();
 
     Ok(Json(serde_json::json!({
         "files": files,
@@ -115,6 +308,22 @@ async fn search_handler(
     })))
 }
 
+/// Add `not '<id>' in parents` clauses for each folder to a Drive query.
+///
+/// This only removes direct children; deeper descendants are still caught by
+/// the ancestry check.
+fn exclude_folders(query: &str, folders: &[String]) -> String {
+    let clauses: Vec<String> = folders
+        .iter()
+        .map(|f| format!("not '{}' in parents", f.replace('\\', "\\\\").replace('\'', "\\'")))
+        .collect();
+    match (query.is_empty(), clauses.is_empty()) {
+        (_, true) => query.to_string(),
+        (true, false) => clauses.join(" and "),
+        (false, false) => format!("({query}) and {}", clauses.join(" and ")),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // GET /doc/{id}
 // ---------------------------------------------------------------------------
@@ -122,16 +331,22 @@ async fn search_handler(
 async fn get_doc_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
+    Query(params): Query<ReadParams>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let markdown = match params.format.as_deref() {
+        None | Some("json") => false,
+        Some("markdown") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": format!("Unknown format '{other}'. Use 'json' or 'markdown'.")})),
+            ))
+        }
+    };
+    let render = parse_render(params.render.as_deref())?;
+
     // Check blocked folders first.
-    if !state.blocked_folders.is_empty()
-        && state.docs.is_in_blocked_folder(&id, &state.blocked_folders).await
-    {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "File not found"})),
-        ));
-    }
+    check_not_blocked(&state, &id).await?;
 
     // Get the file metadata to determine the type.
     let file = state.docs.get_file(&id).await.map_err(|e| {
@@ -154,8 +369,34 @@ async fn get_doc_handler(
             })?;
 
             let mut structured = doc.to_structured();
-            scrub_content(&mut structured, &state.scrub_patterns, state.strip_links);
-            Ok(Json(serde_json::to_value(structured).unwrap()))
+            if params.comments.unwrap_or(false) {
+                let comments = state.docs.list_comments(&id).await.map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({"error": format!("Failed to fetch comments: {e}")})),
+                    )
+                })?;
+                structured.comments = Some(comments);
+            }
+
+            let mut output = if markdown {
+                serde_json::json!({
+                    "type": "document",
+                    "format": "markdown",
+                    "document_id": structured.document_id,
+                    "title": structured.title,
+                    "revision_id": structured.revision_id,
+                    "markdown": markdown::render(&doc, state.scrubber.strips_links()),
+                    "headers": structured.headers,
+                    "footers": structured.footers,
+                    "suggestions": structured.suggestions,
+                    "comments": structured.comments,
+                })
+            } else {
+                serde_json::to_value(structured).unwrap()
+            };
+            state.scrubber.scrub(&mut output);
+            Ok(Json(output))
         }
 
         "application/vnd.google-apps.spreadsheet" => {
@@ -167,7 +408,8 @@ async fn get_doc_handler(
                 )
             })?;
 
-            let structured = convert_spreadsheet(&raw);
+            let mut structured = convert_spreadsheet(&raw, render);
+            state.scrubber.scrub(&mut structured);
             Ok(Json(structured))
         }
 
@@ -180,7 +422,8 @@ async fn get_doc_handler(
                 )
             })?;
 
-            let structured = convert_presentation(&raw);
+            let mut structured = convert_presentation(&raw);
+            state.scrubber.scrub(&mut structured);
             Ok(Json(structured))
         }
 
@@ -196,25 +439,137 @@ async fn get_doc_handler(
             // Also fetch responses.
             let responses = state.docs.get_form_responses(&id).await.ok();
 
-            let structured = convert_form(&form, responses.as_ref());
+            let mut structured = convert_form(&form, responses.as_ref());
+            state.scrubber.scrub(&mut structured);
             Ok(Json(structured))
         }
 
-        _ => {
-            Err((
+        _ => read_drive_file(&state, &file).await,
+    }
+}
+
+/// Read a non-Google file (PDF, Office, CSV or text) for `GET /doc/{id}`.
+///
+/// Only types recognised by [`FileKind::classify`] are fetched, and nothing
+/// larger than `[files] max_bytes` is downloaded. The extracted text is
+/// scrubbed before truncation so a redaction pattern is never cut in half.
+async fn read_drive_file(
+    state: &AppState,
+    file: &DriveFile,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let Some(kind) = FileKind::classify(&file.mime_type, &file.name) else {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(serde_json::json!({
+                "error": format!("Cannot read file of type '{}'.", file.mime_type),
+                "hint": "Supported types: Google Docs, Sheets, Slides and Forms; PDF; Word, Excel and \
+                         PowerPoint (and OpenDocument) files; CSV, TSV, plain text and Markdown.",
+                "file_name": file.name,
+                "mime_type": file.mime_type,
+            })),
+        ));
+    };
+
+    let too_large = || {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(serde_json::json!({
+                "error": format!("File is larger than the {} byte read limit", state.file_max_bytes),
+                "file_name": file.name,
+                "size": file.size,
+            })),
+        )
+    };
+    let size: Option<u64> = file.size.as_deref().and_then(|s| s.parse().ok());
+    if size.is_some_and(|s| s > state.file_max_bytes) {
+        return Err(too_large());
+    }
+
+    let fetched = match kind.conversion() {
+        Some((google_mime, export_mime)) => {
+            let folder = check_convert_folder(state).await?;
+            state
+                .docs
+                .export_converted(&file.id, google_mime, export_mime, state.file_max_bytes, folder)
+                .await
+        }
+        None => state.docs.download(&file.id, state.file_max_bytes).await,
+    };
+    let bytes = fetched.map_err(|e| {
+        if e.is::<FileTooLarge>() {
+            return too_large();
+        }
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to fetch file content: {e}")})),
+        )
+    })?;
+
+    let text = match kind {
+        FileKind::Pdf => files::extract_pdf_text(bytes).await.map_err(|e| {
+            (
                 StatusCode::UNPROCESSABLE_ENTITY,
-                Json(serde_json::json!({
-                    "error": format!(
-                        "Cannot read file of type '{}'. Supported types: Google Docs, Sheets, Slides, and Forms. \
-                         PDFs, images, and other binary files are not supported.",
-                        mime_type
-                    ),
-                    "file_name": file.name,
-                    "mime_type": mime_type,
-                })),
+                Json(serde_json::json!({"error": format!("Could not read PDF: {e}")})),
+            )
+        })?,
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+
+    let mut output = serde_json::json!({
+        "type": "file",
+        "file_id": file.id,
+        "name": file.name,
+        "mime_type": file.mime_type,
+        "size": size,
+        "format": kind.name(),
+    });
+    let truncated = match kind.delimiter() {
+        Some(delimiter) => {
+            let mut rows = files::parse_delimited(&text, delimiter);
+            for cell in rows.iter_mut().flatten() {
+                *cell = state.scrubber.scrub_text(cell);
+            }
+            let (rows, truncated) = files::limit_rows(rows, state.file_max_chars);
+            output["rows"] = serde_json::json!(rows);
+            truncated
+        }
+        None => {
+            let (text, truncated) = files::truncate_chars(state.scrubber.scrub_text(&text), state.file_max_chars);
+            output["text"] = serde_json::json!(text);
+            truncated
+        }
+    };
+    output["truncated"] = serde_json::json!(truncated);
+
+    state.scrubber.scrub(&mut output);
+    Ok(Json(output))
+}
+
+/// Office files are read through a temporary Google copy, which is a write:
+/// refuse it on a read-only token and check the copy's folder against the
+/// write policy like any other new file.
+async fn check_convert_folder(state: &AppState) -> Result<Option<&str>, (StatusCode, Json<serde_json::Value>)> {
+    if state.read_only {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Reading Office files needs a temporary Drive copy, which a read-only proxy cannot create",
+                "hint": "Run `gdocs-proxy reauth --access write` to allow it."
+            })),
+        ));
+    }
+    match state.convert_folder.as_deref() {
+        Some(folder) => check_write_target(state, folder).await?,
+        None if state.write_policy.is_enforced() => {
+            return Err(write_denied(
+                "Reading Office files needs a temporary Drive copy: set `[files] convert_folder` to a writable folder"
+                    .into(),
+                state,
             ))
         }
+        None => {}
     }
+    Ok(state.convert_folder.as_deref())
 }
 
 // ---------------------------------------------------------------------------
@@ -225,14 +580,7 @@ async fn get_file_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    if !state.blocked_folders.is_empty()
-        && state.docs.is_in_blocked_folder(&id, &state.blocked_folders).await
-    {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "File not found"})),
-        ));
-    }
+    check_not_blocked(&state, &id).await?;
 
     let file = state.docs.get_file(&id).await.map_err(|e| {
         (
@@ -272,6 +620,8 @@ async fn create_folder_handler(
         ));
     }
 
+    check_create_parent(&state, req.parent_id.as_deref()).await?;
+
     let folder = state
         .docs
         .create_folder(&req.name, req.parent_id.as_deref())
@@ -283,6 +633,7 @@ async fn create_folder_handler(
             )
         })?;
 
+    state.write_policy.record_created(&folder.id).await;
     tracing::info!(folder_id = %folder.id, name = %folder.name, "folder created");
 
     Ok(Json(serde_json::json!({
@@ -316,6 +667,8 @@ async fn create_sheet_handler(
         ));
     }
 
+    check_create_parent(&state, req.folder_id.as_deref()).await?;
+
     let file = state
         .docs
         .create_spreadsheet(&req.name, req.folder_id.as_deref())
@@ -326,6 +679,7 @@ async fn create_sheet_handler(
                 Json(serde_json::json!({"error": format!("Failed to create spreadsheet: {e}")})),
             )
         })?;
+    state.write_policy.record_created(&file.id).await;
 
     // If initial data was provided, write it.
     if let Some(ref data) = req.data {
@@ -389,6 +743,8 @@ async fn update_sheet_values_handler(
         ));
     }
 
+    check_write_target(&state, &id).await?;
+
     let result = state
         .docs
         .update_sheet_values(&id, &req.range, &req.values)
@@ -405,6 +761,196 @@ async fn update_sheet_values_handler(
     Ok(Json(result))
 }
 
+fn parse_render(render: Option<&str>) -> Result<ValueRender, (StatusCode, Json<serde_json::Value>)> {
+    ValueRender::parse(render).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+    })
+}
+
+// ---------------------------------------------------------------------------
+// GET /sheets/{id}/values
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct SheetValuesParams {
+    /// A1 range, e.g. `Sheet1!A1:D20` or `'Q3 totals'!B:B`.
+    pub range: Option<String>,
+    /// `formatted` (default), `formula` or `raw`.
+    pub render: Option<String>,
+}
+
+async fn get_sheet_values_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<SheetValuesParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let range = match params.range.as_deref().map(str::trim) {
+        Some(r) if !r.is_empty() => r,
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({"error": "Missing required query param: 'range'"})),
+            ))
+        }
+    };
+    let render = parse_render(params.render.as_deref())?;
+
+    check_not_blocked(&state, &id).await?;
+
+    let raw = state
+        .docs
+        .get_sheet_values(&id, range, render)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Failed to read sheet range: {e}")})),
+            )
+        })?;
+
+    let returned_range = raw.get("range").and_then(|r| r.as_str()).unwrap_or(range);
+    let (start_column, start_row) = sheets::range_start(returned_range);
+    let mut rows = sheets::value_rows(raw.get("values"));
+    sheets::trim_trailing_empty(&mut rows);
+
+    let mut output = serde_json::json!({
+        "type": "sheet_range",
+        "spreadsheet_id": id,
+        "range": returned_range,
+        "render": render.name(),
+        "start_row": start_row,
+        "start_column": start_column,
+        "rows": rows
+    });
+    state.scrubber.scrub(&mut output);
+    Ok(Json(output))
+}
+
+// ---------------------------------------------------------------------------
+// POST /sheets/{id}/values/batch
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct BatchUpdateSheetValuesRequest {
+    pub data: Vec<SheetValueRange>,
+}
+
+async fn batch_update_sheet_values_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<BatchUpdateSheetValuesRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    if req.data.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "'data' must contain at least one range"})),
+        ));
+    }
+    if let Some(n) = req.data.iter().position(|d| d.range.trim().is_empty()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("data[{n}]: missing 'range'")})),
+        ));
+    }
+
+    check_write_target(&state, &id).await?;
+
+    let result = state
+        .docs
+        .batch_update_sheet_values(&id, &req.data)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Failed to update sheet values: {e}")})),
+            )
+        })?;
+
+    tracing::info!(sheet_id = %id, ranges = req.data.len(), "sheet values batch updated");
+
+    Ok(Json(result))
+}
+
+// ---------------------------------------------------------------------------
+// POST /sheets/{id}/append
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct AppendRowsRequest {
+    /// Sheet (tab) name. Defaults to the first sheet.
+    pub sheet: Option<String>,
+    pub values: Vec<Vec<String>>,
+}
+
+/// Write `values` starting in column A of the row after the sheet's last
+/// filled row. Unlike the Sheets `append` API, gaps inside the data do not
+/// change where the rows go.
+async fn append_rows_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<AppendRowsRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    if req.values.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": "'values' must contain at least one row"})),
+        ));
+    }
+
+    check_write_target(&state, &id).await?;
+
+    let internal = |what: &str, e: anyhow::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to {what}: {e}")})),
+        )
+    };
+
+    let sheet = match req.sheet {
+        Some(ref s) if !s.trim().is_empty() => s.clone(),
+        _ => state
+            .docs
+            .sheet_titles(&id)
+            .await
+            .map_err(|e| internal("list sheets", e))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| internal("list sheets", anyhow::anyhow!("spreadsheet has no sheets")))?,
+    };
+    let quoted = sheets::quote_sheet_name(&sheet);
+
+    // Find the last filled row and write below it without another append
+    // to this spreadsheet slipping in between.
+    let _guard = state.append_locks.lock(&id).await;
+    let current = state
+        .docs
+        .get_sheet_values(&id, &quoted, ValueRender::Formatted)
+        .await
+        .map_err(|e| internal("read sheet", e))?;
+    let mut filled = sheets::value_rows(current.get("values"));
+    sheets::trim_trailing_empty(&mut filled);
+    let first_row = filled.len() + 1;
+
+    let result = state
+        .docs
+        .update_sheet_values(&id, &format!("{quoted}!A{first_row}"), &req.values)
+        .await
+        .map_err(|e| internal("append rows", e))?;
+
+    tracing::info!(sheet_id = %id, sheet = %sheet, first_row, rows = req.values.len(), "sheet rows appended");
+
+    Ok(Json(serde_json::json!({
+        "spreadsheet_id": id,
+        "sheet": sheet,
+        "first_row": first_row,
+        "rows_appended": req.values.len(),
+        "updated_range": result.get("updatedRange")
+    })))
+}
+
 // ---------------------------------------------------------------------------
 // POST /forms
 // ---------------------------------------------------------------------------
@@ -412,6 +958,8 @@ async fn update_sheet_values_handler(
 #[derive(Deserialize)]
 pub struct CreateFormRequest {
     pub title: String,
+    /// Folder to place the form in; required when the write policy is enforced.
+    pub folder_id: Option<String>,
 }
 
 async fn create_form_handler(
@@ -425,7 +973,9 @@ async fn create_form_handler(
         ));
     }
 
-    let form = state.docs.create_form(&req.title).await.map_err(|e| {
+    check_create_parent(&state, req.folder_id.as_deref()).await?;
+
+    let form = state.docs.create_form(&req.title, req.folder_id.as_deref()).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({"error": format!("Failed to create form: {e}")})),
@@ -434,6 +984,10 @@ async fn create_form_handler(
 
     let form_id = form.get("formId").and_then(|v| v.as_str()).unwrap_or("");
     let responder_uri = form.get("responderUri").and_then(|v| v.as_str()).unwrap_or("");
+    // The new form joins the created-files zone so the agent can keep editing it.
+    if !form_id.is_empty() {
+        state.write_policy.record_created(form_id).await;
+    }
 
     tracing::info!(form_id, title = %req.title, "form created");
 
@@ -445,6 +999,316 @@ async fn create_form_handler(
     })))
 }
 
+// ---------------------------------------------------------------------------
+// POST /forms/{id}/questions
+// ---------------------------------------------------------------------------
+
+async fn edit_form_questions_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<EditFormRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    check_write_target(&state, &id).await?;
+
+    let form = state.docs.get_form(&id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to fetch form: {e}")})),
+        )
+    })?;
+    // Without a revision the batch would apply unguarded over concurrent edits.
+    let revision_id = form
+        .get("revisionId")
+        .and_then(|r| r.as_str())
+        .filter(|r| !r.is_empty())
+        .ok_or_else(|| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"error": format!("Form {id} was returned without a revision ID")})),
+            )
+        })?
+        .to_string();
+
+    let requests = forms::plan(&form, &req.operations).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("Invalid form edit: {e}")})),
+        )
+    })?;
+
+    let result = state
+        .docs
+        .form_batch_update(&id, requests, &revision_id)
+        .await
+        .map_err(|e| {
+            if e.is::<RevisionConflict>() {
+                (
+                    StatusCode::CONFLICT,
+                    Json(serde_json::json!({
+                        "error": format!("Form {id} was modified while the edit was being applied"),
+                        "hint": "Retry the edit; question IDs are unchanged."
+                    })),
+                )
+            } else {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Failed to edit form: {e}")})),
+                )
+            }
+        })?;
+
+    // IDs of new questions, in the order they were added.
+    let created: Vec<&str> = result
+        .get("replies")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|reply| reply.pointer("/createItem/questionId/0").and_then(|q| q.as_str()))
+        .collect();
+
+    tracing::info!(form_id = %id, operations = req.operations.len(), "form questions edited");
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "form_id": id,
+        "applied": req.operations.len(),
+        "created_question_ids": created
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// GET /forms/{id}/responses
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct FormResponsesParams {
+    /// Only responses submitted after this RFC 3339 timestamp.
+    pub since: Option<String>,
+}
+
+/// Form questions plus the responses submitted after `since`. `next_since`
+/// is the latest submission time seen, to pass as `since` on the next call.
+async fn form_responses_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(params): Query<FormResponsesParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let since = params.since.as_deref().filter(|s| !s.trim().is_empty());
+    if let Some(s) = since {
+        chrono::DateTime::parse_from_rfc3339(s).map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("Invalid 'since' timestamp '{s}': {e}"),
+                    "hint": "Use RFC 3339, e.g. 2025-01-31T09:00:00Z, or next_since from the previous call."
+                })),
+            )
+        })?;
+    }
+
+    check_not_blocked(&state, &id).await?;
+
+    let internal = |what: &str, e: anyhow::Error| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to fetch {what}: {e}")})),
+        )
+    };
+    let form = state.docs.get_form(&id).await.map_err(|e| internal("form", e))?;
+    let responses = state
+        .docs
+        .list_form_responses(&id, since)
+        .await
+        .map_err(|e| internal("form responses", e))?;
+
+    let next_since = responses
+        .get("responses")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|r| r.get("lastSubmittedTime").and_then(|t| t.as_str()))
+        .filter_map(|t| chrono::DateTime::parse_from_rfc3339(t).ok().map(|parsed| (parsed, t)))
+        .max_by_key(|(parsed, _)| *parsed)
+        .map(|(_, t)| t.to_string())
+        .or_else(|| since.map(str::to_string));
+
+    let mut structured = convert_form(&form, Some(&responses));
+    state.scrubber.scrub(&mut structured);
+    // Set after scrubbing: the cursor must come back exactly as Google sent it.
+    structured["since"] = serde_json::json!(since);
+    structured["next_since"] = serde_json::json!(next_since);
+    Ok(Json(structured))
+}
+
+// ---------------------------------------------------------------------------
+// GET /changes
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize)]
+pub struct ChangesParams {
+    /// Page token to read from; defaults to the persisted cursor.
+    pub since: Option<String>,
+    pub max: Option<u32>,
+    /// Comma-separated folder IDs. When set, only files inside one of them
+    /// (at any depth) are reported.
+    pub folders: Option<String>,
+}
+
+fn changes_error(context: &str, e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({"error": format!("{context}: {e}")})),
+    )
+}
+
+/// Resynchronise the changes cursor to Drive's current start page token.
+async fn reset_changes_cursor(state: &AppState) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
+    let token = state
+        .docs
+        .start_page_token()
+        .await
+        .map_err(|e| changes_error("Failed to fetch Drive start page token", e))?;
+    state
+        .changes_cursor
+        .reset(&token)
+        .await
+        .map_err(|e| changes_error("Failed to persist changes cursor", e))?;
+    Ok(token)
+}
+
+/// Report files created, modified, shared with the user or commented on since
+/// `since` (or the persisted cursor).
+///
+/// Reads one page of at most `max` Drive changes (`max=0` just reports the
+/// cursor). Files in blocked folders are never reported; with `folders`, only
+/// files inside those folders are. The response `page_token` is the cursor to
+/// pass next time and `more` is set when further changes are already pending.
+/// If a folder or comment lookup fails (a blocked-folder check only in
+/// fail-closed mode), the request fails and the cursor stays put, so the page
+/// is read again on the next call rather than lost.
+async fn changes_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ChangesParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let max = params.max.unwrap_or(50).min(100);
+    let watched: Vec<String> = params
+        .folders
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    let since = match params.since.filter(|s| !s.trim().is_empty()) {
+        Some(since) => since,
+        None => match state.changes_cursor.get().await {
+            Some(since) => since,
+            None => {
+                // First run: start from "now" rather than replaying all of Drive.
+                let token = reset_changes_cursor(&state).await?;
+                return Ok(Json(serde_json::json!({"changes": [], "page_token": token})));
+            }
+        },
+    };
+
+    // `max=0` only reports the current cursor, e.g. to anchor a new subscriber.
+    if max == 0 {
+        return Ok(Json(serde_json::json!({"changes": [], "page_token": since})));
+    }
+
+    let page = state
+        .docs
+        .list_changes(&since, max)
+        .await
+        .map_err(|e| changes_error("Drive changes request failed", e))?;
+    let Some(page) = page else {
+        tracing::warn!(since, "changes page token rejected; resetting to current Drive state");
+        let token = reset_changes_cursor(&state).await?;
+        return Ok(Json(serde_json::json!({"changes": [], "page_token": token, "reset": true})));
+    };
+
+    let mut events = Vec::new();
+    let mut seen: std::collections::HashSet<(String, ChangeKind)> = std::collections::HashSet::new();
+    for change in &page.changes {
+        let (Some(kind), Some(file)) = (changes::classify(change), change.file.as_ref()) else {
+            continue;
+        };
+        if seen.contains(&(file.id.clone(), kind)) {
+            continue;
+        }
+
+        match state.docs.is_in_blocked_folder(&file.id, &state.blocked_folders).await {
+            Ok(false) => {}
+            Ok(true) => continue,
+            Err(e) if blocked_lookup_failed(&state, &file.id, &e) => {
+                return Err(changes_error("Failed to look up the folders of a changed file", e))
+            }
+            Err(_) => {}
+        }
+        if !watched.is_empty() {
+            match state.docs.ancestry(&file.id).await {
+                Ok(ancestry) if ancestry.iter().any(|a| watched.contains(a)) => {}
+                Ok(_) => continue,
+                Err(e) => return Err(changes_error("Failed to look up the folders of a changed file", e)),
+            }
+        }
+
+        let mut modified_by = file.last_modifying_user.as_ref().and_then(|u| u.display_name.clone());
+        let mut by_me = file.last_modifying_user.as_ref().is_some_and(|u| u.me);
+        if kind == ChangeKind::Commented {
+            let Some(window_start) = changes::comment_window_start(change) else {
+                continue;
+            };
+            let comments = state
+                .docs
+                .recent_comments(&file.id, &window_start)
+                .await
+                .map_err(|e| changes_error("Failed to fetch comments on a changed file", e))?;
+            // No recent comment: the change was something not reported
+            // (a rename by someone else, a permission change, ...).
+            let Some(activity) = changes::latest_comment(&comments, &window_start) else {
+                continue;
+            };
+            modified_by = activity.author;
+            by_me = activity.by_me;
+        }
+
+        seen.insert((file.id.clone(), kind));
+        events.push(serde_json::json!({
+            "kind": kind.name(),
+            "file_id": file.id,
+            "name": file.name,
+            "mime_type": file.mime_type,
+            "time": change.time,
+            "modified_time": file.modified_time,
+            "modified_by": modified_by,
+            "by_me": by_me,
+            "web_view_link": file.web_view_link,
+        }));
+    }
+
+    let more = page.next_page_token.is_some();
+    let next = page
+        .next_page_token
+        .or(page.new_start_page_token)
+        .unwrap_or_else(|| since.clone());
+    state
+        .changes_cursor
+        .advance(&next)
+        .await
+        .map_err(|e| changes_error("Failed to persist changes cursor", e))?;
+
+    let mut changes = serde_json::Value::Array(events);
+    state.scrubber.scrub(&mut changes);
+    Ok(Json(serde_json::json!({
+        "changes": changes,
+        "page_token": next,
+        "more": more
+    })))
+}
+
 // ---------------------------------------------------------------------------
 // POST /docs
 // ---------------------------------------------------------------------------
@@ -460,12 +1324,15 @@ async fn create_doc_handler(
         ));
     }
 
+    check_create_parent(&state, req.folder_id.as_deref()).await?;
+
     let doc = state.docs.create_document(&req.title, req.folder_id.as_deref()).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({"error": format!("Failed to create document: {e}")})),
         )
     })?;
+    state.write_policy.record_created(&doc.document_id).await;
 
     // If initial content was provided, append it.
     if let Some(ref content) = req.content {
@@ -501,18 +1368,13 @@ async fn copy_handler(
     Path(id): Path<String>,
     Query(params): Query<CopyParams>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    if !state.blocked_folders.is_empty()
-        && state.docs.is_in_blocked_folder(&id, &state.blocked_folders).await
-    {
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({"error": "File not found"})),
-        ));
-    }
+    check_not_blocked(&state, &id).await?;
+
+    check_create_parent(&state, params.folder_id.as_deref()).await?;
 
     let new_file = state
         .docs
-        .copy_file(&id, params.title.as_deref())
+        .copy_file(&id, params.title.as_deref(), params.folder_id.as_deref())
         .await
         .map_err(|e| {
             (
@@ -521,6 +1383,8 @@ async fn copy_handler(
             )
         })?;
 
+    state.write_policy.record_created(&new_file.id).await;
+
     tracing::info!(
         source_id = %id,
         new_id = %new_file.id,
@@ -547,6 +1411,8 @@ async fn update_doc_handler(
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     if let Some(ref text) = req.append_text {
         if !text.is_empty() {
+            check_write_target(&state, &id).await?;
+
             state.docs.append_text(&id, text).await.map_err(|e| {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -564,6 +1430,69 @@ async fn update_doc_handler(
     })))
 }
 
+// ---------------------------------------------------------------------------
+// POST /doc/{id}/edit
+// ---------------------------------------------------------------------------
+
+async fn edit_doc_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<EditDocRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    check_write_target(&state, &id).await?;
+
+    let conflict = || {
+        (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!("Document {id} was modified since revision {}", req.revision_id),
+                "hint": "Read the document again and re-plan the edit against the new revision_id."
+            })),
+        )
+    };
+
+    let doc = state.docs.get_document(&id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": format!("Failed to fetch document: {e}")})),
+        )
+    })?;
+    if doc.revision_id.as_deref() != Some(req.revision_id.as_str()) {
+        return Err(conflict());
+    }
+
+    let requests = edit::plan(&doc, &req.operations).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({"error": format!("Invalid edit: {e}")})),
+        )
+    })?;
+
+    let revision_id = state
+        .docs
+        .batch_update(&id, requests, &req.revision_id)
+        .await
+        .map_err(|e| {
+            if e.is::<RevisionConflict>() {
+                conflict()
+            } else {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Failed to edit document: {e}")})),
+                )
+            }
+        })?;
+
+    tracing::info!(doc_id = %id, operations = req.operations.len(), "document edited");
+
+    Ok(Json(serde_json::json!({
+        "status": "ok",
+        "document_id": id,
+        "revision_id": revision_id,
+        "applied": req.operations.len()
+    })))
+}
+
 // ---------------------------------------------------------------------------
 // GET /health
 // ---------------------------------------------------------------------------
@@ -579,53 +1508,23 @@ async fn health_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::
         "token": {
             "valid": token_valid,
             "expires_in_secs": expires_in
+        },
+        "ancestry_checks": {
+            "fail_closed": state.fail_closed,
+            "lookup_failures": state.ancestry_failures.load(Ordering::Relaxed)
         }
     }))
 }
 
-// ---------------------------------------------------------------------------
-// Content scrubbing
-// ---------------------------------------------------------------------------
-
-use crate::docs::types::{ContentBlock, StructuredDoc};
-
-fn scrub_content(doc: &mut StructuredDoc, patterns: &[regex::Regex], strip_links: bool) {
-    for block in &mut doc.content {
-        match block {
-            ContentBlock::Heading { text, .. } => {
-                *text = scrub_text(text, patterns);
-            }
-            ContentBlock::Paragraph { text, links } => {
-                *text = scrub_text(text, patterns);
-                if strip_links {
-                    links.clear();
-                }
-            }
-            ContentBlock::Table { rows } => {
-                for row in rows {
-                    for cell in row {
-                        *cell = scrub_text(cell, patterns);
-                    }
-                }
-            }
-        }
-    }
-}
-
-fn scrub_text(text: &str, patterns: &[regex::Regex]) -> String {
-    let mut result = text.to_string();
-    for pat in patterns {
-        result = pat.replace_all(&result, "[REDACTED]").to_string();
-    }
-    result
-}
-
 // ---------------------------------------------------------------------------
 // Spreadsheet → structured JSON
 // ---------------------------------------------------------------------------
 
 /// Convert a raw Google Sheets API response into a readable structured format.
-fn convert_spreadsheet(raw: &serde_json::Value) -> serde_json::Value {
+///
+/// Row `n` of `rows` is spreadsheet row `n + 1`: empty rows between filled
+/// ones are kept as `[]`, only trailing empty rows are dropped.
+fn convert_spreadsheet(raw: &serde_json::Value, render: ValueRender) -> serde_json::Value {
     let title = raw.get("properties")
         .and_then(|p| p.get("title"))
         .and_then(|t| t.as_str())
@@ -650,25 +1549,22 @@ fn convert_spreadsheet(raw: &serde_json::Value) -> serde_json::Value {
                 for grid in grid_data {
                     if let Some(row_data) = grid.get("rowData").and_then(|r| r.as_array()) {
                         for row in row_data {
-                            let mut cells = Vec::new();
-                            if let Some(values) = row.get("values").and_then(|v| v.as_array()) {
-                                for cell in values {
-                                    let text = cell
-                                        .pointer("/formattedValue")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("")
-                                        .to_string();
-                                    cells.push(text);
-                                }
-                            }
-                            // Skip completely empty rows.
-                            if cells.iter().any(|c| !c.is_empty()) {
-                                rows.push(cells);
+                            let mut cells: Vec<String> = row
+                                .get("values")
+                                .and_then(|v| v.as_array())
+                                .into_iter()
+                                .flatten()
+                                .map(|cell| render.grid_cell_text(cell))
+                                .collect();
+                            if cells.iter().all(String::is_empty) {
+                                cells.clear();
                             }
+                            rows.push(cells);
                         }
                     }
                 }
             }
+            sheets::trim_trailing_empty(&mut rows);
 
             sheets.push(serde_json::json!({
                 "name": sheet_title,
@@ -681,6 +1577,7 @@ fn convert_spreadsheet(raw: &serde_json::Value) -> serde_json::Value {
         "type": "spreadsheet",
         "spreadsheet_id": spreadsheet_id,
         "title": title,
+        "render": render.name(),
         "sheets": sheets
     })
 }
@@ -719,6 +1616,12 @@ fn convert_form(form: &serde_json::Value, responses: Option<&serde_json::Value>)
             let mut q = serde_json::json!({
                 "title": q_title,
             });
+            if let Some(q_id) = item
+                .pointer("/questionItem/question/questionId")
+                .and_then(|v| v.as_str())
+            {
+                q["question_id"] = serde_json::json!(q_id);
+            }
             if !q_description.is_empty() {
                 q["description"] = serde_json::json!(q_description);
             }
@@ -884,3 +1787,414 @@ fn convert_presentation(raw: &serde_json::Value) -> serde_json::Value {
         "slides": slides
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{serve, MockApi};
+
+    async fn get(base: &str, path: &str) -> (u16, Value) {
+        let resp = reqwest::get(format!("{base}{path}")).await.unwrap();
+        (resp.status().as_u16(), resp.json().await.unwrap())
+    }
+
+    fn file(id: &str, parents: &[&str]) -> Value {
+        json!({"id": id, "name": id, "mimeType": "application/vnd.google-apps.document", "parents": parents})
+    }
+
+    #[test]
+    fn exclude_folders_adds_one_clause_per_folder() {
+        let folders = vec!["f1".to_string(), "it's".to_string()];
+        assert_eq!(
+            exclude_folders("name contains 'x'", &folders),
+            "(name contains 'x') and not 'f1' in parents and not 'it\\'s' in parents"
+        );
+        assert_eq!(exclude_folders("", &folders[..1]), "not 'f1' in parents");
+        assert_eq!(exclude_folders("q", &[]), "q");
+    }
+
+    #[tokio::test]
+    async fn search_hides_files_reachable_from_a_blocked_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on(
+            "GET /drive/v3/files",
+            200,
+            json!({"files": [file("open", &["root"]), file("two-parents", &["root", "sub"])]}),
+        );
+        mock.parents("root", &[]);
+        mock.parents("sub", &["blocked"]);
+        let mut state = mock.app_state(dir.path());
+        state.blocked_folders = vec!["blocked".into()];
+        state.exclude_blocked_in_query = true;
+        let base = serve(build_router(Arc::new(state))).await;
+
+        let (status, body) = get(&base, "/search?q=name%20contains%20%27x%27").await;
+        assert_eq!(status, 200);
+        let ids: Vec<_> = body["files"].as_array().unwrap().iter().map(|f| f["id"].clone()).collect();
+        assert_eq!(ids, vec![json!("open")]);
+
+        let search = mock.requests().into_iter().find(|r| r.route == "GET /drive/v3/files").unwrap();
+        let url = reqwest::Url::parse(&format!("http://mock/?{}", search.query)).unwrap();
+        let q: String = url
+            .query_pairs()
+            .find(|(k, _)| k == "q")
+            .map(|(_, v)| v.into_owned())
+            .unwrap();
+        assert!(q.contains("not 'blocked' in parents"), "{q}");
+    }
+
+    fn blocked_state(mock: &MockApi, dir: &std::path::Path, fail_closed: bool) -> AppState {
+        mock.parents("ok", &["root"]);
+        mock.parents("root", &[]);
+        mock.parents("hidden", &["blocked"]);
+        mock.parents("lost", &["unreadable"]);
+        let mut state = mock.app_state(dir);
+        state.blocked_folders = vec!["blocked".into()];
+        state.fail_closed = fail_closed;
+        state
+    }
+
+    #[tokio::test]
+    async fn check_not_blocked_fails_closed_on_lookup_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        let state = blocked_state(&mock, dir.path(), true);
+
+        assert!(check_not_blocked(&state, "ok").await.is_ok());
+        assert_eq!(check_not_blocked(&state, "hidden").await.unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(
+            check_not_blocked(&state, "lost").await.unwrap_err().0,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(state.ancestry_failures.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn check_not_blocked_fails_open_when_configured() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        let state = blocked_state(&mock, dir.path(), false);
+
+        assert!(check_not_blocked(&state, "lost").await.is_ok());
+        assert_eq!(check_not_blocked(&state, "hidden").await.unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(state.ancestry_failures.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn search_lookup_failures_follow_fail_closed() {
+        for fail_closed in [true, false] {
+            let dir = tempfile::tempdir().unwrap();
+            let mock = MockApi::start().await;
+            mock.on(
+                "GET /drive/v3/files",
+                200,
+                json!({"files": [file("ok", &["root"]), file("hidden", &["blocked"]), file("lost", &["unreadable"])]}),
+            );
+            let state = Arc::new(blocked_state(&mock, dir.path(), fail_closed));
+            let base = serve(build_router(state.clone())).await;
+
+            let (_, body) = get(&base, "/search").await;
+            let ids: Vec<_> = body["files"].as_array().unwrap().iter().map(|f| f["id"].clone()).collect();
+            let expected = if fail_closed { vec![json!("ok")] } else { vec![json!("ok"), json!("lost")] };
+            assert_eq!(ids, expected, "fail_closed = {fail_closed}");
+
+            let (_, health) = get(&base, "/health").await;
+            assert_eq!(health["ancestry_checks"]["lookup_failures"], 1);
+            assert_eq!(health["ancestry_checks"]["fail_closed"], fail_closed);
+        }
+    }
+
+    /// A Word file `memo` whose conversion copy is `copy1`.
+    fn office_file(mock: &MockApi) {
+        mock.on(
+            "GET /drive/v3/files/memo",
+            200,
+            json!({"id": "memo", "name": "memo.docx", "parents": ["shared"],
+                   "mimeType": "application/vnd.openxmlformats-officedocument.wordprocessingml.document"}),
+        );
+        mock.on("POST /drive/v3/files/memo/copy", 200, json!({"id": "copy1"}));
+        mock.on("GET /drive/v3/files/copy1/export", 200, json!("Quarterly memo"));
+        mock.on("DELETE /drive/v3/files/copy1", 200, json!({}));
+    }
+
+    #[tokio::test]
+    async fn office_read_converts_in_the_convert_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        office_file(&mock);
+        mock.parents("scratch", &["work"]);
+        mock.parents("work", &[]);
+        let mut state = mock.app_state(dir.path());
+        state.write_policy = WritePolicy::new(vec!["work".into()], None);
+        state.convert_folder = Some("scratch".into());
+        let base = serve(build_router(Arc::new(state))).await;
+
+        let (status, body) = get(&base, "/doc/memo").await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["text"], "Quarterly memo");
+        let copy = mock.requests().into_iter().find(|r| r.route == "POST /drive/v3/files/memo/copy").unwrap();
+        assert_eq!(copy.body["parents"], json!(["scratch"]));
+        assert_eq!(mock.count("DELETE /drive/v3/files/copy1"), 1);
+    }
+
+    #[tokio::test]
+    async fn office_read_is_refused_without_a_writable_copy_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        office_file(&mock);
+
+        let mut read_only = mock.app_state(dir.path());
+        read_only.read_only = true;
+        let base = serve(build_router(Arc::new(read_only))).await;
+        let (status, _) = get(&base, "/doc/memo").await;
+        assert_eq!(status, 403);
+
+        let mut confined = mock.app_state(dir.path());
+        confined.write_policy = WritePolicy::new(vec!["work".into()], None);
+        let base = serve(build_router(Arc::new(confined))).await;
+        let (status, body) = get(&base, "/doc/memo").await;
+        assert_eq!(status, 403);
+        assert!(body["error"].as_str().unwrap().contains("convert_folder"));
+
+        assert_eq!(mock.count("POST /drive/v3/files/memo/copy"), 0);
+    }
+
+    #[tokio::test]
+    async fn office_read_fails_when_the_copy_cannot_be_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        office_file(&mock);
+        mock.on("DELETE /drive/v3/files/copy1", 500, json!({"error": {"code": 500}}));
+        let base = serve(build_router(Arc::new(mock.app_state(dir.path())))).await;
+
+        let (status, body) = get(&base, "/doc/memo").await;
+        assert_eq!(status, 500);
+        assert!(body["error"].as_str().unwrap().contains("copy1"), "{body}");
+        assert_eq!(mock.count("DELETE /drive/v3/files/copy1"), 3);
+    }
+
+    #[tokio::test]
+    async fn append_rows_writes_below_the_last_filled_row() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on(
+            "GET /sheets/v4/spreadsheets/s1/values/'Data'",
+            200,
+            json!({"range": "'Data'!A1:B4", "values": [["h1", "h2"], [], ["x", "1"], ["", ""]]}),
+        );
+        mock.on("PUT /sheets/v4/spreadsheets/s1/values/'Data'!A4", 200, json!({"updatedRange": "'Data'!A4:B5"}));
+        let base = serve(build_router(Arc::new(mock.app_state(dir.path())))).await;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/sheets/s1/append"))
+            .json(&json!({"sheet": "Data", "values": [["y", "2"], ["z", "3"]]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["first_row"], 4);
+        assert_eq!(body["rows_appended"], 2);
+
+        let put = mock.requests().into_iter().find(|r| r.route.starts_with("PUT ")).unwrap();
+        assert_eq!(put.body["values"], json!([["y", "2"], ["z", "3"]]));
+    }
+
+    #[tokio::test]
+    async fn form_edit_requires_a_revision_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /forms/v1/forms/f1", 200, json!({"formId": "f1", "items": []}));
+        let base = serve(build_router(Arc::new(mock.app_state(dir.path())))).await;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/forms/f1/questions"))
+            .json(&json!({"operations": [
+                {"op": "add_question", "question": {"title": "Name", "type": "short_answer"}}
+            ]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 500);
+        let body: Value = resp.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("revision"), "{body}");
+        assert_eq!(mock.count("POST /forms/v1/forms/f1:batchUpdate"), 0);
+    }
+
+    #[tokio::test]
+    async fn form_create_needs_a_writable_folder_when_the_policy_is_enforced() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.parents("work", &[]);
+        mock.parents("form1", &["root"]);
+        mock.on("POST /forms/v1/forms", 200, json!({"formId": "form1", "responderUri": "https://forms/r"}));
+        mock.on("PATCH /drive/v3/files/form1", 200, json!({"id": "form1", "parents": ["work"]}));
+        let mut state = mock.app_state(dir.path());
+        state.write_policy = WritePolicy::new(vec!["work".into()], None);
+        let base = serve(build_router(Arc::new(state))).await;
+        let client = reqwest::Client::new();
+
+        let resp = client.post(format!("{base}/forms")).json(&json!({"title": "Survey"})).send().await.unwrap();
+        assert_eq!(resp.status().as_u16(), 403);
+        assert_eq!(mock.count("POST /forms/v1/forms"), 0);
+
+        let resp = client
+            .post(format!("{base}/forms"))
+            .json(&json!({"title": "Survey", "folder_id": "work"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 200);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["form_id"], "form1");
+        let moved = mock.requests().into_iter().find(|r| r.route == "PATCH /drive/v3/files/form1").unwrap();
+        assert!(moved.query.contains("addParents=work"), "{}", moved.query);
+        assert!(moved.query.contains("removeParents=root"), "{}", moved.query);
+    }
+
+    #[tokio::test]
+    async fn form_create_deletes_a_form_it_cannot_move() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.parents("form1", &["root"]);
+        mock.on("POST /forms/v1/forms", 200, json!({"formId": "form1"}));
+        mock.on("PATCH /drive/v3/files/form1", 500, json!({"error": {"code": 500}}));
+        mock.on("DELETE /drive/v3/files/form1", 204, Value::Null);
+        let base = serve(build_router(Arc::new(mock.app_state(dir.path())))).await;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/forms"))
+            .json(&json!({"title": "Survey", "folder_id": "elsewhere"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 500);
+        assert_eq!(mock.count("DELETE /drive/v3/files/form1"), 1);
+    }
+
+    #[tokio::test]
+    async fn read_only_tokens_are_refused_every_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        let mut state = mock.app_state(dir.path());
+        state.read_only = true;
+        let base = serve(build_router(Arc::new(state))).await;
+        let client = reqwest::Client::new();
+
+        let writes = [
+            client.post(format!("{base}/docs")).json(&json!({"title": "t", "content": "c"})),
+            client.put(format!("{base}/doc/d1")).json(&json!({"content": "c"})),
+            client.put(format!("{base}/sheets/s1/values")).json(&json!({"range": "A1", "values": [["x"]]})),
+            client.post(format!("{base}/forms/f1/questions")).json(&json!({"operations": []})),
+        ];
+        for write in writes {
+            let resp = write.send().await.unwrap();
+            assert_eq!(resp.status().as_u16(), 403, "{}", resp.url());
+            let body: Value = resp.json().await.unwrap();
+            assert!(body["error"].as_str().unwrap().contains("read-only"), "{body}");
+        }
+        assert!(mock.requests().is_empty());
+
+        let (status, _) = get(&base, "/health").await;
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn changes_hold_the_cursor_when_a_lookup_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        let change = |id: &str, modified: &str| json!({
+            "fileId": id, "time": "2025-03-01T10:00:30Z",
+            "file": {"id": id, "name": id, "mimeType": "application/vnd.google-apps.document",
+                     "createdTime": "2025-01-01T00:00:00Z", "modifiedTime": modified, "ownedByMe": true}
+        });
+        let state = Arc::new(mock.app_state(dir.path()));
+        let base = serve(build_router(state.clone())).await;
+
+        // A folder lookup fails while filtering by watched folders.
+        mock.on("GET /drive/v3/changes", 200, json!({
+            "changes": [change("d1", "2025-03-01T10:00:29Z")], "newStartPageToken": "11"
+        }));
+        mock.on("GET /drive/v3/files/d1", 500, json!({"error": {"code": 500}}));
+        let (status, body) = get(&base, "/changes?since=10&folders=w1").await;
+        assert_eq!(status, 500, "{body}");
+        assert_eq!(state.changes_cursor.get().await, None);
+
+        // A comment lookup fails for a change only a comment could explain.
+        mock.on("GET /drive/v3/changes", 200, json!({
+            "changes": [change("d2", "2025-02-01T00:00:00Z")], "newStartPageToken": "11"
+        }));
+        mock.on("GET /drive/v3/files/d2/comments", 500, json!({"error": {"code": 500}}));
+        let (status, body) = get(&base, "/changes?since=10").await;
+        assert_eq!(status, 500, "{body}");
+        assert_eq!(state.changes_cursor.get().await, None);
+
+        mock.on("GET /drive/v3/files/d2/comments", 200, json!({"comments": []}));
+        let (status, body) = get(&base, "/changes?since=10").await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["changes"], json!([]));
+        assert_eq!(state.changes_cursor.get().await.as_deref(), Some("11"));
+    }
+
+    #[tokio::test]
+    async fn changes_hold_the_cursor_when_a_blocked_folder_check_fails_closed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        let state = Arc::new(blocked_state(&mock, dir.path(), true));
+        let base = serve(build_router(state.clone())).await;
+        mock.on("GET /drive/v3/changes", 200, json!({
+            "changes": [{
+                "fileId": "lost", "time": "2025-03-01T10:00:30Z",
+                "file": {"id": "lost", "name": "lost", "mimeType": "application/vnd.google-apps.document",
+                         "createdTime": "2025-01-01T00:00:00Z", "modifiedTime": "2025-03-01T10:00:29Z",
+                         "ownedByMe": true}
+            }],
+            "newStartPageToken": "11"
+        }));
+
+        let (status, body) = get(&base, "/changes?since=10").await;
+        assert_eq!(status, 500, "{body}");
+        assert_eq!(state.changes_cursor.get().await, None);
+        assert_eq!(state.ancestry_failures.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn convert_spreadsheet_keeps_row_positions_per_render_mode() {
+        let cell = |formatted: &str, formula: Option<&str>| match formula {
+            Some(f) => json!({"formattedValue": formatted, "userEnteredValue": {"formulaValue": f},
+                              "effectiveValue": {"numberValue": 3}}),
+            None => json!({"formattedValue": formatted, "effectiveValue": {"stringValue": formatted}}),
+        };
+        let raw = json!({
+            "spreadsheetId": "s1",
+            "properties": {"title": "Budget"},
+            "sheets": [{
+                "properties": {"title": "Q3"},
+                "data": [{"rowData": [
+                    {"values": [cell("Item", None), cell("Total", None)]},
+                    {"values": [{}, {}]},
+                    {"values": [cell("Sum", None), cell("$3.00", Some("=SUM(B1:B2)"))]},
+                    {"values": [{}]},
+                    {}
+                ]}]
+            }, {"properties": {"title": "Empty"}}]
+        });
+
+        let formatted = convert_spreadsheet(&raw, ValueRender::Formatted);
+        assert_eq!(formatted["title"], "Budget");
+        assert_eq!(formatted["spreadsheet_id"], "s1");
+        assert_eq!(formatted["render"], "formatted");
+        assert_eq!(formatted["sheets"][0]["name"], "Q3");
+        assert_eq!(formatted["sheets"][0]["rows"], json!([["Item", "Total"], [], ["Sum", "$3.00"]]));
+        assert_eq!(formatted["sheets"][1], json!({"name": "Empty", "rows": []}));
+
+        let formula = convert_spreadsheet(&raw, ValueRender::Formula);
+        assert_eq!(formula["sheets"][0]["rows"][2], json!(["Sum", "=SUM(B1:B2)"]));
+        let raw_values = convert_spreadsheet(&raw, ValueRender::Raw);
+        assert_eq!(raw_values["sheets"][0]["rows"][2], json!(["Sum", "3"]));
+    }
+}
+
@@ -0,0 +1,168 @@
+//! Content scrubbing for everything returned by `GET /doc/{id}`.
+//!
+//! Every file type is first converted to structured JSON and then passed
+//! through the same pipeline, so Docs, Sheets, Slides and Forms get identical
+//! treatment:
+//!
+//!   - `redact_patterns` are applied to every string value, including titles,
+//!     cell values, slide text, form questions and form answers. Structural
+//!     fields (`type` and `*_id`) are left alone.
+//!   - With `strip_links`, every `links` array is dropped.
+//!   - With `mask_respondent_identity`, form answers to questions that ask for
+//!     a name, email address, phone number or address are masked.
+
+use regex::Regex;
+use serde_json::Value;
+
+const REDACTED: &str = "[REDACTED]";
+const MASKED: &str = "[MASKED]";
+
+/// Question titles treated as asking for the respondent's identity.
+const IDENTITY_QUESTION_PATTERN: &str =
+    r"(?i)\b(e-?mail|phone|mobile|cell|telephone|name|address|contact)\b";
+
+pub struct Scrubber {
+    patterns: Vec<Regex>,
+    strip_links: bool,
+    identity_questions: Option<Regex>,
+}
+
+impl Scrubber {
+    pub fn new(patterns: Vec<Regex>, strip_links: bool, mask_respondent_identity: bool) -> Self {
+        Self {
+            patterns,
+            strip_links,
+            identity_questions: mask_respondent_identity
+                .then(|| Regex::new(IDENTITY_QUESTION_PATTERN).unwrap()),
+        }
+    }
+
+    /// Whether link targets are removed from output.
+    pub fn strips_links(&self) -> bool {
+        self.strip_links
+    }
+
+    /// Scrub structured read output in place.
+    pub fn scrub(&self, value: &mut Value) {
+        if value.get("type").and_then(|t| t.as_str()) == Some("form") {
+            self.mask_respondents(value);
+        }
+        self.scrub_value(value);
+    }
+
+    pub fn scrub_text(&self, text: &str) -> String {
+        let mut result = text.to_string();
+        for pat in &self.patterns {
+            result = pat.replace_all(&result, REDACTED).to_string();
+        }
+        result
+    }
+
+    fn scrub_value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.scrub_text(s),
+            Value::Array(items) => items.iter_mut().for_each(|v| self.scrub_value(v)),
+            Value::Object(map) => {
+                if self.strip_links {
+                    map.remove("links");
+                }
+                for (key, v) in map.iter_mut() {
+                    if key == "type" || key.ends_with("_id") {
+                        continue;
+                    }
+                    self.scrub_value(v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Mask answers to identity questions in a converted form.
+    fn mask_respondents(&self, form: &mut Value) {
+        let Some(ref identity) = self.identity_questions else {
+            return;
+        };
+        let identity_ids: Vec<String> = form
+            .get("questions")
+            .and_then(|q| q.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|q| {
+                q.get("title")
+                    .and_then(|t| t.as_str())
+                    .is_some_and(|t| identity.is_match(t))
+            })
+            .filter_map(|q| q.get("question_id").and_then(|id| id.as_str()))
+            .map(str::to_string)
+            .collect();
+
+        let Some(responses) = form.get_mut("responses").and_then(|r| r.as_array_mut()) else {
+            return;
+        };
+        for response in responses {
+            let Some(answers) = response.as_object_mut() else {
+                continue;
+            };
+            for id in &identity_ids {
+                if let Some(answer) = answers.get_mut(id) {
+                    *answer = Value::String(MASKED.to_string());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scrubber(mask: bool) -> Scrubber {
+        Scrubber::new(vec![Regex::new(r"\b\d{6}\b").unwrap()], true, mask)
+    }
+
+    #[test]
+    fn test_redacts_every_text_field_but_not_ids() {
+        let mut sheet = json!({
+            "type": "spreadsheet",
+            "spreadsheet_id": "123456",
+            "title": "Codes 123456",
+            "sheets": [{"name": "Sheet1", "rows": [["otp", "654321"]]}]
+        });
+        scrubber(false).scrub(&mut sheet);
+        assert_eq!(sheet["spreadsheet_id"], "123456");
+        assert_eq!(sheet["title"], "Codes [REDACTED]");
+        assert_eq!(sheet["sheets"][0]["rows"][0][1], "[REDACTED]");
+    }
+
+    #[test]
+    fn test_strip_links_drops_link_arrays() {
+        let mut doc = json!({
+            "type": "document",
+            "content": [{"type": "paragraph", "text": "see", "links": [{"url": "https://x"}]}]
+        });
+        scrubber(false).scrub(&mut doc);
+        assert!(doc["content"][0].get("links").is_none());
+    }
+
+    #[test]
+    fn test_masks_identity_answers_only_when_enabled() {
+        let form = json!({
+            "type": "form",
+            "questions": [
+                {"question_id": "q1", "title": "Email address"},
+                {"question_id": "q2", "title": "Will you attend?"}
+            ],
+            "responses": [{"q1": "a@example.com", "q2": "Yes"}]
+        });
+
+        let mut masked = form.clone();
+        scrubber(true).scrub(&mut masked);
+        assert_eq!(masked["responses"][0]["q1"], "[MASKED]");
+        assert_eq!(masked["responses"][0]["q2"], "Yes");
+
+        let mut unmasked = form;
+        scrubber(false).scrub(&mut unmasked);
+        assert_eq!(unmasked["responses"][0]["q1"], "a@example.com");
+    }
+}
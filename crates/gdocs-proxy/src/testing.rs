@@ -0,0 +1,143 @@
+//! A local stand-in for the Google APIs and token endpoint, for the route
+//! and client tests.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::{Arc, Mutex};
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::auth::TokenManager;
+use crate::docs::client::DocsClient;
+use crate::docs::cursor::ChangesCursor;
+use crate::proxy::policy::WritePolicy;
+use crate::proxy::routes::AppState;
+use crate::proxy::scrub::Scrubber;
+
+/// A request the mock received.
+#[derive(Debug, Clone)]
+pub struct Received {
+    /// `"<METHOD> <path>"`, e.g. `"GET /drive/v3/files/doc1"`.
+    pub route: String,
+    pub query: String,
+    /// The JSON request body, or `Null`.
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct Recorded {
+    /// Canned responses keyed by route; the query is ignored.
+    responses: HashMap<String, (StatusCode, Value)>,
+    requests: Vec<Received>,
+}
+
+pub struct MockApi {
+    base: String,
+    state: Arc<Mutex<Recorded>>,
+}
+
+impl MockApi {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(Recorded::default()));
+        let app = axum::Router::new().fallback(respond).with_state(state.clone());
+        Self {
+            base: serve(app).await,
+            state,
+        }
+    }
+
+    /// Answer `route` (e.g. `"GET /drive/v3/files/doc1"`) with `body`. A JSON
+    /// string is sent as a plain-text body (e.g. file content).
+    pub fn on(&self, route: &str, status: u16, body: Value) {
+        let status = StatusCode::from_u16(status).unwrap();
+        self.state.lock().unwrap().responses.insert(route.to_string(), (status, body));
+    }
+
+    /// Answer Drive parent lookups for `id` with `parents`.
+    pub fn parents(&self, id: &str, parents: &[&str]) {
+        self.on(&format!("GET /drive/v3/files/{id}"), 200, json!({"id": id, "parents": parents}));
+    }
+
+    /// Every request received, in order.
+    pub fn requests(&self) -> Vec<Received> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// How many requests were made to `route`.
+    pub fn count(&self, route: &str) -> usize {
+        self.requests().iter().filter(|r| r.route == route).count()
+    }
+
+    /// A client that sends every API call to this mock.
+    pub fn client(&self) -> DocsClient {
+        DocsClient::new(self.token_manager(), "me@example.com".into()).with_api_root(&self.base)
+    }
+
+    fn token_manager(&self) -> Arc<TokenManager> {
+        Arc::new(TokenManager::new(
+            "client".into(),
+            "secret".into(),
+            "refresh".into(),
+            format!("{}/token", self.base),
+        ))
+    }
+
+    /// Proxy state wired to this mock: nothing blocked, writes unrestricted,
+    /// fail-closed, and the changes cursor stored under `dir`.
+    pub fn app_state(&self, dir: &Path) -> AppState {
+        AppState {
+            docs: Arc::new(self.client()),
+            token_manager: self.token_manager(),
+            scrubber: Scrubber::new(vec![], false, false),
+            blocked_folders: vec![],
+            exclude_blocked_in_query: false,
+            fail_closed: true,
+            ancestry_failures: AtomicU64::new(0),
+            write_policy: WritePolicy::unrestricted(),
+            read_only: false,
+            file_max_bytes: 1024 * 1024,
+            file_max_chars: 10_000,
+            convert_folder: None,
+            append_locks: Default::default(),
+            changes_cursor: Arc::new(ChangesCursor::load(dir.join("changes.json")).unwrap()),
+            start_time: std::time::Instant::now(),
+        }
+    }
+}
+
+async fn respond(
+    State(state): State<Arc<Mutex<Recorded>>>,
+    method: Method,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let route = format!("{method} {}", uri.path());
+    let mut state = state.lock().unwrap();
+    state.requests.push(Received {
+        route: route.clone(),
+        query: uri.query().unwrap_or_default().to_string(),
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    });
+    if route == "POST /token" {
+        return Json(json!({"access_token": "at", "expires_in": 3599, "token_type": "Bearer"})).into_response();
+    }
+    match state.responses.get(&route) {
+        Some((status, Value::String(text))) => (*status, text.clone()).into_response(),
+        Some((status, body)) => (*status, Json(body.clone())).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": {"code": 404, "status": "NOT_FOUND"}}))).into_response(),
+    }
+}
+
+/// Serve `app` on an ephemeral local port and return its base URL.
+pub async fn serve(app: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    base
+}
@@ -2,18 +2,13 @@
 //!
 //! The proxy uses two files:
 //!   - `config.toml` — non-secret settings (paths, scrub rules, etc.)
-//!   - `secrets.toml` — OAuth refresh token; must be 0600
+//!   - `secrets.toml` — OAuth refresh token; must be 0600, optionally encrypted
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize, Clone)]
-pub struct AuthConfig {
-    pub client_id: String,
-    pub client_secret: String,
-    pub secrets_file: String,
-}
+pub use carapace_google::{AuthConfig, Secrets};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct GmailAccountConfig {
@@ -75,6 +70,10 @@ pub struct ProxyConfig {
     pub socket_path: String,
     #[serde(default = "default_concurrency")]
     pub search_fetch_concurrency: usize,
+    /// Where the `/changes` history cursor is persisted. Relative paths are
+    /// resolved against the config directory.
+    #[serde(default = "default_history_cursor_file")]
+    pub history_cursor_file: String,
 }
 
 impl Default for ProxyConfig {
@@ -82,6 +81,7 @@ impl Default for ProxyConfig {
         Self {
             socket_path: default_socket_path(),
             search_fetch_concurrency: default_concurrency(),
+            history_cursor_file: default_history_cursor_file(),
         }
     }
 }
@@ -94,9 +94,36 @@ fn default_concurrency() -> usize {
     4
 }
 
+fn default_history_cursor_file() -> String {
+    "history-cursor.json".into()
+}
+
+/// Limits for `GET /attachment/{message_id}/{attachment_id}`.
 #[derive(Debug, Deserialize, Clone)]
-pub struct Secrets {
-    pub refresh_token: String,
+pub struct AttachmentConfig {
+    /// Attachments larger than this are refused without being downloaded.
+    #[serde(default = "default_attachment_max_bytes")]
+    pub max_bytes: u32,
+    /// Extracted text is truncated to this many characters.
+    #[serde(default = "default_attachment_max_chars")]
+    pub max_text_chars: usize,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: default_attachment_max_bytes(),
+            max_text_chars: default_attachment_max_chars(),
+        }
+    }
+}
+
+fn default_attachment_max_bytes() -> u32 {
+    10 * 1024 * 1024
+}
+
+fn default_attachment_max_chars() -> usize {
+    100_000
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -107,6 +134,8 @@ pub struct ConfigFile {
     pub scrub: ScrubConfig,
     #[serde(default)]
     pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub attachments: AttachmentConfig,
 }
 
 #[derive(Debug, Clone)]
@@ -115,6 +144,7 @@ pub struct Config {
     pub gmail: GmailAccountConfig,
     pub scrub: ScrubConfig,
     pub proxy: ProxyConfig,
+    pub attachments: AttachmentConfig,
     pub secrets: Secrets,
 }
 
@@ -137,8 +167,9 @@ pub fn validate_patterns(config: &ConfigFile) -> Result<()> {
 
 /// Load configuration from the given TOML config file path.
 ///
-/// Resolves the secrets file relative to the config file's parent directory.
-/// On Unix, enforces that the secrets file has 0600 permissions.
+/// Resolves the secrets file relative to the config file's parent directory,
+/// decrypting it if `[auth.encryption]` is set. On Unix, enforces that the
+/// secrets file has 0600 permissions.
 pub fn load_config(path: &Path, skip_permission_check: bool) -> Result<Config> {
     let text = std::fs::read_to_string(path)
         .with_context(|| format!("failed to read config file: {}", path.display()))?;
@@ -149,36 +180,20 @@ pub fn load_config(path: &Path, skip_permission_check: bool) -> Result<Config> {
     validate_patterns(&file).context("config pattern validation failed")?;
 
     let config_dir = path.parent().unwrap_or(Path::new("."));
-    let secrets_path = config_dir.join(&file.auth.secrets_file);
-
-    if !skip_permission_check {
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let metadata = std::fs::metadata(&secrets_path)
-                .with_context(|| format!("failed to stat secrets file: {}", secrets_path.display()))?;
-            let mode = metadata.permissions().mode() & 0o777;
-            if mode != 0o600 {
-                anyhow::bail!(
-                    "secrets file {} has permissions {:04o}, expected 0600",
-                    secrets_path.display(),
-                    mode
-                );
-            }
-        }
-    }
-
-    let secrets_text = std::fs::read_to_string(&secrets_path)
-        .with_context(|| format!("failed to read secrets file: {}", secrets_path.display()))?;
+    let mut proxy = file.proxy;
+    proxy.history_cursor_file = config_dir
+        .join(&proxy.history_cursor_file)
+        .to_string_lossy()
+        .into_owned();
 
-    let secrets: Secrets = toml::from_str(&secrets_text)
-        .with_context(|| format!("failed to parse secrets file: {}", secrets_path.display()))?;
+    let secrets = file.auth.secret_store(config_dir, skip_permission_check)?.load()?;
 
     Ok(Config {
         auth: file.auth,
         gmail: file.gmail,
         scrub: file.scrub,
-        proxy: file.proxy,
+        proxy,
+        attachments: file.attachments,
         secrets,
     })
 }
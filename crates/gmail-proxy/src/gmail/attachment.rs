@@ -0,0 +1,92 @@
+//! Attachment text extraction.
+//!
+//! Only a small allowlist of types is ever decoded: plain text, CSV/TSV,
+//! markdown and the text layer of PDFs. Everything else is listed but never
+//! read, so the agent cannot pull arbitrary binaries through the proxy.
+
+use anyhow::Result;
+
+pub use carapace_google::text::truncate_chars;
+
+/// The extraction strategy for an allowlisted attachment type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentKind {
+    /// UTF-8 (or near enough) text: .txt, .csv, .tsv, .md
+    Text,
+    /// PDF — the embedded text layer is extracted; scanned images yield nothing.
+    Pdf,
+}
+
+const TEXT_MIME_TYPES: &[&str] = &[
+    "text/plain",
+    "text/csv",
+    "text/tab-separated-values",
+    "text/markdown",
+    "application/csv",
+];
+
+const TEXT_EXTENSIONS: &[&str] = &["txt", "csv", "tsv", "md"];
+
+/// Decide whether an attachment can be read, based on MIME type and filename.
+///
+/// Senders frequently label CSVs and PDFs as `application/octet-stream`, so the
+/// extension is consulted when the MIME type is generic.
+pub fn classify_attachment(mime_type: &str, filename: &str) -> Option<AttachmentKind> {
+    let mime = mime_type.to_ascii_lowercase();
+    let ext = filename
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if mime == "application/pdf" {
+        return Some(AttachmentKind::Pdf);
+    }
+    if TEXT_MIME_TYPES.contains(&mime.as_str()) {
+        return Some(AttachmentKind::Text);
+    }
+    if mime.is_empty() || mime == "application/octet-stream" {
+        if ext == "pdf" {
+            return Some(AttachmentKind::Pdf);
+        }
+        if TEXT_EXTENSIONS.contains(&ext.as_str()) {
+            return Some(AttachmentKind::Text);
+        }
+    }
+    None
+}
+
+/// Extract text from raw attachment bytes (PDFs via
+/// [`carapace_google::text::extract_pdf_text`]).
+pub async fn extract_text(kind: AttachmentKind, bytes: Vec<u8>) -> Result<String> {
+    match kind {
+        AttachmentKind::Text => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        AttachmentKind::Pdf => carapace_google::text::extract_pdf_text(bytes).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_mime_type() {
+        assert_eq!(classify_attachment("application/pdf", "invoice"), Some(AttachmentKind::Pdf));
+        assert_eq!(classify_attachment("text/csv", "report.csv"), Some(AttachmentKind::Text));
+        assert_eq!(classify_attachment("image/png", "photo.png"), None);
+    }
+
+    #[test]
+    fn test_classify_octet_stream_uses_extension() {
+        assert_eq!(
+            classify_attachment("application/octet-stream", "Invoice.PDF"),
+            Some(AttachmentKind::Pdf)
+        );
+        assert_eq!(
+            classify_attachment("application/octet-stream", "data.csv"),
+            Some(AttachmentKind::Text)
+        );
+        assert_eq!(classify_attachment("application/octet-stream", "setup.exe"), None);
+        // A specific non-text MIME type is not overridden by the extension.
+        assert_eq!(classify_attachment("application/zip", "notes.txt"), None);
+    }
+}
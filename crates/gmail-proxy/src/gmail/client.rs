@@ -16,14 +16,6 @@ pub struct GmailClient {
     pub account: String,
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct WatchRequestBody<'a> {
-    topic_name: &'a str,
-    label_ids: &'a [String],
-    label_filter_behavior: &'a str,
-}
-
 #[derive(Serialize)]
 struct DraftBody {
     message: DraftMessage,
@@ -70,6 +62,14 @@ impl GmailClient {
     }
 
     pub async fn get_message(&self, id: &str) -> Result<Message> {
+        self.find_message(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Gmail API error {}", reqwest::StatusCode::NOT_FOUND))
+    }
+
+    /// Like [`get_message`](Self::get_message), but `None` when Gmail reports
+    /// the message as gone (404), e.g. deleted after a history record.
+    pub async fn find_message(&self, id: &str) -> Result<Option<Message>> {
         let auth = self.auth_header().await?;
         let resp = self
             .http_client
@@ -79,8 +79,11 @@ impl GmailClient {
             .send()
             .await
             .context("get_message request failed")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
         check_status(&resp)?;
-        resp.json().await.context("failed to deserialize message")
+        resp.json().await.map(Some).context("failed to deserialize message")
     }
 
     pub async fn get_thread(&self, id: &str) -> Result<ThreadResponse> {
@@ -97,6 +100,19 @@ impl GmailClient {
         resp.json().await.context("failed to deserialize thread")
     }
 
+    pub async fn get_attachment(&self, message_id: &str, attachment_id: &str) -> Result<AttachmentBody> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/messages/{message_id}/attachments/{attachment_id}", self.base_url))
+            .header("Authorization", &auth)
+            .send()
+            .await
+            .context("get_attachment request failed")?;
+        check_status(&resp)?;
+        resp.json().await.context("failed to deserialize attachment")
+    }
+
     pub async fn list_labels(&self) -> Result<LabelListResponse> {
         let auth = self.auth_header().await?;
         let resp = self
@@ -110,36 +126,64 @@ impl GmailClient {
         resp.json().await.context("failed to deserialize labels")
     }
 
-    pub async fn history(&self, start_history_id: u64) -> Result<HistoryResponse> {
+    /// List `messageAdded` history records after `start_history_id`.
+    ///
+    /// Returns `Ok(None)` when Gmail no longer has history that far back
+    /// (HTTP 404) — the caller must resynchronise from the current profile.
+    pub async fn history(
+        &self,
+        start_history_id: u64,
+        page_token: Option<&str>,
+    ) -> Result<Option<HistoryResponse>> {
         let auth = self.auth_header().await?;
-        let resp = self
+        let mut req = self
             .http_client
             .get(format!("{}/history", self.base_url))
             .header("Authorization", &auth)
             .query(&[
                 ("startHistoryId", &start_history_id.to_string()),
                 ("historyTypes", &"messageAdded".to_string()),
-            ])
+            ]);
+        if let Some(pt) = page_token {
+            req = req.query(&[("pageToken", pt)]);
+        }
+        let resp = req.send().await.context("history request failed")?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        check_status(&resp)?;
+        resp.json().await.map(Some).context("failed to deserialize history response")
+    }
+
+    /// Fetch the mailbox profile (used for the current history ID).
+    pub async fn get_profile(&self) -> Result<Profile> {
+        let auth = self.auth_header().await?;
+        let resp = self
+            .http_client
+            .get(format!("{}/profile", self.base_url))
+            .header("Authorization", &auth)
             .send()
             .await
-            .context("history request failed")?;
+            .context("get_profile request failed")?;
         check_status(&resp)?;
-        resp.json().await.context("failed to deserialize history response")
+        resp.json().await.context("failed to deserialize profile")
     }
 
     /// Create a draft email.
     ///
-    /// Builds a minimal RFC 2822 message, base64url-encodes it, and posts to
-    /// `drafts.create`. The OAuth scope `gmail.compose` is required.
+    /// Builds a minimal RFC 2822 message (multipart/alternative when `html` is
+    /// given), base64url-encodes it, and posts to `drafts.create`. The OAuth
+    /// scope `gmail.compose` is required.
     pub async fn create_draft(
         &self,
         to: &str,
         subject: &str,
         body: &str,
         cc: Option<&str>,
+        html: Option<&str>,
     ) -> Result<DraftResponse> {
         let auth = self.auth_header().await?;
-        let raw = build_raw_message(&self.account, to, cc, subject, body);
+        let raw = build_raw_message(&self.account, to, cc, subject, body, html);
         let payload = DraftBody {
             message: DraftMessage { raw },
         };
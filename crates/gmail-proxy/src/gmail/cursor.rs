@@ -0,0 +1,104 @@
+//! Persisted Gmail history cursor for `GET /changes`.
+//!
+//! The cursor is the last history ID whose changes have been handed to the
+//! daemon. It is stored as a small JSON file so a proxy restart resumes where
+//! it left off instead of re-delivering (or silently skipping) mail.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorFile {
+    history_id: u64,
+}
+
+pub struct HistoryCursor {
+    path: PathBuf,
+    current: Mutex<Option<u64>>,
+}
+
+impl HistoryCursor {
+    /// Load the cursor from `path`. A missing file yields an empty cursor.
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let current = match std::fs::read_to_string(&path) {
+            Ok(text) => {
+                let file: CursorFile = serde_json::from_str(&text)
+                    .with_context(|| format!("failed to parse history cursor: {}", path.display()))?;
+                Some(file.history_id)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to read history cursor: {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            current: Mutex::new(current),
+        })
+    }
+
+    pub async fn get(&self) -> Option<u64> {
+        *self.current.lock().await
+    }
+
+    /// Move the cursor forward to `history_id`. Never moves it backwards.
+    pub async fn advance(&self, history_id: u64) -> Result<()> {
+        let mut current = self.current.lock().await;
+        if current.is_some_and(|c| c >= history_id) {
+            return Ok(());
+        }
+        write_atomic(&self.path, history_id)?;
+        *current = Some(history_id);
+        Ok(())
+    }
+
+    /// Replace the cursor unconditionally (used after Gmail expires history).
+    pub async fn reset(&self, history_id: u64) -> Result<()> {
+        let mut current = self.current.lock().await;
+        write_atomic(&self.path, history_id)?;
+        *current = Some(history_id);
+        Ok(())
+    }
+}
+
+fn write_atomic(path: &Path, history_id: u64) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create cursor dir {}", parent.display()))?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    let body = serde_json::to_string(&CursorFile { history_id })?;
+    std::fs::write(&tmp, body)
+        .with_context(|| format!("failed to write history cursor: {}", tmp.display()))?;
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("failed to replace history cursor: {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cursor_persists_and_only_advances() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursor.json");
+
+        let cursor = HistoryCursor::load(&path).unwrap();
+        assert_eq!(cursor.get().await, None);
+
+        cursor.advance(100).await.unwrap();
+        cursor.advance(50).await.unwrap();
+        assert_eq!(cursor.get().await, Some(100));
+
+        let reloaded = HistoryCursor::load(&path).unwrap();
+        assert_eq!(reloaded.get().await, Some(100));
+
+        reloaded.reset(7).await.unwrap();
+        assert_eq!(HistoryCursor::load(&path).unwrap().get().await, Some(7));
+    }
+}
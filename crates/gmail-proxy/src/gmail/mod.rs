@@ -1,2 +1,4 @@
+pub mod attachment;
 pub mod client;
+pub mod cursor;
 pub mod types;
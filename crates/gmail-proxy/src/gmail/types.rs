@@ -1,9 +1,12 @@
 //! Data types for Gmail API responses and sanitized output.
 
 use base64::Engine;
-use base64::engine::general_purpose::{URL_SAFE, URL_SAFE_NO_PAD};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE, URL_SAFE_NO_PAD};
 use serde::{Deserialize, Serialize};
 
+use crate::gmail::attachment::classify_attachment;
+use crate::scrub::html::{html_to_text, sanitize_html};
+
 // ---------------------------------------------------------------------------
 // Gmail API response types
 // ---------------------------------------------------------------------------
@@ -21,6 +24,8 @@ pub struct MessageListResponse {
 pub struct MessageRef {
     pub id: String,
     pub thread_id: String,
+    /// Present on history records; absent on search results.
+    pub label_ids: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -37,7 +42,9 @@ pub struct Message {
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MessagePart {
+    pub part_id: Option<String>,
     pub mime_type: Option<String>,
+    pub filename: Option<String>,
     pub headers: Option<Vec<Header>>,
     pub body: Option<MessagePartBody>,
     pub parts: Option<Vec<MessagePart>>,
@@ -50,7 +57,16 @@ pub struct Header {
 }
 
 #[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct MessagePartBody {
+    pub attachment_id: Option<String>,
+    pub data: Option<String>,
+    pub size: Option<u32>,
+}
+
+/// Response from Gmail `messages.attachments.get`.
+#[derive(Debug, Deserialize)]
+pub struct AttachmentBody {
     pub data: Option<String>,
     pub size: Option<u32>,
 }
@@ -91,19 +107,21 @@ pub struct HistoryResponse {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HistoryRecord {
+    pub id: String,
     pub messages_added: Option<Vec<MessageAdded>>,
 }
 
+/// Response from Gmail `users.getProfile`.
 #[derive(Debug, Deserialize)]
-pub struct MessageAdded {
-    pub message: MessageRef,
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub email_address: String,
+    pub history_id: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct TokenResponse {
-    pub access_token: String,
-    pub expires_in: u64,
-    pub token_type: String,
+pub struct MessageAdded {
+    pub message: MessageRef,
 }
 
 // ---------------------------------------------------------------------------
@@ -119,17 +137,9 @@ pub struct CreateDraftRequest {
     /// Optional CC addresses (comma-separated).
     #[serde(default)]
     pub cc: Option<String>,
-}
-
-/// Minimal draft body sent to the Gmail API.
-#[derive(Debug, Serialize)]
-struct GmailDraftBody {
-    message: GmailDraftMessage,
-}
-
-#[derive(Debug, Serialize)]
-struct GmailDraftMessage {
-    raw: String,
+    /// Optional HTML alternative. Sanitized to a safe tag subset before use.
+    #[serde(default)]
+    pub html_body: Option<String>,
 }
 
 /// Response from Gmail `drafts.create`.
@@ -164,51 +174,56 @@ pub struct SanitizedMessage {
     pub has_attachments: bool,
 }
 
+/// One attachment as listed by `GET /message/{id}/attachments`.
+#[derive(Debug, Serialize, Clone)]
+pub struct AttachmentInfo {
+    pub attachment_id: String,
+    pub part_id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u32,
+    /// Whether the proxy can extract text from this attachment type.
+    pub readable: bool,
+}
+
+/// An attachment located inside a message's MIME tree.
+#[derive(Debug, Clone)]
+pub struct AttachmentPart {
+    pub info: AttachmentInfo,
+    /// Inline base64url data, when Gmail embedded it in the message itself.
+    pub inline_data: Option<String>,
+}
+
 // ---------------------------------------------------------------------------
 // Helper methods on Message
 // ---------------------------------------------------------------------------
 
 impl Message {
     /// Extract plain text body from MIME parts, preferring text/plain over text/html.
+    ///
+    /// When the message has no text/plain part anywhere in its MIME tree, the
+    /// first text/html part is converted to plain text instead.
     pub fn extract_text_body(&self) -> Option<String> {
         let payload = self.payload.as_ref()?;
-        Self::extract_text_from_part(payload)
+        Self::find_part_text(payload, "text/plain")
+            .or_else(|| Self::find_part_text(payload, "text/html").map(|html| html_to_text(&html)))
     }
 
-    fn extract_text_from_part(part: &MessagePart) -> Option<String> {
+    /// Depth-first search for the first decodable part with the given MIME type.
+    fn find_part_text(part: &MessagePart, wanted: &str) -> Option<String> {
         let mime = part.mime_type.as_deref().unwrap_or("");
 
-        if mime == "text/plain" {
-            if let Some(body) = &part.body {
-                if let Some(data) = &body.data {
-                    return Self::decode_base64url(data);
-                }
-            }
-            return None;
+        if mime.eq_ignore_ascii_case(wanted) {
+            return part
+                .body
+                .as_ref()
+                .and_then(|b| b.data.as_deref())
+                .and_then(Self::decode_base64url);
         }
 
         if mime.starts_with("multipart/") {
             if let Some(parts) = &part.parts {
-                // First pass: prefer text/plain
-                for sub in parts {
-                    let sub_mime = sub.mime_type.as_deref().unwrap_or("");
-                    if sub_mime == "text/plain" || sub_mime.starts_with("multipart/") {
-                        if let Some(text) = Self::extract_text_from_part(sub) {
-                            return Some(text);
-                        }
-                    }
-                }
-                // Second pass: fall back to text/html
-                for sub in parts {
-                    let sub_mime = sub.mime_type.as_deref().unwrap_or("");
-                    if sub_mime == "text/html" {
-                        if let Some(body) = &sub.body {
-                            if let Some(data) = &body.data {
-                                return Self::decode_base64url(data);
-                            }
-                        }
-                    }
-                }
+                return parts.iter().find_map(|sub| Self::find_part_text(sub, wanted));
             }
         }
 
@@ -216,11 +231,7 @@ impl Message {
     }
 
     fn decode_base64url(data: &str) -> Option<String> {
-        URL_SAFE
-            .decode(data)
-            .or_else(|_| URL_SAFE_NO_PAD.decode(data))
-            .ok()
-            .and_then(|bytes| String::from_utf8(bytes).ok())
+        Self::decode_base64url_bytes(data).and_then(|bytes| String::from_utf8(bytes).ok())
     }
 
     /// Case-insensitive header lookup.
@@ -266,6 +277,48 @@ impl Message {
         false
     }
 
+    /// List every part that carries a filename (i.e. an attachment).
+    pub fn attachments(&self) -> Vec<AttachmentPart> {
+        let mut found = Vec::new();
+        if let Some(payload) = &self.payload {
+            Self::collect_attachments(payload, &mut found);
+        }
+        found
+    }
+
+    fn collect_attachments(part: &MessagePart, found: &mut Vec<AttachmentPart>) {
+        let filename = part.filename.as_deref().unwrap_or("");
+        if !filename.is_empty() {
+            let mime_type = part.mime_type.clone().unwrap_or_default();
+            let body = part.body.as_ref();
+            let part_id = part.part_id.clone().unwrap_or_default();
+            let attachment_id = body
+                .and_then(|b| b.attachment_id.clone())
+                .unwrap_or_else(|| part_id.clone());
+            found.push(AttachmentPart {
+                info: AttachmentInfo {
+                    readable: classify_attachment(&mime_type, filename).is_some(),
+                    attachment_id,
+                    part_id,
+                    filename: filename.to_string(),
+                    mime_type,
+                    size: body.and_then(|b| b.size).unwrap_or(0),
+                },
+                inline_data: body.and_then(|b| b.data.clone()),
+            });
+        }
+        if let Some(parts) = &part.parts {
+            for sub in parts {
+                Self::collect_attachments(sub, found);
+            }
+        }
+    }
+
+    /// Decode a base64url payload as returned by the Gmail API.
+    pub fn decode_base64url_bytes(data: &str) -> Option<Vec<u8>> {
+        URL_SAFE.decode(data).or_else(|_| URL_SAFE_NO_PAD.decode(data)).ok()
+    }
+
     /// Convert to SanitizedMessage using a pre-scrubbed body text.
     pub fn to_sanitized(&self, scrubbed_body: String) -> SanitizedMessage {
         SanitizedMessage {
@@ -284,14 +337,108 @@ impl Message {
 }
 
 /// Build a base64url-encoded RFC 2822 message for the Gmail API.
-pub fn build_raw_message(from: &str, to: &str, cc: Option<&str>, subject: &str, body: &str) -> String {
-    let mut raw = format!(
-        "From: {from}\r\nTo: {to}\r\nSubject: {subject}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=UTF-8\r\n"
-    );
+///
+/// With `html` set, the message becomes `multipart/alternative` with the
+/// plain-text body first and the sanitized HTML second. An empty `body` is
+/// filled in from the HTML so text-only clients still see content. The HTML
+/// part is base64-encoded: sanitized markup often comes out as one long line,
+/// and RFC 5322 caps lines at 998 characters.
+pub fn build_raw_message(
+    from: &str,
+    to: &str,
+    cc: Option<&str>,
+    subject: &str,
+    body: &str,
+    html: Option<&str>,
+) -> String {
+    let mut raw = format!("From: {from}\r\nTo: {to}\r\nSubject: {subject}\r\nMIME-Version: 1.0\r\n");
     if let Some(cc_addr) = cc {
         raw.push_str(&format!("Cc: {cc_addr}\r\n"));
     }
-    raw.push_str("\r\n");
-    raw.push_str(body);
+
+    match html.filter(|h| !h.trim().is_empty()) {
+        None => {
+            raw.push_str("Content-Type: text/plain; charset=UTF-8\r\n\r\n");
+            raw.push_str(body);
+        }
+        Some(html) => {
+            let html = sanitize_html(html);
+            let text = if body.trim().is_empty() { html_to_text(&html) } else { body.to_string() };
+            // Base64 has no '-', so only the text part can collide with the boundary.
+            let boundary = mime_boundary(&[&text]);
+
+            raw.push_str(&format!(
+                "Content-Type: multipart/alternative; boundary=\"{boundary}\"\r\n\r\n"
+            ));
+            raw.push_str(&format!(
+                "--{boundary}\r\nContent-Type: text/plain; charset=UTF-8\r\n\r\n{text}\r\n"
+            ));
+            raw.push_str(&format!(
+                "--{boundary}\r\nContent-Type: text/html; charset=UTF-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+                mime_base64(html.as_bytes())
+            ));
+            raw.push_str(&format!("--{boundary}--\r\n"));
+        }
+    }
+
     URL_SAFE_NO_PAD.encode(raw.as_bytes())
 }
+
+/// Base64-encode a MIME part body in 76-character CRLF-terminated lines.
+fn mime_base64(data: &[u8]) -> String {
+    let encoded = STANDARD.encode(data);
+    let mut out = String::with_capacity(encoded.len() + encoded.len() / 38 + 2);
+    for line in encoded.as_bytes().chunks(76) {
+        // Base64 output is ASCII, so every chunk is valid UTF-8.
+        out.push_str(std::str::from_utf8(line).unwrap_or_default());
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// Pick a multipart boundary that does not occur in any of the parts.
+fn mime_boundary(parts: &[&str]) -> String {
+    let seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let mut n = 0u32;
+    loop {
+        let candidate = format!("carapace-{seed:x}-{n}");
+        if parts.iter().all(|p| !p.contains(&candidate)) {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_raw(raw: &str) -> String {
+        String::from_utf8(URL_SAFE_NO_PAD.decode(raw).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn html_part_is_base64_within_line_limit() {
+        let html = format!("<p>{}</p>", "word ".repeat(400));
+        let raw = decode_raw(&build_raw_message("a@x.com", "b@x.com", None, "Hi", "plain", Some(&html)));
+
+        assert!(raw.contains("Content-Type: text/html; charset=UTF-8\r\nContent-Transfer-Encoding: base64\r\n"));
+        assert!(raw.split("\r\n").all(|line| line.len() <= 998), "RFC 5322 line limit");
+
+        let boundary = raw.split("boundary=\"").nth(1).unwrap().split('"').next().unwrap();
+        let html_part = raw.split(&format!("--{boundary}")).nth(2).unwrap();
+        let encoded: String = html_part.split("\r\n\r\n").nth(1).unwrap().split("\r\n").collect();
+        let decoded = String::from_utf8(STANDARD.decode(encoded).unwrap()).unwrap();
+        assert!(decoded.starts_with("<p>word word"));
+    }
+
+    #[test]
+    fn plain_only_message_has_no_multipart() {
+        let raw = decode_raw(&build_raw_message("a@x.com", "b@x.com", Some("c@x.com"), "Hi", "hello", None));
+        assert!(raw.contains("Cc: c@x.com\r\n"));
+        assert!(raw.ends_with("Content-Type: text/plain; charset=UTF-8\r\n\r\nhello"));
+    }
+}
@@ -3,3 +3,6 @@ pub mod config;
 pub mod gmail;
 pub mod proxy;
 pub mod scrub;
+
+#[cfg(test)]
+mod testing;
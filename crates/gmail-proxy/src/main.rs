@@ -2,7 +2,11 @@
 //!
 //! Usage:
 //!   gmail-proxy setup  --config /etc/carapace/gmail-proxy.toml [--client-json /path/to/client_secret.json]
+//!   gmail-proxy reauth --config /etc/carapace/gmail-proxy.toml [--revoke-old]
+//!   gmail-proxy migrate-secrets --config /etc/carapace/gmail-proxy.toml
 //!   gmail-proxy serve  [--config /path/to/config.toml]
+//!
+//! `setup` and `reauth` take `--flow browser|device` and `--access`.
 
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -10,9 +14,11 @@ use std::sync::Arc;
 use anyhow::Context;
 use clap::{Parser, Subcommand};
 
-use gmail_proxy::auth::{run_oauth_setup, TokenManager};
+use carapace_google::FlowArgs;
+use gmail_proxy::auth::{run_migrate_secrets, run_oauth_setup, run_reauth, Access, TokenManager, COMPOSE_SCOPE};
 use gmail_proxy::config;
 use gmail_proxy::gmail::client::GmailClient;
+use gmail_proxy::gmail::cursor::HistoryCursor;
 use gmail_proxy::proxy::routes::{build_router, AppState};
 use gmail_proxy::scrub::content::ContentScrubber;
 use gmail_proxy::scrub::labels::LabelFilter;
@@ -26,7 +32,7 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Command {
-    /// Interactive OAuth setup — runs once to obtain and store the refresh token.
+    /// OAuth setup — runs once to obtain and store the refresh token.
     Setup {
         /// Path to config.toml
         #[arg(long, default_value = "/etc/carapace/gmail-proxy.toml")]
@@ -35,6 +41,31 @@ enum Command {
         /// If provided, client_id and client_secret are extracted and saved into config.toml.
         #[arg(long)]
         client_json: Option<PathBuf>,
+        /// What the proxy may do; only the scopes this needs are requested.
+        #[arg(long, value_enum, default_value_t = Access::Compose)]
+        access: Access,
+        #[command(flatten)]
+        flow: FlowArgs,
+    },
+    /// Replace the stored refresh token, e.g. to rotate it or change --access.
+    Reauth {
+        /// Path to config.toml
+        #[arg(long, default_value = "/etc/carapace/gmail-proxy.toml")]
+        config: PathBuf,
+        #[arg(long, value_enum, default_value_t = Access::Compose)]
+        access: Access,
+        #[command(flatten)]
+        flow: FlowArgs,
+        /// Revoke the current token before requesting a new one. The running
+        /// proxy loses access until it is restarted with the new token.
+        #[arg(long)]
+        revoke_old: bool,
+    },
+    /// Encrypt the plaintext secrets file as configured in [auth.encryption].
+    MigrateSecrets {
+        /// Path to config.toml
+        #[arg(long, default_value = "/etc/carapace/gmail-proxy.toml")]
+        config: PathBuf,
     },
     /// Run the proxy server.
     Serve {
@@ -48,8 +79,14 @@ enum Command {
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
     match cli.command {
-        Command::Setup { config, client_json } => {
-            run_oauth_setup(config, client_json).await?;
+        Command::Setup { config, client_json, access, flow } => {
+            run_oauth_setup(config, client_json, access, flow).await?;
+        }
+        Command::Reauth { config, access, flow, revoke_old } => {
+            run_reauth(config, access, flow, revoke_old).await?;
+        }
+        Command::MigrateSecrets { config } => {
+            run_migrate_secrets(config)?;
         }
         Command::Serve { config } => {
             serve(config).await?;
@@ -79,7 +116,7 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
         cfg.auth.client_id.clone(),
         cfg.auth.client_secret.clone(),
         cfg.secrets.refresh_token.clone(),
-        "https://oauth2.googleapis.com/token".into(),
+        carapace_google::TOKEN_URL.into(),
     ));
     token_manager
         .get_token()
@@ -100,10 +137,9 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
         .await
         .context("Failed to list Gmail labels")?;
 
+    let labels = labels.labels.unwrap_or_default();
     let blocked_label = labels
-        .labels
-        .unwrap_or_default()
-        .into_iter()
+        .iter()
         .find(|l| l.name.eq_ignore_ascii_case(&cfg.scrub.blocked_label))
         .ok_or_else(|| {
             anyhow::anyhow!(
@@ -119,6 +155,31 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
         "Resolved blocked label"
     );
 
+    // Resolve watch labels (default: INBOX) to IDs for `/changes`.
+    let watch_labels = if cfg.gmail.watch_labels.is_empty() {
+        vec!["INBOX".to_string()]
+    } else {
+        cfg.gmail.watch_labels.clone()
+    };
+    let mut watch_label_ids = Vec::new();
+    for name in &watch_labels {
+        let label = labels
+            .iter()
+            .find(|l| l.name.eq_ignore_ascii_case(name) || l.id == *name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Watch label '{name}' not found in Gmail. Check [gmail] watch_labels in config.toml."
+                )
+            })?;
+        watch_label_ids.push(label.id.clone());
+    }
+    tracing::info!(labels = ?watch_labels, "Resolved watch labels");
+
+    let cursor = Arc::new(
+        HistoryCursor::load(&cfg.proxy.history_cursor_file)
+            .context("Failed to load history cursor")?,
+    );
+
     // Build content scrubber from compiled regexes.
     let scrubber = Arc::new(ContentScrubber::new(
         cfg.scrub.otp_patterns.iter()
@@ -133,6 +194,11 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
         cfg.scrub.strip_links,
     ));
 
+    let drafts_allowed = cfg.secrets.has_scope(COMPOSE_SCOPE);
+    if !drafts_allowed {
+        tracing::info!("Token is read-only — drafts are refused");
+    }
+
     let label_filter = Arc::new(LabelFilter::new(
         blocked_label.id.clone(),
         blocked_label.name.clone(),
@@ -146,6 +212,11 @@ async fn serve(config_path: Option<PathBuf>) -> anyhow::Result<()> {
         blocked_label: cfg.scrub.blocked_label.clone(),
         max_query_depth: 10,
         search_concurrency: cfg.proxy.search_fetch_concurrency,
+        attachment_max_bytes: cfg.attachments.max_bytes,
+        attachment_max_chars: cfg.attachments.max_text_chars,
+        cursor,
+        drafts_allowed,
+        watch_label_ids,
         token_manager: token_manager.clone(),
         start_time: std::time::Instant::now(),
     });
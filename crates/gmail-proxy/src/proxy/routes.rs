@@ -3,8 +3,11 @@
 //! Endpoints:
 //!   GET  /search?q=<query>&max=<n>&page_token=<token>
 //!   GET  /message/{id}
+//!   GET  /message/{id}/attachments
+//!   GET  /attachment/{message_id}/{attachment_id}
 //!   GET  /thread/{id}
-//!   POST /drafts          body: { to, subject, body, cc? }
+//!   GET  /changes?since=<historyId>&max=<n>
+//!   POST /drafts          body: { to, subject, body, cc?, html_body? }
 //!   GET  /health
 
 use std::sync::Arc;
@@ -14,11 +17,12 @@ use axum::http::StatusCode;
 use axum::Json;
 use futures::stream::{self, StreamExt};
 use serde::Deserialize;
-use tokio::sync::RwLock;
 
 use crate::auth::TokenManager;
+use crate::gmail::attachment::{classify_attachment, extract_text, truncate_chars};
 use crate::gmail::client::GmailClient;
-use crate::gmail::types::CreateDraftRequest;
+use crate::gmail::cursor::HistoryCursor;
+use crate::gmail::types::{CreateDraftRequest, Message};
 use crate::scrub::content::ContentScrubber;
 use crate::scrub::labels::LabelFilter;
 use crate::scrub::query::{parse_query, validate_query};
@@ -31,6 +35,14 @@ pub struct AppState {
     pub blocked_label: String,
     pub max_query_depth: usize,
     pub search_concurrency: usize,
+    pub attachment_max_bytes: u32,
+    pub attachment_max_chars: usize,
+    pub cursor: Arc<HistoryCursor>,
+    /// The token has the compose scope; without it `POST /drafts` is refused.
+    pub drafts_allowed: bool,
+    /// Label IDs resolved from `[gmail] watch_labels`; `/changes` only
+    /// reports messages carrying at least one of them.
+    pub watch_label_ids: Vec<String>,
     pub token_manager: Arc<TokenManager>,
     pub start_time: std::time::Instant,
 }
@@ -42,11 +54,23 @@ pub struct SearchParams {
     pub page_token: Option<String>,
 }
 
+#[derive(Deserialize)]
+pub struct ChangesParams {
+    pub since: Option<u64>,
+    pub max: Option<usize>,
+}
+
 pub fn build_router(state: Arc<AppState>) -> axum::Router {
     axum::Router::new()
         .route("/search", axum::routing::get(search_handler))
         .route("/message/{id}", axum::routing::get(get_message_handler))
+        .route("/message/{id}/attachments", axum::routing::get(list_attachments_handler))
+        .route(
+            "/attachment/{message_id}/{attachment_id}",
+            axum::routing::get(get_attachment_handler),
+        )
         .route("/thread/{id}", axum::routing::get(get_thread_handler))
+        .route("/changes", axum::routing::get(changes_handler))
         .route("/drafts", axum::routing::post(create_draft_handler))
         .route("/health", axum::routing::get(health_handler))
         .with_state(state)
@@ -163,7 +187,23 @@ async fn get_message_handler(
     State(state): State<Arc<AppState>>,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    let msg = state.gmail.get_message(&id).await.map_err(|e| {
+    let msg = fetch_visible_message(&state, &id).await?;
+
+    let body = msg.extract_text_body().unwrap_or_default();
+    let scrubbed = state.scrubber.scrub_body(&body);
+    let sanitized = msg.to_sanitized(scrubbed);
+
+    Ok(Json(serde_json::to_value(sanitized).unwrap()))
+}
+
+/// Fetch a message and apply the blocked-label and blocked-sender rules.
+///
+/// Hidden messages are reported as 404 so their existence is not revealed.
+async fn fetch_visible_message(
+    state: &AppState,
+    id: &str,
+) -> Result<Message, (StatusCode, Json<serde_json::Value>)> {
+    let msg = state.gmail.get_message(id).await.map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(serde_json::json!({"error": format!("Failed to fetch message: {e}")})),
@@ -186,11 +226,123 @@ async fn get_message_handler(
         ));
     }
 
-    let body = msg.extract_text_body().unwrap_or_default();
-    let scrubbed = state.scrubber.scrub_body(&body);
-    let sanitized = msg.to_sanitized(scrubbed);
+    Ok(msg)
+}
 
-    Ok(Json(serde_json::to_value(sanitized).unwrap()))
+// ---------------------------------------------------------------------------
+// GET /message/{id}/attachments
+// ---------------------------------------------------------------------------
+
+async fn list_attachments_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let msg = fetch_visible_message(&state, &id).await?;
+    let attachments: Vec<_> = msg.attachments().into_iter().map(|a| a.info).collect();
+
+    Ok(Json(serde_json::json!({
+        "message_id": id,
+        "attachments": attachments
+    })))
+}
+
+// ---------------------------------------------------------------------------
+// GET /attachment/{message_id}/{attachment_id}
+// ---------------------------------------------------------------------------
+
+async fn get_attachment_handler(
+    State(state): State<Arc<AppState>>,
+    Path((message_id, attachment_id)): Path<(String, String)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let msg = fetch_visible_message(&state, &message_id).await?;
+
+    // Gmail attachment IDs are not stable across fetches, so the part ID from
+    // the listing is accepted as well.
+    let part = msg
+        .attachments()
+        .into_iter()
+        .find(|a| a.info.attachment_id == attachment_id || a.info.part_id == attachment_id)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({"error": "Attachment not found"})),
+            )
+        })?;
+    let info = part.info;
+
+    let kind = classify_attachment(&info.mime_type, &info.filename).ok_or_else(|| {
+        (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(serde_json::json!({
+                "error": format!("Attachment type '{}' cannot be read", info.mime_type),
+                "hint": "Only text, CSV/TSV, markdown and PDF attachments can be read"
+            })),
+        )
+    })?;
+
+    if info.size > state.attachment_max_bytes {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(serde_json::json!({
+                "error": format!(
+                    "Attachment is {} bytes; the limit is {} bytes",
+                    info.size, state.attachment_max_bytes
+                )
+            })),
+        ));
+    }
+
+    let data = match part.inline_data {
+        Some(data) => data,
+        None => state
+            .gmail
+            .get_attachment(&message_id, &info.attachment_id)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Failed to fetch attachment: {e}")})),
+                )
+            })?
+            .data
+            .unwrap_or_default(),
+    };
+
+    let bytes = Message::decode_base64url_bytes(&data).ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({"error": "Failed to decode attachment data"})),
+        )
+    })?;
+    if bytes.len() > state.attachment_max_bytes as usize {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(serde_json::json!({
+                "error": format!("Attachment exceeds the {} byte limit", state.attachment_max_bytes)
+            })),
+        ));
+    }
+
+    let text = extract_text(kind, bytes).await.map_err(|e| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({"error": e.to_string()})),
+        )
+    })?;
+    let scrubbed = state.scrubber.scrub_body(&text);
+    let (text, truncated) = truncate_chars(scrubbed, state.attachment_max_chars);
+
+    tracing::info!(message_id = %message_id, filename = %info.filename, truncated, "attachment read");
+
+    Ok(Json(serde_json::json!({
+        "message_id": message_id,
+        "attachment_id": info.attachment_id,
+        "filename": info.filename,
+        "mime_type": info.mime_type,
+        "size": info.size,
+        "text": text,
+        "truncated": truncated
+    })))
 }
 
 // ---------------------------------------------------------------------------
@@ -231,6 +383,181 @@ async fn get_thread_handler(
     })))
 }
 
+// ---------------------------------------------------------------------------
+// GET /changes
+// ---------------------------------------------------------------------------
+
+fn internal_error(context: &str, e: anyhow::Error) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(serde_json::json!({"error": format!("{context}: {e}")})),
+    )
+}
+
+/// The mailbox's current history ID.
+async fn current_history_id(state: &AppState) -> Result<u64, (StatusCode, Json<serde_json::Value>)> {
+    let profile = state
+        .gmail
+        .get_profile()
+        .await
+        .map_err(|e| internal_error("Failed to fetch Gmail profile", e))?;
+    profile.history_id.parse().map_err(|_| {
+        internal_error(
+            "Invalid history ID from Gmail",
+            anyhow::anyhow!("{}", profile.history_id),
+        )
+    })
+}
+
+/// Resynchronise the cursor to the mailbox's current history ID.
+async fn reset_cursor(state: &AppState) -> Result<u64, (StatusCode, Json<serde_json::Value>)> {
+    let history_id = current_history_id(state).await?;
+    state
+        .cursor
+        .reset(history_id)
+        .await
+        .map_err(|e| internal_error("Failed to persist history cursor", e))?;
+    Ok(history_id)
+}
+
+/// Report messages added since `since` (or the persisted cursor).
+///
+/// Backed by Gmail `history.list`, so mail read elsewhere is still delivered
+/// and a restart resumes from the stored cursor. At most `max` messages are
+/// returned (`max=0` just reports the cursor); the response `history_id` is
+/// the cursor to pass next time and `more` is set when further changes are
+/// already pending. Only calls without `since` move the persisted cursor, so
+/// callers tracking their own position do not disturb it.
+async fn changes_handler(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<ChangesParams>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let max = params.max.unwrap_or(50).min(100);
+    let shared = params.since.is_none();
+
+    let since = match params.since {
+        Some(since) => since,
+        None => match state.cursor.get().await {
+            Some(since) => since,
+            None => {
+                // First run: start from "now" rather than replaying the mailbox.
+                let history_id = reset_cursor(&state).await?;
+                return Ok(Json(serde_json::json!({
+                    "messages": [],
+                    "history_id": history_id.to_string()
+                })));
+            }
+        },
+    };
+
+    // `max=0` only reports the current cursor, e.g. to anchor a new subscriber.
+    if max == 0 {
+        return Ok(Json(serde_json::json!({
+            "messages": [],
+            "history_id": since.to_string()
+        })));
+    }
+
+    // Collect added message IDs, stopping at a history-record boundary once
+    // `max` is reached so the returned cursor never skips unreported mail.
+    let mut ids: Vec<String> = Vec::new();
+    let mut next_cursor = since;
+    let mut page_token: Option<String> = None;
+    let mut more = false;
+    'pages: loop {
+        let page = state
+            .gmail
+            .history(since, page_token.as_deref())
+            .await
+            .map_err(|e| internal_error("Gmail history request failed", e))?;
+        let Some(page) = page else {
+            tracing::warn!(since, "history cursor expired; resetting to current mailbox state");
+            let history_id = match shared {
+                true => reset_cursor(&state).await?,
+                false => current_history_id(&state).await?,
+            };
+            return Ok(Json(serde_json::json!({
+                "messages": [],
+                "history_id": history_id.to_string(),
+                "reset": true
+            })));
+        };
+
+        for record in page.history.unwrap_or_default() {
+            if ids.len() >= max {
+                more = true;
+                break 'pages;
+            }
+            for added in record.messages_added.unwrap_or_default() {
+                let labels = added.message.label_ids.unwrap_or_default();
+                if !labels.iter().any(|l| state.watch_label_ids.contains(l)) {
+                    continue;
+                }
+                if !ids.contains(&added.message.id) {
+                    ids.push(added.message.id);
+                }
+            }
+            if let Ok(id) = record.id.parse() {
+                next_cursor = next_cursor.max(id);
+            }
+        }
+
+        match page.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => {
+                if let Some(id) = page.history_id.as_deref().and_then(|h| h.parse().ok()) {
+                    next_cursor = next_cursor.max(id);
+                }
+                break;
+            }
+        }
+    }
+
+    let fetched: Vec<_> = stream::iter(ids)
+        .map(|id| {
+            let gmail = state.gmail.clone();
+            async move { gmail.find_message(&id).await }
+        })
+        .buffered(state.search_concurrency)
+        .collect()
+        .await;
+
+    let mut messages = Vec::new();
+    for result in fetched {
+        // Messages deleted between the history record and the fetch are
+        // skipped; any other failure fails the request so the cursor stays put.
+        let msg = match result.map_err(|e| internal_error("Failed to fetch changed message", e))? {
+            Some(m) => m,
+            None => continue,
+        };
+        let labels = msg.label_ids.clone().unwrap_or_default();
+        if state.label_filter.is_message_blocked(&labels) {
+            continue;
+        }
+        let from = msg.header("From").unwrap_or("");
+        if state.scrubber.check_sender(from).is_blocked() {
+            continue;
+        }
+        let body = msg.extract_text_body().unwrap_or_default();
+        let scrubbed = state.scrubber.scrub_body(&body);
+        messages.push(msg.to_sanitized(scrubbed));
+    }
+
+    if shared {
+        state
+            .cursor
+            .advance(next_cursor)
+            .await
+            .map_err(|e| internal_error("Failed to persist history cursor", e))?;
+    }
+
+    Ok(Json(serde_json::json!({
+        "messages": messages,
+        "history_id": next_cursor.to_string(),
+        "more": more
+    })))
+}
+
 // ---------------------------------------------------------------------------
 // POST /drafts
 // ---------------------------------------------------------------------------
@@ -239,6 +566,15 @@ async fn create_draft_handler(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateDraftRequest>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    if !state.drafts_allowed {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": "Drafts are disabled: gmail-proxy is authorized read-only",
+                "hint": "Run `gmail-proxy reauth --access compose` to allow drafts."
+            })),
+        ));
+    }
     if req.to.trim().is_empty() {
         return Err((
             StatusCode::BAD_REQUEST,
@@ -254,7 +590,7 @@ async fn create_draft_handler(
 
     let draft = state
         .gmail
-        .create_draft(&req.to, &req.subject, &req.body, req.cc.as_deref())
+        .create_draft(&req.to, &req.subject, &req.body, req.cc.as_deref(), req.html_body.as_deref())
         .await
         .map_err(|e| {
             (
@@ -290,3 +626,238 @@ async fn health_handler(State(state): State<Arc<AppState>>) -> Json<serde_json::
         }
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::testing::{serve, MockApi};
+
+    fn history(ids: &[&str]) -> Value {
+        let records: Vec<Value> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| {
+                json!({
+                    "id": (101 + i).to_string(),
+                    "messagesAdded": [{"message": {"id": id, "threadId": "t", "labelIds": ["INBOX"]}}]
+                })
+            })
+            .collect();
+        json!({"history": records, "historyId": "200"})
+    }
+
+    fn message(id: &str) -> Value {
+        json!({
+            "id": id,
+            "threadId": "t",
+            "labelIds": ["INBOX"],
+            "payload": {"mimeType": "text/plain", "headers": [{"name": "From", "value": "a@x.com"}],
+                        "body": {"data": "aGk"}}
+        })
+    }
+
+    async fn get(base: &str, path: &str) -> (u16, Value) {
+        let resp = reqwest::get(format!("{base}{path}")).await.unwrap();
+        (resp.status().as_u16(), resp.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn changes_skip_deleted_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /history", 200, history(&["m1", "m2"]));
+        mock.on("GET /messages/m2", 200, message("m2"));
+        let state = Arc::new(mock.app_state(dir.path()));
+        let base = serve(build_router(state.clone())).await;
+
+        let (status, body) = get(&base, "/changes?since=100").await;
+        assert_eq!(status, 200);
+        let ids: Vec<_> = body["messages"].as_array().unwrap().iter().map(|m| m["id"].clone()).collect();
+        assert_eq!(ids, vec![json!("m2")]);
+        assert!(mock.requests().contains(&"GET /messages/m1".to_string()));
+        assert_eq!(body["history_id"], "200");
+        // An explicit `since` leaves the shared cursor alone.
+        assert_eq!(state.cursor.get().await, None);
+    }
+
+    #[tokio::test]
+    async fn only_changes_without_since_move_the_shared_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /history", 200, history(&["m1"]));
+        mock.on("GET /messages/m1", 200, message("m1"));
+        let state = Arc::new(mock.app_state(dir.path()));
+        state.cursor.advance(100).await.unwrap();
+        let base = serve(build_router(state.clone())).await;
+
+        let (status, body) = get(&base, "/changes?since=150").await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["history_id"], "200");
+        assert_eq!(state.cursor.get().await, Some(100));
+
+        let (_, body) = get(&base, "/changes?max=0").await;
+        assert_eq!(body["history_id"], "100");
+
+        let (status, body) = get(&base, "/changes").await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(state.cursor.get().await, Some(200));
+    }
+
+    #[tokio::test]
+    async fn expired_explicit_since_resets_without_moving_the_shared_cursor() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /history", 404, json!({"error": {"code": 404}}));
+        mock.on("GET /profile", 200, json!({"emailAddress": "a@x.com", "historyId": "500"}));
+        let state = Arc::new(mock.app_state(dir.path()));
+        state.cursor.advance(100).await.unwrap();
+        let base = serve(build_router(state.clone())).await;
+
+        let (status, body) = get(&base, "/changes?since=50").await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["reset"], true);
+        assert_eq!(body["history_id"], "500");
+        assert_eq!(state.cursor.get().await, Some(100));
+    }
+
+    #[tokio::test]
+    async fn changes_fail_without_advancing_on_fetch_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /history", 200, history(&["m1", "m2"]));
+        mock.on("GET /messages/m1", 500, json!({"error": {"code": 500}}));
+        mock.on("GET /messages/m2", 200, message("m2"));
+        let state = Arc::new(mock.app_state(dir.path()));
+        state.cursor.advance(100).await.unwrap();
+        let base = serve(build_router(state.clone())).await;
+
+        let (status, body) = get(&base, "/changes").await;
+        assert_eq!(status, 500);
+        assert!(body["error"].as_str().unwrap().contains("changed message"));
+        assert_eq!(state.cursor.get().await, Some(100));
+    }
+
+    /// A message with a readable inline note, a readable CSV and PNG stored
+    /// apart, and a CSV that is too big to read.
+    fn message_with_attachments(id: &str) -> Value {
+        let note = URL_SAFE_NO_PAD.encode("inline note");
+        json!({
+            "id": id,
+            "threadId": "t",
+            "labelIds": ["INBOX"],
+            "payload": {
+                "mimeType": "multipart/mixed",
+                "headers": [{"name": "From", "value": "a@x.com"}],
+                "parts": [
+                    {"partId": "0", "mimeType": "text/plain", "body": {"data": "aGk"}},
+                    {"partId": "1", "mimeType": "text/plain", "filename": "note.txt",
+                     "body": {"data": note, "size": 11}},
+                    {"partId": "2", "mimeType": "text/csv", "filename": "codes.csv",
+                     "body": {"attachmentId": "att-csv", "size": 60}},
+                    {"partId": "3", "mimeType": "image/png", "filename": "photo.png",
+                     "body": {"attachmentId": "att-png", "size": 100}},
+                    {"partId": "4", "mimeType": "text/csv", "filename": "big.csv",
+                     "body": {"attachmentId": "att-big", "size": 5000}}
+                ]
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn attachments_are_listed_with_their_readability() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /messages/m1", 200, message_with_attachments("m1"));
+        let base = serve(build_router(Arc::new(mock.app_state(dir.path())))).await;
+
+        let (status, body) = get(&base, "/message/m1/attachments").await;
+        assert_eq!(status, 200);
+        let listed: Vec<_> = body["attachments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| (a["filename"].as_str().unwrap(), a["readable"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![("note.txt", true), ("codes.csv", true), ("photo.png", false), ("big.csv", true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn attachment_reads_refuse_unsupported_types_and_oversized_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /messages/m1", 200, message_with_attachments("m1"));
+        let mut state = mock.app_state(dir.path());
+        state.attachment_max_bytes = 1000;
+        let base = serve(build_router(Arc::new(state))).await;
+
+        let (status, body) = get(&base, "/attachment/m1/att-png").await;
+        assert_eq!(status, 415);
+        assert!(body["error"].as_str().unwrap().contains("image/png"), "{body}");
+
+        let (status, body) = get(&base, "/attachment/m1/att-big").await;
+        assert_eq!(status, 413);
+        assert!(body["error"].as_str().unwrap().contains("1000"), "{body}");
+
+        let fetched = mock.requests().into_iter().filter(|r| r.contains("/attachments/")).count();
+        assert_eq!(fetched, 0);
+    }
+
+    #[tokio::test]
+    async fn attachment_text_is_scrubbed_and_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        mock.on("GET /messages/m1", 200, message_with_attachments("m1"));
+        let csv = "code,value\nlogin,482913\nrest,aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\n";
+        mock.on("GET /messages/m1/attachments/att-csv", 200, json!({"data": URL_SAFE_NO_PAD.encode(csv)}));
+        let mut state = mock.app_state(dir.path());
+        state.scrubber = Arc::new(ContentScrubber::new(
+            vec![regex::Regex::new(r"\b\d{6}\b").unwrap()],
+            vec![],
+            vec![],
+            false,
+        ));
+        state.attachment_max_chars = 30;
+        let base = serve(build_router(Arc::new(state))).await;
+
+        let (status, body) = get(&base, "/attachment/m1/att-csv").await;
+        assert_eq!(status, 200, "{body}");
+        let text = body["text"].as_str().unwrap();
+        assert!(!text.contains("482913"), "{text}");
+        assert!(text.contains("[REDACTED]"), "{text}");
+        assert_eq!(text.chars().count(), 30);
+        assert_eq!(body["truncated"], true);
+
+        // Inline parts are read from the message itself, by part ID.
+        let (status, body) = get(&base, "/attachment/m1/1").await;
+        assert_eq!(status, 200, "{body}");
+        assert_eq!(body["text"], "inline note");
+        assert_eq!(body["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn drafts_are_refused_without_the_compose_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockApi::start().await;
+        let mut state = mock.app_state(dir.path());
+        state.drafts_allowed = false;
+        let base = serve(build_router(Arc::new(state))).await;
+
+        let resp = reqwest::Client::new()
+            .post(format!("{base}/drafts"))
+            .json(&json!({"to": "a@x.com", "subject": "Hi", "body": "Hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status().as_u16(), 403);
+        let body: Value = resp.json().await.unwrap();
+        assert!(body["hint"].as_str().unwrap().contains("--access compose"), "{body}");
+        assert!(mock.requests().is_empty());
+    }
+}
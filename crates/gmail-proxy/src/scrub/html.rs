@@ -0,0 +1,525 @@
+//! HTML handling for message bodies.
+//!
+//! Two directions:
+//!   - `html_to_text` — reading: flatten an HTML-only email into plain text so
+//!     newsletters and HTML mail don't come back with an empty body.
+//!   - `sanitize_html` — drafting: reduce agent-supplied HTML to a small safe
+//!     tag subset before it is placed in a draft.
+//!
+//! Both are built on a deliberately small tokenizer. `sanitize_html` never
+//! passes input through verbatim: every tag is re-emitted from the allowlist
+//! and every text run is re-escaped, so malformed markup cannot smuggle
+//! anything through.
+
+/// Tags whose contents are dropped entirely (never rendered as text).
+const DROP_CONTENT_TAGS: &[&str] = &[
+    "script", "style", "head", "title", "iframe", "object", "embed", "noscript",
+    "template", "svg", "math",
+];
+
+/// Tags that start a new line when converting to text.
+const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "br", "tr", "table", "ul", "ol", "blockquote", "pre", "hr",
+    "h1", "h2", "h3", "h4", "h5", "h6", "section", "article", "header", "footer",
+];
+
+/// Tags allowed to survive `sanitize_html`.
+const SAFE_TAGS: &[&str] = &[
+    "p", "br", "b", "strong", "i", "em", "u", "s", "a", "ul", "ol", "li",
+    "blockquote", "h1", "h2", "h3", "h4", "h5", "h6", "pre", "code", "hr",
+    "table", "thead", "tbody", "tr", "th", "td", "span", "div",
+];
+
+/// Void elements — never have a closing tag.
+const VOID_TAGS: &[&str] = &["br", "hr", "img", "meta", "link", "input", "wbr"];
+
+/// URL schemes permitted in a sanitized `<a href>`.
+const SAFE_SCHEMES: &[&str] = &["http://", "https://", "mailto:"];
+
+// ---------------------------------------------------------------------------
+// Tokenizer
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// Raw text between tags (entities not yet decoded).
+    Text(String),
+    /// An opening tag with lowercased name and attributes. `self_closing`
+    /// is set for `<tag/>`, which has no content to drop.
+    Open { name: String, attrs: Vec<(String, String)>, self_closing: bool },
+    /// A closing tag with lowercased name.
+    Close(String),
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            tokens.push(Token::Text(rest.to_string()));
+            break;
+        };
+        if lt > 0 {
+            tokens.push(Token::Text(rest[..lt].to_string()));
+        }
+        rest = &rest[lt..];
+
+        // Comments, doctypes and processing instructions are skipped.
+        if rest.starts_with("<!--") {
+            rest = match rest.find("-->") {
+                Some(end) => &rest[end + 3..],
+                None => "",
+            };
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = match rest.find('>') {
+                Some(end) => &rest[end + 1..],
+                None => "",
+            };
+            continue;
+        }
+
+        let closing = rest.starts_with("</");
+        let name_start = if closing { 2 } else { 1 };
+        let name_len = rest[name_start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(rest.len() - name_start);
+
+        // A bare '<' that doesn't begin a tag is literal text.
+        if name_len == 0 {
+            tokens.push(Token::Text("<".into()));
+            rest = &rest[1..];
+            continue;
+        }
+
+        let name = rest[name_start..name_start + name_len].to_ascii_lowercase();
+        let (attrs, consumed, self_closing) = parse_attrs(&rest[name_start + name_len..]);
+        rest = &rest[(name_start + name_len + consumed).min(rest.len())..];
+
+        if closing {
+            tokens.push(Token::Close(name));
+        } else {
+            tokens.push(Token::Open { name, attrs, self_closing });
+        }
+    }
+
+    tokens
+}
+
+/// Parse attributes up to and including the closing `>`.
+///
+/// Returns the attributes (names lowercased, values entity-decoded), the
+/// number of bytes consumed, and whether the tag ends in `/>`.
+fn parse_attrs(input: &str) -> (Vec<(String, String)>, usize, bool) {
+    let bytes = input.as_bytes();
+    let mut attrs = Vec::new();
+    let mut i = 0;
+
+    loop {
+        let mut slash = false;
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            slash = bytes[i] == b'/';
+            i += 1;
+        }
+        if i >= bytes.len() {
+            return (attrs, i, false);
+        }
+        if bytes[i] == b'>' {
+            return (attrs, i + 1, slash);
+        }
+
+        let name_start = i;
+        while i < bytes.len()
+            && !bytes[i].is_ascii_whitespace()
+            && !matches!(bytes[i], b'=' | b'>' | b'/')
+        {
+            i += 1;
+        }
+        let name = input[name_start..i].to_ascii_lowercase();
+
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'"' || bytes[i] == b'\'') {
+                let quote = bytes[i];
+                i += 1;
+                let value_start = i;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                value = decode_entities(&input[value_start..i]);
+                i = (i + 1).min(bytes.len());
+            } else {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+                value = decode_entities(&input[value_start..i]);
+            }
+        }
+
+        if !name.is_empty() {
+            attrs.push((name, value));
+        } else {
+            // Stray character we couldn't interpret — skip it to guarantee progress.
+            i += 1;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Entities
+// ---------------------------------------------------------------------------
+
+/// Decode the HTML entities that commonly appear in email.
+///
+/// Unknown named entities are left as-is.
+pub fn decode_entities(input: &str) -> String {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let end = match rest[1..].find(|c: char| c == ';' || c == '&' || c.is_whitespace()) {
+            Some(e) if rest.as_bytes()[e + 1] == b';' && e <= 10 => e + 1,
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "bull" => Some('•'),
+            "copy" => Some('©'),
+            "reg" => Some('®'),
+            "trade" => Some('™'),
+            "zwnj" | "zwj" | "shy" => Some('\u{200B}'),
+            _ => {
+                let num = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                    u32::from_str_radix(hex, 16).ok()
+                } else {
+                    entity.strip_prefix('#').and_then(|d| d.parse::<u32>().ok())
+                };
+                num.and_then(char::from_u32)
+            }
+        };
+
+        match decoded {
+            // Zero-width characters are dropped rather than emitted.
+            Some('\u{200B}') => {}
+            Some(c) => out.push(c),
+            None => out.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+fn escape_text(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn escape_attr(input: &str) -> String {
+    escape_text(input).replace('"', "&quot;")
+}
+
+// ---------------------------------------------------------------------------
+// HTML → text
+// ---------------------------------------------------------------------------
+
+/// Convert an HTML document into readable plain text.
+///
+/// Block elements become line breaks, list items are bulleted, link targets
+/// are kept in parentheses after the link text (so URL scrubbing still sees
+/// them), and script/style/head content is discarded.
+pub fn html_to_text(html: &str) -> String {
+    let mut out = String::new();
+    let mut drop_depth = 0usize;
+    let mut pending_href: Vec<Option<String>> = Vec::new();
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(raw) => {
+                if drop_depth > 0 {
+                    continue;
+                }
+                let text = decode_entities(&raw);
+                let mut last_space = out.ends_with(char::is_whitespace) || out.is_empty();
+                for c in text.chars() {
+                    if c.is_whitespace() && c != '\u{A0}' {
+                        if !last_space {
+                            out.push(' ');
+                            last_space = true;
+                        }
+                    } else {
+                        out.push(c);
+                        last_space = false;
+                    }
+                }
+            }
+            Token::Open { name, attrs, self_closing } => {
+                if DROP_CONTENT_TAGS.contains(&name.as_str()) {
+                    drop_depth += usize::from(!self_closing);
+                    continue;
+                }
+                if drop_depth > 0 {
+                    continue;
+                }
+                match name.as_str() {
+                    "li" => {
+                        newline(&mut out);
+                        out.push_str("- ");
+                    }
+                    "td" | "th" if !out.ends_with('\n') && !out.is_empty() => {
+                        out.push_str(" | ");
+                    }
+                    "img" => {
+                        if let Some((_, alt)) = attrs.iter().find(|(k, _)| k == "alt") {
+                            if !alt.trim().is_empty() {
+                                out.push_str(&format!("[image: {}]", alt.trim()));
+                            }
+                        }
+                    }
+                    "a" => {
+                        let href = attrs
+                            .into_iter()
+                            .find(|(k, _)| k == "href")
+                            .map(|(_, v)| v)
+                            .filter(|v| v.starts_with("http://") || v.starts_with("https://"));
+                        pending_href.push(href);
+                    }
+                    n if BLOCK_TAGS.contains(&n) => newline(&mut out),
+                    _ => {}
+                }
+            }
+            Token::Close(name) => {
+                if DROP_CONTENT_TAGS.contains(&name.as_str()) {
+                    drop_depth = drop_depth.saturating_sub(1);
+                    continue;
+                }
+                if drop_depth > 0 {
+                    continue;
+                }
+                match name.as_str() {
+                    "a" => {
+                        if let Some(Some(href)) = pending_href.pop() {
+                            let trimmed = out.trim_end();
+                            if !trimmed.ends_with(href.as_str()) {
+                                out.push_str(&format!(" ({href})"));
+                            }
+                        }
+                    }
+                    "p" | "div" | "table" | "blockquote" | "ul" | "ol" | "pre" | "h1" | "h2"
+                    | "h3" | "h4" | "h5" | "h6" => {
+                        newline(&mut out);
+                        out.push('\n');
+                    }
+                    n if BLOCK_TAGS.contains(&n) || n == "li" => newline(&mut out),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    tidy_lines(&out)
+}
+
+/// Ensure the output ends with a newline (without stacking blank lines).
+fn newline(out: &mut String) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Trim trailing whitespace on every line and collapse runs of blank lines.
+fn tidy_lines(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 || result.is_empty() {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result.trim_end().to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Sanitizer
+// ---------------------------------------------------------------------------
+
+/// Reduce HTML to a safe subset suitable for an outgoing draft.
+///
+/// Only tags in `SAFE_TAGS` survive, with all attributes stripped except
+/// `href` on `<a>` (and only for http, https and mailto targets). Content of
+/// script/style-like tags is removed; everything else that isn't allowed is
+/// unwrapped to its text. The output is always well-formed: unclosed tags are
+/// closed at the end and stray closing tags are dropped.
+pub fn sanitize_html(html: &str) -> String {
+    let mut out = String::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut drop_depth = 0usize;
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(raw) => {
+                if drop_depth == 0 {
+                    out.push_str(&escape_text(&decode_entities(&raw)));
+                }
+            }
+            Token::Open { name, attrs, self_closing } => {
+                if DROP_CONTENT_TAGS.contains(&name.as_str()) {
+                    drop_depth += usize::from(!self_closing);
+                    continue;
+                }
+                if drop_depth > 0 || !SAFE_TAGS.contains(&name.as_str()) {
+                    continue;
+                }
+                out.push('<');
+                out.push_str(&name);
+                if name == "a" {
+                    let href = attrs.iter().find(|(k, _)| k == "href").map(|(_, v)| v.trim());
+                    if let Some(href) = href.filter(|h| is_safe_href(h)) {
+                        out.push_str(&format!(" href=\"{}\"", escape_attr(href)));
+                    }
+                }
+                out.push('>');
+                if !VOID_TAGS.contains(&name.as_str()) {
+                    open.push(name);
+                }
+            }
+            Token::Close(name) => {
+                if DROP_CONTENT_TAGS.contains(&name.as_str()) {
+                    drop_depth = drop_depth.saturating_sub(1);
+                    continue;
+                }
+                if drop_depth > 0 || !open.contains(&name) {
+                    continue;
+                }
+                while let Some(top) = open.pop() {
+                    out.push_str(&format!("</{top}>"));
+                    if top == name {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    while let Some(top) = open.pop() {
+        out.push_str(&format!("</{top}>"));
+    }
+    out
+}
+
+fn is_safe_href(href: &str) -> bool {
+    let lower = href.to_ascii_lowercase();
+    SAFE_SCHEMES.iter().any(|s| lower.starts_with(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text_paragraphs_and_breaks() {
+        let text = html_to_text("<p>Hello <b>there</b>,</p><p>Line one<br>Line two</p>");
+        assert_eq!(text, "Hello there,\n\nLine one\nLine two");
+    }
+
+    #[test]
+    fn test_html_to_text_drops_script_and_style() {
+        let html = "<html><head><style>p{color:red}</style><title>T</title></head>\
+                    <body><script>alert(1)</script><p>Visible</p></body></html>";
+        assert_eq!(html_to_text(html), "Visible");
+    }
+
+    #[test]
+    fn test_html_to_text_keeps_text_after_self_closing_svg() {
+        let html = "<p>Logo <svg/> after</p><math /><p>More <svg><path d=\"M0\"/>hidden</svg>shown</p>";
+        assert_eq!(html_to_text(html), "Logo after\n\nMore shown");
+        assert_eq!(sanitize_html("<svg/>kept"), "kept");
+        // A slash inside an unquoted value does not close the tag.
+        assert_eq!(html_to_text("<svg x=a/>gone</svg>kept"), "kept");
+    }
+
+    #[test]
+    fn test_html_to_text_lists_and_links() {
+        let html = "<ul><li>One</li><li><a href=\"https://example.com/a\">Two</a></li></ul>";
+        assert_eq!(html_to_text(html), "- One\n- Two (https://example.com/a)");
+    }
+
+    #[test]
+    fn test_html_to_text_decodes_entities() {
+        assert_eq!(html_to_text("<p>Fish &amp; chips &#8212; &lt;3&nbsp;you</p>"), "Fish & chips — <3 you");
+    }
+
+    #[test]
+    fn test_sanitize_strips_attributes_and_unsafe_tags() {
+        let html = "<p style=\"color:red\" onclick=\"x()\">Hi <script>evil()</script>\
+                    <img src=x onerror=alert(1)><font>there</font></p>";
+        assert_eq!(sanitize_html(html), "<p>Hi there</p>");
+    }
+
+    #[test]
+    fn test_sanitize_rejects_javascript_href() {
+        assert_eq!(
+            sanitize_html("<a href=\"javascript:alert(1)\">x</a>"),
+            "<a>x</a>"
+        );
+        assert_eq!(
+            sanitize_html("<a href='https://example.com/?a=1&amp;b=\"2\"'>x</a>"),
+            "<a href=\"https://example.com/?a=1&amp;b=&quot;2&quot;\">x</a>"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_balances_tags() {
+        assert_eq!(sanitize_html("<b>bold <i>both</b> after</i>"), "<b>bold <i>both</i></b> after");
+        assert_eq!(sanitize_html("<ul><li>open"), "<ul><li>open</li></ul>");
+    }
+
+    #[test]
+    fn test_sanitize_escapes_text() {
+        assert_eq!(sanitize_html("1 < 2 &amp; 3 > 2"), "1 &lt; 2 &amp; 3 &gt; 2");
+    }
+}
@@ -581,12 +581,12 @@ pub fn reconstruct_query(node: &QueryNode) -> String {
         QueryNode::Group(inner) => format!("({})", reconstruct_query(inner)),
         QueryNode::And(children) => children
             .iter()
-            .map(|c| reconstruct_query(c))
+            .map(reconstruct_query)
             .collect::<Vec<_>>()
             .join(" "),
         QueryNode::Or(children) => children
             .iter()
-            .map(|c| reconstruct_query(c))
+            .map(reconstruct_query)
             .collect::<Vec<_>>()
             .join(" OR "),
     }
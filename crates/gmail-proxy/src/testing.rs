@@ -0,0 +1,111 @@
+//! A local stand-in for the Gmail API and Google's token endpoint, for the
+//! route tests.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::http::{Method, StatusCode, Uri};
+use axum::Json;
+use serde_json::{json, Value};
+
+use crate::auth::TokenManager;
+use crate::gmail::client::GmailClient;
+use crate::gmail::cursor::HistoryCursor;
+use crate::proxy::routes::AppState;
+use crate::scrub::content::ContentScrubber;
+use crate::scrub::labels::LabelFilter;
+
+#[derive(Default)]
+struct Recorded {
+    /// Canned responses keyed by `"<METHOD> <path>"`; the query is ignored.
+    responses: HashMap<String, (StatusCode, Value)>,
+    requests: Vec<String>,
+}
+
+pub struct MockApi {
+    base: String,
+    state: Arc<Mutex<Recorded>>,
+}
+
+impl MockApi {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(Recorded::default()));
+        let app = axum::Router::new().fallback(respond).with_state(state.clone());
+        Self {
+            base: serve(app).await,
+            state,
+        }
+    }
+
+    /// Answer `"<METHOD> <path>"` (e.g. `"GET /messages/m1"`) with `body`.
+    pub fn on(&self, route: &str, status: u16, body: Value) {
+        let status = StatusCode::from_u16(status).unwrap();
+        self.state.lock().unwrap().responses.insert(route.to_string(), (status, body));
+    }
+
+    /// Every request received, as `"<METHOD> <path>"`.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Proxy state wired to this mock, with nothing blocked or scrubbed and
+    /// the history cursor stored under `dir`.
+    pub fn app_state(&self, dir: &Path) -> AppState {
+        let token_manager = Arc::new(TokenManager::new(
+            "client".into(),
+            "secret".into(),
+            "refresh".into(),
+            format!("{}/token", self.base),
+        ));
+        AppState {
+            gmail: Arc::new(GmailClient::new(
+                token_manager.clone(),
+                self.base.clone(),
+                "me@example.com".into(),
+            )),
+            label_filter: Arc::new(LabelFilter::new("Label_blocked".into(), "blocked".into())),
+            scrubber: Arc::new(ContentScrubber::new(vec![], vec![], vec![], false)),
+            allowed_operators: vec![],
+            blocked_label: "blocked".into(),
+            max_query_depth: 10,
+            search_concurrency: 2,
+            attachment_max_bytes: 1024 * 1024,
+            attachment_max_chars: 10_000,
+            cursor: Arc::new(HistoryCursor::load(dir.join("cursor.json")).unwrap()),
+            drafts_allowed: true,
+            watch_label_ids: vec!["INBOX".into()],
+            token_manager,
+            start_time: std::time::Instant::now(),
+        }
+    }
+}
+
+async fn respond(
+    State(state): State<Arc<Mutex<Recorded>>>,
+    method: Method,
+    uri: Uri,
+) -> (StatusCode, Json<Value>) {
+    let key = format!("{method} {}", uri.path());
+    let mut state = state.lock().unwrap();
+    state.requests.push(key.clone());
+    if key == "POST /token" {
+        return (
+            StatusCode::OK,
+            Json(json!({"access_token": "at", "expires_in": 3599, "token_type": "Bearer"})),
+        );
+    }
+    match state.responses.get(&key) {
+        Some((status, body)) => (*status, Json(body.clone())),
+        None => (StatusCode::NOT_FOUND, Json(json!({"error": {"code": 404}}))),
+    }
+}
+
+/// Serve `app` on an ephemeral local port and return its base URL.
+pub async fn serve(app: axum::Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    base
+}
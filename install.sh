@@ -491,6 +491,7 @@ request_timeout = 30              # seconds
 [security]
 audit_log_path = "/Users/carapace/.local/share/carapace/audit.log"
 dead_letter_path = "/Users/carapace/.local/share/carapace/dead_letters"
+cursor_store_path = "/Users/carapace/.local/share/carapace/watch_cursors.json"
 audit_enabled = true
 
 [security.rate_limit]